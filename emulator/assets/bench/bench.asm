@@ -0,0 +1,109 @@
+; Benchmark ROM for yagabor
+;
+; Every frame it runs a fixed arithmetic loop on the CPU, retriggers the first
+; pulse channel and scrolls a background full of tiles, so the CPU, PPU and
+; APU paths all get work. The program never ends, benchmarks run it for a
+; fixed number of frames.
+;
+; Build with RGBDS:
+;   rgbasm -o bench.o bench.asm
+;   rgblink -o bench.gb bench.o
+;   rgbfix -v -p 0xFF -t "YAGABOR BENCH" bench.gb
+
+SECTION "Header", ROM0[$100]
+    nop
+    jp Start
+
+    ds $150 - @, 0          ; rgbfix fills the logo, title and checksums
+
+SECTION "Main", ROM0[$150]
+Start:
+    di
+    ld sp, $FFFE
+
+    ; Sound on, both outputs at full volume
+    ld a, $80
+    ldh [$26], a            ; NR52
+    ld a, $77
+    ldh [$24], a            ; NR50
+    ld a, $FF
+    ldh [$25], a            ; NR51
+
+    ; The LCD can only be turned off during VBlank
+.waitVBlank:
+    ldh a, [$44]            ; LY
+    cp 144
+    jr c, .waitVBlank
+    xor a
+    ldh [$40], a            ; LCDC
+
+    ; Tile data $8000-$8FFF, every byte is the low byte of its address
+    ld hl, $8000
+    ld bc, $1000
+.fillTiles:
+    ld a, l
+    ld [hl+], a
+    dec bc
+    ld a, b
+    or c
+    jr nz, .fillTiles
+
+    ; Background map $9800-$9BFF, same pattern
+    ld hl, $9800
+    ld bc, $0400
+.fillMap:
+    ld a, l
+    ld [hl+], a
+    dec bc
+    ld a, b
+    or c
+    jr nz, .fillMap
+
+    ld a, $E4
+    ldh [$47], a            ; BGP
+    ld a, $91               ; LCD on, tiles at $8000, background on
+    ldh [$40], a
+
+MainLoop:
+    ; CPU: 256 iterations of 8 and 16 bit arithmetic, stack and CB opcodes
+    ld de, $0000
+    ld b, 0
+.cpu:
+    ld a, e
+    add a, b
+    ld e, a
+    ld a, d
+    adc a, 0
+    ld d, a
+    push de
+    pop hl
+    add hl, hl
+    swap a
+    rlc l
+    dec b
+    jr nz, .cpu
+
+    ; APU: retrigger pulse channel 1 with a frequency taken from the result
+    ld a, $F3
+    ldh [$12], a            ; NR12
+    ld a, e
+    ldh [$13], a            ; NR13
+    ld a, $87
+    ldh [$14], a            ; NR14
+
+    ; PPU: scroll the background once per frame
+.waitFrame:
+    ldh a, [$44]
+    cp 144
+    jr nz, .waitFrame
+    ldh a, [$43]            ; SCX
+    inc a
+    ldh [$43], a
+    ldh a, [$42]            ; SCY
+    inc a
+    ldh [$42], a
+.leaveVBlankLine:
+    ldh a, [$44]
+    cp 144
+    jr z, .leaveVBlankLine
+    jr MainLoop
@@ -0,0 +1,321 @@
+use std::{env, io::{Error, ErrorKind}, path::{Path, PathBuf}, process};
+
+use clap::{Args, Parser, Subcommand};
+use gameboy::{cartridge::Cartridge, CartridgeError, config::EmulatorConfig, debugger::SymbolTable, display::{DisplayPipeline, PostFilter, Scaler}, movie::Movie, music::GbsFile, recorder::RecordingOptions, screenshot, serial::SerialConsole, testrom::{self, TestRomResult}, Emulation};
+
+// The window is drawn by the desktop frontend, built next to this binary
+const DESKTOP_BINARY: &str = "desktop-gui";
+
+#[derive(Parser)]
+#[command(name = "yagabor", version, about = "Game Boy emulator")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Play a game in a window, or run it headless with --frames
+    Run(RunArgs),
+    /// Print the cartridge header and what the emulator knows about the ROM
+    Info {
+        rom: PathBuf,
+    },
+    /// Disassemble the code at an address, as mapped at power on
+    Disasm {
+        rom: PathBuf,
+        /// A label, $AAAA or 0xAAAA
+        #[arg(long, default_value = "$0100")]
+        start: String,
+        /// Number of instructions
+        #[arg(long, default_value_t = 32)]
+        count: usize,
+        /// RGBDS .sym file for the labels
+        #[arg(long)]
+        symbols: Option<PathBuf>,
+    },
+    /// Run a blargg or mooneye test ROM and report its verdict
+    TestRom {
+        rom: PathBuf,
+        #[arg(long, default_value_t = 3600)]
+        frames: u64,
+    },
+    /// Log the sound of a game or of a GBS rip as a VGM file
+    Vgm {
+        /// A ROM, or a .gbs file
+        input: PathBuf,
+        output: PathBuf,
+        #[arg(long, default_value_t = 3600)]
+        frames: u64,
+        /// Counted from 1, the first song of the GBS file by default
+        #[arg(long)]
+        song: Option<u8>,
+        /// Inputs recorded with the desktop frontend, for ROMs
+        #[arg(long, conflicts_with = "song")]
+        movie: Option<PathBuf>,
+        #[command(flatten)]
+        options: EmulatorOptions,
+    },
+    /// Save an animated GIF of the first frames, optionally playing a movie
+    Record {
+        rom: PathBuf,
+        output: PathBuf,
+        #[arg(long, default_value_t = 600)]
+        frames: u64,
+        /// Inputs recorded with the desktop frontend
+        #[arg(long)]
+        movie: Option<PathBuf>,
+        /// Shows the sprites a game flickers on alternate frames together
+        #[arg(long)]
+        dedup_flicker: bool,
+        #[command(flatten)]
+        options: EmulatorOptions,
+    },
+}
+
+#[derive(Args)]
+struct RunArgs {
+    rom: PathBuf,
+    /// Run this many frames without a window, then exit
+    #[arg(long)]
+    frames: Option<u64>,
+    /// Start from the state saved in this slot
+    #[arg(long, requires = "frames")]
+    load_slot: Option<u8>,
+    /// Save the state after the last frame in this slot
+    #[arg(long, requires = "frames")]
+    save_slot: Option<u8>,
+    /// Save the last frame as a PNG
+    #[arg(long, requires = "frames")]
+    screenshot: Option<PathBuf>,
+    /// Every Game Boy pixel of the screenshot becomes scale x scale pixels
+    #[arg(long, default_value_t = 1, requires = "screenshot")]
+    scale: u32,
+    /// Shows the sprites a game flickers on alternate frames together in the screenshot
+    #[arg(long, requires = "screenshot")]
+    dedup_flicker: bool,
+    /// Prints what the game sends through the link port
+    #[arg(long)]
+    serial_console: bool,
+    #[command(flatten)]
+    options: EmulatorOptions,
+}
+
+/// Overrides of the settings file shared by the subcommands that emulate
+#[derive(Args)]
+struct EmulatorOptions {
+    /// grayscale, dmg-green, pocket-gray or four RRGGBB colors from white to black
+    #[arg(long)]
+    palette: Option<String>,
+    /// 256 bytes run instead of the built in DMG boot ROM
+    #[arg(long)]
+    boot_rom: Option<PathBuf>,
+    /// Settings shared by every game, ~/.config/yagabor/config.toml by default
+    #[arg(long)]
+    config: Option<PathBuf>,
+}
+
+impl EmulatorOptions {
+    fn settings(&self) -> Result<EmulatorConfig, Error> {
+        let mut settings = match self.config.clone().or_else(EmulatorConfig::default_path) {
+            Some(path) => EmulatorConfig::load(path)?,
+            None => EmulatorConfig::default(),
+        };
+        if let Some(palette) = &self.palette {
+            settings.palette = palette.clone();
+        }
+        if let Some(boot_rom) = &self.boot_rom {
+            settings.boot_rom = Some(boot_rom.clone());
+        }
+        Ok(settings)
+    }
+}
+
+fn main() {
+    if let Err(error) = run(Cli::parse().command) {
+        eprintln!("yagabor: {}", error);
+        process::exit(1);
+    }
+}
+
+fn run(command: Command) -> Result<(), Error> {
+    match command {
+        Command::Run(args) => match args.frames {
+            Some(frames) => run_headless(&args, frames),
+            None => run_desktop(&args),
+        },
+        Command::Info { rom } => info(&rom),
+        Command::Disasm { rom, start, count, symbols } => disasm(&rom, &start, count, symbols.as_deref()),
+        Command::TestRom { rom, frames } => test_rom(&rom, frames),
+        Command::Record { rom, output, frames, movie, dedup_flicker, options } => record(&rom, &output, frames, movie.as_deref(), dedup_flicker, &options),
+        Command::Vgm { input, output, frames, song, movie, options } => vgm(&input, &output, frames, song, movie.as_deref(), &options),
+    }
+}
+
+fn emulation(rom: &Path, settings: &EmulatorConfig) -> Result<Emulation, Error> {
+    let cartridge = Cartridge::new(rom.to_path_buf())?;
+    for issue in cartridge.compatibility_issues() {
+        eprintln!("Warning: this game {}", issue);
+    }
+    let mut emu = Emulation::new(Some(cartridge));
+    settings.apply(&mut emu)?;
+    emu.start();
+    Ok(emu)
+}
+
+fn run_headless(args: &RunArgs, frames: u64) -> Result<(), Error> {
+    let settings = args.options.settings()?;
+    let mut emu = emulation(&args.rom, &settings)?;
+    if let Some(slot) = args.load_slot {
+        emu.load_slot(slot)?;
+    }
+
+    let serial_console = args.serial_console.then(SerialConsole::with_echo);
+    if let Some(console) = &serial_console {
+        emu.connect_serial(Box::new(console.clone()));
+    }
+
+    emu.run_frames(frames)?;
+    if let Some(console) = &serial_console {
+        console.flush_echo();
+    }
+    println!("Ran {} frames, {} cycles, frame CRC32 {:08x}", frames, emu.total_cycles, testrom::frame_crc32(&emu.screen()));
+
+    if let Some(path) = &args.screenshot {
+        let pipeline = DisplayPipeline { palette: settings.color_palette()?, scaler: Scaler::Nearest(args.scale), filter: PostFilter::None };
+        let frame = if args.dedup_flicker { emu.stable_screen() } else { emu.screen() };
+        screenshot::save_rgba_png(&pipeline.render(&frame), path)?;
+        println!("Saved {}", path.display());
+    }
+    if let Some(slot) = args.save_slot {
+        emu.save_slot(slot)?;
+        println!("Saved {}", emu.state_slots()?.state_path(slot).display());
+    }
+    Ok(())
+}
+
+// Only the options the desktop frontend knows are passed on, the others
+// need --frames
+fn run_desktop(args: &RunArgs) -> Result<(), Error> {
+    if args.options.boot_rom.is_some() {
+        return Err(Error::new(ErrorKind::InvalidInput, "--boot-rom needs --frames, set boot_rom in the settings file to use it in the window"));
+    }
+    let binary = env::current_exe()?.with_file_name(format!("{}{}", DESKTOP_BINARY, env::consts::EXE_SUFFIX));
+    let mut desktop = process::Command::new(&binary);
+    desktop.arg(&args.rom);
+    if let Some(palette) = &args.options.palette {
+        desktop.arg("--palette").arg(palette);
+    }
+    if let Some(config) = &args.options.config {
+        desktop.arg("--config").arg(config);
+    }
+    if args.serial_console {
+        desktop.arg("--serial-console");
+    }
+
+    let status = desktop.status().map_err(|error| Error::new(error.kind(), format!("Can't start {}: {}", binary.display(), error)))?;
+    if !status.success() {
+        return Err(Error::other(format!("{} exited with {}", DESKTOP_BINARY, status)));
+    }
+    Ok(())
+}
+
+fn info(rom: &Path) -> Result<(), Error> {
+    let cartridge = Cartridge::new(rom.to_path_buf())?;
+    let header = cartridge.header();
+    let rom_info = cartridge.rom_info();
+
+    println!("Title:            {}", header.title);
+    println!("Cartridge type:   {:02X} {:?}", header.cartridge_type, cartridge.ctype());
+    println!("ROM size:         {} KiB", header.rom_size / 1024);
+    println!("RAM size:         {} KiB", header.ram_size / 1024);
+    println!("Battery:          {}", if cartridge.has_battery() { "yes" } else { "no" });
+    println!("CGB flag:         {:02X}{}", header.cgb_flag, if cartridge.supports_cgb() { " (CGB)" } else { "" });
+    println!("SGB:              {}", if header.sgb { "yes" } else { "no" });
+    println!("Licensee:         {}", header.licensee);
+    println!("Destination:      {}", if header.japanese { "Japan" } else { "Overseas" });
+    println!("Version:          {}", header.version);
+    println!("Header checksum:  {:02X} ({})", header.header_checksum,
+        if header.header_checksum_valid() { "ok".to_string() } else { format!("expected {:02X}", header.computed_header_checksum) });
+    println!("Global checksum:  {:04X}", header.global_checksum);
+    println!("Logo:             {}", if matches!(cartridge.verify_header(), Err(CartridgeError::BadLogo)) { "wrong" } else { "ok" });
+    println!("CRC32:            {:08x}", rom_info.crc32);
+    println!("SHA-1:            {}", rom_info.sha1_hex());
+    println!("Dump:             {:?}{}", rom_info.status, rom_info.region.map(|region| format!(", {}", region)).unwrap_or_default());
+    for issue in cartridge.compatibility_issues() {
+        println!("Warning:          this game {}", issue);
+    }
+    Ok(())
+}
+
+fn disasm(rom: &Path, start: &str, count: usize, symbols: Option<&Path>) -> Result<(), Error> {
+    let symbols = match symbols {
+        Some(path) => SymbolTable::load(path)?,
+        None => SymbolTable::default(),
+    };
+    let mut emu = Emulation::new(Some(Cartridge::new(rom.to_path_buf())?));
+    let debugger = emu.debugger();
+
+    let mut address = symbols.resolve(start)?.address;
+    for _ in 0..count {
+        let instruction = debugger.disassemble(address, Some(&symbols));
+        let bytes: Vec<String> = instruction.bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        println!("{:<24} {:<9} {}", debugger.symbolize(address, Some(&symbols)), bytes.join(" "), instruction.text);
+        address = address.wrapping_add(instruction.bytes.len().max(1) as u16);
+    }
+    Ok(())
+}
+
+fn test_rom(rom: &Path, frames: u64) -> Result<(), Error> {
+    let report = testrom::run_test_rom(Cartridge::new(rom.to_path_buf())?, frames)?;
+    print!("{}", report.serial);
+    println!("{:?} after {} frames", report.result, report.frames);
+    if report.result != TestRomResult::Passed {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn record(rom: &Path, output: &Path, frames: u64, movie: Option<&Path>, dedup_flicker: bool, options: &EmulatorOptions) -> Result<(), Error> {
+    let settings = options.settings()?;
+    let mut emu = emulation(rom, &settings)?;
+    if let Some(path) = movie {
+        emu.play_movie(Movie::load(path)?)?;
+    }
+
+    emu.start_recording(output, RecordingOptions { palette: settings.color_palette()?, dedup_flicker, ..RecordingOptions::default() })?;
+    let result = emu.run_frames(frames);
+    emu.stop_recording()?;
+    result?;
+    println!("Saved {} frames to {}", frames, output.display());
+    Ok(())
+}
+
+fn vgm(input: &Path, output: &Path, frames: u64, song: Option<u8>, movie: Option<&Path>, options: &EmulatorOptions) -> Result<(), Error> {
+    let is_gbs = input.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("gbs"));
+    let mut emu = if is_gbs {
+        let gbs = GbsFile::load(input)?;
+        let mut emu = gbs.emulation()?;
+        if let Some(song) = song {
+            gbs.start_song(&mut emu, song.saturating_sub(1))?;
+        }
+        println!("{} by {}, {} songs", gbs.title, gbs.author, gbs.song_count);
+        emu
+    } else if song.is_some() {
+        return Err(Error::new(ErrorKind::InvalidInput, "--song is for GBS files"));
+    } else {
+        let mut emu = emulation(input, &options.settings()?)?;
+        if let Some(path) = movie {
+            emu.play_movie(Movie::load(path)?)?;
+        }
+        emu
+    };
+
+    emu.start_vgm_log();
+    emu.run_frames(frames)?;
+    if let Some(log) = emu.stop_vgm_log() {
+        log.save(output)?;
+    }
+    println!("Saved {} frames of sound to {}", frames, output.display());
+    Ok(())
+}
@@ -1,35 +1,322 @@
 mod screen;
 
-use std::{io::Error, time::{Duration, Instant}};
+use std::{collections::HashSet, io::Error, mem, time::{Duration, Instant}};
 
 use clap::Parser;
-use gameboy::{Emulation, cartridge::Cartridge, SCREEN_WIDTH, SCREEN_HEIGHT, TILEDATA_WIDTH};
-use sdl2::{event::Event, keyboard::Keycode};
+use gameboy::{Emulation, cartridge::{Cartridge, ImageSource, StaticImage}, serial::SerialConsole, SCREEN_WIDTH, SCREEN_HEIGHT, TILEDATA_WIDTH};
+#[cfg(feature = "webcam")]
+use gameboy::cartridge::Webcam;
+use sdl2::{audio::{AudioCallback, AudioSpecDesired}, event::Event, keyboard::{Keycode, Mod}};
+use tracing_subscriber::EnvFilter;
 
 use gameboy::*;
 
 use crate::screen::Screen;
 
-const FRAME_TIME: u128 = 1000/60;
+// Room for a second of sound, the rate control keeps TARGET_LATENCY_MS queued
+const AUDIO_BUFFER_SECONDS: usize = 1;
+const TARGET_LATENCY_MS: usize = 50;
+const AUDIO_OFFSET_STEP: i32 = 5;
+// How often the events are polled while paused
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// One column per wave sample, one row per level
+const WAVE_WIDTH: u32 = 32;
+const WAVE_HEIGHT: u32 = 16;
+
+// A row per channel, a column per tap sample, about the samples of a frame
+const SCOPE_WIDTH: u32 = 160;
+const SCOPE_CHANNEL_HEIGHT: u32 = 32;
+const SCOPE_SAMPLE_RATE: u32 = 9600;
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum AudioQuality {
+    Off,
+    Nearest,
+    Linear,
+    BandLimited,
+}
+
+struct Playback(audio::AudioConsumer);
+
+impl AudioCallback for Playback {
+    type Channel = f32;
+
+    fn callback(&mut self, output: &mut [f32]) {
+        self.0.pop(output);
+    }
+}
+
+// Prints every kind of diagnostic once per instruction, a game writing VRAM
+// at the wrong time would do it every frame
+#[derive(Default)]
+struct DiagnosticPrinter {
+    seen: HashSet<(u16, u16, mem::Discriminant<debugger::DiagnosticKind>)>,
+}
+
+impl observer::EmulatorObserver for DiagnosticPrinter {
+    fn on_diagnostic(&mut self, diagnostic: &debugger::Diagnostic) {
+        if self.seen.insert((diagnostic.bank, diagnostic.pc, mem::discriminant(&diagnostic.kind))) {
+            eprintln!("{:02X}:{:04X} {:?}", diagnostic.bank, diagnostic.pc, diagnostic.kind);
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ScalerOption {
+    None,
+    Scale2x,
+    Scale3x,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum FilterOption {
+    None,
+    Scanlines,
+    LcdGrid,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum LcdOffOption {
+    White,
+    LastFrame,
+    Dimmed,
+}
 
 #[derive(Parser)]
 struct Cli {
-    cartridge: Option<std::path::PathBuf>
+    cartridge: Option<std::path::PathBuf>,
+    /// IPS or BPS patch applied to the cartridge when loading it
+    #[arg(long)]
+    patch: Option<std::path::PathBuf>,
+    /// Report writes to IO registers that are not emulated yet
+    #[arg(long)]
+    warn_unknown_io: bool,
+    /// Reports suspicious things the game does, like reading write-only registers or
+    /// writing VRAM while the PPU draws, with the bank and address of the instruction
+    #[arg(long)]
+    diagnostics: bool,
+    /// Prints what homebrew writes to FF7F and pauses on LD B,B and on the ED opcode,
+    /// Pause resumes
+    #[arg(long, conflicts_with = "netplay_peer")]
+    developer: bool,
+    /// Game Genie (ABC-DEF-GHI), GameShark (01VVAAAA) or AAAA=VV code, can be repeated.
+    /// ?AAAA=VV only applies the next code while the address holds the value.
+    #[arg(long = "cheat")]
+    cheats: Vec<String>,
+    /// Resampling from the Game Boy clock to the sound card, band-limited sounds best.
+    /// Linear unless the settings file says otherwise.
+    #[arg(long, value_enum)]
+    audio_quality: Option<AudioQuality>,
+    /// Shows wave RAM, click or drag in the window to draw a new waveform
+    #[arg(long)]
+    wave_editor: bool,
+    /// Shows the output of every sound channel. Ctrl+1-4 mutes a channel, Alt+1-4 solos it and Alt+0 plays them all.
+    #[arg(long)]
+    oscilloscope: bool,
+    /// Shows when the game writes to LCDC, the scroll, window and palette registers
+    /// during the last frame, one pixel per dot. F10 saves them as iolog.csv.
+    #[arg(long)]
+    event_viewer: bool,
+    /// Emulation speed, 1 is real time and 0 runs as fast as possible. Hold Tab to fast-forward.
+    #[arg(long, default_value_t = 1.0)]
+    speed: f32,
+    /// Frames skipped between two presented ones
+    #[arg(long, default_value_t = 0)]
+    frame_skip: u32,
+    /// Milliseconds the video is delayed against the sound, negative to delay the sound.
+    /// Saved in the game config, - and = adjust it while playing.
+    #[arg(long, allow_hyphen_values = true)]
+    audio_offset: Option<i32>,
+    /// grayscale, dmg-green, pocket-gray or four RRGGBB colors from white to black,
+    /// grayscale unless the settings file says otherwise
+    #[arg(long)]
+    palette: Option<String>,
+    #[arg(long, value_enum, default_value = "none")]
+    scaler: ScalerOption,
+    #[arg(long, value_enum, default_value = "none")]
+    filter: FilterOption,
+    /// Shown while the game keeps the LCD off, white like the hardware by default
+    #[arg(long, value_enum, default_value = "white")]
+    lcd_off: LcdOffOption,
+    /// Logs the sound of the session as a VGM file, saved on exit
+    #[arg(long)]
+    record_vgm: Option<std::path::PathBuf>,
+    /// Prints what the game sends through the link port, like the results of test ROMs
+    #[arg(long)]
+    serial_console: bool,
+    /// Screenshots and recordings show the sprites a game flickers on alternate frames together
+    #[arg(long)]
+    dedup_flicker: bool,
+    /// Records the inputs and resets from power on, saved on exit
+    #[arg(long, conflicts_with = "play_movie")]
+    record_movie: Option<std::path::PathBuf>,
+    #[arg(long)]
+    play_movie: Option<std::path::PathBuf>,
+    /// Plays with the Game Boy at this address, both players' buttons are held together.
+    /// Both sides need the same ROM and options.
+    #[arg(long, value_name = "HOST:PORT")]
+    netplay_peer: Option<String>,
+    #[arg(long, value_name = "HOST:PORT", default_value = "0.0.0.0:7845")]
+    netplay_bind: String,
+    /// Frames local buttons are delayed by, fewer frames are run again when the network is slow
+    #[arg(long, default_value_t = 2)]
+    input_delay: u32,
+    /// Rhai script run after every frame and on breakpoints, its overlay is drawn over the game
+    #[arg(long)]
+    script: Option<std::path::PathBuf>,
+    /// What the Game Boy Camera sees: a PNG, or webcam / webcam:N when built with the webcam feature
+    #[arg(long, value_name = "SOURCE")]
+    camera: Option<String>,
+    /// Settings shared by every game, ~/.config/yagabor/config.toml by default
+    #[arg(long)]
+    config: Option<std::path::PathBuf>,
+    /// Saves the settings with the palette and audio quality given on the command line
+    #[arg(long)]
+    save_config: bool,
+}
+
+// Samples as bars, the one being played in black
+fn wave_frame(samples: &[u8], position: u8) -> GameBoyFrame {
+    let mut buffer = vec![ColoredPixel::White; (WAVE_WIDTH * WAVE_HEIGHT) as usize];
+    for (x, sample) in samples.iter().enumerate() {
+        let color = if x == position as usize { ColoredPixel::Black } else { ColoredPixel::DarkGray };
+        for y in (WAVE_HEIGHT - 1 - *sample as u32)..WAVE_HEIGHT {
+            buffer[x + (y * WAVE_WIDTH) as usize] = color;
+        }
+    }
+    GameBoyFrame { width: WAVE_WIDTH, height: WAVE_HEIGHT, buffer }
+}
+
+// The last samples of every channel, muted ones in light gray
+fn scope_frame(emu: &Emulation, taps: &[Vec<f32>; 4]) -> GameBoyFrame {
+    let height = SCOPE_CHANNEL_HEIGHT * 4;
+    let mut buffer = vec![ColoredPixel::White; (SCOPE_WIDTH * height) as usize];
+    for (row, channel) in audio::AudioChannel::ALL.into_iter().enumerate() {
+        let color = if emu.is_channel_muted(channel) { ColoredPixel::LightGray } else { ColoredPixel::Black };
+        let samples = &taps[row][taps[row].len().saturating_sub(SCOPE_WIDTH as usize)..];
+        for (x, level) in samples.iter().enumerate() {
+            let offset = ((1.0 - level.clamp(-1.0, 1.0)) / 2.0 * (SCOPE_CHANNEL_HEIGHT - 1) as f32) as u32;
+            buffer[x + ((row as u32 * SCOPE_CHANNEL_HEIGHT + offset) * SCOPE_WIDTH) as usize] = color;
+        }
+    }
+    GameBoyFrame { width: SCOPE_WIDTH, height, buffer }
+}
+
+// The channel of the 1-4 keys
+fn audio_channel(key: Keycode) -> Option<audio::AudioChannel> {
+    let index = match key {
+        Keycode::Num1 => 0,
+        Keycode::Num2 => 1,
+        Keycode::Num3 => 2,
+        Keycode::Num4 => 3,
+        _ => return None,
+    };
+    Some(audio::AudioChannel::ALL[index])
+}
+
+fn set_button(emu: &mut Emulation, netplay: bool, buttons: &mut u8, button: Button, pressed: bool) {
+    let bit = netplay::button_mask(&[button]);
+    match (netplay, pressed) {
+        (true, true) => *buttons |= bit,
+        (true, false) => *buttons &= !bit,
+        (false, true) => emu.button_pressed(button),
+        (false, false) => emu.button_released(button),
+    }
+}
+
+fn camera_source(source: &str) -> Result<Box<dyn ImageSource>, Error> {
+    #[cfg(feature = "webcam")]
+    if let Some(index) = source.strip_prefix("webcam") {
+        let index = index.trim_start_matches(':').parse().unwrap_or(0);
+        return Ok(Box::new(Webcam::open(index)?));
+    }
+    Ok(Box::new(StaticImage::load(source)?))
+}
+
+fn edit_wave(emu: &mut Emulation, wavedebug: &Option<Screen>, window_id: u32, x: i32, y: i32) {
+    if let Some(wave) = wavedebug.as_ref().filter(|wave| wave.window_id() == window_id) {
+        let (column, row) = wave.pixel_at(x, y);
+        emu.debugger().set_wave_sample(column as u8, (WAVE_HEIGHT - 1 - row) as u8);
+    }
 }
 
 fn main() -> Result<(), Error> {
     let args = Cli::parse();
+    // YAGABOR_LOG=yagabor::mapper=debug,yagabor::ppu=trace prints what the core
+    // reports about those subsystems, only warnings are shown without it
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_env("YAGABOR_LOG").unwrap_or_else(|_| EnvFilter::new("warn")))
+        .with_writer(std::io::stderr)
+        .init();
+
+    let settings_path = args.config.clone().or_else(config::EmulatorConfig::default_path);
+    let mut settings = match &settings_path {
+        Some(path) => config::EmulatorConfig::load(path)?,
+        None => config::EmulatorConfig::default(),
+    };
+    if let Some(palette) = &args.palette {
+        settings.palette = palette.clone();
+    }
+    if let Some(quality) = args.audio_quality {
+        settings.audio.enabled = !matches!(quality, AudioQuality::Off);
+        settings.audio.quality = match quality {
+            AudioQuality::Nearest => audio::ResamplerQuality::Nearest,
+            AudioQuality::BandLimited => audio::ResamplerQuality::BandLimited,
+            _ => audio::ResamplerQuality::Linear,
+        };
+    }
+    if let (true, Some(path)) = (args.save_config, &settings_path) {
+        settings.save(path)?;
+        println!("Settings saved to {}", path.display());
+    }
     
-    let cartridge: Option<Cartridge>;
+    let config_path = args.cartridge.as_ref().map(gameconfig::GameConfig::path_for);
+    let battery_path = args.cartridge.as_ref().map(|rom| settings.battery_path_for(rom));
+    let mut config = match &config_path {
+        Some(path) => gameconfig::GameConfig::load(path)?,
+        None => gameconfig::GameConfig::default(),
+    };
+    if let Some(offset) = args.audio_offset {
+        config.audio_offset_ms = offset;
+    }
 
-    if let Some(c) = args.cartridge {
-        cartridge = Some(Cartridge::new(c)?);
+    let mut cartridge: Option<Cartridge>;
+    // GBS music rips play on a cartridge made for them, PageUp and PageDown change the song
+    let mut gbs = None;
+
+    if let Some(c) = args.cartridge.clone().filter(|path| path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("gbs"))) {
+        let file = music::GbsFile::load(&c)?;
+        println!("Playing {} by {}, {} songs", file.title, file.author, file.song_count);
+        cartridge = Some(file.cartridge()?);
+        gbs = Some(file);
+    } else if let Some(c) = args.cartridge {
+        cartridge = Some(match args.patch {
+            Some(patch) => Cartridge::with_patch(c, patch)?,
+            None => Cartridge::new(c)?,
+        });
         println!("Loading cartridge {} with type {:?}", 
                 cartridge.as_ref().unwrap().title(), 
                 cartridge.as_ref().unwrap().ctype());
+        let info = cartridge.as_ref().unwrap().rom_info();
+        println!("CRC32 {:08X}, SHA-1 {}, dump {:?}", info.crc32, info.sha1_hex(), info.status);
+        for issue in cartridge.as_ref().unwrap().compatibility_issues() {
+            println!("Warning: this game {}", issue);
+        }
+        if let Err(error @ (CartridgeError::BadLogo | CartridgeError::BadChecksum { .. })) = cartridge.as_ref().unwrap().verify_header() {
+            println!("Warning: {}, the boot ROM locks up on it", error);
+        }
     }else {
+        println!("No cartridge, running the boot ROM only");
         cartridge = None;
     }
+    if let (Some(cartridge), Some(path)) = (&mut cartridge, &battery_path) {
+        cartridge.set_clock_mode(settings.clock);
+        cartridge.load_battery(path)?;
+    }
+    if let (Some(cartridge), Some(source)) = (&mut cartridge, &args.camera) {
+        cartridge.set_image_source(camera_source(source)?);
+    }
     
     // let gui: bool;
     // if let Some(val) = args.gui {
@@ -39,6 +326,50 @@ fn main() -> Result<(), Error> {
     // }
 
     let mut emu = Emulation::new(cartridge);
+    settings.apply(&mut emu)?;
+    let mut song = gbs.as_ref().map_or(0, |gbs| gbs.first_song);
+    if let Some(gbs) = &gbs {
+        gbs.start_song(&mut emu, song)?;
+    }
+    if args.record_vgm.is_some() {
+        emu.start_vgm_log();
+    }
+    let serial_console = args.serial_console.then(SerialConsole::with_echo);
+    if let Some(console) = &serial_console {
+        emu.connect_serial(Box::new(console.clone()));
+    }
+    if args.warn_unknown_io {
+        emu.set_unknown_io_policy(UnknownIoPolicy::LogOnce);
+    }
+    if args.diagnostics {
+        emu.set_observer(Box::new(DiagnosticPrinter::default()));
+    }
+    emu.set_developer_mode(args.developer);
+    for code in &args.cheats {
+        emu.add_cheat(code)?;
+    }
+    if let Some(path) = &config_path {
+        if let Some(replacements) = config.tile_replacements(path)? {
+            println!("Replacing {} tiles", replacements.len());
+            emu.set_tile_replacements(Some(replacements));
+        }
+    }
+    if args.record_movie.is_some() {
+        emu.start_movie_recording();
+    }
+    if let Some(path) = &args.play_movie {
+        emu.play_movie(movie::Movie::load(path)?)?;
+    }
+    let mut netplay = match &args.netplay_peer {
+        Some(peer) => Some(netplay::NetplaySession::new(Box::new(netplay::UdpTransport::connect(&args.netplay_bind, peer)?), args.input_delay)),
+        None => None,
+    };
+    // With netplay the buttons go through the session
+    let mut buttons = 0;
+    let mut script = args.script.as_ref().map(scripting::Script::load).transpose()?;
+    if let Some(script) = &mut script {
+        script.start(&mut emu)?;
+    }
 
     let sdl_context = sdl2::init().unwrap();
     let mut event_pump = sdl_context.event_pump().unwrap();
@@ -46,86 +377,355 @@ fn main() -> Result<(), Error> {
     // Interaction with hosting machine: screen, keyboard input, ...    
     let video = sdl_context.video().unwrap();
 
+    let mut audio_output = if settings.audio.enabled {
+        let spec = AudioSpecDesired { freq: Some(settings.audio.sample_rate as i32), channels: Some(2), samples: None };
+        let mut sample_rate = settings.audio.sample_rate;
+        let (producer, consumer) = audio::ring_buffer(2 * sample_rate as usize * AUDIO_BUFFER_SECONDS);
+        // The sound card may run at another rate than the one asked for
+        let device = sdl_context.audio().unwrap().open_playback(None, &spec, |obtained| {
+            sample_rate = obtained.freq as u32;
+            Playback(consumer)
+        }).unwrap();
+        device.resume();
+        emu.set_high_pass_filter(settings.audio.high_pass);
+        emu.enable_audio(sample_rate, settings.audio.quality);
+        Some((device, producer, sample_rate))
+    } else {
+        None
+    };
+
+    let pipeline = display::DisplayPipeline {
+        palette: settings.color_palette()?,
+        scaler: match args.scaler {
+            ScalerOption::None => display::Scaler::Nearest(4),
+            ScalerOption::Scale2x => display::Scaler::Scale2x,
+            ScalerOption::Scale3x => display::Scaler::Scale3x,
+        },
+        filter: match args.filter {
+            FilterOption::None => display::PostFilter::None,
+            FilterOption::Scanlines => display::PostFilter::Scanlines,
+            FilterOption::LcdGrid => display::PostFilter::LcdGrid,
+        },
+    };
+
     let mut screen = Screen::new(&video, "Game Boy", SCREEN_WIDTH, SCREEN_HEIGHT, 4, 0);    
     let mut tddebug = Screen::new(&video, "Tile data", TILEDATA_WIDTH, TILEDATA_HEIGHT, 2, 500);
     let mut bgdebug = Screen::new(&video, "Background", BACKGROUND_WIDTH, BACKGROUND_HEIGHT, 2, 900);
+    let mut wavedebug = args.wave_editor.then(|| Screen::new(&video, "Wave RAM", WAVE_WIDTH, WAVE_HEIGHT, 8, -500));
+    let mut scopedebug = args.oscilloscope.then(|| Screen::new(&video, "Oscilloscope", SCOPE_WIDTH, SCOPE_CHANNEL_HEIGHT * 4, 2, -900));
+    if args.oscilloscope {
+        emu.start_channel_taps(SCOPE_SAMPLE_RATE);
+    }
+    let mut eventdebug = args.event_viewer.then(|| Screen::new(&video, "Events", iolog::TIMELINE_WIDTH, iolog::TIMELINE_HEIGHT, 2, -1300));
+    if args.event_viewer {
+        emu.start_event_log(2);
+    }
     
+    let mut clock = clock::Clock::new();
+    clock.set_speed(if args.speed > 0.0 { clock::Speed::Multiplier(args.speed) } else { clock::Speed::Uncapped });
+    clock.set_frame_skip(args.frame_skip);
+
+    let mut avsync = avsync::AvSync::new(config.audio_offset_ms);
+
     let mut execution_time = Duration::from_secs(0);
     let mut displayed_frames = 0;
 
+    emu.set_lcd_off_display(match args.lcd_off {
+        LcdOffOption::White => LcdOffDisplay::White,
+        LcdOffOption::LastFrame => LcdOffDisplay::LastFrame,
+        LcdOffOption::Dimmed => LcdOffDisplay::Dimmed,
+    });
     emu.start();
 
     let mut result_message: String = String::from("");
 
+    // Key names the SDL doesn't know are left unbound
+    let mut input_map = settings.input_map()?;
+    for input in input_map.inputs() {
+        if let inputmap::HostInput::Key(name) = input {
+            if Keycode::from_name(name).is_none() {
+                println!("Unknown key {}", name);
+            }
+        }
+    }
+    // Netplay sends the buttons to the peer, the macros would bypass it
+    let input_macros = if netplay.is_none() { settings.input_macros()? } else { Vec::new() };
+    // SDL reports the gamepads plugged in at start as added too, they stay open while kept here
+    let controller_subsystem = sdl_context.game_controller().unwrap();
+    let mut controllers = Vec::new();
+
+    // Slot used by the quick save and load keys
+    let mut slot: u8 = 0;
+    // Set by the frame advance key, runs one frame while paused
+    let mut advance = false;
+    // F4 draws the outlines of the sprites over the game
+    let mut sprite_boxes = false;
+
     'running: loop {
 
         for event in event_pump.poll_iter() {
+            let macro_input = match &event {
+                Event::KeyDown { keycode: Some(key), repeat: false, .. } => Some(inputmap::HostInput::key(&key.name())),
+                Event::ControllerButtonDown { button, .. } => Some(inputmap::HostInput::gamepad_button(&button.string())),
+                _ => None,
+            };
+            if let Some((_, input_macro)) = input_macros.iter().find(|(input, _)| Some(input) == macro_input.as_ref()) {
+                emu.play_macro(input_macro.clone());
+                continue;
+            }
             match event {
                 Event::Quit {..} => break 'running,
+                Event::KeyDown { keycode: Some(key), .. } if input_map.is_bound(&inputmap::HostInput::key(&key.name())) => {
+                    input_map.set(inputmap::HostInput::key(&key.name()), true)
+                },
+                Event::KeyUp { keycode: Some(key), .. } if input_map.is_bound(&inputmap::HostInput::key(&key.name())) => {
+                    input_map.set(inputmap::HostInput::key(&key.name()), false)
+                },
+                Event::ControllerButtonDown { button, .. } => input_map.set(inputmap::HostInput::gamepad_button(&button.string()), true),
+                Event::ControllerButtonUp { button, .. } => input_map.set(inputmap::HostInput::gamepad_button(&button.string()), false),
+                Event::ControllerAxisMotion { axis, value, .. } => input_map.set_axis(&axis.string(), value),
+                Event::ControllerDeviceAdded { which, .. } => {
+                    if let Ok(controller) = controller_subsystem.open(which) {
+                        println!("Gamepad {} connected", controller.name());
+                        controllers.push(controller);
+                    }
+                },
+                Event::KeyDown { keycode: Some(key), keymod, .. } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) && audio_channel(key).is_some() => {
+                    if let Some(channel) = audio_channel(key) {
+                        emu.set_channel_muted(channel, !emu.is_channel_muted(channel));
+                    }
+                },
+                Event::KeyDown { keycode: Some(key), keymod, .. } if keymod.intersects(Mod::LALTMOD | Mod::RALTMOD) && (key == Keycode::Num0 || audio_channel(key).is_some()) => {
+                    emu.solo_channel(audio_channel(key));
+                },
                 Event::KeyDown { keycode, .. } => {
                     match keycode {
                         Some(Keycode::Escape)   => { 
                             result_message = format!("User terminated emulation."); 
                             break 'running 
                         },
-                        Some(Keycode::A)        => emu.button_pressed(Button::A),
-                        Some(Keycode::S)        => emu.button_pressed(Button::B),
-                        Some(Keycode::Return)   => emu.button_pressed(Button::Start),
-                        Some(Keycode::Space)    => emu.button_pressed(Button::Select),
-                        Some(Keycode::Up)       => emu.button_pressed(Button::Up),
-                        Some(Keycode::Down)     => emu.button_pressed(Button::Down),
-                        Some(Keycode::Left)     => emu.button_pressed(Button::Left),
-                        Some(Keycode::Right)    => emu.button_pressed(Button::Right),
+                        Some(Keycode::Tab)      => clock.set_fast_forward(true),
+                        Some(Keycode::Minus)    => avsync.set_offset_ms(avsync.offset_ms() - AUDIO_OFFSET_STEP),
+                        Some(Keycode::Equals)   => avsync.set_offset_ms(avsync.offset_ms() + AUDIO_OFFSET_STEP),
+                        Some(Keycode::F12)      => {
+                            let view = if args.dedup_flicker { screenshot::View::StableScreen } else { screenshot::View::Screen };
+                            if let Err(error) = emu.export_view(view, "screenshot.png", &pipeline.palette) {
+                                println!("Screenshot failed: {:?}", error);
+                            }
+                        },
+                        Some(Keycode::F4)       => sprite_boxes = !sprite_boxes,
+                        Some(Keycode::F9)       => {
+                            let options = screenshot::SheetOptions { scale: 4, grid: true };
+                            if let Err(error) = emu.export_sheets("sheets", &pipeline.palette, options) {
+                                println!("Tile export failed: {:?}", error);
+                            }
+                        },
+                        Some(Keycode::F11)      => {
+                            let result = if emu.is_recording() {
+                                emu.stop_recording()
+                            } else {
+                                emu.start_recording("recording.gif", recorder::RecordingOptions { dedup_flicker: args.dedup_flicker, ..Default::default() })
+                            };
+                            if let Err(error) = result {
+                                println!("Recording failed: {:?}", error);
+                            }
+                        },
+                        Some(Keycode::F5)       => {
+                            match emu.save_slot(slot) {
+                                Ok(()) => println!("Saved slot {}", slot),
+                                Err(error) => println!("Saving slot {} failed: {}", slot, error),
+                            }
+                        },
+                        // Both sides of a netplay session must run the same frames
+                        Some(Keycode::F8) if netplay.is_none() => {
+                            match emu.load_slot(slot) {
+                                Ok(()) => println!("Loaded slot {}", slot),
+                                Err(error) => println!("Loading slot {} failed: {}", slot, error),
+                            }
+                        },
+                        Some(Keycode::F6)       => {
+                            slot = (slot + savestate::SLOT_COUNT - 1) % savestate::SLOT_COUNT;
+                            println!("Slot {}", slot);
+                        },
+                        Some(Keycode::F7)       => {
+                            slot = (slot + 1) % savestate::SLOT_COUNT;
+                            println!("Slot {}", slot);
+                        },
+                        // Both sides of a netplay session must run the same frames
+                        Some(Keycode::Pause) if netplay.is_none() => {
+                            if emu.is_paused() { emu.resume() } else { emu.pause() }
+                        },
+                        Some(Keycode::Backslash) if netplay.is_none() => advance = true,
+                        Some(key @ (Keycode::PageUp | Keycode::PageDown)) if gbs.is_some() => {
+                            if let Some(gbs) = &gbs {
+                                song = match key {
+                                    Keycode::PageUp => (song + 1) % gbs.song_count,
+                                    _ => song.checked_sub(1).unwrap_or(gbs.song_count - 1),
+                                };
+                                gbs.start_song(&mut emu, song)?;
+                                println!("Song {} of {}", song + 1, gbs.song_count);
+                            }
+                        },
+                        Some(Keycode::F2)       => emu.reset(ResetKind::Soft),
+                        Some(Keycode::F3)       => emu.reset(ResetKind::Hard),
+                        Some(Keycode::F10)      => {
+                            match emu.stop_io_log() {
+                                Some(log) => {
+                                    if let Err(error) = log.save_csv("iolog.csv", 0..=u64::MAX) {
+                                        println!("Saving the IO log failed: {:?}", error);
+                                    }
+                                },
+                                None => emu.start_io_log(),
+                            }
+                        },
                         _                       => {},
                     }
                     
                 },
-                Event::KeyUp { keycode, .. } => {
-                    match keycode {
-                        Some(Keycode::A)        => emu.button_released(Button::A),
-                        Some(Keycode::S)        => emu.button_released(Button::B),
-                        Some(Keycode::Return)   => emu.button_released(Button::Start),
-                        Some(Keycode::Space)    => emu.button_released(Button::Select),
-                        Some(Keycode::Up)       => emu.button_released(Button::Up),
-                        Some(Keycode::Down)     => emu.button_released(Button::Down),
-                        Some(Keycode::Left)     => emu.button_released(Button::Left),
-                        Some(Keycode::Right)    => emu.button_released(Button::Right),
-                        _                       => {},
-                    }
-                    
+                Event::MouseButtonDown { window_id, x, y, .. } => edit_wave(&mut emu, &wavedebug, window_id, x, y),
+                Event::MouseMotion { window_id, x, y, mousestate, .. } if mousestate.left() => {
+                    edit_wave(&mut emu, &wavedebug, window_id, x, y)
                 },
+                Event::KeyUp { keycode: Some(Keycode::Tab), .. } => clock.set_fast_forward(false),
                 _ => {}
             }
         }  
 
-        if emu.running {
+        // Controls are read when a frame runs, so a frame advance takes the buttons held then
+        if !emu.is_paused() || advance {
+            for event in input_map.update() {
+                match event {
+                    InputEvent::Pressed(button) => set_button(&mut emu, netplay.is_some(), &mut buttons, button, true),
+                    InputEvent::Released(button) => set_button(&mut emu, netplay.is_some(), &mut buttons, button, false),
+                    _ => {},
+                }
+            }
+        }
+
+        if emu.running && (!emu.is_paused() || advance) {
             let now = Instant::now();
+            let cycles_before = emu.total_cycles;
             // Emulation step
-            match emu.step() {
-                Ok(emustep) => {
-                    screen.render(emustep.framebuffer);
+            let mut result = if let Some(session) = &mut netplay {
+                match session.advance_frame(&mut emu, buttons) {
+                    Ok(Some(emustep)) => {
+                        if let Some(frame) = avsync.present_frame(emustep.framebuffer) {
+                            screen.render_rgba(&pipeline.render(&frame));
+                        }
+                        Ok(())
+                    },
+                    // Waiting for the peer
+                    Ok(None) => Ok(()),
+                    Err(error) => {
+                        result_message = format!("Netplay stopped: {}", error);
+                        break 'running
+                    },
+                }
+            } else if clock.should_render() || advance {
+                let step = if mem::take(&mut advance) { emu.frame_advance() } else { emu.step() };
+                step.map(|emustep| {
+                    if let Some(frame) = avsync.present_frame(emustep.framebuffer) {
+                        let mut image = pipeline.render(&frame);
+                        // The script draws after the frame, so its overlay goes over the next one
+                        if let Some(script) = &script {
+                            script.overlay().draw(&mut image);
+                        }
+                        if sprite_boxes {
+                            image.blend(&emu.debugger().sprite_overlay());
+                        }
+                        screen.render_rgba(&image);
+                    }
                     tddebug.render(emustep.tiledata);  
                     bgdebug.render(emustep.background);            
-                },
-                Err(error) => {
-                    result_message = format!("{:?}", error);
-                    break 'running
+                    if let Some(wave) = &mut wavedebug {
+                        let debugger = emu.debugger();
+                        wave.render(wave_frame(&debugger.wave_samples(), debugger.wave_position()));
+                    }
+                    if let Some(scope) = &mut scopedebug {
+                        let taps = emu.take_channel_taps();
+                        scope.render(scope_frame(&emu, &taps));
+                    }
+                    if let (Some(events), Some(log)) = (&mut eventdebug, emu.io_log()) {
+                        events.render(log.timeline(log.frame().saturating_sub(1)));
+                    }
+                })
+            } else {
+                emu.skip_frame()
+            };
+            if let Some(script) = &mut script {
+                let handled = match &result {
+                    Ok(()) => script.on_frame(&mut emu).map(|_| true),
+                    Err(EmulationError::Breakpoint { address }) => script.on_breakpoint(&mut emu, *address),
+                    Err(_) => Ok(false),
+                };
+                match handled {
+                    Ok(true) => result = Ok(()),
+                    Ok(false) => {},
+                    Err(error) => {
+                        result_message = format!("Script error: {}", error);
+                        break 'running
+                    },
                 }
             }
-            let elapsed_processing = now.elapsed();
-            let time_to_sleep = FRAME_TIME - elapsed_processing.as_millis();
+            if args.developer {
+                print!("{}", emu.take_debug_output());
+                let _ = std::io::Write::flush(&mut std::io::stdout());
+                if let Err(EmulationError::Breakpoint { address }) = result {
+                    println!("Breakpoint at {:04X}, paused", address);
+                    emu.pause();
+                    result = Ok(());
+                }
+            }
+            if let Err(error) = result {
+                result_message = format!("{}", error);
+                break 'running
+            }
 
-            if elapsed_processing.as_millis() < FRAME_TIME {
-                spin_sleep::sleep(Duration::from_millis(time_to_sleep as u64));
-            }            
+            // Sound is only played at normal speed
+            let samples = emu.take_audio();
+            if let Some((_, producer, sample_rate)) = &mut audio_output {
+                if clock.speed() == clock::Speed::Multiplier(1.0) {
+                    producer.push(&avsync.present_audio(samples, *sample_rate));
+                }
+                // The silence of the offset doesn't count as latency
+                let queued = producer.len().saturating_sub(avsync.audio_delay() * 2);
+                let target = 2 * *sample_rate as usize * TARGET_LATENCY_MS / 1000;
+                emu.adjust_audio_rate(queued as f32 / (2 * target) as f32);
+            }
+
+            spin_sleep::sleep(clock.frame_done(emu.total_cycles - cycles_before));
 
             let elapsed = now.elapsed();
             execution_time += elapsed;
             displayed_frames += 1;
+        } else {
+            // Events are still handled while paused
+            spin_sleep::sleep(PAUSED_POLL_INTERVAL);
         }
     
     }
 
+    emu.stop_recording()?;
+    if let Some(path) = &battery_path {
+        emu.save_battery(path)?;
+    }
+    if let (Some(path), Some(movie)) = (&args.record_movie, emu.stop_movie()) {
+        movie.save(path)?;
+    }
+    if let (Some(path), Some(log)) = (&args.record_vgm, emu.stop_vgm_log()) {
+        log.save(path)?;
+    }
+    if let Some(console) = &serial_console {
+        console.flush_echo();
+    }
+
+    if let Some(path) = config_path {
+        if avsync.offset_ms() != config.audio_offset_ms || args.audio_offset.is_some() {
+            config.audio_offset_ms = avsync.offset_ms();
+            config.save(path)?;
+        }
+    }
+
     println!("Emulation terminated in {} seconds, total executed cycles: {} and {} frames. Reason: {}", execution_time.as_secs_f32() , emu.total_cycles, displayed_frames, result_message );
     
     Ok(())
@@ -1,10 +1,11 @@
-use gameboy::{ColoredPixel, GameBoyFrame};
-use sdl2::{VideoSubsystem, render::Canvas, video::{Window, WindowPos}, pixels::Color, rect::Point};
+use gameboy::{ColoredPixel, GameBoyFrame, display::RgbaImage};
+use sdl2::{VideoSubsystem, render::Canvas, video::{Window, WindowPos}, pixels::{Color, PixelFormatEnum}, rect::Point};
 
 pub struct Screen {
     canvas: Canvas<Window>,
     width: u32,
     height: u32,
+    scale: u32,
 }
 
 fn color_from_pixel(pixel: ColoredPixel) -> Color {
@@ -39,7 +40,18 @@ impl Screen {
         canvas.set_scale(scale as f32, scale as f32).unwrap();
         canvas.clear();
 
-        Screen { canvas, width, height }
+        Screen { canvas, width, height, scale }
+    }
+
+    pub(crate) fn window_id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+
+    // Converts a mouse position in the window to a pixel of the frame
+    pub(crate) fn pixel_at(&self, x: i32, y: i32) -> (u32, u32) {
+        let x = (x.max(0) as u32 / self.scale).min(self.width - 1);
+        let y = (y.max(0) as u32 / self.scale).min(self.height - 1);
+        (x, y)
     }
 
     pub(crate) fn render(&mut self, frame: GameBoyFrame) {
@@ -56,4 +68,15 @@ impl Screen {
                 
         self.canvas.present();
     }
-}
\ No newline at end of file
+
+    // Stretches the image over the whole window
+    pub(crate) fn render_rgba(&mut self, image: &RgbaImage) {
+        let creator = self.canvas.texture_creator();
+        let mut texture = creator.create_texture_streaming(PixelFormatEnum::RGBA32, image.width, image.height).unwrap();
+        texture.update(None, &image.pixels, (image.width * 4) as usize).unwrap();
+
+        self.canvas.clear();
+        self.canvas.copy(&texture, None, None).unwrap();
+        self.canvas.present();
+    }
+}
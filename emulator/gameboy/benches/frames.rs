@@ -0,0 +1,73 @@
+use std::{fs, path::PathBuf};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use gameboy::{Emulation, cartridge::Cartridge};
+
+// Long enough to get past the boot ROM and into the benchmark loop
+const FRAMES: u64 = 300;
+const CODE_ADDR: usize = 0x150;
+
+// ALU, CB and stack opcodes in a loop with the LCD off, for the decoder
+const CPU_LOOP: &[u8] = &[
+    0xF3, 0x31, 0xFE, 0xFF,             // di, ld sp,$FFFE
+    0xAF, 0xE0, 0x40,                   // LCD off
+    0x78, 0x81, 0x4F, 0x04, 0xCB, 0x01, // ld a,b; add c; ld c,a; inc b; rlc c
+    0xC5, 0xD1, 0x19, 0x18, 0xF5,       // push bc; pop de; add hl,de; jr loop
+];
+
+// Background, window and 40 sprites on screen while the CPU only scrolls, for the renderer
+const PPU_SCENE: &[u8] = &[
+    0xF3, 0x31, 0xFE, 0xFF,                         // di, ld sp,$FFFE
+    0xF0, 0x44, 0xFE, 0x90, 0x38, 0xFA, 0xAF, 0xE0, 0x40, // LCD off in VBlank
+    0x21, 0x00, 0xFE, 0x06, 0xA0,                   // every OAM byte is the low byte of its address
+    0x7D, 0x22, 0x05, 0x20, 0xFB,
+    0x21, 0x00, 0x80,                               // same for the tiles and both maps
+    0x7D, 0x22, 0x7C, 0xFE, 0x98, 0x20, 0xF9,
+    0x7D, 0x22, 0x7C, 0xFE, 0xA0, 0x20, 0xF9,
+    0x3E, 0xE4, 0xE0, 0x47, 0xE0, 0x48, 0xE0, 0x49, // BGP, OBP0, OBP1
+    0x3E, 0x07, 0xE0, 0x4B, 0x3E, 0x48, 0xE0, 0x4A, // window from the middle of the screen
+    0x3E, 0xF3, 0xE0, 0x40,                         // LCD, background, window and sprites on
+    0xF0, 0x43, 0x3C, 0xE0, 0x43, 0x18, 0xF9,       // SCX += 1 forever
+];
+
+fn bench_rom_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../assets/bench/bench.gb")
+}
+
+fn bench_rom() -> Cartridge {
+    Cartridge::new(bench_rom_path()).expect("benchmark ROM missing")
+}
+
+// The header of the benchmark ROM passes the boot ROM checks, only the program is replaced
+fn workload_rom(name: &str, program: &[u8]) -> PathBuf {
+    let mut rom = fs::read(bench_rom_path()).expect("benchmark ROM missing");
+    rom[CODE_ADDR..CODE_ADDR + program.len()].copy_from_slice(program);
+    let path = std::env::temp_dir().join(format!("yagabor-{}-{}.gb", name, std::process::id()));
+    fs::write(&path, rom).unwrap();
+    path
+}
+
+fn run(cartridge: Cartridge) {
+    let mut emu = Emulation::new(Some(cartridge));
+    emu.start();
+    emu.run_frames(FRAMES).unwrap();
+}
+
+fn frames(c: &mut Criterion) {
+    c.bench_function("bench.gb 300 frames", |b| b.iter(|| run(bench_rom())));
+}
+
+fn cpu_heavy(c: &mut Criterion) {
+    let path = workload_rom("cpu", CPU_LOOP);
+    c.bench_function("CPU loop 300 frames", |b| b.iter(|| run(Cartridge::new(path.clone()).unwrap())));
+    let _ = fs::remove_file(path);
+}
+
+fn ppu_heavy(c: &mut Criterion) {
+    let path = workload_rom("ppu", PPU_SCENE);
+    c.bench_function("PPU scene 300 frames", |b| b.iter(|| run(Cartridge::new(path.clone()).unwrap())));
+    let _ = fs::remove_file(path);
+}
+
+criterion_group!(benches, frames, cpu_heavy, ppu_heavy);
+criterion_main!(benches);
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Enough instructions to reach interrupts, timers and a few scanlines
+const MAX_INSTRUCTIONS: usize = 10_000;
+
+fuzz_target!(|program: &[u8]| {
+    gameboy::fuzz::execute_opcodes(program, MAX_INSTRUCTIONS);
+});
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+use crate::gameboy::GameBoy;
+
+// Presets trading speed for hardware quirks. The renderer draws whole scanlines
+// and the CPU runs whole instructions in every profile, the profiles only turn
+// on the quirks emulated on top of that.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccuracyProfile {
+    // No quirks, for slow hosts and games that don't need them
+    Fast,
+    // What most games expect, the default
+    #[default]
+    Balanced,
+    // Everything the test ROMs check
+    Accurate,
+}
+
+// The accuracy options a GameBoy is built with. They can still be changed one
+// by one through Emulation and survive resets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EmulationConfig {
+    // VRAM and OAM locked while the PPU uses them, see Emulation::set_vram_oam_blocking
+    pub vram_oam_blocking: bool,
+    // DMG OAM corruption on 16-bit increments, see Emulation::set_oam_bug
+    pub oam_bug: bool,
+    // Volume changes on NRx2 writes while a channel plays, see Emulation::set_zombie_mode
+    pub zombie_mode: bool,
+}
+
+impl EmulationConfig {
+    pub fn profile(profile: AccuracyProfile) -> Self {
+        match profile {
+            AccuracyProfile::Fast => EmulationConfig { vram_oam_blocking: false, oam_bug: false, zombie_mode: false },
+            AccuracyProfile::Balanced => EmulationConfig { vram_oam_blocking: false, oam_bug: false, zombie_mode: true },
+            AccuracyProfile::Accurate => EmulationConfig { vram_oam_blocking: true, oam_bug: true, zombie_mode: true },
+        }
+    }
+
+    pub(crate) fn of(gb: &GameBoy) -> Self {
+        EmulationConfig { vram_oam_blocking: gb.io.lcd.block_cpu_access, oam_bug: gb.io.lcd.oam_bug, zombie_mode: gb.io.apu.zombie_mode }
+    }
+
+    pub(crate) fn apply(&self, gb: &mut GameBoy) {
+        gb.io.lcd.block_cpu_access = self.vram_oam_blocking;
+        gb.io.lcd.oam_bug = self.oam_bug;
+        gb.io.apu.zombie_mode = self.zombie_mode;
+    }
+}
+
+impl Default for EmulationConfig {
+    fn default() -> Self {
+        EmulationConfig::profile(AccuracyProfile::default())
+    }
+}
+
+impl From<AccuracyProfile> for EmulationConfig {
+    fn from(profile: AccuracyProfile) -> Self {
+        EmulationConfig::profile(profile)
+    }
+}
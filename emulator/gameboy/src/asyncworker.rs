@@ -0,0 +1,119 @@
+use std::{io::Error, sync::mpsc::{channel, Receiver, Sender}, thread};
+
+use futures_channel::{mpsc, oneshot};
+
+use crate::{savestate::SaveState, Emulation, EmulationError, EmulationStep, InputEvent};
+
+// Frames and sound chunks kept for a slow reader, newer ones are dropped past that
+const OUTPUT_QUEUE: usize = 8;
+
+enum Request {
+    RunFrames(u64, oneshot::Sender<Result<(), EmulationError>>),
+    Input(InputEvent),
+    SaveState(oneshot::Sender<SaveState>),
+    LoadState(SaveState, oneshot::Sender<Result<(), Error>>),
+    Stop(oneshot::Sender<Emulation>),
+}
+
+// What an AsyncEmulator produces, as streams usable from any executor
+pub struct AsyncOutputs {
+    pub frames: mpsc::Receiver<EmulationStep>,
+    // The samples of every frame, audio has to be enabled on the emulation
+    pub audio: mpsc::Receiver<Vec<f32>>,
+}
+
+// Runs an Emulation on its own thread for async code like bots and streaming
+// servers: frames only run when awaited, so the caller sets the pace, and the
+// executor never waits on the emulation. Dropping it ends the thread.
+pub struct AsyncEmulator {
+    requests: Sender<Request>,
+}
+
+impl AsyncEmulator {
+    pub fn spawn(emulation: Emulation) -> (Self, AsyncOutputs) {
+        let (requests, receiver) = channel();
+        let (frame_sender, frames) = mpsc::channel(OUTPUT_QUEUE);
+        let (audio_sender, audio) = mpsc::channel(OUTPUT_QUEUE);
+        thread::spawn(move || run(emulation, receiver, frame_sender, audio_sender));
+        (AsyncEmulator { requests }, AsyncOutputs { frames, audio })
+    }
+
+    // Resolves once the frame went to the outputs
+    pub async fn run_frame(&self) -> Result<(), EmulationError> {
+        self.run_frames(1).await
+    }
+
+    // Stops at the first frame that fails
+    pub async fn run_frames(&self, frames: u64) -> Result<(), EmulationError> {
+        let (reply, result) = oneshot::channel();
+        self.send(Request::RunFrames(frames, reply));
+        result.await.expect("emulation thread panicked")
+    }
+
+    // Reaches the joypad before the next frame
+    pub fn send_input(&self, input: InputEvent) {
+        self.send(Request::Input(input));
+    }
+
+    pub async fn save_state(&self) -> SaveState {
+        let (reply, state) = oneshot::channel();
+        self.send(Request::SaveState(reply));
+        state.await.expect("emulation thread panicked")
+    }
+
+    pub async fn load_state(&self, state: SaveState) -> Result<(), Error> {
+        let (reply, result) = oneshot::channel();
+        self.send(Request::LoadState(state, reply));
+        result.await.expect("emulation thread panicked")
+    }
+
+    // Gives the emulation back after the frames already asked for
+    pub async fn stop(self) -> Emulation {
+        let (reply, emulation) = oneshot::channel();
+        self.send(Request::Stop(reply));
+        emulation.await.expect("emulation thread panicked")
+    }
+
+    fn send(&self, request: Request) {
+        // The thread only goes away with a panic, reported by the reply
+        let _ = self.requests.send(request);
+    }
+}
+
+fn run(mut emu: Emulation, requests: Receiver<Request>, mut frames: mpsc::Sender<EmulationStep>, mut audio: mpsc::Sender<Vec<f32>>) {
+    while let Ok(request) = requests.recv() {
+        match request {
+            Request::RunFrames(count, reply) => {
+                let mut result = Ok(());
+                for _ in 0..count {
+                    match emu.step() {
+                        // A full or closed output loses the frame, the emulation goes on
+                        Ok(step) => {
+                            let _ = frames.try_send(step);
+                        },
+                        Err(error) => {
+                            result = Err(error);
+                            break;
+                        },
+                    }
+                    let samples = emu.take_audio();
+                    if !samples.is_empty() {
+                        let _ = audio.try_send(samples);
+                    }
+                }
+                let _ = reply.send(result);
+            },
+            Request::Input(input) => emu.send_input(input),
+            Request::SaveState(reply) => {
+                let _ = reply.send(emu.save_state());
+            },
+            Request::LoadState(state, reply) => {
+                let _ = reply.send(emu.load_state(&state));
+            },
+            Request::Stop(reply) => {
+                let _ = reply.send(emu);
+                return;
+            },
+        }
+    }
+}
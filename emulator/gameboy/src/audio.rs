@@ -0,0 +1,369 @@
+use std::{f64::consts::PI, sync::{Arc, atomic::{AtomicU32, AtomicUsize, Ordering}}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::CPU_CLOCK_HZ;
+
+// Machine cycles are the natural sample rate of the APU, the linear
+// resampler interpolates between them
+const CYCLES_PER_SAMPLE: f64 = 4.0;
+
+// Band-limited steps are spread over this many output samples, and their
+// kernel is precomputed for this many positions between two samples
+const BLIP_TAPS: usize = 16;
+const BLIP_PHASES: usize = 32;
+// Cutoff relative to the Nyquist frequency of the output rate
+const BLIP_CUTOFF: f64 = 0.9;
+
+// Largest change of the resampling ratio made by the rate control, small enough
+// for the pitch change to go unnoticed
+const MAX_RATE_DELTA: f64 = 0.005;
+
+// How the APU output is converted from the Game Boy clock to the host sample rate
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResamplerQuality {
+    // Picks the closest sample, the cheapest but high notes alias
+    Nearest,
+    // Interpolates between machine cycles
+    #[default]
+    Linear,
+    // Adds every amplitude change as a band-limited step, like blip_buf.
+    // Costs the most but keeps the high-pitched pulse channels clean.
+    BandLimited,
+}
+
+// Every sound output goes through a capacitor that removes the DC offset of
+// the DACs, so silence sits at 0 and channels turning on or off don't pop.
+// The CGB one lets the offset go faster.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HighPassFilter {
+    // The mixer output as it is, offset included
+    Off,
+    #[default]
+    Dmg,
+    Cgb,
+}
+
+impl HighPassFilter {
+    // The part of its charge the capacitor keeps every clock cycle
+    // https://gbdev.io/pandocs/Audio_details.html#obscure-behavior
+    fn charge_factor(self) -> Option<f64> {
+        match self {
+            HighPassFilter::Off => None,
+            HighPassFilter::Dmg => Some(0.999958),
+            HighPassFilter::Cgb => Some(0.998943),
+        }
+    }
+}
+
+// The four sound generators of the APU, in register order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AudioChannel {
+    Pulse1, Pulse2, Wave, Noise
+}
+
+impl AudioChannel {
+    pub const ALL: [AudioChannel; 4] = [AudioChannel::Pulse1, AudioChannel::Pulse2, AudioChannel::Wave, AudioChannel::Noise];
+}
+
+// The output of every channel on its own, sampled at a fixed rate for
+// oscilloscope views. Muting a channel doesn't change what it taps.
+pub(crate) struct ChannelTaps {
+    // Clock cycles per sample
+    period: u32,
+    next_sample: u32,
+    // Indexed like AudioChannel::ALL, between -1 and 1
+    samples: [Vec<f32>; 4],
+}
+
+impl ChannelTaps {
+    pub(crate) fn new(sample_rate: u32) -> Self {
+        let period = (CPU_CLOCK_HZ as u32 / sample_rate.max(1)).max(1);
+        ChannelTaps { period, next_sample: period, samples: Default::default() }
+    }
+
+    // Clock cycles per sample
+    pub(crate) fn period(&self) -> u32 {
+        self.period
+    }
+
+    // Cycles until the next sample, the APU splits its ticks there
+    pub(crate) fn next_step(&self) -> u32 {
+        self.next_sample
+    }
+
+    // The levels are those of the cycles that just ran
+    pub(crate) fn push(&mut self, levels: [f32; 4], cycles: u32) {
+        self.next_sample -= cycles;
+        if self.next_sample == 0 {
+            for (samples, level) in self.samples.iter_mut().zip(levels) {
+                samples.push(level);
+            }
+            self.next_sample = self.period;
+        }
+    }
+
+    pub(crate) fn take_samples(&mut self) -> [Vec<f32>; 4] {
+        std::mem::take(&mut self.samples)
+    }
+}
+
+// Converts the level changes of the APU into interleaved stereo samples at the host rate
+pub(crate) struct Resampler {
+    quality: ResamplerQuality,
+    // Clock cycles per output sample, nominal and adjusted by the rate control
+    base_ratio: f64,
+    ratio: f64,
+    // Clock cycles until the next output sample
+    next_sample: f64,
+    previous: [f32; 2],
+    blip: BlipBuffer,
+    // Charge kept from one output sample to the next, None without the filter
+    high_pass: Option<f32>,
+    capacitor: [f32; 2],
+    samples: Vec<f32>,
+}
+
+impl Resampler {
+    pub(crate) fn new(sample_rate: u32, quality: ResamplerQuality, filter: HighPassFilter) -> Self {
+        let ratio = CPU_CLOCK_HZ as f64 / sample_rate as f64;
+        let mut resampler = Resampler {
+            quality,
+            base_ratio: ratio,
+            ratio,
+            next_sample: ratio,
+            previous: [0.0; 2],
+            blip: BlipBuffer::new(quality == ResamplerQuality::BandLimited),
+            high_pass: None,
+            capacitor: [0.0; 2],
+            samples: Vec::new(),
+        };
+        resampler.set_high_pass_filter(filter);
+        resampler
+    }
+
+    pub(crate) fn set_high_pass_filter(&mut self, filter: HighPassFilter) {
+        self.high_pass = filter.charge_factor().map(|factor| factor.powf(self.base_ratio) as f32);
+    }
+
+    // The APU output stays at level for the given clock cycles
+    pub(crate) fn push(&mut self, level: [f32; 2], cycles: u32) {
+        let cycles = cycles as f64;
+        let start = self.samples.len();
+
+        match self.quality {
+            ResamplerQuality::Nearest => {
+                while self.next_sample <= cycles {
+                    self.samples.extend_from_slice(&level);
+                    self.next_sample += self.ratio;
+                }
+            },
+            ResamplerQuality::Linear => {
+                while self.next_sample <= cycles {
+                    let weight = (self.next_sample / CYCLES_PER_SAMPLE).min(1.0) as f32;
+                    self.samples.push(self.previous[0] + (level[0] - self.previous[0]) * weight);
+                    self.samples.push(self.previous[1] + (level[1] - self.previous[1]) * weight);
+                    self.next_sample += self.ratio;
+                }
+            },
+            ResamplerQuality::BandLimited => {
+                if level != self.previous {
+                    let delta = [level[0] - self.previous[0], level[1] - self.previous[1]];
+                    self.blip.add_delta(delta);
+                }
+                self.blip.advance(cycles / self.ratio, &mut self.samples);
+            },
+        }
+
+        self.next_sample -= cycles;
+        self.previous = level;
+
+        if let Some(charge) = self.high_pass {
+            for frame in self.samples[start..].chunks_exact_mut(2) {
+                for (sample, capacitor) in frame.iter_mut().zip(&mut self.capacitor) {
+                    let input = *sample;
+                    *sample = input - *capacitor;
+                    *capacitor = input - *sample * charge;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn take_samples(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.samples)
+    }
+
+    // Samples produced but not taken yet
+    pub(crate) fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    // The samples from the given count on as 16 bit PCM, for the observer
+    pub(crate) fn pcm_since(&self, start: usize) -> Vec<i16> {
+        self.samples[start..].iter().map(|sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).collect()
+    }
+
+    // Dynamic rate control: the host and Game Boy clocks drift apart, so the
+    // output rate is nudged to keep the host buffer half full.
+    // https://docs.libretro.com/development/cores/dynamic-rate-control/
+    pub(crate) fn adjust_rate(&mut self, buffer_fill: f32) {
+        let fill = buffer_fill.clamp(0.0, 1.0) as f64;
+        self.ratio = self.base_ratio * (1.0 + MAX_RATE_DELTA * (2.0 * fill - 1.0));
+    }
+}
+
+// Single producer single consumer queue of samples, the emulation pushes and
+// the audio callback of the host pops without locking
+struct RingBuffer {
+    // f32 bits, atomics only to share the slots between the two threads
+    slots: Box<[AtomicU32]>,
+    // Total samples written and read, the slots are indexed modulo their length
+    written: AtomicUsize,
+    read: AtomicUsize,
+}
+
+pub struct AudioProducer {
+    buffer: Arc<RingBuffer>,
+}
+
+pub struct AudioConsumer {
+    buffer: Arc<RingBuffer>,
+    // Repeated on underruns so the output doesn't click
+    last: [f32; 2],
+}
+
+// Capacity is in interleaved samples, a frame of stereo takes 2
+pub fn ring_buffer(capacity: usize) -> (AudioProducer, AudioConsumer) {
+    let buffer = Arc::new(RingBuffer {
+        slots: (0..capacity).map(|_| AtomicU32::new(0)).collect(),
+        written: AtomicUsize::new(0),
+        read: AtomicUsize::new(0),
+    });
+    (AudioProducer { buffer: buffer.clone() }, AudioConsumer { buffer, last: [0.0; 2] })
+}
+
+impl RingBuffer {
+    fn len(&self) -> usize {
+        self.written.load(Ordering::Acquire).wrapping_sub(self.read.load(Ordering::Acquire))
+    }
+}
+
+impl AudioProducer {
+    // Returns how many samples fit, the rest is dropped
+    pub fn push(&mut self, samples: &[f32]) -> usize {
+        let buffer = &self.buffer;
+        let written = buffer.written.load(Ordering::Relaxed);
+        let free = buffer.slots.len() - buffer.len();
+        // Whole stereo frames only
+        let count = samples.len().min(free) & !1;
+
+        for (offset, sample) in samples[..count].iter().enumerate() {
+            buffer.slots[(written + offset) % buffer.slots.len()].store(sample.to_bits(), Ordering::Relaxed);
+        }
+        buffer.written.store(written.wrapping_add(count), Ordering::Release);
+        count
+    }
+
+    // Interleaved samples waiting to be played
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // From 0 empty to 1 full, what Emulation::adjust_audio_rate expects
+    pub fn fill_level(&self) -> f32 {
+        self.buffer.len() as f32 / self.buffer.slots.len() as f32
+    }
+}
+
+impl AudioConsumer {
+    // Fills the whole output, returns how many samples came from the buffer
+    pub fn pop(&mut self, output: &mut [f32]) -> usize {
+        let buffer = &self.buffer;
+        let read = buffer.read.load(Ordering::Relaxed);
+        let count = output.len().min(buffer.len()) & !1;
+
+        for (offset, sample) in output[..count].iter_mut().enumerate() {
+            *sample = f32::from_bits(buffer.slots[(read + offset) % buffer.slots.len()].load(Ordering::Relaxed));
+        }
+        buffer.read.store(read.wrapping_add(count), Ordering::Release);
+
+        if count >= 2 {
+            self.last = [output[count - 2], output[count - 1]];
+        }
+        for (offset, sample) in output[count..].iter_mut().enumerate() {
+            *sample = self.last[offset % 2];
+        }
+        count
+    }
+}
+
+struct BlipBuffer {
+    kernel: Vec<[f32; BLIP_TAPS]>,
+    // Pending deltas, the first one belongs to the next output sample
+    deltas: Vec<[f32; 2]>,
+    // Position of the current time in output samples, relative to deltas[0]
+    time: f64,
+    // Running sum of the deltas, the output level
+    level: [f32; 2],
+}
+
+impl BlipBuffer {
+    fn new(enabled: bool) -> Self {
+        let kernel = if enabled { (0..=BLIP_PHASES).map(BlipBuffer::step_kernel).collect() } else { Vec::new() };
+        BlipBuffer { kernel, deltas: Vec::new(), time: 0.0, level: [0.0; 2] }
+    }
+
+    // Windowed sinc impulse for a step happening phase / BLIP_PHASES after a sample,
+    // normalized so the step always ends at its full height
+    fn step_kernel(phase: usize) -> [f32; BLIP_TAPS] {
+        let offset = phase as f64 / BLIP_PHASES as f64;
+        let mut taps = [0.0; BLIP_TAPS];
+        for (tap, value) in taps.iter_mut().enumerate() {
+            let x = tap as f64 - (BLIP_TAPS / 2) as f64 + 1.0 - offset;
+            let sinc = if x == 0.0 { 1.0 } else { (PI * BLIP_CUTOFF * x).sin() / (PI * BLIP_CUTOFF * x) };
+            // Blackman window over the width of the kernel
+            let position = (x + (BLIP_TAPS / 2) as f64) / BLIP_TAPS as f64;
+            let window = 0.42 - 0.5 * (2.0 * PI * position).cos() + 0.08 * (4.0 * PI * position).cos();
+            *value = (sinc * window) as f32;
+        }
+        let sum: f32 = taps.iter().sum();
+        taps.map(|value| value / sum)
+    }
+
+    fn add_delta(&mut self, delta: [f32; 2]) {
+        let start = self.time.floor();
+        let phase = ((self.time - start) * BLIP_PHASES as f64).round() as usize;
+        let start = start as usize;
+
+        if self.deltas.len() < start + BLIP_TAPS {
+            self.deltas.resize(start + BLIP_TAPS, [0.0; 2]);
+        }
+        for (tap, weight) in self.kernel[phase].iter().enumerate() {
+            self.deltas[start + tap][0] += delta[0] * weight;
+            self.deltas[start + tap][1] += delta[1] * weight;
+        }
+    }
+
+    // Samples before the current time can't receive more deltas and are output
+    fn advance(&mut self, samples: f64, output: &mut Vec<f32>) {
+        self.time += samples;
+        let done = self.time.floor() as usize;
+        if done == 0 {
+            return;
+        }
+
+        if self.deltas.len() < done {
+            self.deltas.resize(done, [0.0; 2]);
+        }
+        for delta in self.deltas.drain(..done) {
+            self.level[0] += delta[0];
+            self.level[1] += delta[1];
+            output.extend_from_slice(&self.level);
+        }
+        self.time -= done as f64;
+    }
+}
@@ -0,0 +1,163 @@
+use std::{fs::File, io::{BufWriter, Error, Seek, SeekFrom, Write}, path::{Path, PathBuf}};
+
+use crate::{audio::{AudioChannel, ChannelTaps, HighPassFilter, Resampler, ResamplerQuality}, CPU_CLOCK_HZ};
+
+// Size of the RIFF, fmt and data chunk headers before the samples
+const WAV_HEADER_SIZE: u32 = 44;
+// Samples of the mix kept before they are written, about a tenth of second
+const FLUSH_SAMPLES: usize = 8192;
+
+pub struct AudioDumpOptions {
+    pub sample_rate: u32,
+    pub quality: ResamplerQuality,
+    // Also writes every channel to a mono file of its own next to the mix,
+    // see stem_path
+    pub stems: bool,
+}
+
+impl Default for AudioDumpOptions {
+    fn default() -> Self {
+        AudioDumpOptions { sample_rate: 44100, quality: ResamplerQuality::BandLimited, stems: false }
+    }
+}
+
+// Where the stem of a channel goes when dumping to the given path: song.wav
+// gets song-pulse1.wav, song-pulse2.wav, song-wave.wav and song-noise.wav
+pub fn stem_path(path: &Path, channel: AudioChannel) -> PathBuf {
+    let name = match channel {
+        AudioChannel::Pulse1 => "pulse1",
+        AudioChannel::Pulse2 => "pulse2",
+        AudioChannel::Wave => "wave",
+        AudioChannel::Noise => "noise",
+    };
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}-{}.wav", stem, name))
+}
+
+// 16 bit PCM written as it comes, the sizes of the header are filled in when finished
+struct WavWriter {
+    file: BufWriter<File>,
+    data_size: u64,
+}
+
+impl WavWriter {
+    fn new(path: &Path, channels: u16, sample_rate: u32) -> Result<Self, Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let block_align = channels * 2;
+        file.write_all(b"RIFF")?;
+        file.write_all(&0u32.to_le_bytes())?;
+        file.write_all(b"WAVEfmt ")?;
+        file.write_all(&16u32.to_le_bytes())?;
+        // PCM
+        file.write_all(&1u16.to_le_bytes())?;
+        file.write_all(&channels.to_le_bytes())?;
+        file.write_all(&sample_rate.to_le_bytes())?;
+        file.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+        file.write_all(&block_align.to_le_bytes())?;
+        file.write_all(&16u16.to_le_bytes())?;
+        file.write_all(b"data")?;
+        file.write_all(&0u32.to_le_bytes())?;
+        Ok(WavWriter { file, data_size: 0 })
+    }
+
+    fn write(&mut self, samples: &[f32]) -> Result<(), Error> {
+        for sample in samples {
+            let pcm = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            self.file.write_all(&pcm.to_le_bytes())?;
+        }
+        self.data_size += samples.len() as u64 * 2;
+        Ok(())
+    }
+
+    // Sizes past 4 GiB don't fit the header, players read the file to its end anyway
+    fn finish(mut self) -> Result<(), Error> {
+        let data_size = self.data_size.min((u32::MAX - WAV_HEADER_SIZE) as u64) as u32;
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file.write_all(&(data_size + WAV_HEADER_SIZE - 8).to_le_bytes())?;
+        self.file.seek(SeekFrom::Start(WAV_HEADER_SIZE as u64 - 4))?;
+        self.file.write_all(&data_size.to_le_bytes())?;
+        self.file.flush()
+    }
+}
+
+// Writes what the APU plays to WAV files, for ripping music and comparing the
+// sound of two builds. It resamples on its own, so it works with audio
+// disabled and doesn't take the samples of the frontend.
+pub(crate) struct AudioDump {
+    resampler: Resampler,
+    mix: WavWriter,
+    stems: Option<(ChannelTaps, [WavWriter; 4])>,
+    // The first error, reported when the dump is finished
+    error: Option<Error>,
+}
+
+impl AudioDump {
+    pub(crate) fn new<P: AsRef<Path>>(path: P, options: AudioDumpOptions, filter: HighPassFilter) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let sample_rate = options.sample_rate.max(1);
+        let mix = WavWriter::new(path, 2, sample_rate)?;
+
+        let stems = if options.stems {
+            let taps = ChannelTaps::new(sample_rate);
+            // The taps take a whole number of clock cycles per sample
+            let rate = CPU_CLOCK_HZ as u32 / taps.period();
+            let writers = [
+                WavWriter::new(&stem_path(path, AudioChannel::Pulse1), 1, rate)?,
+                WavWriter::new(&stem_path(path, AudioChannel::Pulse2), 1, rate)?,
+                WavWriter::new(&stem_path(path, AudioChannel::Wave), 1, rate)?,
+                WavWriter::new(&stem_path(path, AudioChannel::Noise), 1, rate)?,
+            ];
+            Some((taps, writers))
+        } else {
+            None
+        };
+
+        Ok(AudioDump { resampler: Resampler::new(sample_rate, options.quality, filter), mix, stems, error: None })
+    }
+
+    // Cycles until the next stem sample, the APU splits its ticks there
+    pub(crate) fn next_step(&self) -> u32 {
+        self.stems.as_ref().map_or(u32::MAX, |(taps, _)| taps.next_step())
+    }
+
+    // The mix and the channel levels of the cycles that just ran
+    pub(crate) fn push(&mut self, mix: [f32; 2], levels: [f32; 4], cycles: u32) {
+        self.resampler.push(mix, cycles);
+        if let Some((taps, _)) = &mut self.stems {
+            taps.push(levels, cycles);
+        }
+        if self.resampler.sample_count() >= FLUSH_SAMPLES {
+            self.flush();
+        }
+    }
+
+    // The samples are dropped after an error, so they don't pile up
+    fn flush(&mut self) {
+        let mix = self.resampler.take_samples();
+        let stems = self.stems.as_mut().map(|(taps, _)| taps.take_samples()).unwrap_or_default();
+        if self.error.is_some() {
+            return;
+        }
+        let mut result = self.mix.write(&mix);
+        if let Some((_, writers)) = &mut self.stems {
+            for (writer, samples) in writers.iter_mut().zip(stems) {
+                result = result.and_then(|_| writer.write(&samples));
+            }
+        }
+        self.error = result.err();
+    }
+
+    pub(crate) fn finish(mut self) -> Result<(), Error> {
+        self.flush();
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.mix.finish()?;
+        if let Some((_, writers)) = self.stems {
+            for writer in writers {
+                writer.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
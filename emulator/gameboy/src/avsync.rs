@@ -0,0 +1,62 @@
+use std::collections::VecDeque;
+
+use crate::{GameBoyFrame, FPS};
+
+// Shifts the video against the audio when they are presented, so rhythm games
+// feel right on hosts with a large audio output latency. Positive offsets hold
+// frames back, negative ones hold the audio back with silence.
+pub struct AvSync {
+    offset_ms: i32,
+    frames: VecDeque<GameBoyFrame>,
+    // Silence already added in front of the audio, in samples per channel
+    audio_delay: usize,
+}
+
+impl AvSync {
+    pub fn new(offset_ms: i32) -> Self {
+        AvSync { offset_ms, frames: VecDeque::new(), audio_delay: 0 }
+    }
+
+    pub fn offset_ms(&self) -> i32 {
+        self.offset_ms
+    }
+
+    // Takes effect progressively on the next presented frames and samples
+    pub fn set_offset_ms(&mut self, offset_ms: i32) {
+        self.offset_ms = offset_ms;
+    }
+
+    // Silence currently held in front of the audio, in samples per channel
+    pub fn audio_delay(&self) -> usize {
+        self.audio_delay
+    }
+
+    // Returns the frame to show now, None while the delay is filling up
+    pub fn present_frame(&mut self, frame: GameBoyFrame) -> Option<GameBoyFrame> {
+        let delay = (self.offset_ms.max(0) as f32 * FPS / 1000.0).round() as usize;
+        self.frames.push_back(frame);
+
+        let mut shown = None;
+        while self.frames.len() > delay {
+            shown = self.frames.pop_front();
+        }
+        shown
+    }
+
+    // Interleaved stereo samples to queue now
+    pub fn present_audio(&mut self, samples: Vec<f32>, sample_rate: u32) -> Vec<f32> {
+        let delay = (-self.offset_ms).max(0) as usize * sample_rate as usize / 1000;
+
+        if delay > self.audio_delay {
+            let mut delayed = vec![0.0; (delay - self.audio_delay) * 2];
+            delayed.extend(samples);
+            self.audio_delay = delay;
+            delayed
+        } else {
+            // Dropping samples brings the audio forward
+            let dropped = (self.audio_delay - delay).min(samples.len() / 2);
+            self.audio_delay -= dropped;
+            samples[dropped * 2..].to_vec()
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+
+use crate::cartridge;
+
+// The hardware the core can emulate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HardwareModel {
+    Dmg,
+}
+
+impl HardwareModel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HardwareModel::Dmg => "dmg",
+        }
+    }
+}
+
+// What this build of the core supports, for frontends to hide what it can't do
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    pub version: &'static str,
+    // Cargo features the core was built with
+    pub features: Vec<&'static str>,
+    pub mappers: Vec<&'static str>,
+    pub models: Vec<HardwareModel>,
+}
+
+const FEATURES: &[(&str, bool)] = &[
+    ("archives", cfg!(feature = "archives")),
+    ("async", cfg!(feature = "async")),
+    ("rom-database", cfg!(feature = "rom-database")),
+    ("scripting", cfg!(feature = "scripting")),
+];
+
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        features: FEATURES.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| *name).collect(),
+        mappers: cartridge::supported_mappers(),
+        models: vec![HardwareModel::Dmg],
+    }
+}
+
+impl Capabilities {
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.contains(&feature)
+    }
+
+    pub fn supports_mapper(&self, mapper: &str) -> bool {
+        self.mappers.iter().any(|name| name.eq_ignore_ascii_case(mapper))
+    }
+
+    pub fn supports_model(&self, model: &str) -> bool {
+        self.models.iter().any(|supported| supported.name().eq_ignore_ascii_case(model))
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let models: Vec<&str> = self.models.iter().map(HardwareModel::name).collect();
+        writeln!(f, "version {}", self.version)?;
+        writeln!(f, "features: {}", if self.features.is_empty() { "none".to_string() } else { self.features.join(", ") })?;
+        writeln!(f, "mappers: {}", self.mappers.join(", "))?;
+        write!(f, "models: {}", models.join(", "))
+    }
+}
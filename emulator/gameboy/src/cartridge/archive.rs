@@ -0,0 +1,71 @@
+use std::{fs, io::{Error, ErrorKind, Read}, path::Path};
+#[cfg(feature = "archives")]
+use std::io::Cursor;
+
+// The largest cartridges hold 8 MiB, a longer ROM is a broken or hostile file
+pub(crate) const MAX_ROM_SIZE: usize = 8 * 1024 * 1024;
+
+#[cfg(feature = "archives")]
+const ROM_EXTENSIONS: [&str; 2] = ["gb", "gbc"];
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extensions.iter().any(|wanted| extension.eq_ignore_ascii_case(wanted)))
+}
+
+// ROMs are often shared compressed, game.zip with the ROM in it or game.gb.gz.
+// Other files are read as they are.
+pub(crate) fn read_rom(path: &Path) -> Result<Vec<u8>, Error> {
+    let data = fs::read(path)?;
+    if has_extension(path, &["zip"]) {
+        unzip(&data)
+    } else if has_extension(path, &["gz"]) {
+        gunzip(&data)
+    } else {
+        Ok(data)
+    }
+}
+
+// Stops reading past MAX_ROM_SIZE, archives and readers can produce far more
+// data than they take
+pub(crate) fn read_limited<R: Read>(reader: R) -> Result<Vec<u8>, Error> {
+    let mut rom = Vec::new();
+    reader.take(MAX_ROM_SIZE as u64 + 1).read_to_end(&mut rom)?;
+    if rom.len() > MAX_ROM_SIZE {
+        return Err(Error::new(ErrorKind::InvalidData, "The ROM is larger than 8 MiB"));
+    }
+    Ok(rom)
+}
+
+// The only .gb or .gbc file of the archive, the others like a readme are skipped
+#[cfg(feature = "archives")]
+fn unzip(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(Error::other)?;
+    let roms: Vec<String> = archive.file_names()
+        .filter(|name| has_extension(Path::new(name), &ROM_EXTENSIONS))
+        .map(String::from)
+        .collect();
+    let [name] = &roms[..] else {
+        let problem = if roms.is_empty() { "no ROM" } else { "more than one ROM" };
+        return Err(Error::new(ErrorKind::InvalidData, format!("The archive holds {}", problem)));
+    };
+    // The size in the header is not trusted
+    let file = archive.by_name(name).map_err(Error::other)?;
+    read_limited(file)
+}
+
+#[cfg(feature = "archives")]
+fn gunzip(data: &[u8]) -> Result<Vec<u8>, Error> {
+    read_limited(flate2::read::GzDecoder::new(data))
+}
+
+#[cfg(not(feature = "archives"))]
+fn unzip(_data: &[u8]) -> Result<Vec<u8>, Error> {
+    Err(Error::new(ErrorKind::Unsupported, "Opening .zip files needs the archives feature"))
+}
+
+#[cfg(not(feature = "archives"))]
+fn gunzip(_data: &[u8]) -> Result<Vec<u8>, Error> {
+    Err(Error::new(ErrorKind::Unsupported, "Opening .gz files needs the archives feature"))
+}
@@ -0,0 +1,267 @@
+use std::{io::Error, path::Path};
+
+use crate::{mmu::Address, savestate::{StateReader, StateWriter}, screenshot::load_png};
+
+use super::{mapper::Mapper, mbc3::ROM_BANK_SIZE};
+
+pub const CAMERA_WIDTH: usize = 128;
+pub const CAMERA_HEIGHT: usize = 112;
+
+const RAM_BANK_SIZE: usize = 0x2000;
+const RAM_BANKS: usize = 16;
+// Bit 4 of the RAM bank register maps the sensor registers instead of RAM
+const REGISTERS_SELECTED: u8 = 0x10;
+const REGISTERS: usize = 0x36;
+// The picture goes to RAM bank 0 as 16x14 tiles
+const PICTURE_ADDRESS: usize = 0x0100;
+// Exposure at which the image comes out as it is given
+const NEUTRAL_EXPOSURE: u32 = 0x0800;
+
+// Where the camera takes its pictures from, grayscale pixels of
+// CAMERA_WIDTH x CAMERA_HEIGHT from black (0) to white (255)
+pub trait ImageSource: Send {
+    fn capture(&mut self) -> Vec<u8>;
+}
+
+// The same picture every time, from a PNG scaled to the sensor
+pub struct StaticImage {
+    pixels: Vec<u8>,
+}
+
+impl StaticImage {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let (width, height, rgba) = load_png(path.as_ref())?;
+        let luma: Vec<u8> = rgba.chunks(4).map(|pixel| ((pixel[0] as u32 * 299 + pixel[1] as u32 * 587 + pixel[2] as u32 * 114) / 1000) as u8).collect();
+        Ok(StaticImage::new(width as usize, height as usize, &luma))
+    }
+
+    // Any size, nearest neighbour scaled
+    pub fn new(width: usize, height: usize, luma: &[u8]) -> Self {
+        let pixels = (0..CAMERA_WIDTH * CAMERA_HEIGHT).map(|index| {
+            let (x, y) = (index % CAMERA_WIDTH * width / CAMERA_WIDTH, index / CAMERA_WIDTH * height / CAMERA_HEIGHT);
+            luma.get(x + y * width).copied().unwrap_or(0)
+        }).collect();
+        StaticImage { pixels }
+    }
+}
+
+impl ImageSource for StaticImage {
+    fn capture(&mut self) -> Vec<u8> {
+        self.pixels.clone()
+    }
+}
+
+// The first video device of the host, through Video4Linux
+#[cfg(feature = "webcam")]
+pub struct Webcam {
+    stream: v4l::io::mmap::Stream<'static>,
+    width: usize,
+    height: usize,
+    stride: usize,
+}
+
+#[cfg(feature = "webcam")]
+impl Webcam {
+    // Index of /dev/videoN
+    pub fn open(index: usize) -> Result<Self, Error> {
+        use v4l::video::Capture;
+
+        let device = v4l::Device::new(index)?;
+        let mut format = device.format()?;
+        // Luma is every other byte
+        format.fourcc = v4l::FourCC::new(b"YUYV");
+        let format = device.set_format(&format)?;
+        if format.fourcc != v4l::FourCC::new(b"YUYV") {
+            return Err(Error::new(std::io::ErrorKind::Unsupported, "The webcam doesn't support YUYV"));
+        }
+        let stream = v4l::io::mmap::Stream::with_buffers(&device, v4l::buffer::Type::VideoCapture, 4)?;
+        Ok(Webcam { stream, width: format.width as usize, height: format.height as usize, stride: format.stride as usize })
+    }
+}
+
+#[cfg(feature = "webcam")]
+impl ImageSource for Webcam {
+    fn capture(&mut self) -> Vec<u8> {
+        use v4l::io::traits::CaptureStream;
+
+        let (width, height, stride) = (self.width, self.height, self.stride);
+        match self.stream.next() {
+            Ok((buffer, _)) => {
+                // Cropped to the aspect ratio of the sensor
+                let crop = (width * CAMERA_HEIGHT / CAMERA_WIDTH).min(height);
+                let top = (height - crop) / 2;
+                (0..CAMERA_WIDTH * CAMERA_HEIGHT).map(|index| {
+                    let (x, y) = (index % CAMERA_WIDTH * width / CAMERA_WIDTH, top + index / CAMERA_WIDTH * crop / CAMERA_HEIGHT);
+                    buffer.get(y * stride + x * 2).copied().unwrap_or(0)
+                }).collect()
+            },
+            // A gray picture while the device is gone
+            Err(_) => vec![0x80; CAMERA_WIDTH * CAMERA_HEIGHT],
+        }
+    }
+}
+
+// MAC-GBD mapper of the Game Boy Camera with the M64282FP sensor, see
+// https://gbdev.io/pandocs/Gameboy_Camera.html. A capture takes the picture of
+// the image source, scales it by the exposure and dithers it with the 4x4 matrix
+// of thresholds. Gain, edge enhancement and the voltage offset are not emulated.
+pub(crate) struct PocketCamera {
+    rom_bank: u8,
+    ram_bank: u8,
+    ram_enabled: bool,
+    registers: [u8; REGISTERS],
+    // Cycles until the capture in progress is done
+    capture_cycles: usize,
+    source: Option<Box<dyn ImageSource>>,
+}
+
+impl PocketCamera {
+    pub(crate) fn new() -> Self {
+        PocketCamera { rom_bank: 1, ram_bank: 0, ram_enabled: false, registers: [0; REGISTERS], capture_cycles: 0, source: None }
+    }
+
+    fn capturing(&self) -> bool {
+        self.capture_cycles > 0
+    }
+
+    fn exposure(&self) -> u32 {
+        (self.registers[2] as u32) << 8 | self.registers[3] as u32
+    }
+
+    // 32446 cycles of the 1 MiHz sensor clock plus 16 per step of exposure
+    fn capture_time(&self) -> usize {
+        (32446 + 16 * self.exposure() as usize) * 4
+    }
+
+    fn develop(&mut self, ram: &mut [u8]) {
+        if ram.len() < PICTURE_ADDRESS + CAMERA_WIDTH * CAMERA_HEIGHT / 4 {
+            return;
+        }
+        let pixels = match &mut self.source {
+            Some(source) => source.capture(),
+            None => vec![0x80; CAMERA_WIDTH * CAMERA_HEIGHT],
+        };
+        let exposure = self.exposure();
+        let matrix = &self.registers[6..REGISTERS];
+
+        for y in 0..CAMERA_HEIGHT {
+            for x in 0..CAMERA_WIDTH {
+                let light = pixels.get(x + y * CAMERA_WIDTH).copied().unwrap_or(0) as u32;
+                let light = (light * exposure / NEUTRAL_EXPOSURE).min(0xFF) as u8;
+                // Three thresholds from dark to light for each position of the 4x4 matrix
+                let thresholds = &matrix[((y % 4) * 4 + x % 4) * 3..][..3];
+                let color = match thresholds.iter().position(|threshold| light < *threshold) {
+                    Some(level) => 3 - level as u8,
+                    None => 0,
+                };
+
+                let tile = (y / 8) * (CAMERA_WIDTH / 8) + x / 8;
+                let offset = PICTURE_ADDRESS + tile * 16 + (y % 8) * 2;
+                let bit = 7 - (x % 8);
+                for (plane, byte) in ram[offset..offset + 2].iter_mut().enumerate() {
+                    let set = (color >> plane) & 1 != 0;
+                    *byte = (*byte & !(1 << bit)) | ((set as u8) << bit);
+                }
+            }
+        }
+    }
+}
+
+impl Mapper for PocketCamera {
+    fn reset(&mut self) {
+        self.rom_bank = 1;
+        self.ram_bank = 0;
+        self.ram_enabled = false;
+        self.registers = [0; REGISTERS];
+        self.capture_cycles = 0;
+    }
+
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.u8(self.rom_bank);
+        writer.u8(self.ram_bank);
+        writer.bool(self.ram_enabled);
+        writer.bytes(&self.registers);
+        writer.u64(self.capture_cycles as u64);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), Error> {
+        self.rom_bank = reader.u8()?;
+        self.ram_bank = reader.u8()?;
+        self.ram_enabled = reader.bool()?;
+        self.registers = reader.array()?;
+        self.capture_cycles = reader.u64()? as usize;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        let ram = if self.ram_bank & REGISTERS_SELECTED != 0 { "camera registers".to_string() } else { format!("RAM bank {:02X}", self.ram_bank) };
+        format!("Pocket Camera, ROM bank {:02X}, {}{}", self.rom_bank, ram, if self.capturing() { ", capturing" } else { "" })
+    }
+
+    fn rom_offset(&self, address: Address) -> usize {
+        match address {
+            0x0000 ..= 0x3FFF => address as usize,
+            _ => self.rom_bank as usize * ROM_BANK_SIZE + (address as usize - 0x4000),
+        }
+    }
+
+    fn write_rom(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000 ..= 0x1FFF => self.ram_enabled = (value & 0x0F) == 0x0A,
+            // Unlike the MBCs, bank 0 can be mapped
+            0x2000 ..= 0x3FFF => self.rom_bank = value & 0x3F,
+            0x4000 ..= 0x5FFF => self.ram_bank = value & 0x1F,
+            _ => {},
+        }
+    }
+
+    // RAM can be read while it is disabled, but not during a capture
+    fn read_ram(&self, ram: &[u8], address: Address) -> u8 {
+        if self.ram_bank & REGISTERS_SELECTED != 0 {
+            // Only the first register can be read, bit 0 tells if a capture is running
+            let register = (address as usize - 0xA000) & 0x7F;
+            return if register == 0 { (self.registers[0] & !0x01) | self.capturing() as u8 } else { 0x00 };
+        }
+        if self.capturing() {
+            return 0x00;
+        }
+        let offset = (self.ram_bank as usize % RAM_BANKS) * RAM_BANK_SIZE + (address as usize - 0xA000);
+        ram.get(offset).copied().unwrap_or(0xFF)
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], address: Address, value: u8) {
+        if self.ram_bank & REGISTERS_SELECTED != 0 {
+            let register = (address as usize - 0xA000) & 0x7F;
+            if register < REGISTERS {
+                self.registers[register] = value;
+            }
+            if register == 0 && value & 0x01 != 0 && !self.capturing() {
+                self.capture_cycles = self.capture_time();
+            }
+            return;
+        }
+        if !self.ram_enabled || self.capturing() {
+            return;
+        }
+        let offset = (self.ram_bank as usize % RAM_BANKS) * RAM_BANK_SIZE + (address as usize - 0xA000);
+        if let Some(byte) = ram.get_mut(offset) {
+            *byte = value;
+        }
+    }
+
+    // The picture is written to RAM when the capture is over
+    fn step(&mut self, ram: &mut [u8], cycles: usize) {
+        if !self.capturing() {
+            return;
+        }
+        self.capture_cycles = self.capture_cycles.saturating_sub(cycles);
+        if !self.capturing() {
+            self.registers[0] &= !0x01;
+            self.develop(ram);
+        }
+    }
+
+    fn set_image_source(&mut self, source: Box<dyn ImageSource>) {
+        self.source = Some(source);
+    }
+}
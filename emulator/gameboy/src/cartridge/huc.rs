@@ -0,0 +1,303 @@
+use crate::{mmu::Address, savestate::{StateReader, StateWriter}, CPU_CLOCK_HZ};
+
+use super::{mapper::Mapper, mbc3::ROM_BANK_SIZE};
+
+const RAM_BANK_SIZE: usize = 0x2000;
+// Read from A000 in IR mode when no light is seen
+const IR_DARK: u8 = 0xC0;
+
+const HUC3_RAM_READ: u8 = 0x00;
+const HUC3_RAM: u8 = 0x0A;
+const HUC3_RTC_COMMAND: u8 = 0x0B;
+const HUC3_RTC_RESPONSE: u8 = 0x0C;
+const HUC3_RTC_SEMAPHORE: u8 = 0x0D;
+const HUC3_IR: u8 = 0x0E;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+const CYCLES_PER_MINUTE: usize = CPU_CLOCK_HZ * 60;
+
+// Hudson HuC1, an MBC1 look-alike with an infrared port instead of the RAM
+// enable. The port is a stub: the LED goes nowhere and no light is ever received.
+pub(crate) struct HuC1 {
+    rom_bank: u8,
+    ram_bank: u8,
+    ir_mode: bool,
+    ir_led: bool,
+}
+
+impl HuC1 {
+    pub(crate) fn new() -> Self {
+        HuC1 { rom_bank: 1, ram_bank: 0, ir_mode: false, ir_led: false }
+    }
+}
+
+impl Mapper for HuC1 {
+    fn reset(&mut self) {
+        *self = HuC1::new();
+    }
+
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.u8(self.rom_bank);
+        writer.u8(self.ram_bank);
+        writer.bool(self.ir_mode);
+        writer.bool(self.ir_led);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        self.rom_bank = reader.u8()?;
+        self.ram_bank = reader.u8()?;
+        self.ir_mode = reader.bool()?;
+        self.ir_led = reader.bool()?;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("HuC1, ROM bank {:02X}, {}", self.rom_bank, if self.ir_mode { "infrared".to_string() } else { format!("RAM bank {:02X}", self.ram_bank) })
+    }
+
+    fn rom_offset(&self, address: Address) -> usize {
+        match address {
+            0x0000 ..= 0x3FFF => address as usize,
+            _ => self.rom_bank as usize * ROM_BANK_SIZE + (address as usize - 0x4000),
+        }
+    }
+
+    fn write_rom(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000 ..= 0x1FFF => self.ir_mode = value & 0x0F == 0x0E,
+            0x2000 ..= 0x3FFF => {
+                let bank = value & 0x3F;
+                self.rom_bank = if bank == 0 { 1 } else { bank };
+            },
+            0x4000 ..= 0x5FFF => self.ram_bank = value & 0x03,
+            _ => {},
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], address: Address) -> u8 {
+        if self.ir_mode {
+            return IR_DARK;
+        }
+        ram.get(ram_offset(self.ram_bank, address)).copied().unwrap_or(0xFF)
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], address: Address, value: u8) {
+        if self.ir_mode {
+            self.ir_led = value & 0x01 != 0;
+        } else if let Some(byte) = ram.get_mut(ram_offset(self.ram_bank, address)) {
+            *byte = value;
+        }
+    }
+}
+
+// Hudson HuC3, used by Robopon and the Japanese Pokemon Card GB 2. The clock is
+// reached through a small command set written to A000: it counts minutes of the
+// day and days, and the game reads and writes them as nibbles of a 256 nibble memory.
+// The infrared port and the tone generator are stubs like on HuC1.
+pub(crate) struct HuC3 {
+    rom_bank: u8,
+    ram_bank: u8,
+    mode: u8,
+    ir_led: bool,
+    clock: HuC3Clock,
+}
+
+#[derive(Clone)]
+pub(crate) struct HuC3Clock {
+    pub(crate) minutes: u16,
+    pub(crate) days: u16,
+    cycles: usize,
+    // Nibbles 0-2 are the minutes and 3-6 the days once copied
+    memory: [u8; 0x100],
+    address: u8,
+    // Last command in the high nibble, its result in the low one
+    response: u8,
+}
+
+impl HuC3 {
+    pub(crate) fn new() -> Self {
+        HuC3 { rom_bank: 1, ram_bank: 0, mode: HUC3_RAM_READ, ir_led: false, clock: HuC3Clock::default() }
+    }
+}
+
+impl Mapper for HuC3 {
+    // The clock runs on the battery
+    fn reset(&mut self) {
+        self.rom_bank = 1;
+        self.ram_bank = 0;
+        self.mode = HUC3_RAM_READ;
+        self.ir_led = false;
+    }
+
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.u8(self.rom_bank);
+        writer.u8(self.ram_bank);
+        writer.u8(self.mode);
+        writer.bool(self.ir_led);
+
+        let clock = &self.clock;
+        writer.u16(clock.minutes);
+        writer.u16(clock.days);
+        writer.u64(clock.cycles as u64);
+        writer.bytes(&clock.memory);
+        writer.u8(clock.address);
+        writer.u8(clock.response);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        self.rom_bank = reader.u8()?;
+        self.ram_bank = reader.u8()?;
+        self.mode = reader.u8()?;
+        self.ir_led = reader.bool()?;
+
+        let clock = &mut self.clock;
+        clock.minutes = reader.u16()?;
+        clock.days = reader.u16()?;
+        clock.cycles = reader.u64()? as usize;
+        clock.memory = reader.array()?;
+        clock.address = reader.u8()?;
+        clock.response = reader.u8()?;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        let clock = &self.clock;
+        format!("HuC3, ROM bank {:02X}, RAM bank {:02X}, mode {:02X}, day {} {:02}:{:02}",
+            self.rom_bank, self.ram_bank, self.mode, clock.days, clock.minutes / 60, clock.minutes % 60)
+    }
+
+    fn rom_offset(&self, address: Address) -> usize {
+        match address {
+            0x0000 ..= 0x3FFF => address as usize,
+            _ => self.rom_bank as usize * ROM_BANK_SIZE + (address as usize - 0x4000),
+        }
+    }
+
+    fn write_rom(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000 ..= 0x1FFF => self.mode = value & 0x0F,
+            0x2000 ..= 0x3FFF => {
+                let bank = value & 0x7F;
+                self.rom_bank = if bank == 0 { 1 } else { bank };
+            },
+            0x4000 ..= 0x5FFF => self.ram_bank = value & 0x03,
+            _ => {},
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], address: Address) -> u8 {
+        match self.mode {
+            HUC3_RAM_READ | HUC3_RAM => ram.get(ram_offset(self.ram_bank, address)).copied().unwrap_or(0xFF),
+            HUC3_RTC_RESPONSE => 0x80 | self.clock.response,
+            // Commands complete at once, the clock is always ready
+            HUC3_RTC_SEMAPHORE => 0x01,
+            HUC3_IR => IR_DARK,
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], address: Address, value: u8) {
+        match self.mode {
+            HUC3_RAM => {
+                if let Some(byte) = ram.get_mut(ram_offset(self.ram_bank, address)) {
+                    *byte = value;
+                }
+            },
+            HUC3_RTC_COMMAND => self.clock.command(value),
+            HUC3_IR => self.ir_led = value & 0x01 != 0,
+            _ => {},
+        }
+    }
+
+    fn step(&mut self, _ram: &mut [u8], cycles: usize) {
+        self.clock.tick(cycles);
+    }
+
+    // Minutes and days as 32-bit values
+    fn save_clock(&self) -> Vec<u8> {
+        [self.clock.minutes, self.clock.days].iter().flat_map(|value| (*value as u32).to_le_bytes()).collect()
+    }
+
+    fn load_clock(&mut self, clock: &[u8]) {
+        self.clock.minutes = u16::from_le_bytes([clock[0], clock[1]]) % MINUTES_PER_DAY;
+        self.clock.days = u16::from_le_bytes([clock[4], clock[5]]);
+    }
+
+    fn advance_clock(&mut self, seconds: u64) {
+        let minutes = self.clock.minutes as u64 + seconds / 60;
+        self.clock.days = self.clock.days.wrapping_add((minutes / MINUTES_PER_DAY as u64) as u16);
+        self.clock.minutes = (minutes % MINUTES_PER_DAY as u64) as u16;
+        self.clock.tick((seconds % 60) as usize * CPU_CLOCK_HZ);
+    }
+}
+
+impl Default for HuC3Clock {
+    fn default() -> Self {
+        HuC3Clock { minutes: 0, days: 0, cycles: 0, memory: [0; 0x100], address: 0, response: 0 }
+    }
+}
+
+impl HuC3Clock {
+    pub(crate) fn tick(&mut self, cycles: usize) {
+        self.cycles += cycles;
+        while self.cycles >= CYCLES_PER_MINUTE {
+            self.cycles -= CYCLES_PER_MINUTE;
+            self.minutes += 1;
+            if self.minutes == MINUTES_PER_DAY {
+                self.minutes = 0;
+                self.days = self.days.wrapping_add(1);
+            }
+        }
+    }
+
+    // High nibble is the command, low nibble its argument
+    fn command(&mut self, value: u8) {
+        let (command, argument) = ((value >> 4) & 0x07, value & 0x0F);
+        let mut result = 0;
+        match command {
+            // Read a nibble and move to the next one
+            0x1 => {
+                result = self.memory[self.address as usize];
+                self.address = self.address.wrapping_add(1);
+            },
+            // Write a nibble and move to the next one
+            0x3 => {
+                self.memory[self.address as usize] = argument;
+                self.address = self.address.wrapping_add(1);
+            },
+            0x4 => self.address = (self.address & 0xF0) | argument,
+            0x5 => self.address = (self.address & 0x0F) | (argument << 4),
+            0x6 => match argument {
+                0x0 => self.copy_to_memory(),
+                0x1 => self.copy_from_memory(),
+                // Status, the clock has been set
+                0x2 => result = 0x1,
+                _ => {},
+            },
+            _ => {},
+        }
+        self.response = (command << 4) | result;
+    }
+
+    fn copy_to_memory(&mut self) {
+        for nibble in 0..3 {
+            self.memory[nibble] = ((self.minutes >> (nibble * 4)) & 0x0F) as u8;
+        }
+        for nibble in 0..4 {
+            self.memory[3 + nibble] = ((self.days >> (nibble * 4)) & 0x0F) as u8;
+        }
+    }
+
+    fn copy_from_memory(&mut self) {
+        let value = |nibbles: std::ops::Range<usize>| {
+            nibbles.rev().fold(0u16, |value, nibble| value << 4 | self.memory[nibble] as u16)
+        };
+        self.minutes = value(0..3) % MINUTES_PER_DAY;
+        self.days = value(3..7);
+        self.cycles = 0;
+    }
+}
+
+fn ram_offset(bank: u8, address: Address) -> usize {
+    bank as usize * RAM_BANK_SIZE + (address as usize - 0xA000)
+}
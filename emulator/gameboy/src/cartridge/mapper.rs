@@ -0,0 +1,177 @@
+use crate::{mmu::Address, savestate::{StateReader, StateWriter}};
+
+use super::{camera::{ImageSource, PocketCamera}, huc::{HuC1, HuC3}, mbc2::{self, MBC2}, mbc3::{MBC3, ROM_BANK_SIZE}};
+use super::{multicart::{MBC1Multicart, MMM01}, ram_size_from_header, unlicensed::UnlicensedMapper, CTYPE_ADDR, RAM_SIZE_ADDR, ROM_SIZE_ADDR};
+
+// Bank switching hardware inside the cartridge. The cartridge owns the ROM and
+// the RAM and hands them to the mapper, which only keeps its registers. A new
+// mapper is a module implementing this plus an entry in MAPPERS.
+pub(crate) trait Mapper: Send {
+    // Mapper and the banks it has selected, for state dumps
+    fn describe(&self) -> String;
+
+    // Offset inside the ROM data for a read in 0000-7FFF
+    fn rom_offset(&self, address: Address) -> usize;
+
+    // Banks beyond the end of the ROM mirror the beginning
+    fn read_rom(&self, rom: &[u8], address: Address) -> u8 {
+        rom.get(self.rom_offset(address) % rom.len().max(1)).copied().unwrap_or(0xFF)
+    }
+
+    // Writes to the ROM area are commands for the mapper
+    fn write_rom(&mut self, address: Address, value: u8);
+
+    // Without registers writes to the ROM area go nowhere
+    fn has_registers(&self) -> bool {
+        true
+    }
+
+    // Without RAM handling the external RAM area is served by the MMU
+    fn handles_ram(&self) -> bool {
+        true
+    }
+
+    fn read_ram(&self, _ram: &[u8], _address: Address) -> u8 {
+        0xFF
+    }
+
+    fn write_ram(&mut self, _ram: &mut [u8], _address: Address, _value: u8) {}
+
+    // Clocks and captures that run with the CPU
+    fn step(&mut self, _ram: &mut [u8], _cycles: usize) {}
+
+    // Registers back to their power on values, RAM and clocks are battery backed
+    fn reset(&mut self);
+
+    fn save_state(&self, writer: &mut StateWriter);
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), std::io::Error>;
+
+    // Only the Game Boy Camera takes pictures
+    fn set_image_source(&mut self, _source: Box<dyn ImageSource>) {}
+
+    // The clock registers saved after the RAM in battery files, empty without a clock
+    fn save_clock(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    // Gets as many bytes as save_clock gives
+    fn load_clock(&mut self, _clock: &[u8]) {}
+
+    // Time that passed while the game was off
+    fn advance_clock(&mut self, _seconds: u64) {}
+}
+
+// The mapper and the cartridge RAM it comes with
+type Board = (Box<dyn Mapper>, Vec<u8>);
+
+pub(crate) struct MapperEntry {
+    pub(crate) names: &'static [&'static str],
+    // Cartridge type bytes of the header
+    types: &'static [u8],
+    // For mappers that share their type bytes with another one
+    detect: Option<fn(&[u8]) -> bool>,
+    // Takes the whole ROM and the header, which is not at the start for collections
+    create: fn(&[u8], &[u8]) -> Board,
+}
+
+// Searched in order, so entries with a detect go before the ones for the same type
+pub(crate) const MAPPERS: &[MapperEntry] = &[
+    MapperEntry { names: &["MBC1 multicart"], types: &[0x01, 0x02, 0x03], detect: Some(MBC1Multicart::detect), create: |_, _| (Box::new(MBC1Multicart::new()), Vec::new()) },
+    MapperEntry { names: &["MBC2"], types: &[0x05, 0x06], detect: None, create: |_, _| (Box::new(MBC2::new()), vec![0; mbc2::RAM_SIZE]) },
+    MapperEntry { names: &["MMM01"], types: &[0x0B, 0x0C, 0x0D], detect: None, create: |data, header| (Box::new(MMM01::new(data.len())), header_ram(header)) },
+    MapperEntry { names: &["MBC3", "MBC30"], types: &[0x0F, 0x10, 0x11, 0x12, 0x13], detect: None, create: |_, header| {
+        // MBC30 has no type byte of its own, it is the only MBC3 with more
+        // than 2 MiB of ROM or 64 KiB of RAM
+        let ram = header_ram(header);
+        let extended = header[ROM_SIZE_ADDR] > 0x06 || ram.len() > 0x8000;
+        (Box::new(MBC3::new(extended)), ram)
+    } },
+    MapperEntry { names: &["Pocket Camera"], types: &[0xFC], detect: None, create: |_, header| (Box::new(PocketCamera::new()), header_ram(header)) },
+    MapperEntry { names: &["HuC3"], types: &[0xFE], detect: None, create: |_, header| (Box::new(HuC3::new()), header_ram(header)) },
+    MapperEntry { names: &["HuC1"], types: &[0xFF], detect: None, create: |_, header| (Box::new(HuC1::new()), header_ram(header)) },
+];
+
+fn header_ram(header: &[u8]) -> Vec<u8> {
+    vec![0; ram_size_from_header(header[RAM_SIZE_ADDR])]
+}
+
+pub(crate) fn lookup(data: &[u8], header: &[u8]) -> Option<&'static MapperEntry> {
+    let ctype = header[CTYPE_ADDR];
+    MAPPERS.iter().find(|entry| entry.types.contains(&ctype) && entry.detect.is_none_or(|detect| detect(data)))
+}
+
+impl MapperEntry {
+    pub(crate) fn create(&self, data: &[u8], header: &[u8]) -> Board {
+        (self.create)(data, header)
+    }
+}
+
+// Plain 32 KiB ROM, and the fallback for the types without an emulated mapper
+pub(crate) struct RomOnly;
+
+impl Mapper for RomOnly {
+    fn describe(&self) -> String {
+        "no mapper".to_string()
+    }
+
+    fn rom_offset(&self, address: Address) -> usize {
+        address as usize
+    }
+
+    fn write_rom(&mut self, _address: Address, _value: u8) {}
+
+    fn has_registers(&self) -> bool {
+        false
+    }
+
+    fn handles_ram(&self) -> bool {
+        false
+    }
+
+    fn reset(&mut self) {}
+
+    fn save_state(&self, _writer: &mut StateWriter) {}
+
+    fn load_state(&mut self, _reader: &mut StateReader) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
+
+// The public hook for schemes without a type byte, they keep their RAM and state themselves
+pub(crate) struct Unlicensed(pub(crate) Box<dyn UnlicensedMapper>);
+
+impl Mapper for Unlicensed {
+    fn describe(&self) -> String {
+        format!("{}, ROM bank {:02X} at 4000", self.0.name(), self.0.rom_offset(0x4000) / ROM_BANK_SIZE)
+    }
+
+    fn rom_offset(&self, address: Address) -> usize {
+        self.0.rom_offset(address)
+    }
+
+    fn write_rom(&mut self, address: Address, value: u8) {
+        self.0.write_rom(address, value);
+    }
+
+    fn read_ram(&self, _ram: &[u8], address: Address) -> u8 {
+        self.0.read_ram(address)
+    }
+
+    fn write_ram(&mut self, _ram: &mut [u8], address: Address, value: u8) {
+        self.0.write_ram(address, value);
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.vec(&self.0.save_state());
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        self.0.load_state(reader.vec()?);
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{mmu::Address, savestate::{StateReader, StateWriter}};
+
+use super::{mapper::Mapper, mbc3::ROM_BANK_SIZE};
+
+// 512 half bytes inside the mapper chip, the cartridge has no RAM of its own
+pub(super) const RAM_SIZE: usize = 0x200;
+
+// MBC2, up to 256 KiB of ROM. Both registers live in 0000-3FFF and bit 8 of the
+// address tells them apart. Only the low nibble of each RAM byte exists, the high
+// one reads as 1s, and the 512 entries repeat over A000-BFFF.
+pub(crate) struct MBC2 {
+    rom_bank: u8,
+    ram_enabled: bool,
+}
+
+impl MBC2 {
+    pub(crate) fn new() -> Self {
+        MBC2 { rom_bank: 1, ram_enabled: false }
+    }
+}
+
+impl Mapper for MBC2 {
+    fn reset(&mut self) {
+        *self = MBC2::new();
+    }
+
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.u8(self.rom_bank);
+        writer.bool(self.ram_enabled);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        self.rom_bank = reader.u8()?;
+        self.ram_enabled = reader.bool()?;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("MBC2, ROM bank {:02X}, RAM {}", self.rom_bank, if self.ram_enabled { "enabled" } else { "disabled" })
+    }
+
+    fn rom_offset(&self, address: Address) -> usize {
+        match address {
+            0x0000 ..= 0x3FFF => address as usize,
+            _ => self.rom_bank as usize * ROM_BANK_SIZE + (address as usize - 0x4000),
+        }
+    }
+
+    fn write_rom(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000 ..= 0x3FFF if address & 0x0100 == 0 => self.ram_enabled = (value & 0x0F) == 0x0A,
+            0x0000 ..= 0x3FFF => {
+                let bank = value & 0x0F;
+                self.rom_bank = if bank == 0 { 1 } else { bank };
+            },
+            _ => {},
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], address: Address) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+        ram.get(address as usize & (RAM_SIZE - 1)).map_or(0xFF, |nibble| 0xF0 | nibble)
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], address: Address, value: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+        if let Some(nibble) = ram.get_mut(address as usize & (RAM_SIZE - 1)) {
+            *nibble = value & 0x0F;
+        }
+    }
+}
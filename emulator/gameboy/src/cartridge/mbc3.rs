@@ -0,0 +1,261 @@
+use crate::{mmu::Address, savestate::{StateReader, StateWriter}, CPU_CLOCK_HZ};
+
+use super::mapper::Mapper;
+
+const RAM_BANK_SIZE: usize = 0x2000;
+pub(super) const ROM_BANK_SIZE: usize = 0x4000;
+
+// MBC3 with its real time clock. The MBC30 variant found in Pokemon Crystal (Japan)
+// has an 8-bit ROM bank register (4 MiB) and 8 RAM banks (64 KiB).
+pub(crate) struct MBC3 {
+    extended: bool,
+    rom_bank: u8,
+    // 0x00-0x07 selects a RAM bank, 0x08-0x0C a clock register
+    ram_bank: u8,
+    ram_enabled: bool,
+    rtc: RealTimeClock,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct RealTimeClock {
+    pub(crate) seconds: u8,
+    pub(crate) minutes: u8,
+    pub(crate) hours: u8,
+    // 9 bits
+    pub(crate) days: u16,
+    pub(crate) halted: bool,
+    pub(crate) day_carry: bool,
+    // Registers as seen by the program, copied when the clock is latched
+    latched: [u8; 5],
+    latch_armed: bool,
+    cycles: usize,
+}
+
+impl MBC3 {
+    pub(crate) fn new(extended: bool) -> Self {
+        MBC3 { extended, rom_bank: 1, ram_bank: 0, ram_enabled: false, rtc: RealTimeClock::default() }
+    }
+
+    pub(crate) fn ram_banks(&self) -> usize {
+        if self.extended { 8 } else { 4 }
+    }
+}
+
+impl Mapper for MBC3 {
+    fn reset(&mut self) {
+        self.rom_bank = 1;
+        self.ram_bank = 0;
+        self.ram_enabled = false;
+    }
+
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.u8(self.rom_bank);
+        writer.u8(self.ram_bank);
+        writer.bool(self.ram_enabled);
+
+        let rtc = &self.rtc;
+        for register in [rtc.seconds, rtc.minutes, rtc.hours] {
+            writer.u8(register);
+        }
+        writer.u16(rtc.days);
+        writer.bool(rtc.halted);
+        writer.bool(rtc.day_carry);
+        writer.bytes(&rtc.latched);
+        writer.bool(rtc.latch_armed);
+        writer.u64(rtc.cycles as u64);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        self.rom_bank = reader.u8()?;
+        self.ram_bank = reader.u8()?;
+        self.ram_enabled = reader.bool()?;
+
+        let rtc = &mut self.rtc;
+        for register in [&mut rtc.seconds, &mut rtc.minutes, &mut rtc.hours] {
+            *register = reader.u8()?;
+        }
+        rtc.days = reader.u16()?;
+        rtc.halted = reader.bool()?;
+        rtc.day_carry = reader.bool()?;
+        rtc.latched = reader.array()?;
+        rtc.latch_armed = reader.bool()?;
+        rtc.cycles = reader.u64()? as usize;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        let ram = if self.ram_bank >= 0x08 { format!("clock register {:02X}", self.ram_bank) } else { format!("RAM bank {:02X}", self.ram_bank) };
+        format!("MBC3, ROM bank {:02X}, {} {}", self.rom_bank, ram, if self.ram_enabled { "enabled" } else { "disabled" })
+    }
+
+    // Offset inside the ROM data for a read in 0x0000-0x7FFF
+    fn rom_offset(&self, address: Address) -> usize {
+        match address {
+            0x0000 ..= 0x3FFF => address as usize,
+            _ => self.rom_bank as usize * ROM_BANK_SIZE + (address as usize - 0x4000),
+        }
+    }
+
+    fn write_rom(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000 ..= 0x1FFF => self.ram_enabled = (value & 0x0F) == 0x0A,
+            0x2000 ..= 0x3FFF => {
+                let bank = if self.extended { value } else { value & 0x7F };
+                // Bank 0 can't be mapped in the switchable area
+                self.rom_bank = if bank == 0 { 1 } else { bank };
+            },
+            0x4000 ..= 0x5FFF => self.ram_bank = value & 0x0F,
+            _ => {
+                // Writing 0x00 and then 0x01 latches the clock registers
+                if self.rtc.latch_armed && value == 0x01 {
+                    self.rtc.latch();
+                }
+                self.rtc.latch_armed = value == 0x00;
+            }
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], address: Address) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+
+        match self.ram_bank {
+            0x08 ..= 0x0C => self.rtc.latched[(self.ram_bank - 0x08) as usize],
+            bank if (bank as usize) < self.ram_banks() => {
+                let offset = bank as usize * RAM_BANK_SIZE + (address as usize - 0xA000);
+                ram.get(offset).copied().unwrap_or(0xFF)
+            },
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], address: Address, value: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+
+        match self.ram_bank {
+            0x08 ..= 0x0C => self.rtc.write_register(self.ram_bank - 0x08, value),
+            bank if (bank as usize) < self.ram_banks() => {
+                let offset = bank as usize * RAM_BANK_SIZE + (address as usize - 0xA000);
+                if let Some(byte) = ram.get_mut(offset) {
+                    *byte = value;
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn step(&mut self, _ram: &mut [u8], cycles: usize) {
+        self.rtc.tick(cycles);
+    }
+
+    // The layout of VBA-M and BGB: the registers and the latched ones as 32-bit values
+    fn save_clock(&self) -> Vec<u8> {
+        let rtc = &self.rtc;
+        let registers = [rtc.seconds, rtc.minutes, rtc.hours, (rtc.days & 0xFF) as u8, rtc.day_high()];
+        registers.iter().chain(&rtc.latched).flat_map(|register| (*register as u32).to_le_bytes()).collect()
+    }
+
+    fn load_clock(&mut self, clock: &[u8]) {
+        let registers: Vec<u8> = clock.chunks(4).map(|register| register[0]).collect();
+        for (register, value) in registers[..5].iter().enumerate() {
+            self.rtc.write_register(register as u8, *value);
+        }
+        self.rtc.latched.copy_from_slice(&registers[5..]);
+    }
+
+    fn advance_clock(&mut self, seconds: u64) {
+        self.rtc.advance(seconds);
+    }
+}
+
+impl RealTimeClock {
+    pub(crate) fn tick(&mut self, cycles: usize) {
+        if self.halted {
+            return;
+        }
+
+        self.cycles += cycles;
+        while self.cycles >= CPU_CLOCK_HZ {
+            self.cycles -= CPU_CLOCK_HZ;
+            self.advance_second();
+        }
+    }
+
+    // Catches up with the time the game was off in one go
+    fn advance(&mut self, seconds: u64) {
+        if self.halted {
+            return;
+        }
+        let minutes = (self.seconds as u64 + seconds) / 60;
+        self.seconds = ((self.seconds as u64 + seconds) % 60) as u8;
+        let hours = (self.minutes as u64 + minutes) / 60;
+        self.minutes = ((self.minutes as u64 + minutes) % 60) as u8;
+        let days = (self.hours as u64 + hours) / 24;
+        self.hours = ((self.hours as u64 + hours) % 24) as u8;
+        let days = self.days as u64 + days;
+        if days > 0x1FF {
+            self.day_carry = true;
+        }
+        self.days = (days % 0x200) as u16;
+    }
+
+    fn advance_second(&mut self) {
+        self.seconds = (self.seconds + 1) % 60;
+        if self.seconds != 0 { return }
+
+        self.minutes = (self.minutes + 1) % 60;
+        if self.minutes != 0 { return }
+
+        self.hours = (self.hours + 1) % 24;
+        if self.hours != 0 { return }
+
+        self.days += 1;
+        if self.days > 0x1FF {
+            self.days = 0;
+            self.day_carry = true;
+        }
+    }
+
+    fn latch(&mut self) {
+        self.latched = [
+            self.seconds,
+            self.minutes,
+            self.hours,
+            (self.days & 0xFF) as u8,
+            self.day_high(),
+        ];
+    }
+
+    // Bit 0: day counter bit 8, bit 6: halt, bit 7: day counter carry
+    fn day_high(&self) -> u8 {
+        ((self.days >> 8) as u8 & 0b1) | ((self.halted as u8) << 6) | ((self.day_carry as u8) << 7)
+    }
+
+    fn write_register(&mut self, register: u8, value: u8) {
+        match register {
+            0 => {
+                self.seconds = value & 0x3F;
+                // Writing the seconds resets the sub-second counter
+                self.cycles = 0;
+            },
+            1 => self.minutes = value & 0x3F,
+            2 => self.hours = value & 0x1F,
+            3 => self.days = (self.days & 0x100) | value as u16,
+            _ => {
+                self.days = (self.days & 0xFF) | (((value & 0b1) as u16) << 8);
+                self.halted = value & 0b0100_0000 != 0;
+                self.day_carry = value & 0b1000_0000 != 0;
+            }
+        }
+        self.latched[register as usize] = match register {
+            0 => self.seconds,
+            1 => self.minutes,
+            2 => self.hours,
+            3 => (self.days & 0xFF) as u8,
+            _ => self.day_high(),
+        };
+    }
+}
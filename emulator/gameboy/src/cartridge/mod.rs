@@ -0,0 +1,596 @@
+mod archive;
+mod camera;
+mod huc;
+mod mapper;
+mod mbc2;
+mod mbc3;
+mod multicart;
+mod patch;
+mod tests;
+mod unlicensed;
+
+use std::{fs, io::{ErrorKind, Read}, path::{Path, PathBuf}, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::checksum::{crc32, sha1};
+use crate::mmu::Address;
+use crate::savestate::{StateReader, StateWriter};
+use crate::compatibility::{self, CompatibilityIssue};
+use crate::error::CartridgeError;
+
+use super::gameboy::GameBoy;
+
+use mapper::{Mapper, RomOnly, Unlicensed};
+use multicart::MMM01;
+pub use camera::{ImageSource, StaticImage, CAMERA_HEIGHT, CAMERA_WIDTH};
+#[cfg(feature = "webcam")]
+pub use camera::Webcam;
+use mbc3::ROM_BANK_SIZE;
+pub use unlicensed::{unlicensed_scheme, UnlicensedMapper, UnlicensedScheme, WisdomTree, UNLICENSED_SCHEMES};
+
+// const HEADER_BEGIN: usize = 0x0100;
+const HEADER_END: usize = 0x014F;
+
+// const CARTRIDGE_SIZE: usize = HEADER_END+1;
+
+// const ENTRY_START_ADDR: usize = 0x0100;
+// const ENTRY_END_ADDR: usize = 0x0103;
+// const ENTRY_SIZE: usize = ENTRY_END_ADDR-ENTRY_START_ADDR;
+
+const LOGO_START_ADDR: usize = 0x0104;
+const LOGO_END_ADDR: usize = 0x0134;
+
+// Compared by the boot ROM with the one of the cartridge
+const NINTENDO_LOGO: [u8; LOGO_END_ADDR - LOGO_START_ADDR] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+const TITLE_START_ADDR: usize = 0x0134;
+const TITLE_END_ADDR: usize = 0x0143;
+const CGB_FLAG_ADDR: usize = 0x0143;
+
+const NEW_LICENSEE_ADDR: usize = 0x0144;
+const SGB_FLAG_ADDR: usize = 0x0146;
+
+const CTYPE_ADDR: usize = 0x0147;
+const ROM_SIZE_ADDR: usize = 0x0148;
+const RAM_SIZE_ADDR: usize = 0x0149;
+const DESTINATION_ADDR: usize = 0x014A;
+const OLD_LICENSEE_ADDR: usize = 0x014B;
+const VERSION_ADDR: usize = 0x014C;
+const HEADER_CHECKSUM_ADDR: usize = 0x014D;
+const GLOBAL_CHECKSUM_ADDR: usize = 0x014E;
+
+// Old licensee code telling the new one is used instead
+const USE_NEW_LICENSEE: u8 = 0x33;
+
+pub struct Cartridge {
+    data: Vec<u8>,
+    title: String,
+    ctype: CartridgeType,
+    crc32: u32,
+    mapper: Box<dyn Mapper>,
+    // False for the types that fell back to a plain ROM
+    mapper_emulated: bool,
+    // Made for the CGB, alone or also working on DMG
+    cgb: bool,
+    ram: Vec<u8>,
+    clock_mode: ClockMode,
+}
+
+// What the clock of MBC3 and HuC3 cartridges does with the time that passed
+// between saving the battery and loading it again
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClockMode {
+    // Catches up with the host clock, like a cartridge left on the shelf
+    #[default]
+    HostTime,
+    // Starts where it was saved, so movies and tool-assisted runs replay the same
+    Frozen,
+}
+
+// Identification of the ROM image, checked against the embedded database
+// when the rom-database feature is enabled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomInfo {
+    pub crc32: u32,
+    pub sha1: [u8; 20],
+    // Database name when the dump is known, otherwise the title in the header
+    pub title: String,
+    pub region: Option<String>,
+    pub status: DumpStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpStatus {
+    // Matches a good dump from the database
+    Verified,
+    // Not in the database: a bad dump, a hack, a patched ROM or homebrew
+    Unknown,
+    // Built without the rom-database feature
+    NotChecked,
+}
+
+// The fields of the header as stored in the ROM, for tools showing them.
+// Sizes are decoded from their codes, checksums are the stored ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CartridgeHeader {
+    pub title: String,
+    pub cgb_flag: u8,
+    pub sgb: bool,
+    // Two ASCII characters from the new code, or the old one in hex
+    pub licensee: String,
+    pub cartridge_type: u8,
+    pub rom_size: usize,
+    pub ram_size: usize,
+    pub japanese: bool,
+    pub version: u8,
+    pub header_checksum: u8,
+    // The boot ROM locks up when this doesn't match the stored checksum
+    pub computed_header_checksum: u8,
+    pub global_checksum: u16,
+}
+
+impl CartridgeHeader {
+    pub fn header_checksum_valid(&self) -> bool {
+        self.header_checksum == self.computed_header_checksum
+    }
+}
+
+impl RomInfo {
+    pub fn sha1_hex(&self) -> String {
+        self.sha1.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+// Names of the mappers the cartridge knows, for the capabilities of the core
+pub(crate) fn supported_mappers() -> Vec<&'static str> {
+    let registered = mapper::MAPPERS.iter().flat_map(|entry| entry.names.iter().copied());
+    let unlicensed = UNLICENSED_SCHEMES.iter().map(|scheme| scheme.name);
+    std::iter::once("ROM").chain(registered).chain(unlicensed).collect()
+}
+
+#[derive(Debug, Clone)]
+pub enum ROMVersion {
+    Empty, Ram, RamBattery
+}
+
+#[derive(Debug, Clone)]
+pub enum MBCExtras {
+    Empty, Ram, RamBattery
+}
+
+#[derive(Debug, Clone)]
+pub enum MBC2Extras {
+    Empty, Battery
+}
+
+#[derive(Debug, Clone)]
+pub enum MBC3Extras {
+    Empty, Ram, RamBattery, TimerBattery, TimerRamBattery
+}
+
+#[derive(Debug, Clone)]
+pub enum MBC5Extras {
+    Empty, Ram, RamBattery, Rumble, RumbleRam, RumbleRamBattery
+}
+
+#[derive(Debug, Clone)]
+pub enum CartridgeType {
+    ROM(ROMVersion), 
+    MBC1(MBCExtras), 
+    MBC2(MBC2Extras), 
+    MMM01(MBCExtras), 
+    MBC3(MBC3Extras),
+    MBC5(MBC5Extras),
+    MBC6,
+    MBC7,
+    PocketCamera,
+    Tama5,
+    HuC3,
+    HuC1,
+    Unknown(u8)
+}
+
+impl Cartridge {
+    // The loaders only refuse a ROM too small for its header, so homebrew and
+    // bad dumps still run. Call verify_header to get BadLogo, BadChecksum or
+    // UnsupportedMapper.
+    //
+    // .zip and .gz files are opened with the archives feature
+    pub fn new(file: PathBuf) -> Result<Cartridge, CartridgeError> {
+        let data = archive::read_rom(&file)?;
+        Cartridge::from_data(data)
+    }
+
+    // A ROM from memory, already out of its archive
+    pub fn from_bytes(bytes: &[u8]) -> Result<Cartridge, CartridgeError> {
+        Cartridge::from_data(bytes.to_vec())
+    }
+
+    // Reads the whole ROM, from the network or a decompressor, failing past 8 MiB
+    pub fn from_reader<R: Read>(reader: R) -> Result<Cartridge, CartridgeError> {
+        Cartridge::from_data(archive::read_limited(reader)?)
+    }
+
+    // The IPS or BPS patch is applied in memory, the ROM file is left untouched
+    pub fn with_patch(file: PathBuf, patch: PathBuf) -> Result<Cartridge, CartridgeError> {
+        let data = archive::read_rom(&file)?;
+        let patch = std::fs::read(patch)?;
+        Cartridge::from_data(patch::apply_patch(data, &patch)?)
+    }
+
+    pub(crate) fn from_data(data: Vec<u8>) -> Result<Cartridge, CartridgeError> {
+        if data.len() <= HEADER_END {
+            return Err(CartridgeError::TooSmall { size: data.len() });
+        }
+
+        // Collections have the header of their menu at the end
+        let header = if MMM01::detect(&data) { MMM01::menu(&data) } else { &data[..] };
+        let title = parse_title(header);
+        let ctype = CartridgeType::from(header[CTYPE_ADDR]);
+        let crc32 = crc32(&data);
+        let cgb = header[CGB_FLAG_ADDR] & 0x80 != 0;
+        let (mapper, ram, mapper_emulated) = Cartridge::mapper_from_header(&data, header);
+
+        Ok(Cartridge { data, title, ctype, crc32, mapper, mapper_emulated, cgb, ram, clock_mode: ClockMode::default() })
+    }
+
+    // The registry is keyed by the type byte, schemes without one are found by their detection
+    fn mapper_from_header(data: &[u8], header: &[u8]) -> (Box<dyn Mapper>, Vec<u8>, bool) {
+        if let Some(entry) = mapper::lookup(data, header) {
+            let (mapper, ram) = entry.create(data, header);
+            return (mapper, ram, true);
+        }
+        match unlicensed::detect(data) {
+            Some(mapper) => (Box::new(Unlicensed(mapper)), Vec::new(), true),
+            None => (Box::new(RomOnly), Vec::new(), false),
+        }
+    }
+
+    // For cartridges whose bank switching can't be detected from the ROM
+    pub fn set_unlicensed_mapper(&mut self, mapper: Box<dyn UnlicensedMapper>) {
+        self.mapper = Box::new(Unlicensed(mapper));
+        self.mapper_emulated = true;
+        self.ram = Vec::new();
+    }
+
+    // Pictures taken by the Game Boy Camera come from here, a gray
+    // picture without one. Other cartridges ignore it.
+    pub fn set_image_source(&mut self, source: Box<dyn ImageSource>) {
+        self.mapper.set_image_source(source);
+    }
+
+    // game.gb keeps its battery backed RAM in game.sav
+    pub fn battery_path_for<P: AsRef<Path>>(rom: P) -> PathBuf {
+        rom.as_ref().with_extension("sav")
+    }
+
+    pub fn has_battery(&self) -> bool {
+        self.ctype.has_battery() && (!self.ram.is_empty() || self.ctype.has_clock())
+    }
+
+    // Set before loading the battery
+    pub fn set_clock_mode(&mut self, mode: ClockMode) {
+        self.clock_mode = mode;
+    }
+
+    // A missing file leaves the RAM blank, like a new cartridge
+    pub fn load_battery<P: AsRef<Path>>(&mut self, path: P) -> Result<(), std::io::Error> {
+        self.load_battery_at(path, SystemTime::now())
+    }
+
+    pub(crate) fn load_battery_at<P: AsRef<Path>>(&mut self, path: P, now: SystemTime) -> Result<(), std::io::Error> {
+        if !self.has_battery() {
+            return Ok(());
+        }
+        match fs::read(path) {
+            Ok(data) => {
+                let size = data.len().min(self.ram.len());
+                self.ram[..size].copy_from_slice(&data[..size]);
+                // Saves of other emulators may have no clock
+                if self.ctype.has_clock() && data.len() > self.ram.len() {
+                    self.load_clock(&data[self.ram.len()..], now)?;
+                }
+                Ok(())
+            },
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn save_battery<P: AsRef<Path>>(&self, path: P) -> Result<(), std::io::Error> {
+        if !self.has_battery() {
+            return Ok(());
+        }
+        let mut data = self.ram.clone();
+        if self.ctype.has_clock() {
+            data.extend(self.mapper.save_clock());
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            data.extend(timestamp.to_le_bytes());
+        }
+        fs::write(path, data)
+    }
+
+    // The clock registers followed by the time they were saved at, in seconds
+    // since 1970. Some emulators store that time in 32 bits.
+    fn load_clock(&mut self, footer: &[u8], now: SystemTime) -> Result<(), std::io::Error> {
+        let invalid = || std::io::Error::new(ErrorKind::InvalidData, "The clock of the battery save has a wrong size");
+        let clock_size = self.mapper.save_clock().len();
+        if footer.len() < clock_size {
+            return Err(invalid());
+        }
+        let (clock, timestamp) = footer.split_at(clock_size);
+        let saved_at = match timestamp.len() {
+            4 => u32::from_le_bytes(timestamp.try_into().unwrap()) as u64,
+            8 => u64::from_le_bytes(timestamp.try_into().unwrap()),
+            _ => return Err(invalid()),
+        };
+
+        self.mapper.load_clock(clock);
+        if self.clock_mode == ClockMode::HostTime {
+            // A time past what SystemTime holds comes from a corrupt save, like one in the future
+            let elapsed = UNIX_EPOCH.checked_add(Duration::from_secs(saved_at))
+                .and_then(|saved| now.duration_since(saved).ok())
+                .unwrap_or_default();
+            self.mapper.advance_clock(elapsed.as_secs());
+        }
+        Ok(())
+    }
+
+    pub fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    pub fn ctype(&self) -> CartridgeType {
+        self.ctype.clone()
+    }
+
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+
+    pub fn supports_cgb(&self) -> bool {
+        self.cgb
+    }
+
+    pub fn header(&self) -> CartridgeHeader {
+        let header = if MMM01::detect(&self.data) { MMM01::menu(&self.data) } else { &self.data[..] };
+        let licensee = match header[OLD_LICENSEE_ADDR] {
+            USE_NEW_LICENSEE => String::from_utf8_lossy(&header[NEW_LICENSEE_ADDR..NEW_LICENSEE_ADDR + 2]).to_string(),
+            code => format!("{:02X}", code),
+        };
+        let computed_header_checksum = header[TITLE_START_ADDR..HEADER_CHECKSUM_ADDR].iter()
+            .fold(0u8, |checksum, byte| checksum.wrapping_sub(*byte).wrapping_sub(1));
+
+        CartridgeHeader {
+            title: self.title.clone(),
+            cgb_flag: header[CGB_FLAG_ADDR],
+            sgb: header[SGB_FLAG_ADDR] == 0x03,
+            licensee,
+            cartridge_type: header[CTYPE_ADDR],
+            rom_size: 0x8000 << header[ROM_SIZE_ADDR].min(8),
+            ram_size: ram_size_from_header(header[RAM_SIZE_ADDR]),
+            japanese: header[DESTINATION_ADDR] == 0x00,
+            version: header[VERSION_ADDR],
+            header_checksum: header[HEADER_CHECKSUM_ADDR],
+            computed_header_checksum,
+            global_checksum: u16::from_be_bytes([header[GLOBAL_CHECKSUM_ADDR], header[GLOBAL_CHECKSUM_ADDR + 1]]),
+        }
+    }
+
+    pub fn rom_info(&self) -> RomInfo {
+        let sha1 = sha1(&self.data);
+
+        #[cfg(feature = "rom-database")]
+        if let Some(entry) = crate::romdb::lookup(self.crc32, &sha1) {
+            return RomInfo {
+                crc32: self.crc32,
+                sha1,
+                title: entry.title().to_string(),
+                region: entry.region().map(String::from),
+                status: DumpStatus::Verified,
+            };
+        }
+
+        let status = if cfg!(feature = "rom-database") { DumpStatus::Unknown } else { DumpStatus::NotChecked };
+        RomInfo { crc32: self.crc32, sha1, title: self.title.clone(), region: None, status }
+    }
+
+    // What the boot ROM checks before starting the game, then whether the
+    // mapper is emulated. Games still load and run when this fails.
+    pub fn verify_header(&self) -> Result<(), CartridgeError> {
+        if self.data[LOGO_START_ADDR..LOGO_END_ADDR] != NINTENDO_LOGO {
+            return Err(CartridgeError::BadLogo);
+        }
+        let header = self.header();
+        if !header.header_checksum_valid() {
+            return Err(CartridgeError::BadChecksum { stored: header.header_checksum, computed: header.computed_header_checksum });
+        }
+        if self.compatibility_issues().contains(&CompatibilityIssue::UnsupportedMapper) {
+            return Err(CartridgeError::UnsupportedMapper { cartridge_type: header.cartridge_type });
+        }
+        Ok(())
+    }
+
+    // Known problems for this ROM, from the compatibility table and from the header
+    pub fn compatibility_issues(&self) -> Vec<CompatibilityIssue> {
+        let mut issues = Vec::new();
+
+        // Types without an emulated mapper fall back to a plain ROM
+        let plain_rom = matches!(self.ctype, CartridgeType::ROM(_));
+        if !self.mapper_emulated && !plain_rom {
+            issues.push(CompatibilityIssue::UnsupportedMapper);
+        }
+
+        if let Some(game) = compatibility::lookup(self.crc32) {
+            for issue in game.issues {
+                if !issues.contains(issue) {
+                    issues.push(*issue);
+                }
+            }
+        }
+
+        issues
+    }
+
+    pub(crate) fn read_byte(gb: &GameBoy, address: Address) -> u8 {
+        if let Some(cartridge) = &gb.cartridge {
+            cartridge.mapper.read_rom(&cartridge.data, address)
+        }else{
+            // Reading ROM area without cartridge
+            0xFF
+        }
+    }
+
+    fn rom_offset(&self, address: Address) -> usize {
+        // Banks beyond the end of the ROM mirror the beginning
+        self.mapper.rom_offset(address) % self.data.len().max(1)
+    }
+
+    // ROM bank mapped at a ROM address, like the bank numbers of symbol files
+    pub(crate) fn rom_bank(gb: &GameBoy, address: Address) -> u16 {
+        gb.cartridge.as_ref().map_or(0, |cartridge| (cartridge.rom_offset(address) / ROM_BANK_SIZE) as u16)
+    }
+
+    // Mapper and the banks it has selected, for state dumps
+    pub(crate) fn describe_mapper(&self) -> String {
+        self.mapper.describe()
+    }
+
+    // Writes to the ROM area are commands for the mapper
+    pub(crate) fn write_byte(gb: &mut GameBoy, address: Address, value: u8) {
+        if let Some(cartridge) = &mut gb.cartridge {
+            cartridge.mapper.write_rom(address, value);
+            tracing::debug!(target: "yagabor::mapper", address, value, pc = gb.cpu.pc, banks = %cartridge.mapper.describe());
+        }
+    }
+
+    // Some mapper takes the writes to the ROM area
+    pub(crate) fn has_mapper(gb: &GameBoy) -> bool {
+        gb.cartridge.as_ref().is_some_and(|cartridge| cartridge.mapper.has_registers())
+    }
+
+    // Without a mapper the external RAM area is served by the MMU
+    pub(crate) fn handles_ram(gb: &GameBoy) -> bool {
+        gb.cartridge.as_ref().is_some_and(|cartridge| cartridge.mapper.handles_ram())
+    }
+
+    pub(crate) fn read_ram(gb: &GameBoy, address: Address) -> u8 {
+        match &gb.cartridge {
+            Some(Cartridge { mapper, ram, .. }) => mapper.read_ram(ram, address),
+            None => 0xFF
+        }
+    }
+
+    pub(crate) fn write_ram(gb: &mut GameBoy, address: Address, value: u8) {
+        if let Some(Cartridge { mapper, ram, .. }) = &mut gb.cartridge {
+            mapper.write_ram(ram, address, value);
+        }
+    }
+
+    // Mapper registers go back to their power on values, RAM and clock are battery backed.
+    pub(crate) fn reset(&mut self) {
+        self.mapper.reset();
+    }
+
+    // The ROM itself is not stored, the savestate checks it was made with the same one
+    pub(crate) fn save_state(gb: &GameBoy, writer: &mut StateWriter) {
+        if let Some(cartridge) = &gb.cartridge {
+            writer.vec(&cartridge.ram);
+            cartridge.mapper.save_state(writer);
+        }
+    }
+
+    pub(crate) fn load_state(gb: &mut GameBoy, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        if let Some(cartridge) = &mut gb.cartridge {
+            reader.vec_into(&mut cartridge.ram)?;
+            cartridge.mapper.load_state(reader)?;
+        }
+        Ok(())
+    }
+
+    // Advances the cartridge clock or the camera capture
+    pub(crate) fn tick(gb: &mut GameBoy, cycles: usize) {
+        if let Some(Cartridge { mapper, ram, .. }) = &mut gb.cartridge {
+            mapper.step(ram, cycles);
+        }
+    }
+}
+
+fn ram_size_from_header(byte: u8) -> usize {
+    match byte {
+        0x02 => 0x2000,
+        0x03 => 0x8000,
+        0x04 => 0x20000,
+        0x05 => 0x10000,
+        _ => 0
+    }
+}
+
+fn parse_title(buffer: &[u8]) -> String {
+    let start = TITLE_START_ADDR;
+    let end = TITLE_END_ADDR;
+
+    // Titles are ASCII, but the last bytes are reused by later headers.
+    // Anything else is replaced so the title is safe to print and to put in paths.
+    buffer[start..end].iter()
+        .map(|byte| if byte.is_ascii_graphic() || *byte == b' ' || *byte == 0 { char::from(*byte) } else { '?' })
+        .collect::<String>()
+        .trim_matches(char::from(0))
+        .replace(char::from(0), "?")
+}
+
+impl CartridgeType {
+    pub fn has_battery(&self) -> bool {
+        matches!(self,
+            CartridgeType::ROM(ROMVersion::RamBattery) | CartridgeType::MBC1(MBCExtras::RamBattery) |
+            CartridgeType::MBC2(MBC2Extras::Battery) | CartridgeType::MMM01(MBCExtras::RamBattery) |
+            CartridgeType::MBC3(MBC3Extras::RamBattery | MBC3Extras::TimerBattery | MBC3Extras::TimerRamBattery) |
+            CartridgeType::MBC5(MBC5Extras::RamBattery | MBC5Extras::RumbleRamBattery) |
+            CartridgeType::PocketCamera | CartridgeType::HuC1 | CartridgeType::HuC3)
+    }
+
+    pub fn has_clock(&self) -> bool {
+        matches!(self, CartridgeType::MBC3(MBC3Extras::TimerBattery | MBC3Extras::TimerRamBattery) | CartridgeType::HuC3)
+    }
+}
+
+impl std::convert::From<u8> for CartridgeType {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0x00 => CartridgeType::ROM(ROMVersion::Empty),
+            0x01 => CartridgeType::MBC1(MBCExtras::Empty),
+            0x02 => CartridgeType::MBC1(MBCExtras::Ram),
+            0x03 => CartridgeType::MBC1(MBCExtras::RamBattery),
+            0x05 => CartridgeType::MBC2(MBC2Extras::Empty),
+            0x06 => CartridgeType::MBC2(MBC2Extras::Battery),
+            0x08 => CartridgeType::ROM(ROMVersion::Ram),
+            0x09 => CartridgeType::ROM(ROMVersion::RamBattery),
+            0x0B => CartridgeType::MMM01(MBCExtras::Empty),
+            0x0C => CartridgeType::MMM01(MBCExtras::Ram),
+            0x0D => CartridgeType::MMM01(MBCExtras::RamBattery),
+            0x0F => CartridgeType::MBC3(MBC3Extras::TimerBattery),
+            0x10 => CartridgeType::MBC3(MBC3Extras::TimerRamBattery),
+            0x11 => CartridgeType::MBC3(MBC3Extras::Empty),
+            0x12 => CartridgeType::MBC3(MBC3Extras::Ram),
+            0x13 => CartridgeType::MBC3(MBC3Extras::RamBattery),
+            0x19 => CartridgeType::MBC5(MBC5Extras::Empty),
+            0x1A => CartridgeType::MBC5(MBC5Extras::Ram),
+            0x1B => CartridgeType::MBC5(MBC5Extras::RamBattery),
+            0x1C => CartridgeType::MBC5(MBC5Extras::Rumble),
+            0x1D => CartridgeType::MBC5(MBC5Extras::RumbleRam),
+            0x1E => CartridgeType::MBC5(MBC5Extras::RumbleRamBattery),
+            0x20 => CartridgeType::MBC6,
+            0x22 => CartridgeType::MBC7,
+            0xFC => CartridgeType::PocketCamera,
+            0xFD => CartridgeType::Tama5,
+            0xFE => CartridgeType::HuC3,
+            0xFF => CartridgeType::HuC1,
+            byte => CartridgeType::Unknown(byte)
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,244 @@
+use crate::{mmu::Address, savestate::{StateReader, StateWriter}};
+
+use super::{mapper::Mapper, mbc3::ROM_BANK_SIZE, CTYPE_ADDR};
+
+const RAM_BANK_SIZE: usize = 0x2000;
+const MENU_SIZE: usize = 0x8000;
+const LOGO_ADDR: usize = 0x0104;
+const LOGO_SIZE: usize = 0x30;
+// Every game of an MBC1 multicart starts at a 256 KiB boundary
+const MBC1M_GAME_SIZE: usize = 0x40000;
+const MBC1M_ROM_SIZE: usize = 0x100000;
+
+// MMM01, the mapper of the collection cartridges like Momotarou Collection 2. At power on
+// the last 32 KiB of the ROM are mapped, where the menu and its header are. The menu
+// picks the outer ROM and RAM banks of a game, freezes them and sets bit 6 of
+// 0000-1FFF: from then on the game sees an MBC1 and can't get out of its banks.
+// The multiplexing of the address lines is not emulated.
+pub(crate) struct MMM01 {
+    rom_banks: usize,
+    mapped: bool,
+    ram_enabled: bool,
+    rom_bank_low: u8,
+    rom_bank_mid: u8,
+    rom_bank_high: u8,
+    // Bits 1-4 of the low ROM bank the game can't change
+    rom_bank_mask: u8,
+    ram_bank_low: u8,
+    ram_bank_high: u8,
+    mbc1_mode: bool,
+    mbc1_mode_locked: bool,
+}
+
+impl MMM01 {
+    pub(crate) fn new(rom_size: usize) -> Self {
+        MMM01 {
+            rom_banks: (rom_size / ROM_BANK_SIZE).max(2),
+            mapped: false,
+            ram_enabled: false,
+            rom_bank_low: 0,
+            rom_bank_mid: 0,
+            rom_bank_high: 0,
+            rom_bank_mask: 0,
+            ram_bank_low: 0,
+            ram_bank_high: 0,
+            mbc1_mode: false,
+            mbc1_mode_locked: false,
+        }
+    }
+
+    // The menu header sits at the end of the dump, the one at the start is the first game's
+    pub(crate) fn detect(data: &[u8]) -> bool {
+        data.len() > MENU_SIZE && matches!(data[data.len() - MENU_SIZE + CTYPE_ADDR], 0x0B ..= 0x0D)
+    }
+
+    // The header of the menu, for the title and the cartridge type
+    pub(crate) fn menu(data: &[u8]) -> &[u8] {
+        &data[data.len() - MENU_SIZE..]
+    }
+
+    fn rom_bank(&self, address: Address) -> usize {
+        if !self.mapped {
+            return self.rom_banks - if address < 0x4000 { 2 } else { 1 };
+        }
+        let outer = (self.rom_bank_high as usize) << 7 | (self.rom_bank_mid as usize) << 5;
+        let frozen = self.rom_bank_mask << 1;
+        if address < 0x4000 {
+            return outer | (self.rom_bank_low & frozen) as usize;
+        }
+        // Like MBC1, bank 0 of the game can't be mapped at 4000
+        let low = if self.rom_bank_low & !frozen & 0x1F == 0 { self.rom_bank_low | 1 } else { self.rom_bank_low };
+        outer | low as usize
+    }
+
+    fn ram_bank(&self) -> usize {
+        let low = if self.mbc1_mode { self.ram_bank_low } else { 0 };
+        (self.ram_bank_high << 2 | low) as usize
+    }
+}
+
+impl Mapper for MMM01 {
+    // Back to the menu
+    fn reset(&mut self) {
+        *self = MMM01::new(self.rom_banks * ROM_BANK_SIZE);
+    }
+
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.bool(self.mapped);
+        writer.bool(self.ram_enabled);
+        for register in [self.rom_bank_low, self.rom_bank_mid, self.rom_bank_high, self.rom_bank_mask, self.ram_bank_low, self.ram_bank_high] {
+            writer.u8(register);
+        }
+        writer.bool(self.mbc1_mode);
+        writer.bool(self.mbc1_mode_locked);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        self.mapped = reader.bool()?;
+        self.ram_enabled = reader.bool()?;
+        for register in [&mut self.rom_bank_low, &mut self.rom_bank_mid, &mut self.rom_bank_high, &mut self.rom_bank_mask, &mut self.ram_bank_low, &mut self.ram_bank_high] {
+            *register = reader.u8()?;
+        }
+        self.mbc1_mode = reader.bool()?;
+        self.mbc1_mode_locked = reader.bool()?;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        if !self.mapped {
+            return "MMM01, menu mapped".to_string();
+        }
+        format!("MMM01, ROM banks {:02X} and {:02X}, RAM bank {:02X} {}",
+            self.rom_bank(0x0000), self.rom_bank(0x4000), self.ram_bank(), if self.ram_enabled { "enabled" } else { "disabled" })
+    }
+
+    fn rom_offset(&self, address: Address) -> usize {
+        self.rom_bank(address) * ROM_BANK_SIZE + (address as usize & (ROM_BANK_SIZE - 1))
+    }
+
+    // Writes to the outer bank bits only count before the mapping is set
+    fn write_rom(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000 ..= 0x1FFF => {
+                self.ram_enabled = (value & 0x0F) == 0x0A;
+                if !self.mapped && value & 0x40 != 0 {
+                    self.mapped = true;
+                }
+            },
+            0x2000 ..= 0x3FFF => {
+                let writable = if self.mapped { !(self.rom_bank_mask << 1) & 0x1F } else { 0x1F };
+                self.rom_bank_low = (self.rom_bank_low & !writable) | (value & writable);
+                if !self.mapped {
+                    self.rom_bank_mid = (value >> 5) & 0x03;
+                }
+            },
+            0x4000 ..= 0x5FFF => {
+                self.ram_bank_low = value & 0x03;
+                if !self.mapped {
+                    self.ram_bank_high = (value >> 2) & 0x03;
+                    self.rom_bank_high = (value >> 4) & 0x03;
+                    self.mbc1_mode_locked = value & 0x40 != 0;
+                }
+            },
+            _ => {
+                if !self.mapped || !self.mbc1_mode_locked {
+                    self.mbc1_mode = value & 0x01 != 0;
+                }
+                if !self.mapped {
+                    self.rom_bank_mask = (value >> 2) & 0x0F;
+                }
+            },
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], address: Address) -> u8 {
+        if !self.ram_enabled || ram.is_empty() {
+            return 0xFF;
+        }
+        ram[(self.ram_bank() * RAM_BANK_SIZE + (address as usize - 0xA000)) % ram.len()]
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], address: Address, value: u8) {
+        if !self.ram_enabled || ram.is_empty() {
+            return;
+        }
+        let size = ram.len();
+        ram[(self.ram_bank() * RAM_BANK_SIZE + (address as usize - 0xA000)) % size] = value;
+    }
+}
+
+// MBC1M, the MBC1 wiring of Mortal Kombat I & II, Bomberman Collection and other
+// 1 MiB compilations: bit 4 of the ROM bank register is not connected, so the two
+// upper bits select one of four 256 KiB games. In mode 1 they also switch the bank
+// at 0000 to the first bank of the game, which is how the menu starts one. The
+// header says MBC1, they are told apart by the logo at the start of every game.
+pub(crate) struct MBC1Multicart {
+    rom_bank: u8,
+    game: u8,
+    mode: bool,
+}
+
+impl MBC1Multicart {
+    pub(crate) fn new() -> Self {
+        MBC1Multicart { rom_bank: 1, game: 0, mode: false }
+    }
+
+    pub(crate) fn detect(data: &[u8]) -> bool {
+        if data.len() != MBC1M_ROM_SIZE || !matches!(data[CTYPE_ADDR], 0x01 ..= 0x03) {
+            return false;
+        }
+        let logo = &data[LOGO_ADDR..LOGO_ADDR + LOGO_SIZE];
+        // The menu and at least the second game
+        &data[MBC1M_GAME_SIZE + LOGO_ADDR..][..LOGO_SIZE] == logo
+    }
+}
+
+impl Mapper for MBC1Multicart {
+    fn reset(&mut self) {
+        *self = MBC1Multicart::new();
+    }
+
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.u8(self.rom_bank);
+        writer.u8(self.game);
+        writer.bool(self.mode);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        self.rom_bank = reader.u8()?;
+        self.game = reader.u8()?;
+        self.mode = reader.bool()?;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("MBC1 multicart, game {}, ROM bank {:02X}, mode {}", self.game, self.rom_offset(0x4000) / ROM_BANK_SIZE, self.mode as u8)
+    }
+
+    fn rom_offset(&self, address: Address) -> usize {
+        let bank = match address {
+            0x0000 ..= 0x3FFF if self.mode => self.game << 4,
+            0x0000 ..= 0x3FFF => 0,
+            _ => self.game << 4 | (self.rom_bank & 0x0F),
+        };
+        bank as usize * ROM_BANK_SIZE + (address as usize & (ROM_BANK_SIZE - 1))
+    }
+
+    // There is no RAM, its enable register is ignored
+    fn handles_ram(&self) -> bool {
+        false
+    }
+
+    fn write_rom(&mut self, address: Address, value: u8) {
+        match address {
+            0x0000 ..= 0x1FFF => {},
+            0x2000 ..= 0x3FFF => {
+                // The zero check still sees all 5 bits
+                let bank = value & 0x1F;
+                self.rom_bank = if bank == 0 { 1 } else { bank };
+            },
+            0x4000 ..= 0x5FFF => self.game = value & 0x03,
+            _ => self.mode = value & 0x01 != 0,
+        }
+    }
+}
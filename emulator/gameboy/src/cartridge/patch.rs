@@ -0,0 +1,187 @@
+use std::io::{Error, ErrorKind};
+
+use crate::checksum::crc32;
+
+use super::archive::MAX_ROM_SIZE;
+
+const IPS_MAGIC: &[u8] = b"PATCH";
+const IPS_EOF: &[u8] = b"EOF";
+const BPS_MAGIC: &[u8] = b"BPS1";
+// Source, target and patch checksums
+const BPS_FOOTER_SIZE: usize = 12;
+
+fn malformed(format: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Malformed {} patch", format))
+}
+
+// Applies an IPS or BPS patch, detected by its header, to a ROM image
+pub(crate) fn apply_patch(rom: Vec<u8>, patch: &[u8]) -> Result<Vec<u8>, Error> {
+    if patch.starts_with(IPS_MAGIC) {
+        apply_ips(rom, patch)
+    } else if patch.starts_with(BPS_MAGIC) {
+        apply_bps(&rom, patch)
+    } else {
+        Err(Error::new(ErrorKind::InvalidData, "Unknown patch format, expected IPS or BPS"))
+    }
+}
+
+// https://zerosoft.zophar.net/ips.php
+fn apply_ips(mut rom: Vec<u8>, patch: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut reader = PatchReader { data: patch, position: IPS_MAGIC.len(), format: "IPS" };
+
+    loop {
+        let record = reader.bytes(3)?;
+        if record == IPS_EOF {
+            break;
+        }
+        let offset = (record[0] as usize) << 16 | (record[1] as usize) << 8 | record[2] as usize;
+        let size = reader.u16_be()? as usize;
+
+        // A zero size is a run of the same byte
+        let chunk = if size == 0 {
+            let count = reader.u16_be()? as usize;
+            vec![reader.byte()?; count]
+        } else {
+            reader.bytes(size)?.to_vec()
+        };
+
+        if rom.len() < offset + chunk.len() {
+            rom.resize(offset + chunk.len(), 0);
+        }
+        rom[offset..offset + chunk.len()].copy_from_slice(&chunk);
+    }
+
+    // Optional extension: the size to truncate the ROM to
+    if let Ok(size) = reader.bytes(3) {
+        rom.truncate((size[0] as usize) << 16 | (size[1] as usize) << 8 | size[2] as usize);
+    }
+
+    Ok(rom)
+}
+
+// https://github.com/blakesmith/rombp/blob/master/docs/bps_spec.md
+fn apply_bps(source: &[u8], patch: &[u8]) -> Result<Vec<u8>, Error> {
+    if patch.len() < BPS_MAGIC.len() + BPS_FOOTER_SIZE {
+        return Err(malformed("BPS"));
+    }
+
+    let footer = &patch[patch.len() - BPS_FOOTER_SIZE..];
+    let checksum = |bytes: &[u8]| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    if crc32(&patch[..patch.len() - 4]) != checksum(&footer[8..]) {
+        return Err(malformed("BPS"));
+    }
+    if crc32(source) != checksum(&footer[..4]) {
+        return Err(Error::new(ErrorKind::InvalidData, "The BPS patch was made for a different ROM"));
+    }
+
+    let mut reader = PatchReader { data: &patch[..patch.len() - BPS_FOOTER_SIZE], position: BPS_MAGIC.len(), format: "BPS" };
+    let source_size = reader.number()?;
+    let target_size = reader.number()?;
+    let metadata_size = reader.number()?;
+    reader.bytes(metadata_size)?;
+
+    if source_size != source.len() {
+        return Err(Error::new(ErrorKind::InvalidData, "The BPS patch was made for a different ROM"));
+    }
+    // Sizes come from the patch, nothing is allocated or copied past what a ROM can be
+    if target_size > MAX_ROM_SIZE {
+        return Err(malformed("BPS"));
+    }
+
+    let mut target: Vec<u8> = Vec::with_capacity(target_size);
+    let mut source_offset: usize = 0;
+    let mut target_offset: usize = 0;
+
+    while !reader.is_done() {
+        let command = reader.number()?;
+        let length = (command >> 2) + 1;
+        if target.len().checked_add(length).is_none_or(|end| end > target_size) {
+            return Err(malformed("BPS"));
+        }
+
+        match command & 0b11 {
+            // SourceRead: same bytes as the source at the same position
+            0 => {
+                let start = target.len();
+                let bytes = source.get(start..start + length).ok_or_else(|| malformed("BPS"))?;
+                target.extend_from_slice(bytes);
+            },
+            // TargetRead: bytes stored in the patch
+            1 => target.extend_from_slice(reader.bytes(length)?),
+            // SourceCopy: bytes from anywhere in the source
+            2 => {
+                source_offset = reader.relative_offset(source_offset)?;
+                let end = source_offset.checked_add(length).ok_or_else(|| malformed("BPS"))?;
+                target.extend_from_slice(source.get(source_offset..end).ok_or_else(|| malformed("BPS"))?);
+                source_offset = end;
+            },
+            // TargetCopy: bytes already written, the ranges can overlap
+            _ => {
+                target_offset = reader.relative_offset(target_offset)?;
+                for _ in 0..length {
+                    let byte = *target.get(target_offset).ok_or_else(|| malformed("BPS"))?;
+                    target.push(byte);
+                    target_offset += 1;
+                }
+            },
+        }
+    }
+
+    if target.len() != target_size || crc32(&target) != checksum(&footer[4..8]) {
+        return Err(malformed("BPS"));
+    }
+
+    Ok(target)
+}
+
+struct PatchReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    format: &'static str,
+}
+
+impl<'a> PatchReader<'a> {
+    fn is_done(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], Error> {
+        let end = self.position.checked_add(count).ok_or_else(|| malformed(self.format))?;
+        let bytes = self.data.get(self.position..end).ok_or_else(|| malformed(self.format))?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16_be(&mut self) -> Result<u16, Error> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    // BPS variable length number, 7 bits per byte with the last byte flagged by bit 7
+    fn number(&mut self) -> Result<usize, Error> {
+        let mut value: usize = 0;
+        let mut shift: usize = 1;
+        loop {
+            let byte = self.byte()?;
+            let digit = ((byte & 0x7F) as usize).checked_mul(shift).ok_or_else(|| malformed(self.format))?;
+            value = value.checked_add(digit).ok_or_else(|| malformed(self.format))?;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+            shift = shift.checked_mul(128).ok_or_else(|| malformed(self.format))?;
+            value = value.checked_add(shift).ok_or_else(|| malformed(self.format))?;
+        }
+    }
+
+    // Bit 0 is the sign of the offset
+    fn relative_offset(&mut self, current: usize) -> Result<usize, Error> {
+        let data = self.number()?;
+        let delta = data >> 1;
+        let offset = if data & 1 != 0 { current.checked_sub(delta) } else { current.checked_add(delta) };
+        offset.ok_or_else(|| malformed(self.format))
+    }
+}
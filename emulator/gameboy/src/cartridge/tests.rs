@@ -0,0 +1,533 @@
+#[cfg(test)]
+use std::time::{Duration, SystemTime};
+
+#[cfg(test)]
+use crate::{accuracy::EmulationConfig, cartridge::{Cartridge, CartridgeType, ClockMode, StaticImage, patch::apply_patch}, checksum::crc32, compatibility::CompatibilityIssue, error::CartridgeError, gameboy::GameBoy, mmu::MMU, ResetKind};
+
+// A ROM where every bank is filled with its own number
+#[cfg(test)]
+fn banked_rom(ctype: u8, rom_size_code: u8, ram_size_code: u8) -> Vec<u8> {
+    let banks = 2usize << rom_size_code;
+    let mut data = Vec::with_capacity(banks * 0x4000);
+    for bank in 0..banks {
+        data.extend(std::iter::repeat_n(bank as u8, 0x4000));
+    }
+    data[0x0147] = ctype;
+    data[0x0148] = rom_size_code;
+    data[0x0149] = ram_size_code;
+    data
+}
+
+#[test]
+fn mbc3_switches_rom_banks() {
+    let cartridge = Cartridge::from_data(banked_rom(0x13, 0x06, 0x03)).unwrap();
+    let mut gb = GameBoy::new(Some(cartridge), EmulationConfig::default());
+
+    assert_eq!(MMU::read_byte(&gb, 0x4000), 1);
+
+    MMU::write_byte(&mut gb, 0x2000, 0x05);
+    assert_eq!(MMU::read_byte(&gb, 0x4000), 5);
+
+    // Bank 0 is remapped to bank 1
+    MMU::write_byte(&mut gb, 0x2000, 0x00);
+    assert_eq!(MMU::read_byte(&gb, 0x4000), 1);
+
+    // Only 7 bits are used by a regular MBC3
+    MMU::write_byte(&mut gb, 0x2000, 0x81);
+    assert_eq!(MMU::read_byte(&gb, 0x4000), 1);
+}
+
+#[test]
+fn mbc30_uses_8bit_rom_banks_and_8_ram_banks() {
+    let cartridge = Cartridge::from_data(banked_rom(0x10, 0x07, 0x05)).unwrap();
+    let mut gb = GameBoy::new(Some(cartridge), EmulationConfig::default());
+
+    MMU::write_byte(&mut gb, 0x2000, 0x81);
+    assert_eq!(MMU::read_byte(&gb, 0x4000), 0x81);
+
+    MMU::write_byte(&mut gb, 0x0000, 0x0A);
+    for bank in 0..8 {
+        MMU::write_byte(&mut gb, 0x4000, bank);
+        MMU::write_byte(&mut gb, 0xA000, 0x10 + bank);
+    }
+    for bank in 0..8 {
+        MMU::write_byte(&mut gb, 0x4000, bank);
+        assert_eq!(MMU::read_byte(&gb, 0xA000), 0x10 + bank);
+    }
+}
+
+#[test]
+fn mbc3_ram_is_disabled_by_default() {
+    let cartridge = Cartridge::from_data(banked_rom(0x13, 0x01, 0x03)).unwrap();
+    let mut gb = GameBoy::new(Some(cartridge), EmulationConfig::default());
+
+    MMU::write_byte(&mut gb, 0xA000, 0x42);
+    assert_eq!(MMU::read_byte(&gb, 0xA000), 0xFF);
+}
+
+#[test]
+fn wisdom_tree_switches_32k_banks_by_address() {
+    let mut data = vec![0u8; 4 * 0x8000];
+    for bank in 0..4 {
+        data[bank * 0x8000 + 0x0200] = bank as u8;
+    }
+    data[0x0150..0x015B].copy_from_slice(b"WISDOM TREE");
+
+    let mut gb = GameBoy::new(Some(Cartridge::from_data(data).unwrap()), EmulationConfig::default());
+    // Leave the boot ROM out of the way
+    MMU::write_byte(&mut gb, 0xFF50, 0x01);
+
+    assert_eq!(MMU::read_byte(&gb, 0x0200), 0);
+
+    MMU::write_byte(&mut gb, 0x0002, 0xFF);
+    assert_eq!(MMU::read_byte(&gb, 0x0200), 2);
+    assert!(gb.cartridge.as_ref().unwrap().describe_mapper().starts_with("Wisdom Tree"));
+    // No RAM on the board
+    MMU::write_byte(&mut gb, 0xA000, 0x12);
+    assert_eq!(MMU::read_byte(&gb, 0xA000), 0xFF);
+    gb.reset(ResetKind::Soft);
+    MMU::write_byte(&mut gb, 0xFF50, 0x01);
+    assert_eq!(MMU::read_byte(&gb, 0x0200), 0);
+}
+
+#[test]
+fn unlicensed_mappers_can_have_ram() {
+    use crate::cartridge::UnlicensedMapper;
+
+    // 16 KiB banks selected by any write, and 256 bytes of RAM repeated over A000-BFFF
+    struct Homebrew {
+        bank: u8,
+        ram: [u8; 0x100],
+    }
+    impl UnlicensedMapper for Homebrew {
+        fn rom_offset(&self, address: u16) -> usize {
+            if address < 0x4000 { address as usize } else { self.bank as usize * 0x4000 + (address as usize - 0x4000) }
+        }
+        fn write_rom(&mut self, _address: u16, value: u8) {
+            self.bank = value;
+        }
+        fn read_ram(&self, address: u16) -> u8 {
+            self.ram[address as usize & 0xFF]
+        }
+        fn write_ram(&mut self, address: u16, value: u8) {
+            self.ram[address as usize & 0xFF] = value;
+        }
+    }
+
+    let mut cartridge = Cartridge::from_data(banked_rom(0x00, 0x02, 0x00)).unwrap();
+    cartridge.set_unlicensed_mapper(Box::new(Homebrew { bank: 1, ram: [0; 0x100] }));
+    let mut gb = GameBoy::new(Some(cartridge), EmulationConfig::default());
+    MMU::write_byte(&mut gb, 0x6000, 0x05);
+    assert_eq!(MMU::read_byte(&gb, 0x4000), 5);
+    MMU::write_byte(&mut gb, 0xA010, 0x77);
+    assert_eq!(MMU::read_byte(&gb, 0xB110), 0x77);
+}
+
+#[test]
+fn ips_patch_writes_records_and_runs() {
+    let mut patch = b"PATCH".to_vec();
+    // 2 bytes at 0x000001
+    patch.extend([0x00, 0x00, 0x01, 0x00, 0x02, 0xAA, 0xBB]);
+    // Run of 3 bytes at 0x000006, past the end of the ROM
+    patch.extend([0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x03, 0xCC]);
+    patch.extend(b"EOF");
+
+    let patched = apply_patch(vec![0; 4], &patch).unwrap();
+    assert_eq!(patched, vec![0x00, 0xAA, 0xBB, 0x00, 0x00, 0x00, 0xCC, 0xCC, 0xCC]);
+}
+
+#[test]
+fn bps_patch_copies_source_and_patch_bytes() {
+    let source = vec![1, 2, 3, 4];
+    let target = vec![1, 2, 3, 4, 9, 9];
+
+    let mut patch = b"BPS1".to_vec();
+    // Source size, target size and no metadata, numbers below 128 take one byte
+    patch.extend([0x80 | 4, 0x80 | 6, 0x80]);
+    // SourceRead 4 bytes, then TargetRead 2 bytes
+    patch.push(0x80 | (3 << 2));
+    patch.extend([0x80 | (1 << 2) | 1, 9, 9]);
+    patch.extend(crc32(&source).to_le_bytes());
+    patch.extend(crc32(&target).to_le_bytes());
+    patch.extend(crc32(&patch).to_le_bytes());
+
+    assert_eq!(apply_patch(source.clone(), &patch).unwrap(), target);
+    // Rejected when the ROM doesn't match
+    assert!(apply_patch(vec![0; 4], &patch).is_err());
+}
+
+// BPS numbers, 7 bits per byte with one subtracted between bytes
+#[cfg(test)]
+fn bps_number(mut value: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let digit = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(0x80 | digit);
+            return bytes;
+        }
+        bytes.push(digit);
+        value -= 1;
+    }
+}
+
+// A patch with valid checksums for the source and the patch itself, the
+// target checksum is never reached by the broken commands
+#[cfg(test)]
+fn bps_patch(source: &[u8], target_size: usize, commands: &[Vec<u8>]) -> Vec<u8> {
+    let mut patch = b"BPS1".to_vec();
+    patch.extend(bps_number(source.len()));
+    patch.extend(bps_number(target_size));
+    patch.push(0x80);
+    patch.extend(commands.concat());
+    patch.extend(crc32(source).to_le_bytes());
+    patch.extend([0; 4]);
+    patch.extend(crc32(&patch).to_le_bytes());
+    patch
+}
+
+#[test]
+fn bps_patch_sizes_and_offsets_are_bounded() {
+    let source = vec![1, 2, 3, 4];
+    let malformed = |patch: Vec<u8>| apply_patch(source.clone(), &patch).unwrap_err().to_string();
+    let huge = usize::MAX >> 2;
+
+    // Nothing is allocated for a target bigger than a ROM can be
+    assert_eq!(malformed(bps_patch(&source, usize::MAX >> 1, &[])), "Malformed BPS patch");
+    // SourceCopy and TargetCopy with lengths and offsets past the end of memory
+    let source_copy = [bps_number(huge << 2 | 2), bps_number(huge << 1)];
+    assert_eq!(malformed(bps_patch(&source, 16, &source_copy)), "Malformed BPS patch");
+    let source_copy = [bps_number(2), bps_number(usize::MAX - 1)];
+    assert_eq!(malformed(bps_patch(&source, 16, &source_copy)), "Malformed BPS patch");
+    let target_copy = [bps_number(1 << 2 | 1), vec![7], bps_number(huge << 2 | 3), bps_number(0)];
+    assert_eq!(malformed(bps_patch(&source, 16, &target_copy)), "Malformed BPS patch");
+    let target_copy = [bps_number(1 << 2 | 1), vec![7], bps_number(3), bps_number(usize::MAX - 1)];
+    assert_eq!(malformed(bps_patch(&source, 16, &target_copy)), "Malformed BPS patch");
+}
+
+#[cfg(feature = "rom-database")]
+#[test]
+fn benchmark_rom_is_in_the_database() {
+    use crate::cartridge::DumpStatus;
+
+    let cartridge = Cartridge::new(std::path::PathBuf::from("../assets/bench/bench.gb")).unwrap();
+    let info = cartridge.rom_info();
+
+    assert_eq!(info.status, DumpStatus::Verified);
+    assert_eq!(info.title, "Yagabor Benchmark");
+    assert_eq!(info.region.as_deref(), Some("World"));
+}
+
+#[test]
+fn without_cartridge_the_boot_rom_locks_up() {
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+
+    assert_eq!(MMU::read_byte(&gb, 0x0150), 0xFF);
+    assert_eq!(MMU::read_byte(&gb, 0xA000), 0xFF);
+
+    // The logo check fails, so the boot ROM never hands over to the cartridge.
+    // With a cartridge the boot ROM is done after about 340 frames.
+    let mut cycles = 0;
+    while cycles < crate::CPU_CYCLES_PER_FRAME * 500 {
+        cycles += gb.tick().unwrap() as usize;
+    }
+    assert!(gb.cpu.pc < 0x0100);
+}
+
+#[test]
+fn malformed_roms_are_rejected() {
+    assert!(matches!(Cartridge::from_data(vec![]), Err(CartridgeError::TooSmall { size: 0 })));
+    assert!(matches!(Cartridge::from_data(vec![0xFF; 0x014F]), Err(CartridgeError::TooSmall { size: 0x014F })));
+
+    // Whatever the header says, loading must not panic
+    let mut rom = vec![0xFF; 0x0150];
+    rom[0x0134..0x0143].copy_from_slice(&[0xC3; 15]);
+    assert!(Cartridge::from_data(rom).is_ok());
+}
+
+#[test]
+fn header_verified_like_the_boot_rom() {
+    let mut data = banked_rom(0x11, 0x01, 0x00);
+    assert!(matches!(Cartridge::from_data(data.clone()).unwrap().verify_header(), Err(CartridgeError::BadLogo)));
+
+    let logo = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/dmgrom.bin")).unwrap()[0xA8..0xD8].to_vec();
+    data[0x0104..0x0134].copy_from_slice(&logo);
+    data[0x0134..0x0143].copy_from_slice(b"T\xC3\xA9ST\x01\0\0\0\0\0\0\0GO");
+    let cartridge = Cartridge::from_data(data.clone()).unwrap();
+    // Non-ASCII and control bytes are replaced, the padding inside is shown too
+    assert_eq!(cartridge.title(), "T??ST????????GO");
+    assert!(matches!(cartridge.verify_header(), Err(CartridgeError::BadChecksum { .. })));
+
+    let checksum = |data: &[u8]| data[0x0134..0x014D].iter().fold(0u8, |checksum, byte| checksum.wrapping_sub(*byte).wrapping_sub(1));
+    data[0x014D] = checksum(&data);
+    assert!(Cartridge::from_data(data.clone()).unwrap().verify_header().is_ok());
+
+    // MBC5 runs as a plain ROM
+    data[0x0147] = 0x19;
+    data[0x014D] = checksum(&data);
+    let error = Cartridge::from_data(data).unwrap().verify_header().unwrap_err();
+    assert!(matches!(error, CartridgeError::UnsupportedMapper { cartridge_type: 0x19 }));
+    assert_eq!(std::io::Error::from(error).kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+#[cfg(feature = "archives")]
+fn roms_opened_from_memory_and_archives() {
+    use std::io::Write;
+
+    let rom = banked_rom(0x11, 0x01, 0x00);
+    assert_eq!(Cartridge::from_bytes(&rom).unwrap().crc32(), crc32(&rom));
+    assert_eq!(Cartridge::from_reader(&rom[..]).unwrap().crc32(), crc32(&rom));
+
+    let directory = std::env::temp_dir().join(format!("yagabor-archives-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let zip = |files: &[(&str, &[u8])]| {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, data) in files {
+            writer.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    };
+    // The ROM is found by its extension, whatever its case
+    std::fs::write(directory.join("game.zip"), zip(&[("README.txt", b"Have fun"), ("Game (World).GB", &rom)])).unwrap();
+    assert_eq!(Cartridge::new(directory.join("game.zip")).unwrap().crc32(), crc32(&rom));
+    std::fs::write(directory.join("empty.zip"), zip(&[("README.txt", b"Have fun")])).unwrap();
+    assert!(Cartridge::new(directory.join("empty.zip")).is_err());
+
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gzip.write_all(&rom).unwrap();
+    std::fs::write(directory.join("game.gb.gz"), gzip.finish().unwrap()).unwrap();
+    assert_eq!(Cartridge::new(directory.join("game.gb.gz")).unwrap().crc32(), crc32(&rom));
+
+    // A few KiB that unpack to more than the largest ROM
+    let too_large = |result: Result<Cartridge, CartridgeError>| matches!(result, Err(CartridgeError::Io(error)) if error.kind() == std::io::ErrorKind::InvalidData);
+    let bomb = vec![0; 9 * 1024 * 1024];
+    std::fs::write(directory.join("bomb.zip"), zip(&[("bomb.gb", &bomb)])).unwrap();
+    assert!(too_large(Cartridge::new(directory.join("bomb.zip"))));
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gzip.write_all(&bomb).unwrap();
+    std::fs::write(directory.join("bomb.gb.gz"), gzip.finish().unwrap()).unwrap();
+    assert!(too_large(Cartridge::new(directory.join("bomb.gb.gz"))));
+    assert!(too_large(Cartridge::from_reader(std::io::repeat(0))));
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn camera_captures_a_dithered_picture() {
+    let mut cartridge = Cartridge::from_data(banked_rom(0xFC, 0x05, 0x04)).unwrap();
+    // Black on the left half, white on the right one
+    cartridge.set_image_source(Box::new(StaticImage::new(2, 1, &[0x00, 0xFF])));
+    let mut gb = GameBoy::new(Some(cartridge), EmulationConfig::default());
+
+    MMU::write_byte(&mut gb, 0x4000, 0x10);
+    MMU::write_byte(&mut gb, 0xA002, 0x08);
+    MMU::write_byte(&mut gb, 0xA003, 0x00);
+    for register in 0..48 {
+        MMU::write_byte(&mut gb, 0xA006 + register, [0x40, 0x80, 0xC0][register as usize % 3]);
+    }
+    MMU::write_byte(&mut gb, 0xA000, 0x01);
+    assert_eq!(MMU::read_byte(&gb, 0xA000) & 0x01, 0x01);
+
+    Cartridge::tick(&mut gb, 1 << 20);
+    assert_eq!(MMU::read_byte(&gb, 0xA000) & 0x01, 0x00);
+
+    MMU::write_byte(&mut gb, 0x4000, 0x00);
+    // First row of the first tile is black, the last tile of the row is white
+    assert_eq!((MMU::read_byte(&gb, 0xA100), MMU::read_byte(&gb, 0xA101)), (0xFF, 0xFF));
+    assert_eq!((MMU::read_byte(&gb, 0xA1F0), MMU::read_byte(&gb, 0xA1F1)), (0x00, 0x00));
+}
+
+#[test]
+fn huc3_clock_is_read_through_commands() {
+    let cartridge = Cartridge::from_data(banked_rom(0xFE, 0x04, 0x03)).unwrap();
+    let mut gb = GameBoy::new(Some(cartridge), EmulationConfig::default());
+
+    MMU::write_byte(&mut gb, 0x2000, 0x09);
+    assert_eq!(MMU::read_byte(&gb, 0x4000), 9);
+
+    // Two days and 90 minutes
+    Cartridge::tick(&mut gb, crate::CPU_CLOCK_HZ * 60 * (2 * 24 * 60 + 90));
+    MMU::write_byte(&mut gb, 0x0000, 0x0B);
+    for command in [0x60, 0x40, 0x50] {
+        MMU::write_byte(&mut gb, 0xA000, command);
+    }
+    let mut nibbles = Vec::new();
+    for _ in 0..7 {
+        MMU::write_byte(&mut gb, 0x0000, 0x0B);
+        MMU::write_byte(&mut gb, 0xA000, 0x10);
+        MMU::write_byte(&mut gb, 0x0000, 0x0C);
+        nibbles.push(MMU::read_byte(&gb, 0xA000) & 0x0F);
+    }
+    assert_eq!(nibbles, vec![0xA, 0x5, 0x0, 0x2, 0x0, 0x0, 0x0]);
+
+    // RAM is only written in mode 0A
+    MMU::write_byte(&mut gb, 0x0000, 0x00);
+    MMU::write_byte(&mut gb, 0xA000, 0x42);
+    MMU::write_byte(&mut gb, 0x0000, 0x0A);
+    assert_eq!(MMU::read_byte(&gb, 0xA000), 0x00);
+    MMU::write_byte(&mut gb, 0xA000, 0x42);
+    assert_eq!(MMU::read_byte(&gb, 0xA000), 0x42);
+}
+
+#[test]
+fn mbc2_keeps_half_bytes_that_repeat_over_the_ram_area() {
+    let cartridge = Cartridge::from_data(banked_rom(0x06, 0x03, 0x00)).unwrap();
+    let mut gb = GameBoy::new(Some(cartridge), EmulationConfig::default());
+
+    // Bit 8 of the address selects the ROM bank register
+    MMU::write_byte(&mut gb, 0x2100, 0x07);
+    assert_eq!(MMU::read_byte(&gb, 0x4000), 7);
+    MMU::write_byte(&mut gb, 0x2000, 0x03);
+    assert_eq!(MMU::read_byte(&gb, 0x4000), 7);
+
+    MMU::write_byte(&mut gb, 0xA001, 0x5A);
+    assert_eq!(MMU::read_byte(&gb, 0xA001), 0xFF);
+    MMU::write_byte(&mut gb, 0x0000, 0x0A);
+    MMU::write_byte(&mut gb, 0xA001, 0x5A);
+    assert_eq!(MMU::read_byte(&gb, 0xA001), 0xFA);
+    assert_eq!(MMU::read_byte(&gb, 0xA201), 0xFA);
+    assert_eq!(MMU::read_byte(&gb, 0xBE01), 0xFA);
+
+    let path = std::env::temp_dir().join("yagabor-mbc2.sav");
+    let cartridge = gb.cartridge.as_ref().unwrap();
+    assert!(cartridge.has_battery());
+    cartridge.save_battery(&path).unwrap();
+    let mut reloaded = Cartridge::from_data(banked_rom(0x06, 0x03, 0x00)).unwrap();
+    reloaded.load_battery(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(reloaded.ram[1], 0x0A);
+}
+
+#[test]
+fn mmm01_boots_the_menu_and_locks_the_game_banks() {
+    let mut data = banked_rom(0x01, 0x02, 0x00);
+    // The menu is in the last 32 KiB with the header of the collection
+    data[0x18000 + 0x0147] = 0x0B;
+    let cartridge = Cartridge::from_data(data).unwrap();
+    assert!(matches!(cartridge.ctype(), CartridgeType::MMM01(_)));
+    let mut gb = GameBoy::new(Some(cartridge), EmulationConfig::default());
+    MMU::write_byte(&mut gb, 0xFF50, 0x01);
+    assert_eq!((MMU::read_byte(&gb, 0x0000), MMU::read_byte(&gb, 0x4000)), (6, 7));
+
+    // The menu starts the game at bank 2 and freezes bits 1-4 of the bank
+    MMU::write_byte(&mut gb, 0x2000, 0x02);
+    MMU::write_byte(&mut gb, 0x6000, 0x3C);
+    MMU::write_byte(&mut gb, 0x0000, 0x40);
+    assert_eq!((MMU::read_byte(&gb, 0x0000), MMU::read_byte(&gb, 0x4000)), (2, 3));
+
+    // The game can only switch between its two banks
+    MMU::write_byte(&mut gb, 0x2000, 0x1F);
+    assert_eq!(MMU::read_byte(&gb, 0x4000), 3);
+    MMU::write_byte(&mut gb, 0x6000, 0x00);
+    MMU::write_byte(&mut gb, 0x0000, 0x00);
+    assert_eq!(MMU::read_byte(&gb, 0x0000), 2);
+}
+
+#[test]
+fn mbc1_multicart_maps_each_game_at_0000() {
+    let mut data = banked_rom(0x01, 0x05, 0x00);
+    for game in 0..4 {
+        data[game * 0x40000 + 0x0104..][..0x30].copy_from_slice(&[0xCE; 0x30]);
+    }
+    let mut gb = GameBoy::new(Some(Cartridge::from_data(data).unwrap()), EmulationConfig::default());
+    MMU::write_byte(&mut gb, 0xFF50, 0x01);
+
+    // Bit 4 of the bank register is not connected
+    MMU::write_byte(&mut gb, 0x2000, 0x12);
+    assert_eq!(MMU::read_byte(&gb, 0x4000), 0x02);
+    MMU::write_byte(&mut gb, 0x4000, 0x01);
+    assert_eq!(MMU::read_byte(&gb, 0x4000), 0x12);
+    assert_eq!(MMU::read_byte(&gb, 0x0100), 0x00);
+    MMU::write_byte(&mut gb, 0x6000, 0x01);
+    assert_eq!(MMU::read_byte(&gb, 0x0100), 0x10);
+}
+
+#[test]
+fn mappers_are_found_by_the_cartridge_type() {
+    let mbc2 = Cartridge::from_data(banked_rom(0x06, 0x02, 0x00)).unwrap();
+    assert!(mbc2.describe_mapper().starts_with("MBC2"));
+    let huc1 = Cartridge::from_data(banked_rom(0xFF, 0x02, 0x02)).unwrap();
+    assert!(huc1.describe_mapper().starts_with("HuC1"));
+
+    // MBC5 is not emulated, it runs as a plain ROM and is reported
+    let mbc5 = Cartridge::from_data(banked_rom(0x19, 0x02, 0x00)).unwrap();
+    assert_eq!(mbc5.describe_mapper(), "no mapper");
+    assert!(mbc5.compatibility_issues().contains(&CompatibilityIssue::UnsupportedMapper));
+    let plain = Cartridge::from_data(banked_rom(0x00, 0x00, 0x00)).unwrap();
+    assert!(!plain.compatibility_issues().contains(&CompatibilityIssue::UnsupportedMapper));
+}
+
+#[test]
+fn compatibility_table_lists_what_the_header_cant_tell() {
+    use crate::compatibility::KNOWN_GAMES;
+
+    for (index, game) in KNOWN_GAMES.iter().enumerate() {
+        assert!(!game.issues.is_empty() && !game.issues.contains(&CompatibilityIssue::UnsupportedMapper), "{:08X}", game.crc32);
+        assert!(KNOWN_GAMES[..index].iter().all(|other| other.crc32 != game.crc32), "{:08X} is listed twice", game.crc32);
+    }
+}
+
+#[test]
+fn header_fields_and_checksum() {
+    let mut data = banked_rom(0x1B, 0x02, 0x03);
+    data[0x0134..0x0138].copy_from_slice(b"TEST");
+    data[0x014B] = 0x33;
+    data[0x0144..0x0146].copy_from_slice(b"01");
+    data[0x014A] = 0x01;
+    data[0x014E..0x0150].copy_from_slice(&[0x12, 0x34]);
+    data[0x014D] = data[0x0134..0x014D].iter().fold(0u8, |checksum, byte| checksum.wrapping_sub(*byte).wrapping_sub(1));
+
+    let header = Cartridge::from_data(data.clone()).unwrap().header();
+    assert_eq!((header.title.as_str(), header.licensee.as_str(), header.japanese), ("TEST", "01", false));
+    assert_eq!((header.cartridge_type, header.rom_size, header.ram_size), (0x1B, 0x20000, 0x8000));
+    assert_eq!(header.global_checksum, 0x1234);
+    assert!(header.header_checksum_valid());
+
+    data[0x014C] = 1;
+    assert!(!Cartridge::from_data(data).unwrap().header().header_checksum_valid());
+}
+
+#[cfg(test)]
+fn latched_clock(cartridge: Cartridge) -> Vec<u8> {
+    let mut gb = GameBoy::new(Some(cartridge), EmulationConfig::default());
+    MMU::write_byte(&mut gb, 0x0000, 0x0A);
+    MMU::write_byte(&mut gb, 0x6000, 0x00);
+    MMU::write_byte(&mut gb, 0x6000, 0x01);
+    (0x08..=0x0A).map(|register| {
+        MMU::write_byte(&mut gb, 0x4000, register);
+        MMU::read_byte(&gb, 0xA000)
+    }).collect()
+}
+
+#[test]
+fn mbc3_clock_catches_up_with_the_host_time_unless_frozen() {
+    let mut gb = GameBoy::new(Some(Cartridge::from_data(banked_rom(0x10, 0x02, 0x02)).unwrap()), EmulationConfig::default());
+    MMU::write_byte(&mut gb, 0x0000, 0x0A);
+    MMU::write_byte(&mut gb, 0x4000, 0x08);
+    MMU::write_byte(&mut gb, 0xA000, 10);
+
+    let path = std::env::temp_dir().join(format!("yagabor-rtc-{}.sav", std::process::id()));
+    gb.cartridge.as_ref().unwrap().save_battery(&path).unwrap();
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 0x2000 + 48);
+
+    let later = SystemTime::now() + Duration::from_secs(3661);
+    let mut reloaded = Cartridge::from_data(banked_rom(0x10, 0x02, 0x02)).unwrap();
+    reloaded.load_battery_at(&path, later).unwrap();
+    assert_eq!(latched_clock(reloaded), [11, 1, 1]);
+
+    let mut frozen = Cartridge::from_data(banked_rom(0x10, 0x02, 0x02)).unwrap();
+    frozen.set_clock_mode(ClockMode::Frozen);
+    frozen.load_battery_at(&path, later).unwrap();
+    assert_eq!(latched_clock(frozen), [10, 0, 0]);
+
+    let mut corrupt = std::fs::read(&path).unwrap();
+    let footer = corrupt.len() - 8;
+    corrupt[footer..].copy_from_slice(&u64::MAX.to_le_bytes());
+    std::fs::write(&path, corrupt).unwrap();
+    let mut reloaded = Cartridge::from_data(banked_rom(0x10, 0x02, 0x02)).unwrap();
+    reloaded.load_battery_at(&path, later).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(latched_clock(reloaded), [10, 0, 0]);
+}
@@ -0,0 +1,113 @@
+use crate::mmu::Address;
+
+const WISDOM_TREE_BANK_SIZE: usize = 0x8000;
+const WISDOM_TREE_SIGNATURES: [&[u8]; 2] = [b"WISDOM TREE", b"WISDOM\x00TREE"];
+
+// Hook for unlicensed and homebrew bank switching schemes that have no header type byte.
+// A new scheme is a type implementing it plus an entry in UNLICENSED_SCHEMES, the
+// cartridge and the MMU forward everything to it.
+pub trait UnlicensedMapper: Send {
+    // Offset inside the ROM data for a read in 0x0000-0x7FFF
+    fn rom_offset(&self, address: u16) -> usize;
+
+    // The program wrote to 0x0000-0x7FFF
+    fn write_rom(&mut self, address: u16, value: u8);
+
+    // A000-BFFF, for boards with RAM of their own. Most have none and read open bus.
+    fn read_ram(&self, _address: u16) -> u8 {
+        0xFF
+    }
+
+    fn write_ram(&mut self, _address: u16, _value: u8) {}
+
+    // Registers back to their power on values, mappers without any can ignore it
+    fn reset(&mut self) {}
+
+    // For state dumps and the debugger
+    fn name(&self) -> &str {
+        "unlicensed mapper"
+    }
+
+    // Bank registers for savestates, mappers without state can keep the defaults
+    fn save_state(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    fn load_state(&mut self, _state: &[u8]) {}
+}
+
+pub struct UnlicensedScheme {
+    pub name: &'static str,
+    // Recognizes a ROM of the scheme, only tried when the header names no emulated mapper
+    detect: fn(&[u8]) -> bool,
+    create: fn() -> Box<dyn UnlicensedMapper>,
+}
+
+pub const UNLICENSED_SCHEMES: &[UnlicensedScheme] = &[
+    UnlicensedScheme { name: "wisdom-tree", detect: WisdomTree::detect, create: || Box::new(WisdomTree::default()) },
+];
+
+impl UnlicensedScheme {
+    pub fn create(&self) -> Box<dyn UnlicensedMapper> {
+        (self.create)()
+    }
+}
+
+// For ROMs that don't give their scheme away, like hacks and bad dumps
+pub fn unlicensed_scheme(name: &str) -> Option<&'static UnlicensedScheme> {
+    UNLICENSED_SCHEMES.iter().find(|scheme| scheme.name == name)
+}
+
+pub(crate) fn detect(data: &[u8]) -> Option<Box<dyn UnlicensedMapper>> {
+    UNLICENSED_SCHEMES.iter().find(|scheme| (scheme.detect)(data)).map(UnlicensedScheme::create)
+}
+
+// Wisdom Tree switches the whole 32 KiB area at once, the bank number is
+// the low byte of the address written to, the value is ignored
+#[derive(Default)]
+pub struct WisdomTree {
+    bank: u8,
+}
+
+impl WisdomTree {
+    // They declare themselves as ROM only, so they are found by the publisher name
+    pub(crate) fn detect(data: &[u8]) -> bool {
+        let header_type = data.get(0x0147).copied();
+        if data.len() <= WISDOM_TREE_BANK_SIZE || !matches!(header_type, Some(0x00) | Some(0xC0)) {
+            return false;
+        }
+
+        let first_bank = &data[..WISDOM_TREE_BANK_SIZE];
+        WISDOM_TREE_SIGNATURES.iter().any(|signature| {
+            first_bank.windows(signature.len()).any(|window| window == *signature)
+        })
+    }
+}
+
+impl UnlicensedMapper for WisdomTree {
+    fn rom_offset(&self, address: Address) -> usize {
+        self.bank as usize * WISDOM_TREE_BANK_SIZE + address as usize
+    }
+
+    fn write_rom(&mut self, address: Address, _value: u8) {
+        if address <= 0x3FFF {
+            self.bank = (address & 0xFF) as u8;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.bank = 0;
+    }
+
+    fn name(&self) -> &str {
+        "Wisdom Tree"
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        vec![self.bank]
+    }
+
+    fn load_state(&mut self, state: &[u8]) {
+        self.bank = state.first().copied().unwrap_or(0);
+    }
+}
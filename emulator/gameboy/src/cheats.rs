@@ -0,0 +1,231 @@
+use std::io::{Error, ErrorKind};
+
+use crate::{gameboy::GameBoy, mmu::{Address, GAMEROM_N_END, MMU}, savestate::{StateReader, StateWriter}};
+
+pub type CheatId = usize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheatCode {
+    // Replaces a byte read from ROM, optionally only when the original byte matches
+    GameGenie { address: u16, value: u8, compare: Option<u8> },
+    // Writes a byte to RAM every frame
+    GameShark { bank: u8, address: u16, value: u8 },
+    // Same as GameShark for any address past the ROM, written as AAAA=VV
+    Freeze { address: u16, value: u8 },
+    // The next code only applies while the address holds the value, written as ?AAAA=VV.
+    // Several in a row must all hold.
+    Condition { address: u16, value: u8 },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cheat {
+    pub id: CheatId,
+    pub code: CheatCode,
+    pub enabled: bool,
+    // Whether its conditions held at the last VBlank
+    pub active: bool,
+}
+
+#[derive(Default)]
+pub struct CheatManager {
+    cheats: Vec<Cheat>,
+    next_id: CheatId,
+}
+
+fn invalid(code: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, format!("Invalid cheat code {}", code))
+}
+
+fn hex_digits(code: &str) -> Result<Vec<u8>, Error> {
+    code.chars()
+        .filter(|c| *c != '-')
+        .map(|c| c.to_digit(16).map(|d| d as u8).ok_or_else(|| invalid(code)))
+        .collect()
+}
+
+impl CheatCode {
+    // Game Genie codes look like ABC-DEF or ABC-DEF-GHI, GameShark ones are 8 hex digits
+    pub fn parse(code: &str) -> Result<CheatCode, Error> {
+        let code = code.trim();
+        if let Some((address, value)) = code.split_once('=') {
+            let (condition, address) = match address.strip_prefix('?') {
+                Some(address) => (true, address),
+                None => (false, address),
+            };
+            let address = u16::from_str_radix(address.trim(), 16).map_err(|_| invalid(code))?;
+            let value = u8::from_str_radix(value.trim(), 16).map_err(|_| invalid(code))?;
+            // Writes to the ROM area would reach the mapper registers and switch banks
+            if !condition && address <= GAMEROM_N_END {
+                return Err(invalid(code));
+            }
+            return Ok(if condition { CheatCode::Condition { address, value } } else { CheatCode::Freeze { address, value } });
+        }
+        let digits = hex_digits(code)?;
+
+        match (code.contains('-'), digits.len()) {
+            (true, 6) | (true, 9) => {
+                // AB is the new value and FCDE the address, with the top nibble inverted
+                let value = digits[0] << 4 | digits[1];
+                let address = ((digits[5] ^ 0xF) as u16) << 12
+                    | (digits[2] as u16) << 8
+                    | (digits[3] as u16) << 4
+                    | digits[4] as u16;
+                // GI rotated right by 2 and XORed with BA is the compare value, H is not used
+                let compare = if digits.len() == 9 {
+                    Some((digits[6] << 4 | digits[8]).rotate_right(2) ^ 0xBA)
+                } else {
+                    None
+                };
+
+                if address > 0x7FFF {
+                    return Err(invalid(code));
+                }
+                Ok(CheatCode::GameGenie { address, value, compare })
+            },
+            (false, 8) => {
+                // ttvvaaaa, the address is little endian
+                let bank = digits[0] << 4 | digits[1];
+                let value = digits[2] << 4 | digits[3];
+                let address = (digits[6] as u16) << 12
+                    | (digits[7] as u16) << 8
+                    | (digits[4] as u16) << 4
+                    | digits[5] as u16;
+                Ok(CheatCode::GameShark { bank, address, value })
+            },
+            _ => Err(invalid(code))
+        }
+    }
+
+    pub(crate) fn save(&self, writer: &mut StateWriter) {
+        match *self {
+            CheatCode::GameGenie { address, value, compare } => {
+                writer.u8(0);
+                writer.u16(address);
+                writer.u8(value);
+                writer.option_u8(compare);
+            },
+            CheatCode::GameShark { bank, address, value } => {
+                writer.u8(1);
+                writer.u16(address);
+                writer.u8(value);
+                writer.u8(bank);
+            },
+            CheatCode::Freeze { address, value } => {
+                writer.u8(2);
+                writer.u16(address);
+                writer.u8(value);
+            },
+            CheatCode::Condition { address, value } => {
+                writer.u8(3);
+                writer.u16(address);
+                writer.u8(value);
+            },
+        }
+    }
+
+    pub(crate) fn load(reader: &mut StateReader) -> Result<CheatCode, Error> {
+        let kind = reader.u8()?;
+        let address = reader.u16()?;
+        let value = reader.u8()?;
+        let code = match kind {
+            0 => CheatCode::GameGenie { address, value, compare: reader.option_u8()? },
+            1 => CheatCode::GameShark { bank: reader.u8()?, address, value },
+            2 => CheatCode::Freeze { address, value },
+            3 => CheatCode::Condition { address, value },
+            _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid cheat code")),
+        };
+        Ok(code)
+    }
+}
+
+// Changed through Emulation::add_cheat and the methods next to it, so movies
+// record the changes and savestates hold the codes
+impl CheatManager {
+    pub(crate) fn add(&mut self, code: CheatCode) -> CheatId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.cheats.push(Cheat { id, code, enabled: true, active: true });
+        id
+    }
+
+    // The id the next code added gets
+    pub(crate) fn next_id(&self) -> CheatId {
+        self.next_id
+    }
+
+    pub(crate) fn remove(&mut self, id: CheatId) -> Option<Cheat> {
+        let index = self.cheats.iter().position(|cheat| cheat.id == id)?;
+        Some(self.cheats.remove(index))
+    }
+
+    pub(crate) fn set_enabled(&mut self, id: CheatId, enabled: bool) {
+        if let Some(cheat) = self.cheats.iter_mut().find(|cheat| cheat.id == id) {
+            cheat.enabled = enabled;
+        }
+    }
+
+    pub fn cheats(&self) -> &[Cheat] {
+        &self.cheats
+    }
+
+    pub(crate) fn save_state(&self, writer: &mut StateWriter) {
+        writer.u32(self.next_id as u32);
+        writer.u32(self.cheats.len() as u32);
+        for cheat in &self.cheats {
+            writer.u32(cheat.id as u32);
+            writer.bool(cheat.enabled);
+            writer.bool(cheat.active);
+            cheat.code.save(writer);
+        }
+    }
+
+    pub(crate) fn load_state(&mut self, reader: &mut StateReader) -> Result<(), Error> {
+        let next_id = reader.u32()? as CheatId;
+        let mut cheats = Vec::new();
+        for _ in 0..reader.u32()? {
+            let id = reader.u32()? as CheatId;
+            let enabled = reader.bool()?;
+            let active = reader.bool()?;
+            cheats.push(Cheat { id, code: CheatCode::load(reader)?, enabled, active });
+        }
+        *self = CheatManager { cheats, next_id };
+        Ok(())
+    }
+
+    // Called for every read of the cartridge ROM area
+    pub(crate) fn patch_rom(&self, address: Address, original: u8) -> u8 {
+        for cheat in self.cheats.iter().filter(|cheat| cheat.enabled && cheat.active) {
+            if let CheatCode::GameGenie { address: patched, value, compare } = cheat.code {
+                if patched == address && compare.is_none_or(|c| c == original) {
+                    return value;
+                }
+            }
+        }
+        original
+    }
+
+    // Called at the start of VBlank, right after the queued pokes. Codes apply in
+    // order, so a condition sees the writes of the codes before it.
+    pub(crate) fn apply_ram_cheats(gb: &mut GameBoy) {
+        let mut conditions_hold = true;
+        for index in 0..gb.cheats.cheats.len() {
+            let cheat = &gb.cheats.cheats[index];
+            if !cheat.enabled {
+                continue;
+            }
+            match cheat.code {
+                CheatCode::Condition { address, value } => {
+                    conditions_hold &= MMU::read_byte(gb, address) == value;
+                    continue;
+                },
+                CheatCode::GameShark { address, value, .. } | CheatCode::Freeze { address, value } if conditions_hold => {
+                    MMU::write_byte(gb, address, value);
+                },
+                _ => {},
+            }
+            // Game Genie codes patch reads until the next VBlank
+            gb.cheats.cheats[index].active = conditions_hold;
+            conditions_hold = true;
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::time::{Duration, Instant};
+
+use crate::CPU_CLOCK_HZ;
+
+pub const MIN_SPEED: f32 = 0.25;
+
+// When the host falls behind by more than this the clock starts over from
+// the current time instead of running fast to catch up
+const MAX_LAG: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Speed {
+    // 1.0 is the real Game Boy speed
+    Multiplier(f32),
+    Uncapped,
+}
+
+// Paces the emulation to real time. The frontend runs a frame, reports its
+// cycles with frame_done and sleeps for the returned duration.
+pub struct Clock {
+    speed: Speed,
+    fast_forward_speed: Speed,
+    fast_forward: bool,
+    // Frames not rendered between two rendered ones
+    frame_skip: u32,
+    frames: u64,
+    // Cycles emulated since the reference time
+    reference: Instant,
+    cycles: u64,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock::new()
+    }
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Clock {
+            speed: Speed::Multiplier(1.0),
+            fast_forward_speed: Speed::Uncapped,
+            fast_forward: false,
+            frame_skip: 0,
+            frames: 0,
+            reference: Instant::now(),
+            cycles: 0,
+        }
+    }
+
+    pub fn set_speed(&mut self, speed: Speed) {
+        self.speed = Clock::clamp(speed);
+        self.restart();
+    }
+
+    pub fn set_fast_forward_speed(&mut self, speed: Speed) {
+        self.fast_forward_speed = Clock::clamp(speed);
+        self.restart();
+    }
+
+    pub fn set_fast_forward(&mut self, enabled: bool) {
+        self.fast_forward = enabled;
+        self.restart();
+    }
+
+    pub fn toggle_fast_forward(&mut self) {
+        self.set_fast_forward(!self.fast_forward);
+    }
+
+    pub fn is_fast_forwarding(&self) -> bool {
+        self.fast_forward
+    }
+
+    // Speed currently applied, the fast-forward one while it is enabled
+    pub fn speed(&self) -> Speed {
+        if self.fast_forward { self.fast_forward_speed } else { self.speed }
+    }
+
+    // Renders one frame out of frames + 1, the skipped ones still run the PPU
+    // so the game timing doesn't change
+    pub fn set_frame_skip(&mut self, frames: u32) {
+        self.frame_skip = frames;
+    }
+
+    // Whether the frame about to run should be presented
+    pub fn should_render(&self) -> bool {
+        self.frames.is_multiple_of(self.frame_skip as u64 + 1)
+    }
+
+    // Returns how long to wait before running the next frame
+    pub fn frame_done(&mut self, cycles: u64) -> Duration {
+        self.frame_done_at(cycles, Instant::now())
+    }
+
+    fn frame_done_at(&mut self, cycles: u64, now: Instant) -> Duration {
+        self.frames += 1;
+        self.cycles += cycles;
+
+        let Speed::Multiplier(multiplier) = self.speed() else {
+            self.restart_at(now);
+            return Duration::ZERO;
+        };
+
+        let target = Duration::from_secs_f64(self.cycles as f64 / (CPU_CLOCK_HZ as f64 * multiplier as f64));
+        let elapsed = now.saturating_duration_since(self.reference);
+        if target > elapsed {
+            return target - elapsed;
+        }
+        if elapsed - target > MAX_LAG {
+            self.restart_at(now);
+        }
+        Duration::ZERO
+    }
+
+    fn clamp(speed: Speed) -> Speed {
+        match speed {
+            Speed::Multiplier(multiplier) if multiplier.is_finite() => Speed::Multiplier(multiplier.max(MIN_SPEED)),
+            _ => Speed::Uncapped,
+        }
+    }
+
+    fn restart(&mut self) {
+        self.restart_at(Instant::now());
+    }
+
+    fn restart_at(&mut self, now: Instant) {
+        self.reference = now;
+        self.cycles = 0;
+    }
+}
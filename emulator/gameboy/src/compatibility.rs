@@ -0,0 +1,33 @@
+use std::fmt;
+
+// Known problems of specific games with this emulator. Entries are keyed by the CRC32 of the
+// whole ROM and should be updated when an issue is triaged or fixed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompatibilityIssue {
+    // The bug is only emulated when turned on
+    ReliesOnOamBug,
+    // The header declares a cartridge type that is not emulated
+    UnsupportedMapper,
+}
+
+impl fmt::Display for CompatibilityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompatibilityIssue::ReliesOnOamBug => write!(f, "relies on the OAM corruption bug, turn it on with Emulation::set_oam_bug or AccuracyProfile::Accurate"),
+            CompatibilityIssue::UnsupportedMapper => write!(f, "uses a cartridge type that is not emulated"),
+        }
+    }
+}
+
+pub(crate) struct KnownGame {
+    pub(crate) crc32: u32,
+    pub(crate) issues: &'static [CompatibilityIssue],
+}
+
+// Only what the header can't tell, an unemulated mapper is already reported from
+// the cartridge type
+pub(crate) const KNOWN_GAMES: &[KnownGame] = &[];
+
+pub(crate) fn lookup(crc32: u32) -> Option<&'static KnownGame> {
+    KNOWN_GAMES.iter().find(|game| game.crc32 == crc32)
+}
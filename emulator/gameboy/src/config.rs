@@ -0,0 +1,260 @@
+use std::{env, fs, io::{Error, ErrorKind}, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{accuracy::AccuracyProfile, audio::{HighPassFilter, ResamplerQuality}, cartridge::{Cartridge, ClockMode}, inputmacro::InputMacro, inputmap::{HostInput, InputMap, Turbo}, palette::ColorPalette, Button, Emulation};
+
+const DEFAULT_TURBO_PERIOD: u32 = 6;
+
+// Settings of the emulator shared by every game, gameconfig::GameConfig has
+// those of a single game. Frontends load it when they start, let their
+// options override it and save it back when the user changes something.
+// Missing keys take their default, so older files keep working.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmulatorConfig {
+    pub keys: KeyBindings,
+    // Controls pressing and releasing a button every turbo_period frames while held
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub turbo: Vec<TurboBinding>,
+    pub turbo_period: u32,
+    // Controls playing an input macro when pressed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub macros: Vec<MacroBinding>,
+    // A preset name or four RRGGBB colors, see palette::ColorPalette::parse
+    pub palette: String,
+    // Battery saves go here instead of next to the ROM
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub save_directory: Option<PathBuf>,
+    pub accuracy: AccuracyProfile,
+    // 256 bytes run instead of the DMG boot ROM built in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boot_rom: Option<PathBuf>,
+    // Frozen keeps the cartridge clocks from catching up with the time the game was off
+    pub clock: ClockMode,
+    pub audio: AudioConfig,
+}
+
+// The controls pressing each button, as parsed by inputmap::HostInput: key
+// names, "pad:" gamepad buttons and "axis:" gamepad axes. A single one can
+// be written without the list. The defaults are SDL key names.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    #[serde(deserialize_with = "one_or_many")]
+    pub a: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub b: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub start: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub select: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub up: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub down: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub left: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub right: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TurboBinding {
+    pub input: String,
+    pub button: Button,
+    // Overrides turbo_period for this control
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period: Option<u32>,
+    // Frames pressed out of the period, half of it by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pressed: Option<u32>,
+}
+
+// The steps are parsed by InputMacro::parse
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MacroBinding {
+    pub input: String,
+    pub steps: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    pub enabled: bool,
+    // Asked to the sound card, which may pick another one
+    pub sample_rate: u32,
+    pub quality: ResamplerQuality,
+    // Off gives the raw output, with its DC offset
+    pub high_pass: HighPassFilter,
+}
+
+impl EmulatorConfig {
+    // $XDG_CONFIG_HOME/yagabor/config.toml, or under ~/.config
+    pub fn default_path() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()).map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(base.join("yagabor").join("config.toml"))
+    }
+
+    // A missing file gives the default settings
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        match fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(|error| Error::new(ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(EmulatorConfig::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    // Creates the directory of the file when needed
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        if let Some(directory) = path.as_ref().parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let text = toml::to_string(self).map_err(Error::other)?;
+        fs::write(path, text)
+    }
+
+    pub fn color_palette(&self) -> Result<ColorPalette, Error> {
+        ColorPalette::preset(&self.palette)
+            .or_else(|| ColorPalette::parse(&self.palette))
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Unknown palette {}", self.palette)))
+    }
+
+    pub fn input_map(&self) -> Result<InputMap, Error> {
+        let mut map = InputMap::new();
+        for button in Button::ALL {
+            for input in self.keys.inputs(button) {
+                map.bind(HostInput::parse(input)?, button);
+            }
+        }
+        for binding in &self.turbo {
+            let period = binding.period.unwrap_or(self.turbo_period);
+            let turbo = match binding.pressed {
+                Some(pressed) => Turbo::with_duty(period, pressed),
+                None => Turbo::new(period),
+            };
+            map.bind_turbo(HostInput::parse(&binding.input)?, binding.button, turbo);
+        }
+        Ok(map)
+    }
+
+    // Checks every macro, so mistakes show when the frontend starts
+    pub fn input_macros(&self) -> Result<Vec<(HostInput, InputMacro)>, Error> {
+        self.macros.iter()
+            .map(|binding| Ok((HostInput::parse(&binding.input)?, InputMacro::parse(&binding.steps)?)))
+            .collect()
+    }
+
+    pub fn battery_path_for<P: AsRef<Path>>(&self, rom: P) -> PathBuf {
+        let path = Cartridge::battery_path_for(&rom);
+        match (&self.save_directory, path.file_name()) {
+            (Some(directory), Some(name)) => directory.join(name),
+            _ => path,
+        }
+    }
+
+    // The accuracy profile and the boot ROM, which are part of the emulation.
+    // Before starting it, the boot ROM runs from the beginning.
+    pub fn apply(&self, emu: &mut Emulation) -> Result<(), Error> {
+        emu.set_emulation_config(self.accuracy.into());
+        if let Some(path) = &self.boot_rom {
+            emu.set_boot_rom(&fs::read(path)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for EmulatorConfig {
+    fn default() -> Self {
+        EmulatorConfig {
+            keys: KeyBindings::default(),
+            turbo: Vec::new(),
+            turbo_period: DEFAULT_TURBO_PERIOD,
+            macros: Vec::new(),
+            palette: "grayscale".to_string(),
+            save_directory: None,
+            accuracy: AccuracyProfile::default(),
+            boot_rom: None,
+            clock: ClockMode::default(),
+            audio: AudioConfig::default(),
+        }
+    }
+}
+
+impl KeyBindings {
+    // Names are compared ignoring case
+    pub fn button(&self, input: &str) -> Option<Button> {
+        Button::ALL.into_iter().find(|button| self.inputs(*button).iter().any(|bound| bound.eq_ignore_ascii_case(input)))
+    }
+
+    pub fn inputs(&self, button: Button) -> &[String] {
+        match button {
+            Button::A => &self.a,
+            Button::B => &self.b,
+            Button::Start => &self.start,
+            Button::Select => &self.select,
+            Button::Up => &self.up,
+            Button::Down => &self.down,
+            Button::Left => &self.left,
+            Button::Right => &self.right,
+        }
+    }
+
+    // Replaces the controls of the button
+    pub fn set(&mut self, button: Button, input: &str) {
+        *self.inputs_mut(button) = vec![input.to_string()];
+    }
+
+    pub fn add(&mut self, button: Button, input: &str) {
+        self.inputs_mut(button).push(input.to_string());
+    }
+
+    fn inputs_mut(&mut self, button: Button) -> &mut Vec<String> {
+        match button {
+            Button::A => &mut self.a,
+            Button::B => &mut self.b,
+            Button::Start => &mut self.start,
+            Button::Select => &mut self.select,
+            Button::Up => &mut self.up,
+            Button::Down => &mut self.down,
+            Button::Left => &mut self.left,
+            Button::Right => &mut self.right,
+        }
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let key = |name: &str| vec![name.to_string()];
+        KeyBindings {
+            a: key("A"),
+            b: key("S"),
+            start: key("Return"),
+            select: key("Space"),
+            up: key("Up"),
+            down: key("Down"),
+            left: key("Left"),
+            right: key("Right"),
+        }
+    }
+}
+
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(input) => vec![input],
+        OneOrMany::Many(inputs) => inputs,
+    })
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        AudioConfig { enabled: true, sample_rate: 48000, quality: ResamplerQuality::default(), high_pass: HighPassFilter::default() }
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt;
+
+use crate::{cpu::instructions::{decode::Instruction, disassembly::Operands}, gameboy::GameBoy, mmu::MMU};
+
+// 256 opcodes and 256 more after the CB prefix
+const OPCODES: usize = 512;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Opcode {
+    pub prefixed: bool,
+    pub byte: u8,
+}
+
+// How many times every opcode ran while coverage was enabled, to see which
+// instructions the test ROMs never exercise
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpcodeCoverage {
+    counts: Vec<u64>,
+}
+
+impl Default for OpcodeCoverage {
+    fn default() -> Self {
+        OpcodeCoverage { counts: vec![0; OPCODES] }
+    }
+}
+
+impl Opcode {
+    fn all() -> impl Iterator<Item = Opcode> {
+        [false, true].into_iter().flat_map(|prefixed| (0..=0xFF).map(move |byte| Opcode { prefixed, byte }))
+    }
+
+    fn index(&self) -> usize {
+        self.byte as usize + if self.prefixed { 0x100 } else { 0 }
+    }
+
+    fn instruction(&self) -> Option<&'static Instruction> {
+        if self.prefixed {
+            Some(&Instruction::decode_prefixed(self.byte).instruction)
+        } else {
+            Instruction::decode(self.byte).map(|entry| &entry.instruction)
+        }
+    }
+
+    // The 11 unused opcodes lock up the CPU and the CB prefix is not an
+    // instruction of its own, they can't be covered
+    pub fn is_legal(&self) -> bool {
+        self.instruction().is_some()
+    }
+
+    pub fn mnemonic(&self) -> String {
+        let label = |_| "a16".to_string();
+        self.instruction().map_or("-".to_string(), |instruction| instruction.disassemble(&Operands { address: 0, byte: 0, word: 0, label: &label }))
+    }
+}
+
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.prefixed {
+            write!(f, "CB {:02X}", self.byte)
+        } else {
+            write!(f, "{:02X}", self.byte)
+        }
+    }
+}
+
+impl OpcodeCoverage {
+    pub fn new() -> Self {
+        OpcodeCoverage::default()
+    }
+
+    // Before the instruction at PC runs
+    pub(crate) fn instruction(gb: &mut GameBoy) {
+        let byte = MMU::read_byte(gb, gb.cpu.pc);
+        let opcode = if byte == 0xCB {
+            Opcode { prefixed: true, byte: MMU::read_byte(gb, gb.cpu.pc.wrapping_add(1)) }
+        } else {
+            Opcode { prefixed: false, byte }
+        };
+        if let Some(coverage) = &mut gb.opcode_coverage {
+            coverage.counts[opcode.index()] += 1;
+        }
+    }
+
+    pub fn count(&self, opcode: Opcode) -> u64 {
+        self.counts[opcode.index()]
+    }
+
+    // Adds the counts of another run, like the other ROMs of a suite
+    pub fn merge(&mut self, other: &OpcodeCoverage) {
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+    }
+
+    pub fn covered(&self) -> usize {
+        Opcode::all().filter(|opcode| self.count(*opcode) > 0).count()
+    }
+
+    pub fn legal() -> usize {
+        Opcode::all().filter(Opcode::is_legal).count()
+    }
+
+    pub fn percent(&self) -> f64 {
+        self.covered() as f64 * 100.0 / OpcodeCoverage::legal() as f64
+    }
+
+    pub fn missing(&self) -> Vec<Opcode> {
+        Opcode::all().filter(|opcode| opcode.is_legal() && self.count(*opcode) == 0).collect()
+    }
+}
+
+// Both opcode tables with # for the opcodes that ran, . for the ones that
+// didn't and a blank for the unused ones, then the missing ones by name
+impl fmt::Display for OpcodeCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Opcode coverage: {}/{} ({:.1}%)", self.covered(), OpcodeCoverage::legal(), self.percent())?;
+        for prefixed in [false, true] {
+            writeln!(f, "\n{}   0123456789ABCDEF", if prefixed { "CB" } else { "  " })?;
+            for row in 0..16u8 {
+                let cells: String = (0..16u8).map(|column| {
+                    let opcode = Opcode { prefixed, byte: row << 4 | column };
+                    match (opcode.is_legal(), self.count(opcode) > 0) {
+                        (false, _) => ' ',
+                        (true, true) => '#',
+                        (true, false) => '.',
+                    }
+                }).collect();
+                writeln!(f, "{:X}x   {}", row, cells)?;
+            }
+        }
+        let missing = self.missing();
+        if !missing.is_empty() {
+            writeln!(f, "\nNever executed:")?;
+            for opcode in missing {
+                writeln!(f, "  {:<6} {}", opcode.to_string(), opcode.mnemonic())?;
+            }
+        }
+        Ok(())
+    }
+}
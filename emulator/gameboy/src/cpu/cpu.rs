@@ -1,24 +1,44 @@
-use std::io::Error;
-
+use crate::error::EmulationError;
 use crate::gameboy::GameBoy;
 use crate::io::interrupts::Interrupts;
-use crate::io::io::{SERIAL_CONTROL_ADDRESS, SERIAL_DATA_ADDRESS, SerialTransferMode};
+use crate::io::io::{IO, SerialTransferMode};
 use crate::io::timers::Timers;
 use crate::mmu::{MMU, Address};
+use crate::debugger::{CallStack, Diagnostics};
+use crate::coverage::OpcodeCoverage;
+use crate::profiler::Profiler;
+use crate::savestate::{StateReader, StateWriter};
 
-use super::instructions::decode::Instruction;
+use super::instructions::decode::{Instruction, LoadType, WordRegister};
+use super::instructions::table::DecodeEntry;
 use super::registers::Registers;
 
 pub(crate) type ProgramCounter = Address;
 pub(crate) type StackPointer = Address;
 pub(crate) type ClockCycles = u16;
 
+// What to do when the CPU fetches an opcode without an instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IllegalOpcodePolicy {
+    // Lock up the CPU like the hardware does, the rest of the Game Boy keeps running
+    Trap,
+    // Skip the opcode as if it was a NOP
+    Nop,
+    // Stop the emulation with EmulationError::IllegalOpcode
+    Stop,
+}
+
 pub(crate) struct CPU{
     pub(crate) regs: Registers,
     pub(crate) sp: StackPointer,
     pub(crate) pc: ProgramCounter,
     pub(crate) is_halted: bool,
+    // Set by STOP, only a button press gets out of it
+    pub(crate) is_stopped: bool,
     pub(crate) ime: bool,
+    // Set by an illegal opcode with the Trap policy, only a reset gets out of it
+    pub(crate) is_locked: bool,
+    pub(crate) illegal_opcode_policy: IllegalOpcodePolicy,
 }
 
 impl CPU {
@@ -28,53 +48,116 @@ impl CPU {
             sp: 0x0, 
             pc: 0x0,  
             is_halted: false,
+            is_stopped: false,
             ime: true,
+            is_locked: false,
+            illegal_opcode_policy: IllegalOpcodePolicy::Stop,
         }
     }
 
-    pub(crate) fn step(gb: &mut GameBoy) -> Result<ClockCycles, Error> {
-        let mut mcycles = MachineCycles::One;
+    pub(crate) fn save_state(gb: &GameBoy, writer: &mut StateWriter) {
+        let cpu = &gb.cpu;
+        writer.u16(cpu.regs.get_af());
+        writer.u16(cpu.regs.get_bc());
+        writer.u16(cpu.regs.get_de());
+        writer.u16(cpu.regs.get_hl());
+        writer.u16(cpu.sp);
+        writer.u16(cpu.pc);
+        writer.bool(cpu.is_halted);
+        writer.bool(cpu.is_stopped);
+        writer.bool(cpu.ime);
+        writer.bool(cpu.is_locked);
+    }
+
+    pub(crate) fn load_state(gb: &mut GameBoy, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        let cpu = &mut gb.cpu;
+        cpu.regs.set_af(reader.u16()?);
+        cpu.regs.set_bc(reader.u16()?);
+        cpu.regs.set_de(reader.u16()?);
+        cpu.regs.set_hl(reader.u16()?);
+        cpu.sp = reader.u16()?;
+        cpu.pc = reader.u16()?;
+        cpu.is_halted = reader.bool()?;
+        cpu.is_stopped = reader.bool()?;
+        cpu.ime = reader.bool()?;
+        cpu.is_locked = reader.bool()?;
+        Ok(())
+    }
 
-        CPU::handle_interrupts(gb);
+    pub(crate) fn step(gb: &mut GameBoy) -> Result<ClockCycles, EmulationError> {
+        let mut mcycles = MachineCycles::One;
+        gb.io.unknown_read.set(None);
+        Diagnostics::clear(gb);
+        // Writes made outside of instructions, like pokes, don't stop the emulation
+        gb.watchpoints.hit = None;
+
+        let pc = gb.cpu.pc;
+        if !gb.cpu.is_locked {
+            CPU::handle_interrupts(gb);
+        }
+        let (address, sp, interrupted) = (gb.cpu.pc, gb.cpu.sp, gb.cpu.pc != pc);
+        let mut flow = Flow::Other;
         
-        if !gb.cpu.is_halted {
-            let instruction = CPU::fetch_decode(gb)?;
-            // if gb.cpu.pc > 0x100 {
-            //     println!("{} {:?}", gb, instruction);
-            // }
-            //println!("{:?}", gb.io.joypad);
-            mcycles = instruction.execute(gb)?;           
+        if !gb.cpu.is_halted && !gb.cpu.is_locked {
+            match CPU::fetch_decode(gb) {
+                Ok(entry) => {
+                    tracing::trace!(target: "yagabor::cpu", pc = address, sp, instruction = ?entry.instruction);
+                    if gb.opcode_coverage.is_some() {
+                        OpcodeCoverage::instruction(gb);
+                    }
+                    flow = Flow::of(&entry.instruction);
+                    mcycles = (entry.handler)(&entry.instruction, gb)?
+                },
+                Err(error) => {
+                    tracing::warn!(target: "yagabor::cpu", %error, policy = ?gb.cpu.illegal_opcode_policy);
+                    match gb.cpu.illegal_opcode_policy {
+                        IllegalOpcodePolicy::Trap => gb.cpu.is_locked = true,
+                        IllegalOpcodePolicy::Nop => gb.cpu.pc = gb.cpu.pc.wrapping_add(1),
+                        IllegalOpcodePolicy::Stop => return Err(error),
+                    }
+                },
+            }
+        }
+
+        if let Some((address, value)) = gb.io.unknown_write.take() {
+            return Err(EmulationError::UnmappedWrite { address, value });
+        }
+        if let Some(address) = gb.io.unknown_read.take() {
+            return Err(EmulationError::UnmappedRead { address });
         }
 
         Timers::tick(gb, u8::from(mcycles.clone()));        
 
+        if let Some(hit) = &mut gb.watchpoints.hit {
+            hit.pc = address;
+        }
+        CallStack::instruction(gb, pc, address, sp, interrupted, flow);
+        Diagnostics::instruction(gb, address, flow);
+        if gb.profiler.is_some() {
+            Profiler::instruction(gb, address, sp, interrupted, flow, ClockCycles::from(mcycles.clone()));
+        }
         Ok(ClockCycles::from(mcycles))
     }   
 
-    pub(super) fn fetch_decode(gb: &GameBoy) -> Result<Instruction, Error> {
+    pub(super) fn fetch_decode(gb: &GameBoy) -> Result<&'static DecodeEntry, EmulationError> {
         let instruction_byte = MMU::read_byte(gb, gb.cpu.pc);
-        let byte0 = MMU::read_byte(gb, gb.cpu.pc+1);
-
-        let prefixed = instruction_byte == 0xCB;
-        let mut instruction_byte = instruction_byte;
-        if prefixed {
-            instruction_byte = byte0;
+        if instruction_byte == 0xCB {
+            return Ok(Instruction::decode_prefixed(MMU::read_byte(gb, gb.cpu.pc.wrapping_add(1))));
         }
-
-        if prefixed {
-            Instruction::from_byte_prefixed(instruction_byte)
-        } else {
-            Instruction::from_byte_not_prefixed(instruction_byte)
-        }      
+        Instruction::decode(instruction_byte)
+            .ok_or(EmulationError::IllegalOpcode { opcode: instruction_byte, address: gb.cpu.pc })
     }
 
     pub(crate) fn send_serial(gb: &mut GameBoy) -> Option<u8> {
-        let serial_transfer = SerialTransferMode::parse_from_byte(MMU::read_byte(&gb, SERIAL_CONTROL_ADDRESS));
-        let serial_data = MMU::read_byte(&gb, SERIAL_DATA_ADDRESS);
+        let serial_transfer = SerialTransferMode::parse_from_byte(IO::get_serial_control(gb));
+        let serial_data = IO::get_serial_data(gb);
         
         match serial_transfer {
             SerialTransferMode::TransferInternalClock => Some(serial_data),
-            SerialTransferMode::TransferExternalClock => Some(serial_data),
+            SerialTransferMode::TransferExternalClock => {
+                let ready = gb.serial_device.as_mut().is_none_or(|device| device.ready());
+                ready.then_some(serial_data)
+            },
             _ => None
         }
     }
@@ -82,7 +165,8 @@ impl CPU {
     pub(crate) fn handle_interrupts(gb: &mut GameBoy) {
         if Interrupts::some_interrupt_enabled(gb) {
             if gb.cpu.ime {
-                let interrupt = Interrupts::interrupt_to_handle(gb).unwrap();
+                let Some(interrupt) = Interrupts::interrupt_to_handle(gb) else { return };
+                tracing::debug!(target: "yagabor::cpu", ?interrupt, pc = gb.cpu.pc, halted = gb.cpu.is_halted, "interrupt");
                 gb.cpu.is_halted = false;
                 gb.cpu.ime = false;
                 CPU::push_stack(gb, gb.cpu.pc);
@@ -101,6 +185,7 @@ impl CPU {
     }
     
     pub(crate) fn pop_stack(gb: &mut GameBoy) -> u16 {
+        Diagnostics::pop(gb);
         let lsb = MMU::read_byte(&gb, gb.cpu.sp) as u16;
         gb.cpu.sp = gb.cpu.sp.wrapping_add(1);
     
@@ -112,6 +197,27 @@ impl CPU {
 
 }
 
+// How an instruction moves between functions, for the call stack and the profiler
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Flow {
+    Other,
+    Call,
+    Return,
+    // LD SP, the program may be dropping frames or switching stacks
+    SetStack,
+}
+
+impl Flow {
+    fn of(instruction: &Instruction) -> Flow {
+        match instruction {
+            Instruction::CALL(_) | Instruction::RST(_) => Flow::Call,
+            Instruction::RET(_) | Instruction::RETI => Flow::Return,
+            Instruction::LDSPHL | Instruction::LD(LoadType::Word(WordRegister::SP)) => Flow::SetStack,
+            _ => Flow::Other,
+        }
+    }
+}
+
 // We use machine cycles for reference, but in the translation we multiply by 4
 #[derive(Debug, Clone)]
 pub(crate) enum MachineCycles {
@@ -1,12 +1,12 @@
-use std::io::Error;
+use crate::error::EmulationError;
 
-use crate::{cpu::cpu::MachineCycles, gameboy::GameBoy, mmu::MMU};
+use crate::{cpu::cpu::MachineCycles, gameboy::GameBoy, io::oambug::OamBug, mmu::MMU};
 
 use super::decode::{RegistersIndDir, WordRegister, RegistersIndirect, BitType, RotateDirection, BitTarget, ResSetType, Instruction};
 
 impl Instruction {
 
-    pub(super) fn add(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, Error> {
+    pub(super) fn add(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, EmulationError> {
         let value = get_arithmetic_target_val(gb, &target);
 
         let (new_value, did_overflow) = gb.cpu.regs.a.overflowing_add(value);
@@ -26,7 +26,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn addsps8(&self, gb: &mut GameBoy) -> Result<MachineCycles, Error> {
+    pub(super) fn addsps8(&self, gb: &mut GameBoy) -> Result<MachineCycles, EmulationError> {
         // To preserve the sign
         let value = MMU::read_next_byte(gb, gb.cpu.pc) as i8 as i16 as u16;
 
@@ -40,7 +40,7 @@ impl Instruction {
         Ok(MachineCycles::Four)
     }
 
-    pub(super) fn add16(&self, gb: &mut GameBoy, target: WordRegister) -> Result<MachineCycles, Error> {
+    pub(super) fn add16(&self, gb: &mut GameBoy, target: WordRegister) -> Result<MachineCycles, EmulationError> {
         let value = match target {
             WordRegister::BC => gb.cpu.regs.get_bc(),
             WordRegister::DE => gb.cpu.regs.get_de(),
@@ -58,7 +58,7 @@ impl Instruction {
         Ok(MachineCycles::Two)
     }
 
-    pub(super) fn adc(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, Error> {
+    pub(super) fn adc(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, EmulationError> {
         let value = get_arithmetic_target_val(gb, &target);
 
         let (new_value1, did_overflow1) = gb.cpu.regs.a.overflowing_add(value);
@@ -78,7 +78,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn sub(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, Error> {
+    pub(super) fn sub(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, EmulationError> {
         let value = get_arithmetic_target_val(gb, &target);
 
         let (new_value, did_overflow) = gb.cpu.regs.a.overflowing_sub(value);
@@ -97,7 +97,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn sbc(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, Error> {
+    pub(super) fn sbc(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, EmulationError> {
 
         let value = get_arithmetic_target_val(gb, &target);
 
@@ -122,7 +122,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn and(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, Error> {
+    pub(super) fn and(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, EmulationError> {
         let value = get_arithmetic_target_val(gb, &target);
 
         gb.cpu.regs.a = gb.cpu.regs.a & value;
@@ -139,7 +139,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn xor(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, Error> {
+    pub(super) fn xor(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, EmulationError> {
         let value = get_arithmetic_target_val(gb, &target);
 
         gb.cpu.regs.a = gb.cpu.regs.a ^ value;
@@ -156,7 +156,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn or(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, Error> {
+    pub(super) fn or(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, EmulationError> {
         let value = get_arithmetic_target_val(gb, &target);
 
         gb.cpu.regs.a = gb.cpu.regs.a | value;
@@ -173,7 +173,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn cp(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, Error> {
+    pub(super) fn cp(&self, gb: &mut GameBoy, target: RegistersIndDir) -> Result<MachineCycles, EmulationError> {
         let value = get_arithmetic_target_val(gb, &target);
 
         let (result, did_overflow) = gb.cpu.regs.a.overflowing_sub(value);
@@ -191,7 +191,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn inc(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, Error> {
+    pub(super) fn inc(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, EmulationError> {
         gb.cpu.regs.flags.subtract = false;
 
         match target {
@@ -247,7 +247,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn dec(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, Error> {
+    pub(super) fn dec(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, EmulationError> {
         gb.cpu.regs.flags.subtract = true;
 
         match target {
@@ -303,7 +303,8 @@ impl Instruction {
         }
     }
 
-    pub(super) fn inc16(&self, gb: &mut GameBoy, target: WordRegister) -> Result<MachineCycles, Error> {
+    pub(super) fn inc16(&self, gb: &mut GameBoy, target: WordRegister) -> Result<MachineCycles, EmulationError> {
+        OamBug::write(gb, word_register(gb, &target));
         match target {
             WordRegister::BC => gb.cpu.regs.set_bc(gb.cpu.regs.get_bc().wrapping_add(1)),
             WordRegister::DE => gb.cpu.regs.set_de(gb.cpu.regs.get_de().wrapping_add(1)),
@@ -314,7 +315,8 @@ impl Instruction {
         Ok(MachineCycles::Two)
     }
 
-    pub(super) fn dec16(&self, gb: &mut GameBoy, target: WordRegister) -> Result<MachineCycles, Error> {
+    pub(super) fn dec16(&self, gb: &mut GameBoy, target: WordRegister) -> Result<MachineCycles, EmulationError> {
+        OamBug::write(gb, word_register(gb, &target));
         match target {
             WordRegister::BC => gb.cpu.regs.set_bc(gb.cpu.regs.get_bc().wrapping_sub(1)),
             WordRegister::DE => gb.cpu.regs.set_de(gb.cpu.regs.get_de().wrapping_sub(1)),
@@ -325,7 +327,7 @@ impl Instruction {
         Ok(MachineCycles::Two)
     }
 
-    pub(super) fn bit(&self, gb: &mut GameBoy, bit_type: BitType) -> Result<MachineCycles, Error> {
+    pub(super) fn bit(&self, gb: &mut GameBoy, bit_type: BitType) -> Result<MachineCycles, EmulationError> {
         let BitType::Registers(t, s) = bit_type;
         let target = t;
         let source = s;
@@ -347,35 +349,35 @@ impl Instruction {
 
     // RLA, RRA, ... are legacy instructions made for compatibility with 8080
     // No zero flag is set
-    pub(super) fn rla(&self, gb: &mut GameBoy) -> Result<MachineCycles, Error> {
+    pub(super) fn rla(&self, gb: &mut GameBoy) -> Result<MachineCycles, EmulationError> {
         bitwise_rotate(gb, &RegistersIndirect::A, RotateDirection::Left, false);
         gb.cpu.regs.flags.zero = false;
         gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
         Ok(MachineCycles::One)
     }
 
-    pub(super) fn rlca(&self, gb: &mut GameBoy) -> Result<MachineCycles, Error> {
+    pub(super) fn rlca(&self, gb: &mut GameBoy) -> Result<MachineCycles, EmulationError> {
         bitwise_rotate(gb, &RegistersIndirect::A, RotateDirection::Left, true);
         gb.cpu.regs.flags.zero = false;
         gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
         Ok(MachineCycles::One)
     }
 
-    pub(super) fn rra(&self, gb: &mut GameBoy) -> Result<MachineCycles, Error> {
+    pub(super) fn rra(&self, gb: &mut GameBoy) -> Result<MachineCycles, EmulationError> {
         bitwise_rotate(gb, &RegistersIndirect::A, RotateDirection::Right, false);
         gb.cpu.regs.flags.zero = false;
         gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
         Ok(MachineCycles::One)
     }
 
-    pub(super) fn rrca(&self, gb: &mut GameBoy) -> Result<MachineCycles, Error> {
+    pub(super) fn rrca(&self, gb: &mut GameBoy) -> Result<MachineCycles, EmulationError> {
         bitwise_rotate(gb, &RegistersIndirect::A, RotateDirection::Right, true);
         gb.cpu.regs.flags.zero = false;
         gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
         Ok(MachineCycles::One)
     }
 
-    pub(super) fn sla(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, Error> {
+    pub(super) fn sla(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, EmulationError> {
         bitwise_rotate(gb, &target, RotateDirection::Left, true);
         res_set(gb, ResSetType::Registers(BitTarget::Zero, target.clone()), false);
         set_flag_zero(gb, &target);
@@ -387,7 +389,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn sra(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, Error> {
+    pub(super) fn sra(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, EmulationError> {
         let value = get_register_indirect_val(gb, target.clone());
         let bit7 = get_bit_val(7, value);
 
@@ -402,7 +404,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn srl(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, Error> {
+    pub(super) fn srl(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, EmulationError> {
         bitwise_rotate(gb, &target, RotateDirection::Right, true);
         res_set(gb, ResSetType::Registers(BitTarget::Seven, target.clone()), false);
         set_flag_zero(gb, &target);
@@ -414,7 +416,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn rr(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, Error> {
+    pub(super) fn rr(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, EmulationError> {
         bitwise_rotate(gb, &target, RotateDirection::Right, false);
         set_flag_zero(gb, &target);
         gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
@@ -425,7 +427,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn rrc(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, Error> {
+    pub(super) fn rrc(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, EmulationError> {
         bitwise_rotate(gb, &target, RotateDirection::Right, true);
         set_flag_zero(gb, &target);
         gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
@@ -436,7 +438,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn rl(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, Error> {
+    pub(super) fn rl(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, EmulationError> {
         bitwise_rotate(gb, &target, RotateDirection::Left, false);
         set_flag_zero(gb, &target);
         gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
@@ -447,7 +449,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn rlc(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, Error> {
+    pub(super) fn rlc(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, EmulationError> {
         bitwise_rotate(gb, &target, RotateDirection::Left, true);
         set_flag_zero(gb, &target);
         gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
@@ -458,7 +460,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn swap(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, Error> {
+    pub(super) fn swap(&self, gb: &mut GameBoy, target: RegistersIndirect) -> Result<MachineCycles, EmulationError> {
         let value = get_register_indirect_val(gb, target.clone());
 
         let low = value & 0x0F;
@@ -484,10 +486,13 @@ impl Instruction {
         gb.cpu.regs.flags.carry = false;
         set_flag_zero(gb, &target);
         gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
-        Ok(MachineCycles::Two)
+        match target {
+            RegistersIndirect::HLI => Ok(MachineCycles::Four),
+            _ => Ok(MachineCycles::Two),
+        }
     }
 
-    pub(super) fn res(&self, gb: &mut GameBoy, target: ResSetType) -> Result<MachineCycles, Error> {
+    pub(super) fn res(&self, gb: &mut GameBoy, target: ResSetType) -> Result<MachineCycles, EmulationError> {
 
         let ResSetType::Registers(_, register) = target.clone();
 
@@ -501,7 +506,7 @@ impl Instruction {
         }
     }
 
-    pub(super) fn set(&self, gb: &mut GameBoy, target: ResSetType) -> Result<MachineCycles, Error> {
+    pub(super) fn set(&self, gb: &mut GameBoy, target: ResSetType) -> Result<MachineCycles, EmulationError> {
 
         let ResSetType::Registers(_, register) = target.clone();
 
@@ -548,6 +553,15 @@ pub(super) fn res_set(gb: &mut GameBoy, target: ResSetType, value: bool) {
     };
 }
 
+fn word_register(gb: &GameBoy, register: &WordRegister) -> u16 {
+    match register {
+        WordRegister::BC => gb.cpu.regs.get_bc(),
+        WordRegister::DE => gb.cpu.regs.get_de(),
+        WordRegister::HL => gb.cpu.regs.get_hl(),
+        WordRegister::SP => gb.cpu.sp,
+    }
+}
+
 fn get_register_indirect_val(gb: &GameBoy, source: RegistersIndirect) -> u8 {
     match source {
         RegistersIndirect::A => gb.cpu.regs.a,
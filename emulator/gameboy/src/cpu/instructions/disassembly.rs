@@ -0,0 +1,172 @@
+use super::decode::*;
+
+// Operands are the bytes following the opcode, the labeler turns addresses into
+// names (or $hex) so jumps and memory accesses can be shown by symbol
+pub(crate) struct Operands<'a> {
+    pub(crate) address: u16,
+    pub(crate) byte: u8,
+    pub(crate) word: u16,
+    pub(crate) label: &'a dyn Fn(u16) -> String,
+}
+
+impl Instruction {
+    // RGBDS syntax
+    pub(crate) fn disassemble(&self, operands: &Operands) -> String {
+        let d8 = format!("${:02X}", operands.byte);
+        let label = operands.label;
+        match self {
+            Instruction::NOP => "nop".to_string(),
+            Instruction::HALT => "halt".to_string(),
+            Instruction::STOP => "stop".to_string(),
+            Instruction::SCF => "scf".to_string(),
+            Instruction::DAA => "daa".to_string(),
+            Instruction::CCF => "ccf".to_string(),
+            Instruction::CPL => "cpl".to_string(),
+            Instruction::ADD(source) => format!("add a, {}", source_name(source, &d8)),
+            Instruction::ADC(source) => format!("adc a, {}", source_name(source, &d8)),
+            Instruction::SUB(source) => format!("sub a, {}", source_name(source, &d8)),
+            Instruction::SBC(source) => format!("sbc a, {}", source_name(source, &d8)),
+            Instruction::AND(source) => format!("and a, {}", source_name(source, &d8)),
+            Instruction::OR(source) => format!("or a, {}", source_name(source, &d8)),
+            Instruction::CP(source) => format!("cp a, {}", source_name(source, &d8)),
+            Instruction::XOR(source) => format!("xor a, {}", source_name(source, &d8)),
+            Instruction::INC(target) => format!("inc {}", register_name(target)),
+            Instruction::DEC(target) => format!("dec {}", register_name(target)),
+            Instruction::ADDSPS8 => format!("add sp, {}", operands.byte as i8),
+            Instruction::ADD16(source) => format!("add hl, {}", word_name(source)),
+            Instruction::INC16(target) => format!("inc {}", word_name(target)),
+            Instruction::DEC16(target) => format!("dec {}", word_name(target)),
+            Instruction::LD(load) => match load {
+                LoadType::Byte(target, source) => format!("ld {}, {}", register_name(target), source_name(source, &d8)),
+                LoadType::Word(target) => format!("ld {}, {}", word_name(target), label(operands.word)),
+                LoadType::AFromIndirect(source) => format!("ld a, {}", indirect_name(source)),
+                LoadType::IndirectFromA(target) => format!("ld {}, a", indirect_name(target)),
+                LoadType::AFromDirect => format!("ld a, [{}]", label(operands.word)),
+                LoadType::DirectFromA => format!("ld [{}], a", label(operands.word)),
+            },
+            Instruction::LDHLSPD8 => format!("ld hl, sp{:+}", operands.byte as i8),
+            Instruction::LDSPHL => "ld sp, hl".to_string(),
+            Instruction::LDFF(load) => match load {
+                LoadFFType::AtoFFC => "ldh [c], a".to_string(),
+                LoadFFType::FFCtoA => "ldh a, [c]".to_string(),
+                LoadFFType::FFa8toA => format!("ldh a, [{}]", label(0xFF00 | operands.byte as u16)),
+                LoadFFType::AtoFFa8 => format!("ldh [{}], a", label(0xFF00 | operands.byte as u16)),
+            },
+            Instruction::LDSPA16 => format!("ld [{}], sp", label(operands.word)),
+            Instruction::JP(test) => with_condition("jp", test, &label(operands.word)),
+            Instruction::JR(test) => {
+                let target = operands.address.wrapping_add(2).wrapping_add(operands.byte as i8 as u16);
+                with_condition("jr", test, &label(target))
+            },
+            Instruction::JPHL => "jp hl".to_string(),
+            Instruction::CALL(test) => with_condition("call", test, &label(operands.word)),
+            Instruction::RET(JumpTest::Always) => "ret".to_string(),
+            Instruction::RET(test) => format!("ret {}", condition_name(test)),
+            Instruction::RST(target) => format!("rst {}", label(bit_number(target) as u16 * 8)),
+            Instruction::RETI => "reti".to_string(),
+            Instruction::PUSH(target) => format!("push {}", stack_name(target)),
+            Instruction::POP(target) => format!("pop {}", stack_name(target)),
+            Instruction::BIT(BitType::Registers(bit, target)) => format!("bit {}, {}", bit_number(bit), register_name(target)),
+            Instruction::RES(ResSetType::Registers(bit, target)) => format!("res {}, {}", bit_number(bit), register_name(target)),
+            Instruction::SET(ResSetType::Registers(bit, target)) => format!("set {}, {}", bit_number(bit), register_name(target)),
+            Instruction::RL(target) => format!("rl {}", register_name(target)),
+            Instruction::RLC(target) => format!("rlc {}", register_name(target)),
+            Instruction::RR(target) => format!("rr {}", register_name(target)),
+            Instruction::RRC(target) => format!("rrc {}", register_name(target)),
+            Instruction::SLA(target) => format!("sla {}", register_name(target)),
+            Instruction::SRA(target) => format!("sra {}", register_name(target)),
+            Instruction::SRL(target) => format!("srl {}", register_name(target)),
+            Instruction::SWAP(target) => format!("swap {}", register_name(target)),
+            Instruction::RLA => "rla".to_string(),
+            Instruction::RLCA => "rlca".to_string(),
+            Instruction::RRA => "rra".to_string(),
+            Instruction::RRCA => "rrca".to_string(),
+            Instruction::DI => "di".to_string(),
+            Instruction::EI => "ei".to_string(),
+        }
+    }
+}
+
+fn with_condition(mnemonic: &str, test: &JumpTest, target: &str) -> String {
+    match test {
+        JumpTest::Always => format!("{} {}", mnemonic, target),
+        _ => format!("{} {}, {}", mnemonic, condition_name(test), target),
+    }
+}
+
+fn condition_name(test: &JumpTest) -> &'static str {
+    match test {
+        JumpTest::NotZero => "nz",
+        JumpTest::Zero => "z",
+        JumpTest::NotCarry => "nc",
+        JumpTest::Carry => "c",
+        JumpTest::Always => "",
+    }
+}
+
+fn register_name(register: &RegistersIndirect) -> &'static str {
+    match register {
+        RegistersIndirect::A => "a",
+        RegistersIndirect::B => "b",
+        RegistersIndirect::C => "c",
+        RegistersIndirect::D => "d",
+        RegistersIndirect::E => "e",
+        RegistersIndirect::H => "h",
+        RegistersIndirect::L => "l",
+        RegistersIndirect::HLI => "[hl]",
+    }
+}
+
+fn source_name(source: &RegistersIndDir, d8: &str) -> String {
+    match source {
+        RegistersIndDir::A => "a",
+        RegistersIndDir::B => "b",
+        RegistersIndDir::C => "c",
+        RegistersIndDir::D => "d",
+        RegistersIndDir::E => "e",
+        RegistersIndDir::H => "h",
+        RegistersIndDir::L => "l",
+        RegistersIndDir::HLI => "[hl]",
+        RegistersIndDir::D8 => d8,
+    }.to_string()
+}
+
+fn word_name(register: &WordRegister) -> &'static str {
+    match register {
+        WordRegister::BC => "bc",
+        WordRegister::DE => "de",
+        WordRegister::HL => "hl",
+        WordRegister::SP => "sp",
+    }
+}
+
+fn stack_name(register: &StackTarget) -> &'static str {
+    match register {
+        StackTarget::BC => "bc",
+        StackTarget::DE => "de",
+        StackTarget::HL => "hl",
+        StackTarget::AF => "af",
+    }
+}
+
+fn indirect_name(source: &LoadIndirectSource) -> &'static str {
+    match source {
+        LoadIndirectSource::BC => "[bc]",
+        LoadIndirectSource::DE => "[de]",
+        LoadIndirectSource::HLInc => "[hl+]",
+        LoadIndirectSource::HLDec => "[hl-]",
+    }
+}
+
+fn bit_number(bit: &BitTarget) -> u8 {
+    match bit {
+        BitTarget::Zero => 0,
+        BitTarget::One => 1,
+        BitTarget::Two => 2,
+        BitTarget::Three => 3,
+        BitTarget::Four => 4,
+        BitTarget::Five => 5,
+        BitTarget::Six => 6,
+        BitTarget::Seven => 7,
+    }
+}
@@ -1,76 +1,95 @@
-use std::io::Error;
+use crate::error::EmulationError;
 
-use crate::{cpu::cpu::{MachineCycles, CPU}, mmu::MMU, gameboy::GameBoy, io::interrupts::Interrupts};
+use crate::{cpu::cpu::{MachineCycles, CPU}, mmu::MMU, gameboy::GameBoy, io::{interrupts::Interrupts, io::IO, lcd::LCD, oambug::OamBug}};
 
 use super::decode::*;
 
+pub(crate) type Handler = fn(&Instruction, &mut GameBoy) -> Result<MachineCycles, EmulationError>;
+
+// The operand is taken back out of the instruction the handler is called with
+macro_rules! with_operand {
+    ($variant:ident, $method:ident) => {
+        |instruction, gb| match instruction {
+            Instruction::$variant(operand) => instruction.$method(gb, operand.clone()),
+            _ => unreachable!(),
+        }
+    };
+}
+
 impl Instruction {
 
-    pub(crate) fn execute(&self, gb: &mut GameBoy) -> Result<MachineCycles, Error> {
-        match self.clone() {
-            Instruction::CALL(test)             => self.call(gb, test),
-            Instruction::RET(test)              => self.ret(gb, test),
-            Instruction::JP(test)               => self.jump(gb, test),
-            Instruction::JR(test)               => self.jump_relative(gb, test),
-            Instruction::JPHL                             => self.jump_hl(gb),
-            Instruction::NOP                              => self.nop(gb),
-            Instruction::HALT                             => self.halt(gb),
-            Instruction::SCF                              => self.scf(gb),
-            Instruction::CCF                              => self.ccf(gb),
-            Instruction::CPL                              => self.cpl(gb),
-            Instruction::ADD(target)     => self.add(gb, target),
-            Instruction::ADC(target)     => self.adc(gb, target),
-            Instruction::INC(target)   => self.inc(gb, target),
-            Instruction::DEC(target)   => self.dec(gb, target),
-            Instruction::ADD16(target)      => self.add16(gb, target),
-            Instruction::INC16(target)      => self.inc16(gb, target),
-            Instruction::DEC16(target)      => self.dec16(gb, target),
-            Instruction::ADDSPS8                          => self.addsps8(gb),
-            Instruction::SUB(target)     => self.sub(gb, target),
-            Instruction::SBC(target)     => self.sbc(gb, target),
-            Instruction::AND(target)     => self.and(gb, target),
-            Instruction::XOR(target)     => self.xor(gb, target),
-            Instruction::OR(target)      => self.or(gb, target),
-            Instruction::CP(target)      => self.cp(gb, target),
-            Instruction::LD(load_type)          => self.load(gb, load_type),
-            Instruction::LDHLSPD8                         => self.ldhlspd8(gb),
-            Instruction::LDSPHL                           => self.ldsphl(gb),
-            Instruction::LDSPA16                          => self.ldspa16(gb),
-            Instruction::LDFF(load_type)      => self.ldff(gb, load_type),
-            Instruction::PUSH(target)        => self.push(gb, target),
-            Instruction::POP(target)         => self.pop(gb, target),
-            Instruction::RST(target)           => self.rst(gb, target),
-            Instruction::BIT(bit_type)           => self.bit(gb, bit_type),
-            Instruction::RETI                             => self.reti(gb),
-            Instruction::DAA                              => self.daa(gb),
-            Instruction::RL(target)    => self.rl(gb, target),
-            Instruction::RLC(target)   => self.rlc(gb, target),
-            Instruction::RR(target)    => self.rr(gb, target),
-            Instruction::RRC(target)   => self.rrc(gb, target),
-            Instruction::RLA                              => self.rla(gb),
-            Instruction::RLCA                             => self.rlca(gb),
-            Instruction::RRA                              => self.rra(gb),
-            Instruction::RRCA                             => self.rrca(gb),
-            Instruction::SRA(target)   => self.sra(gb, target),
-            Instruction::SLA(target)   => self.sla(gb, target),
-            Instruction::SRL(target)   => self.srl(gb, target),
-            Instruction::SWAP(target)  => self.swap(gb, target),
-            Instruction::EI                               => self.ei(gb),
-            Instruction::DI                               => self.di(gb),
-            Instruction::RES(target)          => self.res(gb, target),
-            Instruction::SET(target)          => self.set(gb, target),
-            Instruction::STOP                             => self.nop(gb),
+    // The CPU calls the handlers of the decode tables, tests build instructions by hand
+    #[cfg(test)]
+    pub(crate) fn execute(&self, gb: &mut GameBoy) -> Result<MachineCycles, EmulationError> {
+        (self.handler())(self, gb)
+    }
+
+    // The method of each variant, picked once per opcode when the decode tables are built
+    pub(crate) const fn handler(&self) -> Handler {
+        match self {
+            Instruction::CALL(_) => with_operand!(CALL, call),
+            Instruction::RET(_) => with_operand!(RET, ret),
+            Instruction::JP(_) => with_operand!(JP, jump),
+            Instruction::JR(_) => with_operand!(JR, jump_relative),
+            Instruction::JPHL => |instruction, gb| instruction.jump_hl(gb),
+            Instruction::NOP => |instruction, gb| instruction.nop(gb),
+            Instruction::HALT => |instruction, gb| instruction.halt(gb),
+            Instruction::SCF => |instruction, gb| instruction.scf(gb),
+            Instruction::CCF => |instruction, gb| instruction.ccf(gb),
+            Instruction::CPL => |instruction, gb| instruction.cpl(gb),
+            Instruction::ADD(_) => with_operand!(ADD, add),
+            Instruction::ADC(_) => with_operand!(ADC, adc),
+            Instruction::INC(_) => with_operand!(INC, inc),
+            Instruction::DEC(_) => with_operand!(DEC, dec),
+            Instruction::ADD16(_) => with_operand!(ADD16, add16),
+            Instruction::INC16(_) => with_operand!(INC16, inc16),
+            Instruction::DEC16(_) => with_operand!(DEC16, dec16),
+            Instruction::ADDSPS8 => |instruction, gb| instruction.addsps8(gb),
+            Instruction::SUB(_) => with_operand!(SUB, sub),
+            Instruction::SBC(_) => with_operand!(SBC, sbc),
+            Instruction::AND(_) => with_operand!(AND, and),
+            Instruction::XOR(_) => with_operand!(XOR, xor),
+            Instruction::OR(_) => with_operand!(OR, or),
+            Instruction::CP(_) => with_operand!(CP, cp),
+            Instruction::LD(_) => with_operand!(LD, load),
+            Instruction::LDHLSPD8 => |instruction, gb| instruction.ldhlspd8(gb),
+            Instruction::LDSPHL => |instruction, gb| instruction.ldsphl(gb),
+            Instruction::LDSPA16 => |instruction, gb| instruction.ldspa16(gb),
+            Instruction::LDFF(_) => with_operand!(LDFF, ldff),
+            Instruction::PUSH(_) => with_operand!(PUSH, push),
+            Instruction::POP(_) => with_operand!(POP, pop),
+            Instruction::RST(_) => with_operand!(RST, rst),
+            Instruction::BIT(_) => with_operand!(BIT, bit),
+            Instruction::RETI => |instruction, gb| instruction.reti(gb),
+            Instruction::DAA => |instruction, gb| instruction.daa(gb),
+            Instruction::RL(_) => with_operand!(RL, rl),
+            Instruction::RLC(_) => with_operand!(RLC, rlc),
+            Instruction::RR(_) => with_operand!(RR, rr),
+            Instruction::RRC(_) => with_operand!(RRC, rrc),
+            Instruction::RLA => |instruction, gb| instruction.rla(gb),
+            Instruction::RLCA => |instruction, gb| instruction.rlca(gb),
+            Instruction::RRA => |instruction, gb| instruction.rra(gb),
+            Instruction::RRCA => |instruction, gb| instruction.rrca(gb),
+            Instruction::SRA(_) => with_operand!(SRA, sra),
+            Instruction::SLA(_) => with_operand!(SLA, sla),
+            Instruction::SRL(_) => with_operand!(SRL, srl),
+            Instruction::SWAP(_) => with_operand!(SWAP, swap),
+            Instruction::EI => |instruction, gb| instruction.ei(gb),
+            Instruction::DI => |instruction, gb| instruction.di(gb),
+            Instruction::RES(_) => with_operand!(RES, res),
+            Instruction::SET(_) => with_operand!(SET, set),
+            Instruction::STOP => |instruction, gb| instruction.stop(gb),
         }
-    }    
+    }
 
-    fn jump(&self, gb: &mut GameBoy , test: JumpTest) -> Result<MachineCycles, Error> {
+    fn jump(&self, gb: &mut GameBoy , test: JumpTest) -> Result<MachineCycles, EmulationError> {
         let should_jump = should_jump(gb, test);
      
         if should_jump {
             // Gameboy is little endian so read pc + 2 as most significant bit
             // and pc + 1 as least significant bit
-            let least_significant_byte = MMU::read_byte(gb, gb.cpu.pc + 1) as u16;
-            let most_significant_byte = MMU::read_byte(gb, gb.cpu.pc + 2) as u16;
+            let least_significant_byte = MMU::read_byte(gb, gb.cpu.pc.wrapping_add(1)) as u16;
+            let most_significant_byte = MMU::read_byte(gb, gb.cpu.pc.wrapping_add(2)) as u16;
             gb.cpu.pc = (most_significant_byte << 8) | least_significant_byte;
     
             Ok(MachineCycles::Four)
@@ -80,7 +99,7 @@ impl Instruction {
         }
     }
     
-    fn jump_relative(&self, gb: &mut GameBoy , test: JumpTest) -> Result<MachineCycles, Error> {
+    fn jump_relative(&self, gb: &mut GameBoy , test: JumpTest) -> Result<MachineCycles, EmulationError> {
         let should_jump = should_jump(gb, test);
      
         if should_jump {
@@ -94,29 +113,29 @@ impl Instruction {
         }
     }
     
-    fn jump_hl(&self, gb: &mut GameBoy ) -> Result<MachineCycles, Error> {
+    fn jump_hl(&self, gb: &mut GameBoy ) -> Result<MachineCycles, EmulationError> {
         gb.cpu.pc = gb.cpu.regs.get_hl();
         Ok(MachineCycles::One)
     }   
         
-    fn nop(&self, gb: &mut GameBoy) -> Result<MachineCycles, Error> {
+    fn nop(&self, gb: &mut GameBoy) -> Result<MachineCycles, EmulationError> {
         gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
         Ok(MachineCycles::One)
     }
     
-    fn ei(&self, gb: &mut GameBoy ) -> Result<MachineCycles, Error> {
+    fn ei(&self, gb: &mut GameBoy ) -> Result<MachineCycles, EmulationError> {
         gb.cpu.ime = true;
         gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
         Ok(MachineCycles::One)
     }
     
-    fn di(&self, gb: &mut GameBoy ) -> Result<MachineCycles, Error> {
+    fn di(&self, gb: &mut GameBoy ) -> Result<MachineCycles, EmulationError> {
         gb.cpu.ime = false;
         gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
         Ok(MachineCycles::One)
     }
     
-    fn scf(&self, gb: &mut GameBoy ) -> Result<MachineCycles, Error> {
+    fn scf(&self, gb: &mut GameBoy ) -> Result<MachineCycles, EmulationError> {
         gb.cpu.regs.flags.carry = true;
         gb.cpu.regs.flags.subtract = false;
         gb.cpu.regs.flags.half_carry = false;
@@ -124,7 +143,7 @@ impl Instruction {
         Ok(MachineCycles::One)
     }
     
-    fn cpl(&self, gb: &mut GameBoy ) -> Result<MachineCycles, Error> {
+    fn cpl(&self, gb: &mut GameBoy ) -> Result<MachineCycles, EmulationError> {
         gb.cpu.regs.a = !gb.cpu.regs.a; 
         gb.cpu.regs.flags.subtract = true;
         gb.cpu.regs.flags.half_carry = true;
@@ -132,7 +151,7 @@ impl Instruction {
         Ok(MachineCycles::One)
     }
     
-    fn ccf(&self, gb: &mut GameBoy ) -> Result<MachineCycles, Error> {
+    fn ccf(&self, gb: &mut GameBoy ) -> Result<MachineCycles, EmulationError> {
         gb.cpu.regs.flags.carry = !gb.cpu.regs.flags.carry;
         gb.cpu.regs.flags.subtract = false;
         gb.cpu.regs.flags.half_carry = false;
@@ -141,7 +160,7 @@ impl Instruction {
     }
     
     // https://forums.nesdev.org/viewtopic.php?t=15944
-    fn daa(&self, gb: &mut GameBoy ) -> Result<MachineCycles, Error> {
+    fn daa(&self, gb: &mut GameBoy ) -> Result<MachineCycles, EmulationError> {
         if !gb.cpu.regs.flags.subtract {  // after an addition, adjust if (half-)carry occurred or if result is out of bounds
             if gb.cpu.regs.flags.carry || gb.cpu.regs.a > 0x99 { 
                 gb.cpu.regs.a = gb.cpu.regs.a.wrapping_add(0x60);
@@ -165,7 +184,7 @@ impl Instruction {
         Ok(MachineCycles::One)
     }
     
-    fn call(&self, gb: &mut GameBoy , test: JumpTest) -> Result<MachineCycles, Error> {
+    fn call(&self, gb: &mut GameBoy , test: JumpTest) -> Result<MachineCycles, EmulationError> {
         let should_jump = should_jump(gb, test);
 
         if should_jump {
@@ -180,24 +199,26 @@ impl Instruction {
         }
     }
     
-    fn ret(&self, gb: &mut GameBoy , test: JumpTest) -> Result<MachineCycles, Error> {
+    fn ret(&self, gb: &mut GameBoy , test: JumpTest) -> Result<MachineCycles, EmulationError> {
+        // Checking the condition takes a cycle of its own
+        let conditional = !matches!(test, JumpTest::Always);
         let jump_condition = should_jump(gb, test);
         if jump_condition {
             gb.cpu.pc = CPU::pop_stack(gb, );
-            Ok(MachineCycles::Five) 
+            Ok(if conditional { MachineCycles::Five } else { MachineCycles::Four })
         } else {
             gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
             Ok(MachineCycles::Two) 
         }
     }
     
-    fn reti(&self, gb: &mut GameBoy ) -> Result<MachineCycles, Error> {
+    fn reti(&self, gb: &mut GameBoy ) -> Result<MachineCycles, EmulationError> {
         gb.cpu.pc = CPU::pop_stack(gb, );
         gb.cpu.ime = true;
         Ok(MachineCycles::Four)
     }
     
-    fn rst(&self, gb: &mut GameBoy , target: BitTarget) -> Result<MachineCycles, Error> {
+    fn rst(&self, gb: &mut GameBoy , target: BitTarget) -> Result<MachineCycles, EmulationError> {
         CPU::push_stack(gb, gb.cpu.pc.wrapping_add(u16::from(self.size())));
     
         let address: u16 = match target {
@@ -215,7 +236,7 @@ impl Instruction {
         Ok(MachineCycles::Four)
     }
     
-    fn ldhlspd8(&self, gb: &mut GameBoy ) -> Result<MachineCycles, Error> {
+    fn ldhlspd8(&self, gb: &mut GameBoy ) -> Result<MachineCycles, EmulationError> {
         let value = MMU::read_next_byte(gb, gb.cpu.pc) as i8 as u16;
         let new_value = gb.cpu.sp.wrapping_add(value);
     
@@ -229,13 +250,13 @@ impl Instruction {
         Ok(MachineCycles::Three)        
     }
     
-    fn ldsphl(&self, gb: &mut GameBoy ) -> Result<MachineCycles, Error> {
+    fn ldsphl(&self, gb: &mut GameBoy ) -> Result<MachineCycles, EmulationError> {
         gb.cpu.sp = gb.cpu.regs.get_hl();
         gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
         Ok(MachineCycles::Two) 
     }
     
-    fn ldspa16(&self, gb: &mut GameBoy) -> Result<MachineCycles, Error> {
+    fn ldspa16(&self, gb: &mut GameBoy) -> Result<MachineCycles, EmulationError> {
         let address = MMU::read_next_word(gb, gb.cpu.pc);
     
         let lsb = (gb.cpu.sp & 0x00FF) as u8;
@@ -247,7 +268,7 @@ impl Instruction {
         Ok(MachineCycles::Five) 
     }
     
-    fn load(&self, gb: &mut GameBoy , load_type: LoadType) -> Result<MachineCycles, Error> {
+    fn load(&self, gb: &mut GameBoy , load_type: LoadType) -> Result<MachineCycles, EmulationError> {
          
         match &load_type {
             LoadType::Byte(target, source) => {
@@ -303,12 +324,14 @@ impl Instruction {
                     LoadIndirectSource::HLInc => {
                         let addr = gb.cpu.regs.get_hl();
                         gb.cpu.regs.a = MMU::read_byte(gb, addr);
+                        OamBug::read_increment(gb, addr);
                         let new_value = gb.cpu.regs.get_hl().wrapping_add(1);
                         gb.cpu.regs.set_hl(new_value);
                     },
                     LoadIndirectSource::HLDec => {
                         let addr = gb.cpu.regs.get_hl();
                         gb.cpu.regs.a = MMU::read_byte(gb, addr);
+                        OamBug::read_increment(gb, addr);
                         let new_value = gb.cpu.regs.get_hl().wrapping_sub(1);
                         gb.cpu.regs.set_hl(new_value);
                     }
@@ -327,12 +350,14 @@ impl Instruction {
                     LoadIndirectSource::HLInc => {
                         let addr = gb.cpu.regs.get_hl();
                         MMU::write_byte(gb, addr, gb.cpu.regs.a);
+                        OamBug::write(gb, addr);
                         let new_value = gb.cpu.regs.get_hl().wrapping_add(1);
                         gb.cpu.regs.set_hl(new_value);
                     },
                     LoadIndirectSource::HLDec => {
                         let addr = gb.cpu.regs.get_hl();
                         MMU::write_byte(gb, addr, gb.cpu.regs.a);
+                        OamBug::write(gb, addr);
                         let new_value = gb.cpu.regs.get_hl().wrapping_sub(1);
                         gb.cpu.regs.set_hl(new_value);
                     }
@@ -351,6 +376,8 @@ impl Instruction {
     
         // Result
         match load_type {
+            LoadType::Byte(RegistersIndirect::HLI, RegistersIndDir::D8) => Ok(MachineCycles::Three),
+            LoadType::Byte(_, RegistersIndDir::D8) => Ok(MachineCycles::Two),
             LoadType::Byte(_,RegistersIndDir::HLI) => Ok(MachineCycles::Two) ,
             LoadType::Byte(RegistersIndirect::HLI, _) => Ok(MachineCycles::Two) ,
             LoadType::AFromIndirect(_) => Ok(MachineCycles::Two) ,
@@ -362,7 +389,7 @@ impl Instruction {
         }
     }
     
-    fn ldff(&self, gb: &mut GameBoy , load_type: LoadFFType) -> Result<MachineCycles, Error> {
+    fn ldff(&self, gb: &mut GameBoy , load_type: LoadFFType) -> Result<MachineCycles, EmulationError> {
         match load_type {
             LoadFFType::AtoFFC => { 
                 let addr: u16 = 0xFF00 + gb.cpu.regs.c as u16;       
@@ -391,7 +418,7 @@ impl Instruction {
         }
     }
     
-    pub(crate) fn push(&self, gb: &mut GameBoy , target: StackTarget) -> Result<MachineCycles, Error> {
+    pub(crate) fn push(&self, gb: &mut GameBoy , target: StackTarget) -> Result<MachineCycles, EmulationError> {
         let value = match target {
             StackTarget::BC => gb.cpu.regs.get_bc(),
             StackTarget::DE => gb.cpu.regs.get_de(),
@@ -403,7 +430,7 @@ impl Instruction {
         Ok(MachineCycles::Four)
     }
     
-    pub(crate) fn pop(&self, gb: &mut GameBoy , target: StackTarget) -> Result<MachineCycles, Error> {
+    pub(crate) fn pop(&self, gb: &mut GameBoy , target: StackTarget) -> Result<MachineCycles, EmulationError> {
         let result = CPU::pop_stack(gb, );
         match target {
             StackTarget::BC => gb.cpu.regs.set_bc(result),
@@ -415,7 +442,7 @@ impl Instruction {
         Ok(MachineCycles::Three)
     }
     
-    fn halt(&self, gb: &mut GameBoy ) -> Result<MachineCycles, Error> {
+    fn halt(&self, gb: &mut GameBoy ) -> Result<MachineCycles, EmulationError> {
         if Interrupts::some_interrupt_enabled(gb) {
             if !gb.cpu.ime {
                 // Halt bug, no PC increment
@@ -431,6 +458,18 @@ impl Instruction {
         Ok(MachineCycles::One)
     }
 
+    // Switches the speed on CGB when armed, otherwise the clocks stop until a button is pressed
+    fn stop(&self, gb: &mut GameBoy) -> Result<MachineCycles, EmulationError> {
+        if IO::speed_switch_armed(gb) {
+            IO::switch_speed(gb);
+        } else {
+            IO::reset_div(gb);
+            LCD::freeze_screen(gb);
+            gb.cpu.is_stopped = true;
+        }
+        gb.cpu.pc = gb.cpu.pc.wrapping_add(u16::from(self.size()));
+        Ok(MachineCycles::One)
+    }
 }
 
 fn should_jump(gb: &GameBoy, test: JumpTest) -> bool {
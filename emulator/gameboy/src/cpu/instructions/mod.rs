@@ -1,3 +1,5 @@
 pub(crate) mod instructions;
 pub(crate) mod alu;
-pub(crate) mod decode;
\ No newline at end of file
+pub(crate) mod decode;
+pub(crate) mod disassembly;
+pub(crate) mod table;
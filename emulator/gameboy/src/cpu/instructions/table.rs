@@ -0,0 +1,74 @@
+use super::{decode::{Instruction, InstructionSize}, instructions::Handler};
+
+// Bytes after the opcode the instruction reads
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Operand {
+    None,
+    Byte,
+    Word,
+}
+
+// Everything the CPU, the disassembler and the tools need about an opcode,
+// worked out at compile time so fetching an instruction is an index
+pub(crate) struct DecodeEntry {
+    pub(crate) instruction: Instruction,
+    // Including the opcode and the CB prefix
+    pub(crate) size: u8,
+    pub(crate) operand: Operand,
+    // Machine cycles without and with the branch taken
+    pub(crate) cycles: (u8, u8),
+    pub(crate) handler: Handler,
+}
+
+// The 11 unused opcodes and CB have no entry
+pub(crate) static DECODE_TABLE: [Option<DecodeEntry>; 256] = build_table();
+pub(crate) static PREFIXED_DECODE_TABLE: [DecodeEntry; 256] = build_prefixed_table();
+
+impl DecodeEntry {
+    const fn new(instruction: Instruction, prefixed: bool) -> Self {
+        let size = match instruction.size() {
+            InstructionSize::OneByte => 1,
+            InstructionSize::TwoBytes => 2,
+            InstructionSize::ThreeBytes => 3,
+        };
+        let operand = match (prefixed, size) {
+            (false, 2) => Operand::Byte,
+            (false, 3) => Operand::Word,
+            _ => Operand::None,
+        };
+        let (cycles, handler) = (instruction.cycles(), instruction.handler());
+        DecodeEntry { instruction, size, operand, cycles, handler }
+    }
+}
+
+const fn build_table() -> [Option<DecodeEntry>; 256] {
+    let mut table = [const { None }; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        if let Some(instruction) = Instruction::from_byte_not_prefixed(byte as u8) {
+            table[byte] = Some(DecodeEntry::new(instruction, false));
+        }
+        byte += 1;
+    }
+    table
+}
+
+const fn build_prefixed_table() -> [DecodeEntry; 256] {
+    let mut table = [const { DecodeEntry::new(Instruction::NOP, false) }; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        table[byte] = DecodeEntry::new(Instruction::from_byte_prefixed(byte as u8), true);
+        byte += 1;
+    }
+    table
+}
+
+impl Instruction {
+    pub(crate) fn decode(opcode: u8) -> Option<&'static DecodeEntry> {
+        DECODE_TABLE[opcode as usize].as_ref()
+    }
+
+    pub(crate) fn decode_prefixed(opcode: u8) -> &'static DecodeEntry {
+        &PREFIXED_DECODE_TABLE[opcode as usize]
+    }
+}
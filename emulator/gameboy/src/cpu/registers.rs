@@ -33,38 +33,38 @@ impl Registers {
                 }
     }
 
-    pub(super) fn get_bc(&self) -> u16 {
+    pub(crate) fn get_bc(&self) -> u16 {
         (self.b as u16) << 8 | self.c as u16
     }
     
-    pub(super) fn set_bc(&mut self, value: u16) {
+    pub(crate) fn set_bc(&mut self, value: u16) {
         self.b = ((value & 0xFF00) >> 8) as u8;
         self.c = (value & 0xFF) as u8;
     }
 
-    pub(super) fn get_de(&self) -> u16 {
+    pub(crate) fn get_de(&self) -> u16 {
         (self.d as u16) << 8 | self.e as u16
     }
 
-    pub(super) fn set_de(&mut self, value: u16) {
+    pub(crate) fn set_de(&mut self, value: u16) {
         self.d = ((value & 0xFF00) >> 8) as u8;
         self.e = (value & 0xFF) as u8;
     }
 
-    pub(super) fn get_hl(&self) -> u16 {
+    pub(crate) fn get_hl(&self) -> u16 {
         (self.h as u16) << 8 | self.l as u16
     }
 
-    pub(super) fn set_hl(&mut self, value: u16) {
+    pub(crate) fn set_hl(&mut self, value: u16) {
         self.h = ((value & 0xFF00) >> 8) as u8;
         self.l = (value & 0xFF) as u8;
     }
 
-    pub(super) fn get_af(&self) -> u16 {
+    pub(crate) fn get_af(&self) -> u16 {
         (self.a as u16) << 8 | u8::from(self.flags.clone()) as u16
     }
 
-    pub(super) fn set_af(&mut self, value: u16) {
+    pub(crate) fn set_af(&mut self, value: u16) {
         self.a = ((value & 0xFF00) >> 8) as u8;
         self.flags = FlagsRegister::from((value & 0xFF) as u8);
     }
@@ -2,11 +2,11 @@
 use std::path::PathBuf;
 
 #[cfg(test)]
-use crate::{cartridge::Cartridge, cpu::instructions::decode::{Instruction, RegistersIndDir, StackTarget, RegistersIndirect}, gameboy::GameBoy, mmu::MMU};
+use crate::{accuracy::EmulationConfig, cartridge::Cartridge, cpu::instructions::decode::{Instruction, RegistersIndDir, StackTarget, RegistersIndirect}, gameboy::GameBoy, mmu::MMU};
 
 #[test]
 fn add_without_carry() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
     gb.cpu.regs.a = 0b00000001;    
@@ -26,7 +26,7 @@ fn add_without_carry() {
 
 #[test]
 fn add_with_half_carry() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
     gb.cpu.regs.a = 0b00001111;
@@ -45,7 +45,7 @@ fn add_with_half_carry() {
 }
 #[test]
 fn add_with_carry() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
     gb.cpu.regs.a = 0b11111111;
@@ -65,7 +65,7 @@ fn add_with_carry() {
 
 #[test]
 fn adc_with_carry() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
     gb.cpu.regs.a = 0b11111110;
@@ -86,7 +86,7 @@ fn adc_with_carry() {
 
 #[test]
 fn adc_with_half_carry() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
     gb.cpu.regs.a = 0b00001110;
@@ -107,7 +107,7 @@ fn adc_with_half_carry() {
 
 #[test]
 fn sub_with_carry() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
     gb.cpu.regs.a = 0b00001111;
@@ -127,7 +127,7 @@ fn sub_with_carry() {
 
 #[test]
 fn sub_with_half_carry() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
     gb.cpu.regs.a = 0x1;
@@ -147,7 +147,7 @@ fn sub_with_half_carry() {
 
 #[test]
 fn sbc_with_carry() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
     gb.cpu.regs.a = 0b00001111;
@@ -169,7 +169,7 @@ fn sbc_with_carry() {
 
 #[test]
 fn sbc_with_half_carry() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
     gb.cpu.regs.a = 0x0;
@@ -189,7 +189,7 @@ fn sbc_with_half_carry() {
 
 #[test]
 fn get_af() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
 
@@ -204,7 +204,7 @@ fn get_af() {
 
 #[test]
 fn set_af() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
     gb.cpu.regs.set_af(0b0101010101010000);
@@ -218,7 +218,7 @@ fn set_af() {
 
 #[test]
 fn stack_push() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
     let init_sp = 0xDFFF;
@@ -240,7 +240,7 @@ fn stack_push() {
 
 #[test]
 fn stack_push_pop() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
     gb.cpu.sp = 0xDFFF;
     gb.cpu.pc = 0x100;
 
@@ -258,7 +258,7 @@ fn stack_push_pop() {
 
 #[test]
 fn rla() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
 
@@ -292,7 +292,7 @@ fn rla() {
 
 #[test]
 fn rlca() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
 
@@ -326,7 +326,7 @@ fn rlca() {
 
 #[test]
 fn srl() {
-    let mut gb = GameBoy::new(None);
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
 
     gb.cpu.pc = 0x100;
 
@@ -365,9 +365,32 @@ fn srl() {
 
 }
 
+#[test]
+fn illegal_opcode_policies() {
+    use crate::{cpu::cpu::IllegalOpcodePolicy, error::EmulationError};
+
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+    MMU::write_byte(&mut gb, 0xC000, 0xD3);
+    gb.cpu.pc = 0xC000;
+
+    assert_eq!(gb.tick(), Err(EmulationError::IllegalOpcode { opcode: 0xD3, address: 0xC000 }));
+
+    gb.cpu.illegal_opcode_policy = IllegalOpcodePolicy::Nop;
+    gb.tick().unwrap();
+    assert_eq!(gb.cpu.pc, 0xC001);
+
+    // Locked up for good, time still passes
+    gb.cpu.pc = 0xC000;
+    gb.cpu.illegal_opcode_policy = IllegalOpcodePolicy::Trap;
+    for _ in 0..10 {
+        assert_eq!(gb.tick(), Ok(4));
+    }
+    assert_eq!(gb.cpu.pc, 0xC000);
+}
+
 #[cfg(test)]
 fn assert_serial_result(cartridge: Cartridge) {
-    let mut gb: GameBoy = GameBoy::new(Some(cartridge));
+    let mut gb: GameBoy = GameBoy::new(Some(cartridge), EmulationConfig::default());
     let mut serial = Vec::<char>::new();
     loop {
         match gb.tick() {
@@ -461,4 +484,62 @@ fn cpu_instrs_11() {
 // fn halt_bug() {
 //     let cartridge = Cartridge::halt_bug();
 //     assert_serial_result(cartridge);
-// }
\ No newline at end of file
+// }
+
+#[test]
+fn call_stack_follows_calls_and_returns() {
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+    // C000: call C010, C010: call C020, C013: ret, C020: pop hl, C021: ret
+    for (address, bytes) in [(0xC000, &[0xCD, 0x10, 0xC0][..]), (0xC010, &[0xCD, 0x20, 0xC0, 0xC9]), (0xC020, &[0xE1, 0xC9])] {
+        for (offset, byte) in bytes.iter().enumerate() {
+            MMU::write_byte(&mut gb, address + offset as u16, *byte);
+        }
+    }
+    gb.cpu.pc = 0xC000;
+    gb.cpu.sp = 0xDFFE;
+
+    gb.tick().unwrap();
+    gb.tick().unwrap();
+    let backtrace: Vec<(u16, u16)> = gb.call_stack.frames.iter().rev().map(|frame| (frame.caller, frame.target)).collect();
+    assert_eq!(backtrace, vec![(0xC010, 0xC020), (0xC000, 0xC010)]);
+    assert_eq!(gb.call_stack.frames[1].return_address, 0xC013);
+
+    // Returning without the return address of C020 skips a level
+    gb.tick().unwrap();
+    gb.tick().unwrap();
+    assert_eq!(gb.cpu.pc, 0xC003);
+    assert!(gb.call_stack.frames.is_empty());
+    let warning = gb.call_stack.warnings[0];
+    assert_eq!((warning.address, warning.returned_to, warning.expected.target), (0xC021, 0xC003, 0xC020));
+}
+
+#[test]
+fn decode_tables_match_executed_cycles() {
+    use crate::cpu::{cpu::CPU, instructions::table::{DECODE_TABLE, PREFIXED_DECODE_TABLE}};
+
+    let entries = DECODE_TABLE.iter().enumerate().filter_map(|(opcode, entry)| entry.as_ref().map(|entry| (vec![opcode as u8], entry)));
+    let prefixed = PREFIXED_DECODE_TABLE.iter().enumerate().map(|(opcode, entry)| (vec![0xCB, opcode as u8], entry));
+    let entries: Vec<_> = entries.chain(prefixed).collect();
+    assert_eq!(entries.len(), 256 - 12 + 256);
+    // RET, LD B,d8, LD (HL),d8, RET Z and SWAP (HL), checked against execution below
+    let cycles = [0xC9, 0x06, 0x36, 0xC8].map(|opcode| DECODE_TABLE[opcode].as_ref().unwrap().cycles);
+    assert_eq!(cycles, [(4, 4), (2, 2), (3, 3), (2, 5)]);
+    assert_eq!(PREFIXED_DECODE_TABLE[0x36].cycles, (4, 4));
+
+    // Every condition is false with the flags set and true without
+    for (code, entry) in entries {
+        for flags in [false, true] {
+            let mut gb = GameBoy::new(None, EmulationConfig::default());
+            for (offset, byte) in code.iter().enumerate() {
+                MMU::write_byte(&mut gb, 0xC000 + offset as u16, *byte);
+            }
+            gb.cpu.pc = 0xC000;
+            gb.cpu.sp = 0xDFF0;
+            gb.cpu.regs.set_hl(0xC100);
+            (gb.cpu.regs.flags.zero, gb.cpu.regs.flags.carry) = (flags, flags);
+
+            let mcycles = (CPU::step(&mut gb).unwrap() / 4) as u8;
+            assert!(mcycles == entry.cycles.0 || mcycles == entry.cycles.1, "{:02X?} took {} cycles, the table says {:?}", code, mcycles, entry.cycles);
+        }
+    }
+}
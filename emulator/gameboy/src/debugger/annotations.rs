@@ -0,0 +1,107 @@
+use std::{collections::BTreeMap, fs, io::{Error, ErrorKind}, path::{Path, PathBuf}};
+
+use super::{bank_of_ram, Symbol, SymbolTable};
+
+// Names the user gave to RAM addresses while reverse engineering a game, kept
+// next to the ROM as "player_x = C0A3" lines so the next session starts with them.
+// They are added to the symbols, so breakpoints, watchpoints, disassembly and
+// traces show them like the labels of a symbol file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Annotations {
+    by_address: BTreeMap<u16, String>,
+}
+
+impl Annotations {
+    // game.gb is annotated by game.labels
+    pub fn path_for<P: AsRef<Path>>(rom: P) -> PathBuf {
+        rom.as_ref().with_extension("labels")
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut annotations = Annotations::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split(';').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            annotations.add(line).map_err(|error| Error::new(ErrorKind::InvalidData, format!("{} at line {}", error, number + 1)))?;
+        }
+        Ok(annotations)
+    }
+
+    // A missing file has no annotations
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        match fs::read_to_string(path) {
+            Ok(text) => Annotations::parse(&text),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Annotations::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write(path, self.to_string())
+    }
+
+    // "name = AAAA", replacing what the address or the name had before
+    pub fn add(&mut self, text: &str) -> Result<(), Error> {
+        let invalid = || Error::new(ErrorKind::InvalidInput, format!("Invalid annotation {}, expected name = C0A3", text));
+        let (name, address) = text.split_once('=').ok_or_else(invalid)?;
+        let address = address.trim();
+        let address = address.strip_prefix("0x").or_else(|| address.strip_prefix('$')).unwrap_or(address);
+        let address = u16::from_str_radix(address, 16).map_err(|_| invalid())?;
+        self.set(name.trim(), address)
+    }
+
+    pub fn set(&mut self, name: &str, address: u16) -> Result<(), Error> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Invalid name {:?}, use letters, digits, _ and .", name)));
+        }
+        // ROM code is named by the symbol file of the build
+        if address < 0x8000 {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("{:04X} is not a RAM address", address)));
+        }
+        self.by_address.retain(|_, existing| existing != name);
+        self.by_address.insert(address, name.to_string());
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.by_address.len();
+        self.by_address.retain(|_, existing| existing != name);
+        self.by_address.len() != count
+    }
+
+    pub fn name_at(&self, address: u16) -> Option<&str> {
+        self.by_address.get(&address).map(String::as_str)
+    }
+
+    pub fn address_of(&self, name: &str) -> Option<u16> {
+        self.by_address.iter().find(|(_, existing)| *existing == name).map(|(address, _)| *address)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &str)> {
+        self.by_address.iter().map(|(address, name)| (*address, name.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_address.is_empty()
+    }
+
+    // Names already in the symbol file win over the annotations
+    pub fn add_to(&self, symbols: &mut SymbolTable) {
+        for (address, name) in self.iter() {
+            if symbols.lookup(name).is_none() {
+                symbols.insert(Symbol { bank: bank_of_ram(address), address, name: name.to_string() });
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Annotations {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (address, name) in self.iter() {
+            writeln!(f, "{} = {:04X}", name, address)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use std::io::{Error, ErrorKind};
+
+use crate::{savestate::SaveState, Emulation, CPU_CYCLES_PER_FRAME};
+
+use super::Expression;
+
+// Where an invariant stopped holding between two savestates
+#[derive(Clone, Debug)]
+pub struct Bisection {
+    // Frames run from the good state before the one where it breaks
+    pub frame: u64,
+    // Instructions run in that frame before the one that breaks it
+    pub instruction: u64,
+    // Address of the instruction that breaks it
+    pub pc: u16,
+    // Right before that instruction, load it to step through the failure
+    pub state: SaveState,
+}
+
+// Finds the first instruction after which the invariant doesn't hold anymore,
+// replaying from the good state: first by whole frames, then by instructions
+// inside the frame that breaks it. The replay has no input, so the bad state must
+// be reachable from the good one without pressing buttons. The emulation is left
+// at the returned state.
+pub fn bisect(emulation: &mut Emulation, good: &SaveState, bad: &SaveState, invariant: &Expression) -> Result<Bisection, Error> {
+    if bad.total_cycles() <= good.total_cycles() {
+        return Err(Error::new(ErrorKind::InvalidInput, "The bad savestate must be made after the good one"));
+    }
+    emulation.load_state(bad)?;
+    if holds(emulation, invariant) {
+        return Err(Error::new(ErrorKind::InvalidInput, "The invariant holds in the bad savestate"));
+    }
+    emulation.load_state(good)?;
+    if !holds(emulation, invariant) {
+        return Err(Error::new(ErrorKind::InvalidInput, "The invariant doesn't hold in the good savestate"));
+    }
+
+    let frames = (bad.total_cycles() - good.total_cycles()).div_ceil(CPU_CYCLES_PER_FRAME as u64);
+    let breaks = |emulation: &mut Emulation, frames: u64| -> Result<bool, Error> {
+        for _ in 0..frames {
+            emulation.skip_frame()?;
+        }
+        Ok(!holds(emulation, invariant))
+    };
+    if !breaks(emulation, frames)? {
+        return Err(Error::other("The replay doesn't break the invariant, the bad savestate may depend on input"));
+    }
+
+    // Invariant: it holds at lo and is broken at hi
+    let (mut lo, mut hi) = (0, frames);
+    let mut lo_state = good.clone();
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        emulation.load_state(&lo_state)?;
+        if breaks(emulation, mid - lo)? {
+            hi = mid;
+        } else {
+            lo = mid;
+            lo_state = emulation.save_state();
+        }
+    }
+    let frame = lo;
+    let frame_state = lo_state;
+
+    // Same search on the instructions of the breaking frame
+    emulation.load_state(&frame_state)?;
+    let mut instructions = 0;
+    let mut cycles = 0;
+    while cycles < CPU_CYCLES_PER_FRAME as u64 {
+        cycles += emulation.step_instruction()?;
+        instructions += 1;
+    }
+    let breaks = |emulation: &mut Emulation, instructions: u64| -> Result<bool, Error> {
+        for _ in 0..instructions {
+            emulation.step_instruction()?;
+        }
+        Ok(!holds(emulation, invariant))
+    };
+    let (mut lo, mut hi) = (0, instructions);
+    let mut lo_state = frame_state;
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        emulation.load_state(&lo_state)?;
+        if breaks(emulation, mid - lo)? {
+            hi = mid;
+        } else {
+            lo = mid;
+            lo_state = emulation.save_state();
+        }
+    }
+
+    emulation.load_state(&lo_state)?;
+    let pc = emulation.debugger().register("pc").unwrap_or(0);
+    Ok(Bisection { frame, instruction: lo, pc, state: lo_state })
+}
+
+fn holds(emulation: &mut Emulation, invariant: &Expression) -> bool {
+    invariant.holds(&emulation.debugger())
+}
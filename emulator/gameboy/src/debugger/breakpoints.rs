@@ -0,0 +1,31 @@
+use crate::{error::EmulationError, gameboy::GameBoy, mmu::Address};
+
+use super::{bank_at, Location};
+
+#[derive(Default)]
+pub(crate) struct Breakpoints {
+    pub(crate) locations: Vec<Location>,
+    // The instruction that stopped the emulation runs on the next step
+    resume_at: Option<Address>,
+}
+
+impl Breakpoints {
+    // Before every instruction, a halted CPU isn't about to run one
+    pub(crate) fn check(gb: &mut GameBoy) -> Result<(), EmulationError> {
+        if gb.breakpoints.locations.is_empty() || gb.cpu.is_halted {
+            return Ok(());
+        }
+        let pc = gb.cpu.pc;
+        if gb.breakpoints.resume_at.take() == Some(pc) {
+            return Ok(());
+        }
+        let bank = bank_at(gb, pc);
+        let hit = gb.breakpoints.locations.iter()
+            .any(|location| location.address == pc && location.bank.is_none_or(|b| b == bank));
+        if hit {
+            gb.breakpoints.resume_at = Some(pc);
+            return Err(EmulationError::Breakpoint { address: pc });
+        }
+        Ok(())
+    }
+}
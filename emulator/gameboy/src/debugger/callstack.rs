@@ -0,0 +1,105 @@
+use std::collections::VecDeque;
+
+use crate::{cpu::cpu::Flow, gameboy::GameBoy, mmu::{Address, MMU}};
+
+// Deeper calls push the outermost ones out
+const MAX_DEPTH: usize = 256;
+const MAX_WARNINGS: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CallFrame {
+    // The CALL or RST, or the instruction an interrupt came before
+    pub caller: u16,
+    pub target: u16,
+    pub return_address: u16,
+    // Where the return address is on the stack
+    pub sp: u16,
+    pub interrupt: bool,
+}
+
+// A RET that didn't return to the caller, usually a push/pop mismatch
+// or a buffer overflow in the stack
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StackWarning {
+    pub address: u16,
+    pub sp: u16,
+    pub returned_to: u16,
+    // What the innermost call expected
+    pub expected: CallFrame,
+}
+
+// Shadow of the calls the CPU made. Frames are left with RET/RETI, or with LD SP
+// when the program moves the stack above them.
+#[derive(Default)]
+pub(crate) struct CallStack {
+    pub(crate) frames: Vec<CallFrame>,
+    pub(crate) warnings: VecDeque<StackWarning>,
+}
+
+impl CallStack {
+    // After the CPU ran the instruction at address, sp is the stack pointer it started with
+    pub(crate) fn instruction(gb: &mut GameBoy, caller: Address, address: Address, sp: u16, interrupted: bool, flow: Flow) {
+        let (pc, sp_after) = (gb.cpu.pc, gb.cpu.sp);
+        let called = flow == Flow::Call && sp_after == sp.wrapping_sub(2);
+        let return_address = if called {
+            MMU::read_byte(gb, sp_after) as u16 | (MMU::read_byte(gb, sp_after.wrapping_add(1)) as u16) << 8
+        } else {
+            0
+        };
+        let stack = &mut gb.call_stack;
+
+        if interrupted {
+            // The return address is where the interrupt came from
+            stack.push(CallFrame { caller, target: address, return_address: caller, sp, interrupt: true });
+        }
+
+        let mut warning = None;
+        if flow == Flow::Return && sp_after == sp.wrapping_add(2) {
+            if let Some(frame) = stack.frames.last().copied() {
+                // Returning from below the frame is a jump through PUSH and RET
+                let unbalanced = sp > frame.sp;
+                let smashed = sp == frame.sp && pc != frame.return_address;
+                if unbalanced || smashed {
+                    warning = Some(StackWarning { address, sp, returned_to: pc, expected: frame });
+                }
+                if sp >= frame.sp {
+                    stack.unwind(sp_after);
+                }
+            }
+        }
+        if flow == Flow::SetStack {
+            stack.unwind(sp_after);
+        }
+        if called {
+            stack.push(CallFrame { caller: address, target: pc, return_address, sp: sp_after, interrupt: false });
+        }
+
+        if let Some(warning) = warning {
+            if stack.warnings.len() == MAX_WARNINGS {
+                stack.warnings.pop_front();
+            }
+            stack.warnings.push_back(warning);
+            if let Some(observer) = &mut gb.observer {
+                observer.on_stack_warning(&warning);
+            }
+        }
+    }
+
+    fn push(&mut self, frame: CallFrame) {
+        if self.frames.len() == MAX_DEPTH {
+            self.frames.remove(0);
+        }
+        self.frames.push(frame);
+    }
+
+    // Frames whose return address is above the stack pointer are gone
+    fn unwind(&mut self, sp: u16) {
+        while self.frames.last().is_some_and(|frame| frame.sp < sp) {
+            self.frames.pop();
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{error::EmulationError, gameboy::GameBoy, mmu::{Address, MMU}};
+
+// Bytes written here are printed, homebrew's putchar
+pub(crate) const DEBUG_OUTPUT_ADDRESS: Address = 0xFF7F;
+// LD B,B, the source code breakpoint of BGB
+const LD_B_B: u8 = 0x40;
+// An opcode without an instruction, skipped after the break
+const BREAK_OPCODE: u8 = 0xED;
+
+// Registers and opcodes that only exist in emulators, for homebrew to print
+// debug messages and stop where it wants. Off by default, games see the
+// hardware: FF7F isn't mapped, LD B,B does nothing and ED is illegal.
+#[derive(Default)]
+pub(crate) struct DeveloperMode {
+    pub(crate) enabled: bool,
+    output: String,
+    // The breakpoint that stopped the emulation runs on the next step
+    resume_at: Option<Address>,
+}
+
+impl DeveloperMode {
+    pub(crate) fn print(gb: &mut GameBoy, value: u8) {
+        gb.developer.output.push(value as char);
+    }
+
+    pub(crate) fn take_output(gb: &mut GameBoy) -> String {
+        std::mem::take(&mut gb.developer.output)
+    }
+
+    // Before every instruction, like the breakpoints of the debugger
+    pub(crate) fn check(gb: &mut GameBoy) -> Result<(), EmulationError> {
+        if !gb.developer.enabled || gb.cpu.is_halted {
+            return Ok(());
+        }
+        let pc = gb.cpu.pc;
+        let opcode = MMU::read_byte(gb, pc);
+        if opcode != LD_B_B && opcode != BREAK_OPCODE {
+            return Ok(());
+        }
+        if gb.developer.resume_at.take() == Some(pc) {
+            if opcode == BREAK_OPCODE {
+                gb.cpu.pc = pc.wrapping_add(1);
+            }
+            return Ok(());
+        }
+        gb.developer.resume_at = Some(pc);
+        Err(EmulationError::Breakpoint { address: pc })
+    }
+}
@@ -0,0 +1,108 @@
+use std::cell::Cell;
+
+use crate::{cartridge::Cartridge, cpu::cpu::Flow, gameboy::GameBoy, io::lcd::{LCDMode, LCD}, mmu::{Address, Region}};
+
+// Sound registers that only take writes, reading them gives 0xFF on hardware:
+// NR13, NR23, NR31, NR33 and NR41, then the CGB HDMA source and destination
+const WRITE_ONLY: [Address; 9] = [0xFF13, 0xFF18, 0xFF1B, 0xFF1D, 0xFF20, 0xFF51, 0xFF52, 0xFF53, 0xFF54];
+// SP left by the boot ROM, until the program sets its own
+const INITIAL_STACK: u16 = 0xFFFE;
+
+// Something the hardware lets a program do that is almost always a bug
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    // The cartridge has no mapper, or one that isn't emulated, to take the write
+    RomWriteWithoutMapper { address: u16, value: u8 },
+    WriteOnlyRead { address: u16 },
+    // The PPU is drawing from VRAM in mode 3, the write is lost
+    VramWriteDuringTransfer { address: u16, value: u8 },
+    // Also lost, OAM is busy in modes 2 and 3
+    OamWriteDuringScan { address: u16, value: u8 },
+    // POP or RET above the stack set by the last LD SP. Copy loops that pop
+    // from a table show up here too.
+    StackUnderflow { sp: u16 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    // The instruction that did it and the ROM bank it runs from
+    pub pc: u16,
+    pub bank: u16,
+}
+
+// Checks made while an observer is set, delivered after every instruction
+pub(crate) struct Diagnostics {
+    pending: Vec<DiagnosticKind>,
+    // Reads only borrow the Game Boy
+    write_only_read: Cell<Option<Address>>,
+    stack_base: u16,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Diagnostics { pending: Vec::new(), write_only_read: Cell::new(None), stack_base: INITIAL_STACK }
+    }
+}
+
+impl Diagnostics {
+    pub(crate) fn enabled(gb: &GameBoy) -> bool {
+        gb.observer.is_some()
+    }
+
+    pub(crate) fn report(gb: &mut GameBoy, kind: DiagnosticKind) {
+        if Diagnostics::enabled(gb) {
+            gb.diagnostics.pending.push(kind);
+        }
+    }
+
+    pub(crate) fn io_read(gb: &GameBoy, address: Address) {
+        if Diagnostics::enabled(gb) && WRITE_ONLY.contains(&address) {
+            gb.diagnostics.write_only_read.set(Some(address));
+        }
+    }
+
+    // Writes the hardware drops, before the MMU does the write
+    pub(crate) fn write(gb: &mut GameBoy, region: Region, address: Address, value: u8) {
+        let kind = match (region, LCD::mode(gb)) {
+            (Region::BootRom | Region::Rom0 | Region::RomN, _) if !Cartridge::has_mapper(gb) => DiagnosticKind::RomWriteWithoutMapper { address, value },
+            (Region::Vram, LCDMode::Transfering) => DiagnosticKind::VramWriteDuringTransfer { address, value },
+            (Region::Oam, LCDMode::SearchingOAM | LCDMode::Transfering) => DiagnosticKind::OamWriteDuringScan { address, value },
+            _ => return,
+        };
+        gb.diagnostics.pending.push(kind);
+    }
+
+    // Called by POP and RET before moving the stack pointer
+    pub(crate) fn pop(gb: &mut GameBoy) {
+        if gb.cpu.sp >= gb.diagnostics.stack_base {
+            Diagnostics::report(gb, DiagnosticKind::StackUnderflow { sp: gb.cpu.sp });
+        }
+    }
+
+    // Pokes and cheats between instructions are not the program's
+    pub(crate) fn clear(gb: &mut GameBoy) {
+        gb.diagnostics.pending.clear();
+        gb.diagnostics.write_only_read.set(None);
+    }
+
+    // After the CPU ran the instruction at address
+    pub(crate) fn instruction(gb: &mut GameBoy, address: Address, flow: Flow) {
+        if flow == Flow::SetStack {
+            gb.diagnostics.stack_base = gb.cpu.sp;
+        }
+        if let Some(read) = gb.diagnostics.write_only_read.take() {
+            gb.diagnostics.pending.push(DiagnosticKind::WriteOnlyRead { address: read });
+        }
+        if gb.diagnostics.pending.is_empty() {
+            return;
+        }
+        let bank = Cartridge::rom_bank(gb, address);
+        let pending = std::mem::take(&mut gb.diagnostics.pending);
+        if let Some(observer) = &mut gb.observer {
+            for kind in pending {
+                observer.on_diagnostic(&Diagnostic { kind, pc: address, bank });
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::io::{Error, ErrorKind};
+
+use super::Debugger;
+
+// Conditions on the machine state, like "[0xC0A0] < 3 && a != 0".
+// Numbers are decimal, or hexadecimal with a 0x or $ prefix, registers use
+// their lowercase names and [address] reads a byte. Values are 16-bit and
+// wrap, comparisons and logic operators give 1 or 0. In watchpoint conditions
+// old and new are the value before and after the write.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expression {
+    Number(u16),
+    Register(String),
+    Variable(String),
+    Memory(Box<Expression>),
+    Not(Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Or, And,
+    Equal, NotEqual, Less, LessEqual, Greater, GreaterEqual,
+    BitOr, BitXor, BitAnd,
+    Add, Sub,
+}
+
+// Binary operators from the loosest to the tightest binding
+const PRECEDENCE: [&[(&str, Operator)]; 7] = [
+    &[("||", Operator::Or)],
+    &[("&&", Operator::And)],
+    &[("==", Operator::Equal), ("!=", Operator::NotEqual), ("<=", Operator::LessEqual),
+      (">=", Operator::GreaterEqual), ("<", Operator::Less), (">", Operator::Greater)],
+    &[("|", Operator::BitOr)],
+    &[("^", Operator::BitXor)],
+    &[("&", Operator::BitAnd)],
+    &[("+", Operator::Add), ("-", Operator::Sub)],
+];
+
+const REGISTERS: [&str; 14] = ["a", "f", "b", "c", "d", "e", "h", "l", "af", "bc", "de", "hl", "sp", "pc"];
+const VARIABLES: [&str; 2] = ["old", "new"];
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Expression, Error> {
+        let mut parser = Parser { source, position: 0 };
+        let expression = parser.binary(0)?;
+        parser.skip_whitespace();
+        if parser.position < source.len() {
+            return Err(parser.error("unexpected input"));
+        }
+        Ok(expression)
+    }
+
+    pub fn evaluate(&self, debugger: &Debugger) -> u16 {
+        self.evaluate_with(debugger, &[])
+    }
+
+    // Variables without a value are 0
+    pub fn evaluate_with(&self, debugger: &Debugger, variables: &[(&str, u16)]) -> u16 {
+        match self {
+            Expression::Number(value) => *value,
+            Expression::Register(name) => debugger.register(name).unwrap_or(0),
+            Expression::Variable(name) => variables.iter().find(|(variable, _)| variable == name).map_or(0, |(_, value)| *value),
+            Expression::Memory(address) => debugger.read_byte(address.evaluate_with(debugger, variables)) as u16,
+            Expression::Not(operand) => (operand.evaluate_with(debugger, variables) == 0) as u16,
+            Expression::Binary(operator, left, right) => {
+                let left = left.evaluate_with(debugger, variables);
+                // Logic operators short-circuit
+                match operator {
+                    Operator::Or if left != 0 => return 1,
+                    Operator::And if left == 0 => return 0,
+                    _ => {}
+                }
+                let right = right.evaluate_with(debugger, variables);
+                match operator {
+                    Operator::Or | Operator::And => (right != 0) as u16,
+                    Operator::Equal => (left == right) as u16,
+                    Operator::NotEqual => (left != right) as u16,
+                    Operator::Less => (left < right) as u16,
+                    Operator::LessEqual => (left <= right) as u16,
+                    Operator::Greater => (left > right) as u16,
+                    Operator::GreaterEqual => (left >= right) as u16,
+                    Operator::BitOr => left | right,
+                    Operator::BitXor => left ^ right,
+                    Operator::BitAnd => left & right,
+                    Operator::Add => left.wrapping_add(right),
+                    Operator::Sub => left.wrapping_sub(right),
+                }
+            }
+        }
+    }
+
+    pub fn holds(&self, debugger: &Debugger) -> bool {
+        self.evaluate(debugger) != 0
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, reason: &str) -> Error {
+        Error::new(ErrorKind::InvalidInput, format!("Invalid expression at column {}: {}", self.position + 1, reason))
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.source[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.position = self.source.len() - self.rest().trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if !self.rest().starts_with(token) {
+            return false;
+        }
+        // | and & must not match the start of || and &&, ! the start of !=
+        let next = &self.rest()[token.len()..];
+        let longer = match token {
+            "|" | "&" => next.starts_with(token),
+            "!" => next.starts_with('='),
+            _ => false,
+        };
+        if !longer {
+            self.position += token.len();
+        }
+        !longer
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expression, Error> {
+        if level == PRECEDENCE.len() {
+            return self.unary();
+        }
+        let mut left = self.binary(level + 1)?;
+        'operators: loop {
+            for (token, operator) in PRECEDENCE[level] {
+                if self.eat(token) {
+                    let right = self.binary(level + 1)?;
+                    left = Expression::Binary(*operator, Box::new(left), Box::new(right));
+                    continue 'operators;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expression, Error> {
+        if self.eat("!") {
+            return Ok(Expression::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let inner = self.binary(0)?;
+            return if self.eat(")") { Ok(inner) } else { Err(self.error("expected )")) };
+        }
+        if self.eat("[") {
+            let address = self.binary(0)?;
+            return if self.eat("]") { Ok(Expression::Memory(Box::new(address))) } else { Err(self.error("expected ]")) };
+        }
+
+        let word: &str = self.rest().split(|c: char| !c.is_ascii_alphanumeric() && c != '$').next().unwrap_or("");
+        if word.is_empty() {
+            return Err(self.error("expected a number, register, [address] or ("));
+        }
+        let expression = if let Some(hex) = word.strip_prefix("0x").or_else(|| word.strip_prefix('$')) {
+            u16::from_str_radix(hex, 16).map(Expression::Number).map_err(|_| self.error("invalid number"))?
+        } else if word.starts_with(|c: char| c.is_ascii_digit()) {
+            word.parse().map(Expression::Number).map_err(|_| self.error("invalid number"))?
+        } else if REGISTERS.contains(&word) {
+            Expression::Register(word.to_string())
+        } else if VARIABLES.contains(&word) {
+            Expression::Variable(word.to_string())
+        } else {
+            return Err(self.error("unknown register"));
+        };
+        self.position += word.len();
+        Ok(expression)
+    }
+}
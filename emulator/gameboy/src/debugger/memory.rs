@@ -0,0 +1,113 @@
+use crate::{gameboy::GameBoy, mmu::{Address, MMU}};
+
+use std::fmt::Write;
+
+use super::{Debugger, SymbolTable};
+
+// Identifies a region watched with Debugger::watch_region
+pub type RegionId = u32;
+
+struct WatchedRegion {
+    id: RegionId,
+    start: Address,
+    // Contents at the last notification
+    snapshot: Vec<u8>,
+}
+
+// Regions of memory shown by frontends, like the panes of a hex editor. Their
+// contents are compared once per frame and the observer gets the changed ones.
+#[derive(Default)]
+pub(crate) struct MemoryWatches {
+    next_id: RegionId,
+    regions: Vec<WatchedRegion>,
+}
+
+impl MemoryWatches {
+    // Called at VBlank, nothing is read when there is no observer
+    pub(crate) fn notify(gb: &mut GameBoy) {
+        if gb.memory_watches.regions.is_empty() || gb.observer.is_none() {
+            return;
+        }
+        let mut regions = std::mem::take(&mut gb.memory_watches.regions);
+        for region in regions.iter_mut() {
+            let mut current = vec![0; region.snapshot.len()];
+            read_into(gb, region.start, &mut current);
+            if current != region.snapshot {
+                region.snapshot = current;
+                if let Some(observer) = &mut gb.observer {
+                    observer.on_memory_changed(region.id, region.start, &region.snapshot);
+                }
+            }
+        }
+        gb.memory_watches.regions = regions;
+    }
+}
+
+// Addresses past FFFF wrap around to 0000
+fn read_into(gb: &GameBoy, start: Address, buffer: &mut [u8]) {
+    for (offset, byte) in buffer.iter_mut().enumerate() {
+        *byte = MMU::read_byte(gb, start.wrapping_add(offset as Address));
+    }
+}
+
+impl Debugger<'_> {
+    // Fills the buffer from start on, as the CPU would read it
+    pub fn read_into(&self, start: u16, buffer: &mut [u8]) {
+        read_into(self.gb, start, buffer);
+    }
+
+    pub fn read_range(&self, start: u16, len: usize) -> Vec<u8> {
+        let mut buffer = vec![0; len];
+        self.read_into(start, &mut buffer);
+        buffer
+    }
+
+    // Rows of 16 bytes from start on for a memory viewer, each followed by
+    // the labels of its addresses
+    pub fn memory_view(&self, start: u16, len: usize, symbols: Option<&SymbolTable>) -> String {
+        let mut view = String::new();
+        for row in (0..len).step_by(16) {
+            let address = start.wrapping_add(row as u16);
+            let bytes = self.read_range(address, (len - row).min(16));
+            let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            let labels: Vec<String> = (0..bytes.len() as u16).map(|offset| address.wrapping_add(offset)).filter_map(|address| {
+                symbols?.name_at(self.bank_at(address), address).map(|name| format!("{:04X} {}", address, name))
+            }).collect();
+            let line = format!("{:04X}  {:<47}  {}", address, hex.join(" "), labels.join(", "));
+            let _ = writeln!(view, "{}", line.trim_end());
+        }
+        view
+    }
+
+    // Written immediately as the CPU would write it, so the ROM area
+    // sends commands to the mapper instead of changing the ROM
+    pub fn write_byte(&mut self, address: u16, value: u8) {
+        MMU::write_byte(self.gb, address, value);
+    }
+
+    pub fn write_bytes(&mut self, start: u16, bytes: &[u8]) {
+        for (offset, value) in bytes.iter().enumerate() {
+            self.write_byte(start.wrapping_add(offset as u16), *value);
+        }
+    }
+
+    // EmulatorObserver::on_memory_changed gets the region whenever its contents
+    // differ at the end of a frame
+    pub fn watch_region(&mut self, start: u16, len: usize) -> RegionId {
+        let watches = &mut self.gb.memory_watches;
+        let id = watches.next_id;
+        watches.next_id += 1;
+
+        let snapshot = self.read_range(start, len);
+        self.gb.memory_watches.regions.push(WatchedRegion { id, start, snapshot });
+        id
+    }
+
+    // Returns false when the region was not watched
+    pub fn unwatch_region(&mut self, id: RegionId) -> bool {
+        let regions = &mut self.gb.memory_watches.regions;
+        let count = regions.len();
+        regions.retain(|region| region.id != id);
+        regions.len() != count
+    }
+}
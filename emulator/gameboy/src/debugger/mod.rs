@@ -0,0 +1,240 @@
+mod annotations;
+mod bisect;
+mod breakpoints;
+mod callstack;
+mod developer;
+mod diagnostics;
+mod expression;
+mod memory;
+mod search;
+mod symbols;
+mod watchpoints;
+mod tests;
+mod views;
+
+pub use annotations::Annotations;
+pub use bisect::{bisect, Bisection};
+pub use expression::Expression;
+pub use memory::RegionId;
+pub(crate) use memory::MemoryWatches;
+pub(crate) use breakpoints::Breakpoints;
+pub use callstack::{CallFrame, StackWarning};
+pub(crate) use callstack::CallStack;
+pub use diagnostics::{Diagnostic, DiagnosticKind};
+pub(crate) use diagnostics::Diagnostics;
+pub(crate) use developer::{DeveloperMode, DEBUG_OUTPUT_ADDRESS};
+pub use search::{MemorySearch, SearchFilter};
+pub use symbols::{Location, Symbol, SymbolTable};
+pub use watchpoints::Watchpoint;
+pub(crate) use watchpoints::Watchpoints;
+pub use views::{OamEntry, PaletteView, Palettes, SpriteImage, SpriteStatus, SpriteVisibility, TileMap};
+pub use crate::io::lcd::ScanlineRegisters;
+
+use crate::{cartridge::Cartridge, cpu::instructions::{decode::Instruction, disassembly::Operands, table::Operand}, gameboy::GameBoy, mmu::{Address, MMU, WRAM_BEGIN, WRAM_END}};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DisassembledInstruction {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub text: String,
+    // Machine cycles without and with the branch taken, None for data
+    pub cycles: Option<(u8, u8)>,
+}
+
+// Inspection of a running emulation, obtained through Emulation::debugger
+pub struct Debugger<'a> {
+    gb: &'a mut GameBoy,
+}
+
+impl<'a> Debugger<'a> {
+    pub(crate) fn new(gb: &'a mut GameBoy) -> Self {
+        Debugger { gb }
+    }
+
+    // Reads as the CPU would, without side effects on the emulation
+    pub fn read_byte(&self, address: u16) -> u8 {
+        MMU::read_byte(self.gb, address)
+    }
+
+    // 8 and 16-bit registers by their lowercase name, like "a", "hl" or "pc"
+    pub fn register(&self, name: &str) -> Option<u16> {
+        let regs = &self.gb.cpu.regs;
+        let value = match name {
+            "a" => regs.a as u16,
+            "f" => regs.get_af() & 0xFF,
+            "b" => regs.b as u16,
+            "c" => regs.c as u16,
+            "d" => regs.d as u16,
+            "e" => regs.e as u16,
+            "h" => regs.h as u16,
+            "l" => regs.l as u16,
+            "af" => regs.get_af(),
+            "bc" => regs.get_bc(),
+            "de" => regs.get_de(),
+            "hl" => regs.get_hl(),
+            "sp" => self.gb.cpu.sp,
+            "pc" => self.gb.cpu.pc,
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    // Same names as register, false for unknown ones. 8-bit registers keep the low byte.
+    pub fn set_register(&mut self, name: &str, value: u16) -> bool {
+        let regs = &mut self.gb.cpu.regs;
+        match name {
+            "a" => regs.a = value as u8,
+            "f" => regs.set_af((regs.a as u16) << 8 | value & 0xFF),
+            "b" => regs.b = value as u8,
+            "c" => regs.c = value as u8,
+            "d" => regs.d = value as u8,
+            "e" => regs.e = value as u8,
+            "h" => regs.h = value as u8,
+            "l" => regs.l = value as u8,
+            "af" => regs.set_af(value),
+            "bc" => regs.set_bc(value),
+            "de" => regs.set_de(value),
+            "hl" => regs.set_hl(value),
+            "sp" => self.gb.cpu.sp = value,
+            "pc" => self.gb.cpu.pc = value,
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn work_ram(&self) -> Vec<u8> {
+        (WRAM_BEGIN..=WRAM_END).map(|address| self.read_byte(address)).collect()
+    }
+
+    // Index of the 4-bit sample the wave channel is playing, 0-31
+    pub fn wave_position(&self) -> u8 {
+        self.gb.io.apu.wave.position
+    }
+
+    // The 32 samples of wave RAM, high nibbles first like the channel plays them
+    pub fn wave_samples(&self) -> Vec<u8> {
+        self.gb.io.apu.wave_ram.iter().flat_map(|byte| [byte >> 4, byte & 0x0F]).collect()
+    }
+
+    // Changes a sample while the game runs, the channel picks it up
+    // the next time it reaches that position
+    pub fn set_wave_sample(&mut self, index: u8, sample: u8) {
+        let byte = &mut self.gb.io.apu.wave_ram[(index as usize / 2) % 16];
+        *byte = if index.is_multiple_of(2) {
+            (*byte & 0x0F) | ((sample & 0x0F) << 4)
+        } else {
+            (*byte & 0xF0) | (sample & 0x0F)
+        };
+    }
+
+    // SCX, SCY, WX, WY, LCDC and BGP as each line of the last frame was drawn
+    pub fn scanline_registers(&self) -> Vec<ScanlineRegisters> {
+        self.gb.io.lcd.last_frame_registers.to_vec()
+    }
+
+    // Starts a memory search from the current contents of work RAM
+    pub fn start_search(&self) -> MemorySearch {
+        MemorySearch::new(self.work_ram())
+    }
+
+    // Bank of the symbol file an address belongs to with the current mapping
+    pub fn bank_at(&self, address: u16) -> u16 {
+        bank_at(self.gb, address)
+    }
+
+    // The address with its label when the symbols have one near it
+    pub fn symbolize(&self, address: u16, symbols: Option<&SymbolTable>) -> String {
+        symbols.and_then(|symbols| symbols.symbolize(self.bank_at(address), address))
+            .unwrap_or_else(|| format!("${:04X}", address))
+    }
+
+    // Decodes the instruction at an address as mapped now, jump targets and
+    // memory operands are shown by name when there are symbols
+    pub fn disassemble(&self, address: u16, symbols: Option<&SymbolTable>) -> DisassembledInstruction {
+        let opcode = self.read_byte(address);
+        let entry = if opcode == 0xCB {
+            Some(Instruction::decode_prefixed(self.read_byte(address.wrapping_add(1))))
+        } else {
+            Instruction::decode(opcode)
+        };
+        let Some(entry) = entry else {
+            return DisassembledInstruction { address, bytes: vec![opcode], text: format!("db ${:02X}", opcode), cycles: None };
+        };
+
+        let bytes: Vec<u8> = (0..entry.size as u16).map(|offset| self.read_byte(address.wrapping_add(offset))).collect();
+        let (byte, word) = match entry.operand {
+            Operand::None => (0, 0),
+            Operand::Byte => (bytes[1], 0),
+            Operand::Word => (bytes[1], u16::from_le_bytes([bytes[1], bytes[2]])),
+        };
+        let label = |target| self.symbolize(target, symbols);
+        let text = entry.instruction.disassemble(&Operands { address, byte, word, label: &label });
+        DisassembledInstruction { address, bytes, text, cycles: Some(entry.cycles) }
+    }
+
+    // The emulation stops with EmulationError::Watchpoint after the instruction
+    // that wrote the address
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.gb.watchpoints.watchpoints.push(watchpoint);
+    }
+
+    // Removes every watchpoint on the address
+    pub fn remove_watchpoints(&mut self, address: u16) -> bool {
+        let watchpoints = &mut self.gb.watchpoints.watchpoints;
+        let count = watchpoints.len();
+        watchpoints.retain(|watchpoint| watchpoint.address != address);
+        watchpoints.len() != count
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.gb.watchpoints.watchpoints
+    }
+
+    // Calls that haven't returned yet, innermost first
+    pub fn backtrace(&self) -> Vec<CallFrame> {
+        self.gb.call_stack.frames.iter().rev().copied().collect()
+    }
+
+    // The last RETs that didn't go back to their caller, oldest first
+    pub fn stack_warnings(&self) -> Vec<StackWarning> {
+        self.gb.call_stack.warnings.iter().copied().collect()
+    }
+
+    pub fn clear_stack_warnings(&mut self) {
+        self.gb.call_stack.warnings.clear();
+    }
+
+    // The emulation stops with EmulationError::Breakpoint before running the
+    // instruction, stepping again runs it
+    pub fn add_breakpoint(&mut self, location: Location) {
+        if !self.gb.breakpoints.locations.contains(&location) {
+            self.gb.breakpoints.locations.push(location);
+        }
+    }
+
+    pub fn remove_breakpoint(&mut self, location: Location) -> bool {
+        let count = self.gb.breakpoints.locations.len();
+        self.gb.breakpoints.locations.retain(|breakpoint| *breakpoint != location);
+        self.gb.breakpoints.locations.len() != count
+    }
+
+    pub fn breakpoints(&self) -> &[Location] {
+        &self.gb.breakpoints.locations
+    }
+}
+
+// RGBDS numbers the switchable banks, WRAM at D000 is bank 1 on the DMG
+pub(crate) fn bank_at(gb: &GameBoy, address: Address) -> u16 {
+    match address {
+        0x0000 ..= 0x7FFF => Cartridge::rom_bank(gb, address),
+        _ => bank_of_ram(address),
+    }
+}
+
+// The switchable work RAM is bank 1 in symbol files, the only one on the DMG
+fn bank_of_ram(address: Address) -> u16 {
+    match address {
+        0xD000 ..= 0xDFFF => 1,
+        _ => 0,
+    }
+}
@@ -0,0 +1,65 @@
+use crate::mmu::{Address, WRAM_BEGIN};
+
+use super::{Annotations, Debugger};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchFilter {
+    // Compared against the snapshot taken by the previous filter
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+    Equals(u8),
+}
+
+// Narrows down the work RAM addresses that hold a value, like health or score,
+// by comparing snapshots while the game runs
+pub struct MemorySearch {
+    snapshot: Vec<u8>,
+    candidates: Vec<Address>,
+}
+
+impl MemorySearch {
+    pub(crate) fn new(snapshot: Vec<u8>) -> Self {
+        let candidates = (0..snapshot.len()).map(|offset| WRAM_BEGIN + offset as Address).collect();
+        MemorySearch { snapshot, candidates }
+    }
+
+    // Keeps the candidates matching the filter and takes a new snapshot
+    pub fn filter(&mut self, debugger: &Debugger, filter: SearchFilter) {
+        let current = debugger.work_ram();
+
+        self.candidates.retain(|address| {
+            let offset = (address - WRAM_BEGIN) as usize;
+            let (before, now) = (self.snapshot[offset], current[offset]);
+            match filter {
+                SearchFilter::Changed => now != before,
+                SearchFilter::Unchanged => now == before,
+                SearchFilter::Increased => now > before,
+                SearchFilter::Decreased => now < before,
+                SearchFilter::Equals(value) => now == value,
+            }
+        });
+        self.snapshot = current;
+    }
+
+    pub fn results(&self) -> &[u16] {
+        &self.candidates
+    }
+
+    // The candidates with the names the user gave them
+    pub fn labeled_results<'a>(&self, annotations: &'a Annotations) -> Vec<(u16, Option<&'a str>)> {
+        self.candidates.iter().map(|address| (*address, annotations.name_at(*address))).collect()
+    }
+
+    // Value of a candidate in the last snapshot
+    pub fn value(&self, address: u16) -> Option<u8> {
+        let offset = address.checked_sub(WRAM_BEGIN)? as usize;
+        self.snapshot.get(offset).copied()
+    }
+
+    // GameShark code that keeps the address at the given value, ready for CheatManager::add
+    pub fn to_gameshark(address: u16, value: u8) -> String {
+        format!("01{:02X}{:02X}{:02X}", value, address & 0xFF, address >> 8)
+    }
+}
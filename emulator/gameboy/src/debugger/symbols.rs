@@ -0,0 +1,116 @@
+use std::{collections::HashMap, fs, io::{Error, ErrorKind}, path::Path};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub bank: u16,
+    pub address: u16,
+    pub name: String,
+}
+
+// An address with the bank it must be mapped from, any bank when there is none
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub bank: Option<u16>,
+    pub address: u16,
+}
+
+// Labels of an RGBDS .sym file, one "BB:AAAA Name" per line
+// https://rgbds.gbdev.io/sym/
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    // Sorted by bank and address
+    symbols: Vec<Symbol>,
+    by_name: HashMap<String, usize>,
+}
+
+impl SymbolTable {
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut symbols = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split(';').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || Error::new(ErrorKind::InvalidData, format!("Invalid symbol at line {}", number + 1));
+            let (location, name) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let (bank, address) = location.split_once(':').ok_or_else(invalid)?;
+            symbols.push(Symbol {
+                bank: u16::from_str_radix(bank, 16).map_err(|_| invalid())?,
+                address: u16::from_str_radix(address, 16).map_err(|_| invalid())?,
+                name: name.trim().to_string(),
+            });
+        }
+        symbols.sort_by_key(|symbol| (symbol.bank, symbol.address));
+        let by_name = symbols.iter().enumerate().map(|(index, symbol)| (symbol.name.clone(), index)).collect();
+        Ok(SymbolTable { symbols, by_name })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        SymbolTable::parse(&fs::read_to_string(path)?)
+    }
+
+    // Replaces a symbol with the same name
+    pub fn insert(&mut self, symbol: Symbol) {
+        self.symbols.retain(|existing| existing.name != symbol.name);
+        let index = self.symbols.partition_point(|existing| (existing.bank, existing.address) <= (symbol.bank, symbol.address));
+        self.symbols.insert(index, symbol);
+        self.by_name = self.symbols.iter().enumerate().map(|(index, symbol)| (symbol.name.clone(), index)).collect();
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&Symbol> {
+        self.by_name.get(name).map(|&index| &self.symbols[index])
+    }
+
+    pub fn name_at(&self, bank: u16, address: u16) -> Option<&str> {
+        self.symbols.binary_search_by_key(&(bank, address), |symbol| (symbol.bank, symbol.address))
+            .ok().map(|index| self.symbols[index].name.as_str())
+    }
+
+    // Nearest label at or before the address in the same memory area
+    pub fn containing(&self, bank: u16, address: u16) -> Option<&Symbol> {
+        let index = self.symbols.partition_point(|symbol| (symbol.bank, symbol.address) <= (bank, address));
+        self.symbols[..index].last()
+            .filter(|symbol| symbol.bank == bank && area(symbol.address) == area(address))
+    }
+
+    // The containing label with the distance from it, like "Main+$12"
+    pub fn symbolize(&self, bank: u16, address: u16) -> Option<String> {
+        let symbol = self.containing(bank, address)?;
+        let offset = address - symbol.address;
+        Some(if offset == 0 { symbol.name.clone() } else { format!("{}+${:X}", symbol.name, offset) })
+    }
+
+    // A label, "BB:AAAA" or a plain address in hex with 0x or $
+    pub fn resolve(&self, text: &str) -> Result<Location, Error> {
+        let text = text.trim();
+        if let Some(symbol) = self.lookup(text) {
+            return Ok(Location { bank: Some(symbol.bank), address: symbol.address });
+        }
+        let invalid = || Error::new(ErrorKind::InvalidInput, format!("Unknown symbol or address {}", text));
+        let hex = |digits: &str| u16::from_str_radix(digits, 16).map_err(|_| invalid());
+        if let Some((bank, address)) = text.split_once(':') {
+            return Ok(Location { bank: Some(hex(bank)?), address: hex(address)? });
+        }
+        let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix('$')).ok_or_else(invalid)?;
+        Ok(Location { bank: None, address: hex(digits)? })
+    }
+}
+
+// Sections in RGBDS can't cross these boundaries
+fn area(address: u16) -> u16 {
+    match address {
+        0x0000 ..= 0x3FFF => 0x0000,
+        0x4000 ..= 0x7FFF => 0x4000,
+        0x8000 ..= 0x9FFF => 0x8000,
+        0xA000 ..= 0xBFFF => 0xA000,
+        0xC000 ..= 0xCFFF => 0xC000,
+        0xD000 ..= 0xDFFF => 0xD000,
+        0xE000 ..= 0xFF7F => 0xE000,
+        0xFF80 ..= 0xFFFE => 0xFF80,
+        0xFFFF => 0xFFFF,
+    }
+}
@@ -0,0 +1,334 @@
+#[cfg(test)]
+use crate::{cheats::CheatCode, savestate::SaveState, Emulation};
+
+#[cfg(test)]
+use crate::EmulationError;
+
+#[cfg(test)]
+use super::{bisect, Annotations, Diagnostic, DiagnosticKind, Expression, Location, MemorySearch, OamEntry, SearchFilter, SpriteVisibility, SymbolTable, TileMap, Watchpoint};
+
+#[test]
+fn expressions_parse_with_precedence() {
+    let mut emulation = Emulation::new(None);
+    let debugger = emulation.debugger();
+
+    assert_eq!(Expression::parse("1 + 2 == 3 && !0").unwrap().evaluate(&debugger), 1);
+    assert_eq!(Expression::parse("0xF0 | $0F & 3").unwrap().evaluate(&debugger), 0xF3);
+    assert_eq!(Expression::parse("(pc + 1) - 2").unwrap().evaluate(&debugger), 0xFFFF);
+    assert_eq!(Expression::parse("[0x0000] == 0x31").unwrap().evaluate(&debugger), 1);
+    assert!(Expression::parse("1 +").is_err());
+    assert!(Expression::parse("ix == 0").is_err());
+    assert!(Expression::parse("[1").is_err());
+}
+
+#[test]
+fn savestate_round_trip() {
+    let mut emulation = Emulation::new(None);
+    emulation.skip_frame().unwrap();
+    let state = emulation.save_state();
+
+    let first = emulation.step().unwrap().framebuffer;
+    let cycles = emulation.total_cycles;
+    emulation.load_state(&SaveState::from_bytes(state.as_bytes().to_vec()).unwrap()).unwrap();
+    assert_eq!(emulation.total_cycles, state.total_cycles());
+    assert_eq!(emulation.step().unwrap().framebuffer, first);
+    assert_eq!(emulation.total_cycles, cycles);
+
+    assert!(SaveState::from_bytes(b"YGBS".to_vec()).is_err());
+}
+
+#[test]
+fn bisect_finds_the_breaking_instruction() {
+    // The boot ROM clears VRAM backwards from 9FFF with LD (HL-),A at 0007
+    let mut emulation = Emulation::new(None);
+    for _ in 0..3 {
+        emulation.step_instruction().unwrap();
+    }
+    let good = emulation.save_state();
+    for _ in 0..4 {
+        emulation.skip_frame().unwrap();
+    }
+    let bad = emulation.save_state();
+
+    let invariant = Expression::parse("hl >= 0x9000").unwrap();
+    let bisection = bisect(&mut emulation, &good, &bad, &invariant).unwrap();
+    assert_eq!(bisection.pc, 0x0007);
+    assert_eq!(emulation.debugger().register("hl"), Some(0x9000));
+
+    emulation.step_instruction().unwrap();
+    assert!(!invariant.holds(&emulation.debugger()));
+
+    // The order of the states matters
+    assert!(bisect(&mut emulation, &bad, &good, &invariant).is_err());
+}
+
+#[test]
+fn oam_entries_and_sprites_decoded() {
+    use crate::{mmu::MMU, ColoredPixel};
+
+    let mut emulation = Emulation::new(None);
+    let gb = &mut emulation.gameboy;
+    // First row of tile 1 uses color 1, sprite 0 shows it flipped in both directions
+    MMU::write_byte(gb, 0x8010, 0xFF);
+    for (offset, value) in [16, 8, 1, 0b0110_0000].into_iter().enumerate() {
+        MMU::write_byte(gb, 0xFE00 + offset as u16, value);
+    }
+    MMU::write_byte(gb, 0xFF48, 0b1110_0100);
+    // Tile 1 at row 1, column 1 of the 9C00 map
+    MMU::write_byte(gb, 0x9C21, 0x01);
+
+    let debugger = emulation.debugger();
+    let entries = debugger.oam_entries();
+    assert_eq!(entries.len(), 40);
+    let entry = entries[0];
+    assert_eq!(entry, OamEntry { index: 0, y: 16, x: 8, tile: 1, attributes: 0b0110_0000 });
+    assert_eq!((entry.screen_x(), entry.screen_y()), (0, 0));
+    assert!(entry.flip_x() && entry.flip_y() && !entry.behind_background());
+
+    let palettes = debugger.palettes();
+    assert_eq!(palettes.sprites[0].value, 0b1110_0100);
+    assert_eq!(palettes.sprites[0].colors[1], ColoredPixel::LightGray);
+
+    let sprite = debugger.sprite(&entry);
+    assert_eq!((sprite.width, sprite.height), (8, 8));
+    assert_eq!(sprite.pixels[0], None);
+    assert!(sprite.pixels[56..].iter().all(|pixel| *pixel == Some(ColoredPixel::LightGray)));
+    assert_eq!(debugger.tile_map(TileMap::High).width, 256);
+    let tiles = debugger.tile_data_2bpp();
+    assert_eq!((tiles.len(), &tiles[16..18]), (384 * 16, &[0xFF, 0x00][..]));
+    let indices = debugger.tile_map_indices(TileMap::High);
+    assert_eq!((indices.len(), indices[33]), (1024, 0x01));
+    assert_eq!(debugger.window_map(), TileMap::Low);
+}
+
+#[test]
+fn watched_regions_notify_changes_once_per_frame() {
+    use std::sync::{Arc, Mutex};
+    use crate::observer::EmulatorObserver;
+
+    type Change = (u32, u16, Vec<u8>);
+    struct Changes(Arc<Mutex<Vec<Change>>>);
+    impl EmulatorObserver for Changes {
+        fn on_memory_changed(&mut self, region: u32, start: u16, bytes: &[u8]) {
+            self.0.lock().unwrap().push((region, start, bytes.to_vec()));
+        }
+    }
+
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let mut emulation = Emulation::new(None);
+    emulation.set_observer(Box::new(Changes(changes.clone())));
+
+    let mut debugger = emulation.debugger();
+    let watched = debugger.watch_region(0xC000, 4);
+    let removed = debugger.watch_region(0xC100, 4);
+    assert!(debugger.unwatch_region(removed));
+    debugger.write_bytes(0xC002, &[0xAB, 0xCD, 0xEF]);
+    debugger.write_byte(0xC100, 0x12);
+    assert_eq!(debugger.read_range(0xC001, 4), vec![0x00, 0xAB, 0xCD, 0xEF]);
+
+    emulation.skip_frame().unwrap();
+    emulation.skip_frame().unwrap();
+    assert_eq!(*changes.lock().unwrap(), vec![(watched, 0xC000, vec![0x00, 0x00, 0xAB, 0xCD])]);
+}
+
+#[test]
+fn symbols_name_breakpoints_and_disassembly() {
+    assert!(SymbolTable::parse("00:0000 Boot\nnot a symbol").is_err());
+    let symbols = SymbolTable::parse("; File generated by rgblink\n00:0000 Boot\n00:0007 Boot.clearVram\n01:4000 Banked\n").unwrap();
+    assert_eq!(symbols.symbolize(0, 0x0003).as_deref(), Some("Boot+$3"));
+    assert_eq!(symbols.symbolize(0, 0x8000), None);
+    assert_eq!(symbols.resolve("0x0150").unwrap(), Location { bank: None, address: 0x0150 });
+    assert_eq!(symbols.resolve("01:4000").unwrap(), Location { bank: Some(1), address: 0x4000 });
+    assert!(symbols.resolve("Main").is_err());
+
+    let mut emulation = Emulation::new(None);
+    let mut debugger = emulation.debugger();
+    assert_eq!(debugger.disassemble(0x0000, None).text, "ld sp, $FFFE");
+    assert_eq!(debugger.disassemble(0x000A, Some(&symbols)).text, "jr nz, Boot.clearVram");
+    assert_eq!(debugger.disassemble(0x000A, Some(&symbols)).bytes, vec![0x20, 0xFB]);
+
+    debugger.add_breakpoint(symbols.resolve("Boot.clearVram").unwrap());
+    assert_eq!(emulation.skip_frame(), Err(EmulationError::Breakpoint { address: 0x0007 }));
+    assert_eq!(emulation.debugger().register("hl"), Some(0x9FFF));
+    // Resuming runs the instruction, the loop comes back to it
+    assert_eq!(emulation.skip_frame(), Err(EmulationError::Breakpoint { address: 0x0007 }));
+    assert_eq!(emulation.debugger().register("hl"), Some(0x9FFE));
+}
+
+#[test]
+fn annotations_name_ram_addresses() {
+    let mut annotations = Annotations::parse("; Lives and position\nlives = D005\nplayer_x = $C0A3\n").unwrap();
+    assert!(annotations.add("rom = 0150").is_err());
+    annotations.add("player_y = C0A4").unwrap();
+    // Names move to their new address
+    annotations.add("lives = C0A0").unwrap();
+    assert_eq!(annotations.to_string(), "lives = C0A0\nplayer_x = C0A3\nplayer_y = C0A4\n");
+    assert_eq!(Annotations::parse(&annotations.to_string()).unwrap(), annotations);
+
+    let mut symbols = SymbolTable::parse("00:0000 Boot\n").unwrap();
+    annotations.add_to(&mut symbols);
+    assert_eq!(symbols.resolve("player_x").unwrap(), Location { bank: Some(0), address: 0xC0A3 });
+
+    let mut emulation = Emulation::new(None);
+    let mut debugger = emulation.debugger();
+    debugger.write_bytes(0xC0A0, &[3, 0, 0, 0x50]);
+    let view = debugger.memory_view(0xC0A0, 4, Some(&symbols));
+    assert!(view.starts_with("C0A0  03 00 00 50  ") && view.ends_with("  C0A0 lives, C0A3 player_x\n"), "{}", view);
+    assert_eq!(debugger.start_search().labeled_results(&annotations)[3], (0xC003, None));
+}
+
+#[test]
+fn profiler_follows_calls() {
+    // The boot ROM decompresses the logo with calls to 0095 and 0096
+    let symbols = SymbolTable::parse("00:0000 Boot\n00:0095 Decompress\n00:00A3 Copy\n").unwrap();
+    let mut emulation = Emulation::new(None);
+    emulation.start_profiler();
+    for _ in 0..10 {
+        emulation.skip_frame().unwrap();
+    }
+    let profiler = emulation.stop_profiler().unwrap();
+
+    let functions = profiler.functions(&symbols);
+    assert_eq!(functions.iter().map(|function| function.cycles).sum::<u64>(), profiler.total_cycles());
+    assert!(functions.iter().any(|function| function.name == "Decompress"));
+
+    let mut collapsed = Vec::new();
+    profiler.write_collapsed(&mut collapsed, &symbols).unwrap();
+    let collapsed = String::from_utf8(collapsed).unwrap();
+    assert!(collapsed.lines().any(|line| line.starts_with("Boot;Decompress ")), "{}", collapsed);
+    assert!(collapsed.lines().all(|line| line.starts_with("Boot")));
+}
+
+#[test]
+fn watchpoints_on_io_registers() {
+    let symbols = SymbolTable::default();
+    let mut emulation = Emulation::new(None);
+    // The boot ROM turns the APU on with LD (HL-),A at 0013
+    emulation.debugger().add_watchpoint(Watchpoint::parse("NR52", &symbols).unwrap());
+    let stop = (0..10).map(|_| emulation.skip_frame()).find(Result::is_err);
+    assert_eq!(stop, Some(Err(EmulationError::Watchpoint { pc: 0x0013, address: 0xFF26, old: 0x70, new: 0xF0 })));
+    assert_eq!(emulation.debugger().register("pc"), Some(0x0014));
+
+    assert!(emulation.debugger().remove_watchpoints(0xFF26));
+    emulation.debugger().add_watchpoint(Watchpoint::parse("LCDC if !(old & 0x80) && new & 0x80", &symbols).unwrap());
+    let stop = (0..10).map(|_| emulation.skip_frame()).find(Result::is_err);
+    let Some(Err(EmulationError::Watchpoint { address, new, .. })) = stop else { panic!("the LCD wasn't turned on") };
+    assert_eq!((address, new), (0xFF40, 0x91));
+    assert!(Watchpoint::parse("LCDC if old &", &symbols).is_err());
+}
+
+#[test]
+fn diagnostics_report_suspicious_instructions() {
+    use std::sync::{Arc, Mutex};
+    use crate::observer::EmulatorObserver;
+
+    struct Reports(Arc<Mutex<Vec<Diagnostic>>>);
+    impl EmulatorObserver for Reports {
+        fn on_diagnostic(&mut self, diagnostic: &Diagnostic) {
+            self.0.lock().unwrap().push(*diagnostic);
+        }
+    }
+
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let mut emulation = Emulation::new(None);
+    emulation.set_observer(Box::new(Reports(reports.clone())));
+    let mut debugger = emulation.debugger();
+    // LDH A,(NR13); LD (2000),A; LD SP,C100; POP BC
+    debugger.write_bytes(0xC000, &[0xF0, 0x13, 0xEA, 0x00, 0x20, 0x31, 0x00, 0xC1, 0xC1]);
+    debugger.set_register("pc", 0xC000);
+    for _ in 0..4 {
+        emulation.step_instruction().unwrap();
+    }
+
+    assert_eq!(*reports.lock().unwrap(), vec![
+        Diagnostic { kind: DiagnosticKind::WriteOnlyRead { address: 0xFF13 }, pc: 0xC000, bank: 0 },
+        Diagnostic { kind: DiagnosticKind::RomWriteWithoutMapper { address: 0x2000, value: 0xFF }, pc: 0xC002, bank: 0 },
+        Diagnostic { kind: DiagnosticKind::StackUnderflow { sp: 0xC100 }, pc: 0xC008, bank: 0 },
+    ]);
+}
+
+#[test]
+fn developer_mode_prints_and_breaks() {
+    let mut emulation = Emulation::new(None);
+    let mut debugger = emulation.debugger();
+    // Prints "Hi" through FF7F, then LD B,B, the ED breakpoint and a NOP
+    debugger.write_bytes(0xC000, &[0x3E, 0x48, 0xE0, 0x7F, 0x3E, 0x69, 0xE0, 0x7F, 0x40, 0xED, 0x00]);
+    debugger.set_register("pc", 0xC000);
+    emulation.set_developer_mode(true);
+    for _ in 0..4 {
+        emulation.step_instruction().unwrap();
+    }
+    assert_eq!(emulation.take_debug_output(), "Hi");
+    assert_eq!(emulation.step_instruction(), Err(EmulationError::Breakpoint { address: 0xC008 }));
+    emulation.step_instruction().unwrap();
+    assert_eq!(emulation.step_instruction(), Err(EmulationError::Breakpoint { address: 0xC009 }));
+    emulation.step_instruction().unwrap();
+    assert_eq!(emulation.debugger().register("pc"), Some(0xC00B));
+
+    // The game sees the hardware without it
+    emulation.set_developer_mode(false);
+    emulation.debugger().set_register("pc", 0xC000);
+    for _ in 0..5 {
+        emulation.step_instruction().unwrap();
+    }
+    assert_eq!(emulation.take_debug_output(), "");
+}
+
+#[test]
+fn oam_scan_drops_the_eleventh_sprite_of_a_line() {
+    use crate::mmu::MMU;
+
+    let mut emulation = Emulation::new(None);
+    emulation.run_frames(10).unwrap();
+    let gb = &mut emulation.gameboy;
+    // 11 sprites on lines 16 to 23, then one hidden at X 0
+    for index in 0..11 {
+        gb.ppu.oam[index * 4..index * 4 + 2].copy_from_slice(&[32, 8 + 8 * index as u8]);
+    }
+    gb.ppu.oam[44..46].copy_from_slice(&[50, 0]);
+    MMU::write_byte(gb, 0xFF40, 0x93);
+    emulation.run_frames(2).unwrap();
+
+    let statuses = emulation.debugger().sprite_statuses();
+    assert_eq!((statuses[0].visibility, statuses[0].lines_drawn), (SpriteVisibility::Drawn, 8));
+    assert_eq!((statuses[10].visibility, statuses[10].lines_dropped), (SpriteVisibility::DroppedByLineLimit, 8));
+    assert_eq!(statuses[11].visibility, SpriteVisibility::OffscreenX);
+    assert_eq!(statuses[12].visibility, SpriteVisibility::OffscreenY);
+
+    // The box of sprite 0 starts at the top left of its tile
+    let overlay = emulation.debugger().sprite_overlay();
+    let pixel = |x: u32, y: u32| overlay.pixels[((x + y * overlay.width) * 4 + 3) as usize];
+    assert_eq!((pixel(0, 16), pixel(3, 19), pixel(80, 23)), (0xC0, 0x00, 0xC0));
+}
+
+#[test]
+fn memory_search_narrows_down_the_candidates() {
+    let mut emulation = Emulation::new(None);
+    let mut debugger = emulation.debugger();
+    debugger.write_bytes(0xC000, &[0x10, 0x10, 0x10]);
+    let mut search = debugger.start_search();
+    assert_eq!(search.results().len(), 0x2000);
+
+    debugger.write_bytes(0xC000, &[0x11, 0x09]);
+    search.filter(&debugger, SearchFilter::Changed);
+    assert_eq!(search.results(), [0xC000, 0xC001]);
+    debugger.write_bytes(0xC000, &[0x12, 0x08]);
+    search.filter(&debugger, SearchFilter::Increased);
+    assert_eq!(search.results(), [0xC000]);
+    assert_eq!((search.value(0xC000), search.value(0x8000)), (Some(0x12), None));
+
+    let mut search = debugger.start_search();
+    debugger.write_bytes(0xC002, &[0x05]);
+    search.filter(&debugger, SearchFilter::Decreased);
+    assert_eq!(search.results(), [0xC002]);
+    search.filter(&debugger, SearchFilter::Unchanged);
+    search.filter(&debugger, SearchFilter::Equals(0x05));
+    assert_eq!(search.results(), [0xC002]);
+    search.filter(&debugger, SearchFilter::Equals(0x06));
+    assert!(search.results().is_empty());
+
+    // The code freezes the value found
+    let code = MemorySearch::to_gameshark(0xC0A3, 0x63);
+    assert_eq!(code, "0163A3C0");
+    assert_eq!(CheatCode::parse(&code).unwrap(), CheatCode::GameShark { bank: 0x01, address: 0xC0A3, value: 0x63 });
+}
@@ -0,0 +1,255 @@
+use crate::{
+    display::RgbaImage,
+    io::{lcd::{LCDControl, Palette, LCD, LCD_BGPALETTE_ADDRESS, LCD_OBP0_ADDRESS, LCD_OBP1_ADDRESS}, oamscan::OAM_ENTRIES},
+    mmu::{Address, VRAM_BEGIN},
+    ppu::{TilePixelValue, PPU, BGMAP0_ADDRESS, BGMAP1_ADDRESS},
+    ColoredPixel, GameBoyFrame, SCREEN_HEIGHT, SCREEN_WIDTH, TILEDATA_HEIGHT, TILEDATA_WIDTH,
+};
+
+use super::Debugger;
+
+// Sprites with X 0 or from 168 are selected but left out of the screen
+const SCREEN_X_END: u8 = SCREEN_WIDTH as u8 + 8;
+// 384 tiles of 16 bytes, then the two maps
+const TILE_DATA_SIZE: usize = (BGMAP0_ADDRESS - VRAM_BEGIN) as usize;
+const TILE_MAP_SIZE: usize = 32 * 32;
+
+// The two 32x32 tile maps in VRAM, LCDC selects which one the background and the window use
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileMap {
+    // 9800-9BFF
+    Low,
+    // 9C00-9FFF
+    High,
+}
+
+// One of the 40 sprites, as stored in OAM
+// https://gbdev.io/pandocs/OAM.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OamEntry {
+    pub index: u8,
+    pub y: u8,
+    pub x: u8,
+    pub tile: u8,
+    pub attributes: u8,
+}
+
+// What became of a sprite in the last complete frame, from the OAM scan of
+// every line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpriteVisibility {
+    // On some lines at least, see lines_dropped
+    Drawn,
+    // Every line it covers already had 10 sprites before it in OAM
+    DroppedByLineLimit,
+    // Selected, so it counts for the limit of its lines, but X is 0 or from 168
+    OffscreenX,
+    // Not on any visible line
+    OffscreenY,
+    // LCDC turned sprites off on its lines
+    SpritesDisabled,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpriteStatus {
+    // As OAM holds it now, the frame may have been drawn with other values
+    pub entry: OamEntry,
+    pub visibility: SpriteVisibility,
+    pub lines_drawn: u8,
+    pub lines_dropped: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PaletteView {
+    pub value: u8,
+    // Colors for the indices 0 to 3
+    pub colors: [ColoredPixel; 4],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palettes {
+    pub background: PaletteView,
+    pub sprites: [PaletteView; 2],
+}
+
+// A sprite decoded with its palette and flips, transparent pixels are None
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpriteImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<Option<ColoredPixel>>,
+}
+
+impl OamEntry {
+    // Coordinates are stored with an offset so sprites can be partially off screen
+    pub fn screen_x(&self) -> i16 {
+        self.x as i16 - 8
+    }
+
+    pub fn screen_y(&self) -> i16 {
+        self.y as i16 - 16
+    }
+
+    pub fn behind_background(&self) -> bool {
+        self.attributes & 0b1000_0000 != 0
+    }
+
+    pub fn flip_y(&self) -> bool {
+        self.attributes & 0b0100_0000 != 0
+    }
+
+    pub fn flip_x(&self) -> bool {
+        self.attributes & 0b0010_0000 != 0
+    }
+
+    // 0 for OBP0, 1 for OBP1
+    pub fn palette(&self) -> usize {
+        ((self.attributes >> 4) & 1) as usize
+    }
+}
+
+impl PaletteView {
+    fn new(value: u8) -> Self {
+        let colors = [0, 2, 4, 6].map(|shift| ColoredPixel::from(value >> shift));
+        PaletteView { value, colors }
+    }
+}
+
+impl SpriteImage {
+    // Transparent pixels take the given color, to show the sprite over a background
+    pub fn to_frame(&self, transparent: ColoredPixel) -> GameBoyFrame {
+        let buffer = self.pixels.iter().map(|pixel| pixel.unwrap_or(transparent)).collect();
+        GameBoyFrame::new(self.width, self.height, buffer)
+    }
+}
+
+// Views of VRAM, OAM and the palettes for tile and sprite viewers. They show the
+// current contents, not what was used to draw the last frame.
+impl Debugger<'_> {
+    pub fn oam_entries(&self) -> Vec<OamEntry> {
+        self.gb.ppu.oam.chunks(4).take(OAM_ENTRIES).enumerate().map(|(index, entry)| {
+            OamEntry { index: index as u8, y: entry[0], x: entry[1], tile: entry[2], attributes: entry[3] }
+        }).collect()
+    }
+
+    // Every OAM entry with what the last frame did with it
+    pub fn sprite_statuses(&self) -> Vec<SpriteStatus> {
+        let scan = &self.gb.io.oam_scan.last_frame;
+        self.oam_entries().into_iter().zip(scan).map(|(entry, lines)| {
+            let visibility = if lines.selected > 0 && (entry.x == 0 || entry.x >= SCREEN_X_END) {
+                SpriteVisibility::OffscreenX
+            } else if lines.selected > 0 {
+                SpriteVisibility::Drawn
+            } else if lines.dropped > 0 {
+                SpriteVisibility::DroppedByLineLimit
+            } else if lines.hidden > 0 {
+                SpriteVisibility::SpritesDisabled
+            } else {
+                SpriteVisibility::OffscreenY
+            };
+            SpriteStatus { entry, visibility, lines_drawn: lines.selected, lines_dropped: lines.dropped }
+        }).collect()
+    }
+
+    // Outlines of the sprites at the screen size, transparent elsewhere, to
+    // blend over the frame: green when drawn, yellow when lines of it were
+    // dropped, red when it was dropped entirely and gray with sprites off
+    pub fn sprite_overlay(&self) -> RgbaImage {
+        let (width, height) = (SCREEN_WIDTH as i32, SCREEN_HEIGHT as i32);
+        let mut pixels = vec![0; (width * height * 4) as usize];
+        let sprite_height = if LCD::read_control(self.gb, LCDControl::SpriteSize) { 16 } else { 8 };
+        for status in self.sprite_statuses() {
+            let color = match status.visibility {
+                SpriteVisibility::Drawn if status.lines_dropped > 0 => [0xFF, 0xD0, 0x00, 0xC0],
+                SpriteVisibility::Drawn => [0x00, 0xE0, 0x40, 0xC0],
+                SpriteVisibility::DroppedByLineLimit => [0xFF, 0x20, 0x20, 0xC0],
+                SpriteVisibility::SpritesDisabled => [0x80, 0x80, 0x80, 0xC0],
+                SpriteVisibility::OffscreenX | SpriteVisibility::OffscreenY => continue,
+            };
+            let (left, top) = (status.entry.screen_x() as i32, status.entry.screen_y() as i32);
+            let (right, bottom) = (left + 7, top + sprite_height - 1);
+            for y in top.max(0)..=bottom.min(height - 1) {
+                for x in left.max(0)..=right.min(width - 1) {
+                    if x == left || x == right || y == top || y == bottom {
+                        let offset = ((x + y * width) * 4) as usize;
+                        pixels[offset..offset + 4].copy_from_slice(&color);
+                    }
+                }
+            }
+        }
+        RgbaImage { width: SCREEN_WIDTH, height: SCREEN_HEIGHT, pixels }
+    }
+
+    // 8x8 or 8x16 depending on the sprite size in LCDC
+    pub fn sprite(&self, entry: &OamEntry) -> SpriteImage {
+        let tall = LCD::read_control(self.gb, LCDControl::SpriteSize);
+        let height = if tall { 16 } else { 8 };
+        // In 8x16 mode the lowest bit of the tile number is ignored
+        let first_tile = if tall { entry.tile & 0xFE } else { entry.tile } as usize;
+        let palette = Palette::from(self.palettes().sprites[entry.palette()].value);
+        let tiles = PPU::tile_set(self.gb);
+
+        let mut pixels = Vec::with_capacity(8 * height);
+        for y in 0..height {
+            let row = if entry.flip_y() { height - 1 - y } else { y };
+            for x in 0..8 {
+                let column = if entry.flip_x() { 7 - x } else { x };
+                let value = tiles[first_tile + row / 8][row % 8][column];
+                pixels.push((!matches!(value, TilePixelValue::Zero)).then(|| palette.apply(value)));
+            }
+        }
+        SpriteImage { width: 8, height: height as u32, pixels }
+    }
+
+    pub fn palettes(&self) -> Palettes {
+        let read = |address| PaletteView::new(LCD::read_byte(self.gb, address));
+        Palettes {
+            background: read(LCD_BGPALETTE_ADDRESS),
+            sprites: [read(LCD_OBP0_ADDRESS), read(LCD_OBP1_ADDRESS)],
+        }
+    }
+
+    // The 384 tiles in rows of 16, with the background palette
+    pub fn tile_data(&self) -> GameBoyFrame {
+        let buffer = LCD::tiles_to_buffer(self.gb, PPU::tile_set(self.gb), TILEDATA_WIDTH, TILEDATA_HEIGHT);
+        GameBoyFrame::new(TILEDATA_WIDTH, TILEDATA_HEIGHT, buffer)
+    }
+
+    // The tile data as stored in VRAM, the 2bpp format of RGBDS
+    pub fn tile_data_2bpp(&self) -> Vec<u8> {
+        self.gb.ppu.vram[..TILE_DATA_SIZE].to_vec()
+    }
+
+    // The full 256x256 map, with the background palette
+    pub fn tile_map(&self, map: TileMap) -> GameBoyFrame {
+        LCD::render_tile_map(self.gb, map.address())
+    }
+
+    // The 32x32 tile numbers row by row, like a .tilemap of RGBDS. LCDC
+    // picks whether they count from 8000 or signed from 9000.
+    pub fn tile_map_indices(&self, map: TileMap) -> Vec<u8> {
+        let start = (map.address() - VRAM_BEGIN) as usize;
+        self.gb.ppu.vram[start..start + TILE_MAP_SIZE].to_vec()
+    }
+
+    pub fn background_map(&self) -> TileMap {
+        TileMap::from_address(LCD::background_tile_map(self.gb))
+    }
+
+    pub fn window_map(&self) -> TileMap {
+        TileMap::from_address(LCD::window_tile_map(self.gb))
+    }
+}
+
+impl TileMap {
+    fn from_address(address: Address) -> Self {
+        if address == BGMAP1_ADDRESS { TileMap::High } else { TileMap::Low }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            TileMap::Low => BGMAP0_ADDRESS,
+            TileMap::High => BGMAP1_ADDRESS,
+        }
+    }
+}
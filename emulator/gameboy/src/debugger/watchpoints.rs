@@ -0,0 +1,78 @@
+use std::io::{Error, ErrorKind};
+
+use crate::{error::EmulationError, gameboy::GameBoy, iolog, mmu::{Address, MMU}};
+
+use super::{Debugger, Expression, SymbolTable};
+
+// Stops the emulation after a write to the address, when the condition holds.
+// Works on IO registers too, where the value read back can differ from the one
+// written (DIV goes back to 0, STAT keeps its mode bits).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub address: u16,
+    pub condition: Option<Expression>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct WatchHit {
+    pub(crate) pc: Address,
+    pub(crate) address: Address,
+    pub(crate) old: u8,
+    pub(crate) new: u8,
+}
+
+#[derive(Default)]
+pub(crate) struct Watchpoints {
+    pub(crate) watchpoints: Vec<Watchpoint>,
+    pub(crate) hit: Option<WatchHit>,
+}
+
+impl Watchpoint {
+    // "TARGET" or "TARGET if CONDITION", the target is an IO register name like LCDC,
+    // a label or an address, e.g. "LCDC if old & 0x80 && !(new & 0x80)"
+    pub fn parse(text: &str, symbols: &SymbolTable) -> Result<Self, Error> {
+        let (target, condition) = match text.split_once(" if ") {
+            Some((target, condition)) => (target, Some(Expression::parse(condition)?)),
+            None => (text, None),
+        };
+        let target = target.trim();
+        let address = match iolog::register_address(target) {
+            Some(address) => address,
+            None => symbols.resolve(target).map_err(|_| Error::new(ErrorKind::InvalidInput, format!("Unknown register or address {}", target)))?.address,
+        };
+        Ok(Watchpoint { address, condition })
+    }
+}
+
+impl Watchpoints {
+    // Around every write of the CPU, the value before it is passed in
+    pub(crate) fn check(gb: &mut GameBoy, address: Address, old: u8) {
+        let new = MMU::read_byte(gb, address);
+        let watchpoints = std::mem::take(&mut gb.watchpoints.watchpoints);
+        let hit = {
+            let debugger = Debugger::new(gb);
+            watchpoints.iter().filter(|watchpoint| watchpoint.address == address).any(|watchpoint| {
+                watchpoint.condition.as_ref().is_none_or(|condition| {
+                    condition.evaluate_with(&debugger, &[("old", old as u16), ("new", new as u16)]) != 0
+                })
+            })
+        };
+        gb.watchpoints.watchpoints = watchpoints;
+        if hit && gb.watchpoints.hit.is_none() {
+            gb.watchpoints.hit = Some(WatchHit { pc: gb.cpu.pc, address, old, new });
+        }
+    }
+
+    // The emulation stops before the instruction after the write, so
+    // the rest of the hardware has caught up with it
+    pub(crate) fn check_hit(gb: &mut GameBoy) -> Result<(), EmulationError> {
+        match gb.watchpoints.hit.take() {
+            Some(WatchHit { pc, address, old, new }) => Err(EmulationError::Watchpoint { pc, address, old, new }),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn watches(gb: &GameBoy, address: Address) -> bool {
+        gb.watchpoints.watchpoints.iter().any(|watchpoint| watchpoint.address == address)
+    }
+}
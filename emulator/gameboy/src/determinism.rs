@@ -0,0 +1,119 @@
+use std::{collections::{BTreeMap, BTreeSet}, fs, io::{Error, ErrorKind}, path::Path};
+
+use crate::{cartridge::Cartridge, checksum::crc32, cpu::cpu::CPU, gameboy::GameBoy, mmu::MMU, ppu::PPU, savestate::{StateReader, StateWriter}};
+
+const MAGIC: &[u8; 4] = b"YGBH";
+const VERSION: u8 = 1;
+
+// A hash of the CPU registers, WRAM, HRAM, VRAM, OAM and cartridge RAM and
+// banking after every frame, keyed by the clock cycle the frame ended on.
+// Two runs of the same inputs must give the same hashes, so comparing them
+// finds the first frame where a savestate, a movie or a netplay rollback
+// didn't simulate what the original run did. Frames simulated again after
+// loading a state replace the hashes of the first time.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateHashes {
+    hashes: BTreeMap<u64, u32>,
+}
+
+// The first frame two runs disagree on, None on the side that has no frame
+// ending on that cycle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    pub cycles: u64,
+    pub expected: Option<u32>,
+    pub actual: Option<u32>,
+}
+
+impl StateHashes {
+    pub fn new() -> Self {
+        StateHashes::default()
+    }
+
+    pub(crate) fn record(&mut self, gb: &GameBoy, total_cycles: u64) {
+        self.hashes.insert(total_cycles, state_hash(gb));
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    pub fn get(&self, cycles: u64) -> Option<u32> {
+        self.hashes.get(&cycles).copied()
+    }
+
+    // In the order of the frames
+    pub fn iter(&self) -> impl Iterator<Item = (u64, u32)> + '_ {
+        self.hashes.iter().map(|(cycles, hash)| (*cycles, *hash))
+    }
+
+    // Only the frames both runs went through are compared, so a run can be
+    // checked against a longer recording of itself
+    pub fn first_divergence(&self, actual: &StateHashes) -> Option<Divergence> {
+        let (Some(start), Some(end)) = (self.start().max(actual.start()), self.end().min(actual.end())) else {
+            return None;
+        };
+        let cycles: BTreeSet<u64> = self.hashes.range(start..=end).chain(actual.hashes.range(start..=end)).map(|(cycles, _)| *cycles).collect();
+        cycles.into_iter()
+            .map(|cycles| Divergence { cycles, expected: self.get(cycles), actual: actual.get(cycles) })
+            .find(|divergence| divergence.expected != divergence.actual)
+    }
+
+    fn start(&self) -> Option<u64> {
+        self.hashes.keys().next().copied()
+    }
+
+    fn end(&self) -> Option<u64> {
+        self.hashes.keys().next_back().copied()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = StateWriter::default();
+        writer.bytes(MAGIC);
+        writer.u8(VERSION);
+        writer.u32(self.hashes.len() as u32);
+        for (cycles, hash) in &self.hashes {
+            writer.u64(*cycles);
+            writer.u32(*hash);
+        }
+        writer.into_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = StateReader::new(bytes);
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "Not a state hash log"));
+        }
+        if reader.u8()? != VERSION {
+            return Err(Error::new(ErrorKind::InvalidData, "State hash log made by another version"));
+        }
+        let mut hashes = BTreeMap::new();
+        for _ in 0..reader.u32()? {
+            hashes.insert(reader.u64()?, reader.u32()?);
+        }
+        Ok(StateHashes { hashes })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        StateHashes::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write(path, self.to_bytes())
+    }
+}
+
+// The CPU and the memories. The IO registers are left out, a difference there
+// shows up in memory a few frames later.
+pub(crate) fn state_hash(gb: &GameBoy) -> u32 {
+    let mut writer = StateWriter::default();
+    CPU::save_state(gb, &mut writer);
+    MMU::save_state(gb, &mut writer);
+    PPU::save_state(gb, &mut writer);
+    Cartridge::save_state(gb, &mut writer);
+    crc32(&writer.into_bytes())
+}
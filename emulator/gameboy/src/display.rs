@@ -0,0 +1,203 @@
+use crate::{palette::ColorPalette, ColoredPixel, GameBoyFrame};
+
+// Scanlines and grid lines keep this share of the brightness
+const LINE_BRIGHTNESS: u32 = 60;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scaler {
+    #[default]
+    None,
+    // Pixels repeated n times in both directions
+    Nearest(u32),
+    // EPX, rounds the diagonals without blurring
+    // https://www.scale2x.it/algorithm
+    Scale2x,
+    Scale3x,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PostFilter {
+    #[default]
+    None,
+    // Darkens the last row of every Game Boy pixel, like a CRT
+    Scanlines,
+    // Darkens the last row and column of every Game Boy pixel, like the DMG screen
+    LcdGrid,
+}
+
+// Called with every line the PPU draws before it goes into the frame, the pixels
+// can be changed for custom effects or read for analysis. Closures taking the
+// line and the pixels work as hooks.
+pub trait ScanlineHook: Send {
+    fn on_scanline(&mut self, line: u8, pixels: &mut [ColoredPixel]);
+}
+
+impl<F: FnMut(u8, &mut [ColoredPixel]) + Send> ScanlineHook for F {
+    fn on_scanline(&mut self, line: u8, pixels: &mut [ColoredPixel]) {
+        self(line, pixels)
+    }
+}
+
+// Games flicker objects on alternate frames to show more sprites than a line
+// can hold. Pixels back to what they were two frames ago after changing keep
+// the darker of their two states, so captures show those objects steadily,
+// while pixels that move on are left alone and nothing gets blurred.
+pub fn merge_flicker(current: &GameBoyFrame, previous: &GameBoyFrame, before: &GameBoyFrame) -> GameBoyFrame {
+    let buffer = current.buffer.iter().zip(&previous.buffer).zip(&before.buffer)
+        .map(|((current, previous), before)| match current == before && current != previous {
+            true if u8::from(*previous) > u8::from(*current) => *previous,
+            _ => *current,
+        })
+        .collect();
+    GameBoyFrame { width: current.width, height: current.height, buffer }
+}
+
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    // 4 bytes per pixel
+    pub pixels: Vec<u8>,
+}
+
+// Turns frames into what the frontend shows: scaling first, then the palette, then the filter
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DisplayPipeline {
+    pub palette: ColorPalette,
+    pub scaler: Scaler,
+    pub filter: PostFilter,
+}
+
+impl Scaler {
+    pub fn factor(&self) -> u32 {
+        match self {
+            Scaler::None => 1,
+            Scaler::Nearest(factor) => (*factor).max(1),
+            Scaler::Scale2x => 2,
+            Scaler::Scale3x => 3,
+        }
+    }
+
+    pub fn scale(&self, frame: &GameBoyFrame) -> GameBoyFrame {
+        match self {
+            Scaler::None => frame.clone(),
+            Scaler::Nearest(_) => {
+                let factor = self.factor();
+                Scaler::expand(frame, factor, |frame, x, y| vec![frame.pixel(x, y); (factor * factor) as usize])
+            },
+            Scaler::Scale2x => Scaler::expand(frame, 2, Scaler::scale2x_block),
+            Scaler::Scale3x => Scaler::expand(frame, 3, Scaler::scale3x_block),
+        }
+    }
+
+    // Builds the output from a factor x factor block per source pixel, in row order
+    fn expand(frame: &GameBoyFrame, factor: u32, block: impl Fn(&GameBoyFrame, i32, i32) -> Vec<ColoredPixel>) -> GameBoyFrame {
+        let width = frame.width * factor;
+        let mut buffer = vec![ColoredPixel::White; (width * frame.height * factor) as usize];
+        for y in 0..frame.height {
+            for x in 0..frame.width {
+                for (index, pixel) in block(frame, x as i32, y as i32).into_iter().enumerate() {
+                    let out_x = x * factor + index as u32 % factor;
+                    let out_y = y * factor + index as u32 / factor;
+                    buffer[(out_x + out_y * width) as usize] = pixel;
+                }
+            }
+        }
+        GameBoyFrame { width, height: frame.height * factor, buffer }
+    }
+
+    fn scale2x_block(frame: &GameBoyFrame, x: i32, y: i32) -> Vec<ColoredPixel> {
+        let e = frame.pixel(x, y);
+        let (b, d, f, h) = (frame.pixel(x, y - 1), frame.pixel(x - 1, y), frame.pixel(x + 1, y), frame.pixel(x, y + 1));
+        if b == h || d == f {
+            return vec![e; 4];
+        }
+        vec![
+            if d == b { d } else { e }, if b == f { f } else { e },
+            if d == h { d } else { e }, if h == f { f } else { e },
+        ]
+    }
+
+    fn scale3x_block(frame: &GameBoyFrame, x: i32, y: i32) -> Vec<ColoredPixel> {
+        let [a, b, c] = [frame.pixel(x - 1, y - 1), frame.pixel(x, y - 1), frame.pixel(x + 1, y - 1)];
+        let [d, e, f] = [frame.pixel(x - 1, y), frame.pixel(x, y), frame.pixel(x + 1, y)];
+        let [g, h, i] = [frame.pixel(x - 1, y + 1), frame.pixel(x, y + 1), frame.pixel(x + 1, y + 1)];
+        if b == h || d == f {
+            return vec![e; 9];
+        }
+        vec![
+            if d == b { d } else { e },
+            if (d == b && e != c) || (b == f && e != a) { b } else { e },
+            if b == f { f } else { e },
+            if (d == b && e != g) || (d == h && e != a) { d } else { e },
+            e,
+            if (b == f && e != i) || (h == f && e != c) { f } else { e },
+            if d == h { d } else { e },
+            if (d == h && e != i) || (h == f && e != g) { h } else { e },
+            if h == f { f } else { e },
+        ]
+    }
+}
+
+impl RgbaImage {
+    // Draws an image with transparency on top, stretched to the size of this
+    // one without smoothing, for overlays made at the Game Boy resolution
+    pub fn blend(&mut self, overlay: &RgbaImage) {
+        if overlay.width == 0 || overlay.height == 0 {
+            return;
+        }
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let from = (((x * overlay.width / self.width) + (y * overlay.height / self.height) * overlay.width) * 4) as usize;
+                let alpha = overlay.pixels[from + 3] as u32;
+                if alpha == 0 {
+                    continue;
+                }
+                let to = ((x + y * self.width) * 4) as usize;
+                for channel in 0..3 {
+                    let (source, target) = (overlay.pixels[from + channel] as u32, self.pixels[to + channel] as u32);
+                    self.pixels[to + channel] = ((source * alpha + target * (255 - alpha)) / 255) as u8;
+                }
+            }
+        }
+    }
+}
+
+impl GameBoyFrame {
+    // Edges are repeated outside of the frame
+    fn pixel(&self, x: i32, y: i32) -> ColoredPixel {
+        let x = x.clamp(0, self.width as i32 - 1) as u32;
+        let y = y.clamp(0, self.height as i32 - 1) as u32;
+        self.buffer[(x + y * self.width) as usize]
+    }
+}
+
+impl DisplayPipeline {
+    pub fn render(&self, frame: &GameBoyFrame) -> RgbaImage {
+        let scaled = self.scaler.scale(frame);
+        let mut pixels = scaled.to_rgba(&self.palette);
+
+        let factor = self.scaler.factor();
+        // Lines need at least two output pixels per Game Boy pixel
+        if self.filter != PostFilter::None && factor > 1 {
+            for y in 0..scaled.height {
+                for x in 0..scaled.width {
+                    let last_row = y % factor == factor - 1;
+                    let last_column = x % factor == factor - 1;
+                    let darken = match self.filter {
+                        PostFilter::None => false,
+                        PostFilter::Scanlines => last_row,
+                        PostFilter::LcdGrid => last_row || last_column,
+                    };
+                    if darken {
+                        let offset = ((x + y * scaled.width) * 4) as usize;
+                        for channel in &mut pixels[offset..offset + 3] {
+                            *channel = (*channel as u32 * LINE_BRIGHTNESS / 100) as u8;
+                        }
+                    }
+                }
+            }
+        }
+
+        RgbaImage { width: scaled.width, height: scaled.height, pixels }
+    }
+}
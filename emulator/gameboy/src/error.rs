@@ -0,0 +1,87 @@
+use std::fmt;
+
+// Why the emulation stopped, Emulation::step and step_instruction return it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmulationError {
+    // Fetched one of the 11 opcodes without an instruction, with IllegalOpcodePolicy::Stop
+    IllegalOpcode { opcode: u8, address: u16 },
+    // Access to an IO register that is not emulated, with UnknownIoPolicy::Break
+    UnmappedRead { address: u16 },
+    UnmappedWrite { address: u16, value: u8 },
+    // Reached a breakpoint set through the debugger, the instruction hasn't run
+    Breakpoint { address: u16 },
+    // The instruction at pc wrote to a watched address, old and new are read back around the write.
+    // Reported before the next instruction runs.
+    Watchpoint { pc: u16, address: u16, old: u8, new: u8 },
+}
+
+impl fmt::Display for EmulationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmulationError::IllegalOpcode { opcode, address } => write!(f, "Illegal opcode {:02X} at {:04X}", opcode, address),
+            EmulationError::UnmappedRead { address } => write!(f, "Read from unimplemented IO register {:04X}", address),
+            EmulationError::UnmappedWrite { address, value } => write!(f, "Write {:02X} to unimplemented IO register {:04X}", value, address),
+            EmulationError::Breakpoint { address } => write!(f, "Breakpoint at {:04X}", address),
+            EmulationError::Watchpoint { pc, address, old, new } => write!(f, "Write to {:04X} at {:04X}, {:02X} -> {:02X}", address, pc, old, new),
+        }
+    }
+}
+
+impl std::error::Error for EmulationError {}
+
+// Keeps ? working in functions that return io errors, like the frontends
+impl From<EmulationError> for std::io::Error {
+    fn from(error: EmulationError) -> Self {
+        std::io::Error::other(error)
+    }
+}
+
+// Why a cartridge can't be loaded, or what Cartridge::verify_header found wrong
+#[derive(Debug)]
+pub enum CartridgeError {
+    // Reading the ROM or applying its patch failed
+    Io(std::io::Error),
+    // Not even the header fits
+    TooSmall { size: usize },
+    // The boot ROM locks up on these two
+    BadLogo,
+    BadChecksum { stored: u8, computed: u8 },
+    // Runs as a plain ROM, see CompatibilityIssue::UnsupportedMapper
+    UnsupportedMapper { cartridge_type: u8 },
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CartridgeError::Io(error) => write!(f, "{}", error),
+            CartridgeError::TooSmall { size } => write!(f, "The ROM is smaller than its header ({} bytes)", size),
+            CartridgeError::BadLogo => write!(f, "The Nintendo logo of the header is wrong"),
+            CartridgeError::BadChecksum { stored, computed } => write!(f, "Header checksum {:02X}, expected {:02X}", stored, computed),
+            CartridgeError::UnsupportedMapper { cartridge_type } => write!(f, "Cartridge type {:02X} is not emulated", cartridge_type),
+        }
+    }
+}
+
+impl std::error::Error for CartridgeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CartridgeError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for CartridgeError {
+    fn from(error: std::io::Error) -> Self {
+        CartridgeError::Io(error)
+    }
+}
+
+impl From<CartridgeError> for std::io::Error {
+    fn from(error: CartridgeError) -> Self {
+        match error {
+            CartridgeError::Io(error) => error,
+            error => std::io::Error::new(std::io::ErrorKind::InvalidData, error),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{accuracy::EmulationConfig, cartridge::Cartridge, gameboy::GameBoy, mmu::MMU};
+
+const ROM_SIZE: usize = 0x8000;
+
+// Entry points for the cargo-fuzz targets in gameboy/fuzz. Whatever the input,
+// they must return instead of panicking.
+
+// Runs arbitrary bytes as a program, mapped at 0x0000 of a plain 32 KiB ROM
+// with the boot ROM already unmapped
+pub fn execute_opcodes(program: &[u8], max_instructions: usize) {
+    let mut rom = vec![0; ROM_SIZE];
+    let size = program.len().min(ROM_SIZE);
+    rom[..size].copy_from_slice(&program[..size]);
+    // Keep the sandbox on a cartridge without mapper
+    rom[0x0147] = 0x00;
+
+    let Ok(cartridge) = Cartridge::from_data(rom) else { return };
+    let mut gb = GameBoy::new(Some(cartridge), EmulationConfig::default());
+    MMU::set_boot_mapping(&mut gb, 1);
+    gb.cpu.pc = 0x0000;
+
+    for _ in 0..max_instructions {
+        if gb.tick().is_err() {
+            return;
+        }
+    }
+}
+
+// Loads arbitrary bytes as a cartridge and goes through its whole address space
+pub fn load_cartridge(data: &[u8]) {
+    let Ok(cartridge) = Cartridge::from_data(data.to_vec()) else { return };
+    let _ = cartridge.rom_info();
+    let _ = cartridge.compatibility_issues();
+
+    let mut gb = GameBoy::new(Some(cartridge), EmulationConfig::default());
+    MMU::set_boot_mapping(&mut gb, 1);
+    for (i, value) in data.iter().take(0x100).enumerate() {
+        // Mapper registers and external RAM
+        MMU::write_byte(&mut gb, (i as u16) << 8, *value);
+        MMU::write_byte(&mut gb, 0xA000 + i as u16, *value);
+    }
+    for address in (0x0000..0x8000).chain(0xA000..0xC000) {
+        MMU::read_byte(&gb, address);
+    }
+}
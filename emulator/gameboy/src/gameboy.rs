@@ -1,13 +1,31 @@
-use std::io::Error;
-use std::fmt;
+use std::{borrow::Cow, fmt::{self, Write}};
 
-use crate::GameBoyFrame;
+use crate::{GameBoyFrame, ResetKind, CPU_CYCLES_PER_FRAME};
+use crate::accuracy::EmulationConfig;
+use crate::audio::{ChannelTaps, HighPassFilter, Resampler};
+use crate::audiodump::AudioDump;
+use crate::error::EmulationError;
+use crate::cheats::CheatManager;
+use crate::debugger::{Breakpoints, CallStack, DeveloperMode, Diagnostics, MemoryWatches, Watchpoints};
+use crate::infrared::IrTransceiver;
+use crate::iolog::IoLog;
+use crate::music::VgmLog;
+use crate::coverage::OpcodeCoverage;
+use crate::profiler::Profiler;
+use crate::observer::EmulatorObserver;
+use crate::display::ScanlineHook;
+use crate::recorder::Recorder;
+use crate::serial::SerialDevice;
+use crate::tilepack::TileReplacements;
 
 use super::cartridge::Cartridge;
-use super::cpu::cpu::{CPU, ClockCycles};
+use super::cpu::cpu::{CPU, ClockCycles, MachineCycles};
+use super::io::apu::APU;
+use super::io::hdma::Hdma;
 use super::io::io::IO;
-use super::io::lcd::LCD;
-use super::mmu::MMU;
+use super::io::joypad::Joypad;
+use super::io::lcd::{LCD, PpuTiming};
+use super::mmu::{MMU, Address};
 use super::ppu::PPU;
 
 pub struct GameBoy {
@@ -16,44 +34,160 @@ pub struct GameBoy {
     pub(crate) ppu: PPU,
     pub(crate) io: IO,
     pub(crate) cartridge: Option<Cartridge>,
-    pub(crate) serial: Option<u8>
+    pub(crate) serial: Option<u8>,
+    pub(crate) serial_device: Option<Box<dyn SerialDevice>>,
+    pub(crate) ir_transceiver: Option<Box<dyn IrTransceiver>>,
+    // Writes requested from outside the emulated program (pokes, cheats, scripts).
+    // They are deferred to the start of VBlank so runs stay reproducible.
+    pub(crate) pending_writes: Vec<(Address, u8)>,
+    pub(crate) cheats: CheatManager,
+    pub(crate) observer: Option<Box<dyn EmulatorObserver>>,
+    pub(crate) recorder: Option<Recorder>,
+    // Only set when the embedder wants audio, mixing is skipped otherwise
+    pub(crate) audio: Option<Resampler>,
+    // Kept for when audio is enabled again
+    pub(crate) high_pass_filter: HighPassFilter,
+    pub(crate) channel_taps: Option<ChannelTaps>,
+    pub(crate) audio_dump: Option<AudioDump>,
+    pub(crate) io_log: Option<IoLog>,
+    pub(crate) vgm_log: Option<VgmLog>,
+    pub(crate) profiler: Option<Profiler>,
+    pub(crate) opcode_coverage: Option<OpcodeCoverage>,
+    pub(crate) memory_watches: MemoryWatches,
+    pub(crate) breakpoints: Breakpoints,
+    pub(crate) call_stack: CallStack,
+    pub(crate) diagnostics: Diagnostics,
+    pub(crate) developer: DeveloperMode,
+    pub(crate) watchpoints: Watchpoints,
+    // Only used when drawing, VRAM and savestates don't see them
+    pub(crate) tile_replacements: Option<TileReplacements>,
+    pub(crate) scanline_hook: Option<Box<dyn ScanlineHook>>,
 }
 
 impl GameBoy {
-    pub fn new(cartridge: Option<Cartridge>) -> Self {
+    pub fn new(cartridge: Option<Cartridge>, config: EmulationConfig) -> Self {
         let io = IO::new();
         let mmu = MMU::new();
         let cpu = CPU::new();
         let ppu = PPU::new();
 
-        GameBoy { cpu, mmu, ppu, io, cartridge, serial: None }
+        let mut gb = GameBoy { cpu, mmu, ppu, io, cartridge, serial: None, serial_device: None, ir_transceiver: None, pending_writes: Vec::new(), cheats: CheatManager::default(), observer: None, recorder: None, audio: None, high_pass_filter: HighPassFilter::default(), channel_taps: None, audio_dump: None, io_log: None, vgm_log: None, profiler: None, opcode_coverage: None, memory_watches: MemoryWatches::default(), breakpoints: Breakpoints::default(), call_stack: CallStack::default(), diagnostics: Diagnostics::default(), developer: DeveloperMode::default(), watchpoints: Watchpoints::default(), tile_replacements: None, scanline_hook: None };
+        config.apply(&mut gb);
+        gb
     }
     
-    pub(crate) fn tick(&mut self) -> Result<ClockCycles, Error> {
+    pub(crate) fn tick(&mut self) -> Result<ClockCycles, EmulationError> {
+        Watchpoints::check_hit(self)?;
+        Breakpoints::check(self)?;
+        DeveloperMode::check(self)?;
+        // Nothing runs while stopped, the frame still takes its time for the frontend
+        if self.cpu.is_stopped {
+            if !Joypad::wakes_from_stop(self) {
+                return Ok(ClockCycles::from(MachineCycles::One));
+            }
+            self.cpu.is_stopped = false;
+        }
         let cycles = CPU::step(self)? as ClockCycles;
+        // The timers run with the CPU, everything else keeps its pace at double
+        // speed and sees half the cycles. The VRAM DMA wait is already in real time
+        let cycles = if IO::double_speed(self) { cycles / 2 } else { cycles };
+        let cycles = cycles + Hdma::stall(self);
 
-        // if self.cpu.pc == 0x100 {
-        //     return Err(Error::new(ErrorKind::Other, "test"));
-        // }
-        
         if let Some(data) = CPU::send_serial(self){
             self.serial = Some(data);
+            if let Some(device) = &mut self.serial_device {
+                let received = device.exchange(data);
+                IO::receive_serial(self, received);
+            }
             IO::ack_sent_serial(self);
+            if let Some(observer) = &mut self.observer {
+                observer.on_serial(data);
+            }
         }else{
             self.serial = None;
         }
 
         LCD::tick(self, cycles);
+        APU::tick(self, cycles as u32);
+        Cartridge::tick(self, cycles as usize);
 
         Ok(cycles)
     }
 
+    // Whole frames as fast as the host allows, nothing in the core waits for
+    // real time. Returns the clock cycles executed.
+    pub fn run_frames(&mut self, frames: u64) -> Result<u64, EmulationError> {
+        let mut cycles = 0;
+        for _ in 0..frames {
+            let mut frame_cycles = 0;
+            while frame_cycles < CPU_CYCLES_PER_FRAME {
+                frame_cycles += self.tick()? as usize;
+            }
+            cycles += frame_cycles as u64;
+        }
+        Ok(cycles)
+    }
+
+    // Settings chosen by the embedder survive, like the host side hooks
+    pub(crate) fn reset(&mut self, kind: ResetKind) {
+        self.cpu = CPU { illegal_opcode_policy: self.cpu.illegal_opcode_policy, ..CPU::new() };
+
+        let config = EmulationConfig::of(self);
+        let mut io = IO::new();
+        io.unknown_policy = self.io.unknown_policy;
+        io.lcd.off_display = self.io.lcd.off_display;
+        self.io = io;
+        config.apply(self);
+        // RP is cleared with the rest of IO
+        if let Some(transceiver) = &mut self.ir_transceiver {
+            transceiver.set_led(false);
+        }
+
+        match kind {
+            ResetKind::Soft => MMU::set_boot_mapping(self, 0),
+            ResetKind::Hard => {
+                MMU::clear(self);
+                self.ppu = PPU::new();
+            },
+        }
+        if let Some(cartridge) = &mut self.cartridge {
+            cartridge.reset();
+        }
+        self.serial = None;
+        self.pending_writes.clear();
+        self.call_stack.clear();
+        self.diagnostics = Diagnostics::default();
+    }
+
+    pub(crate) fn queue_write(&mut self, address: Address, value: u8) {
+        self.pending_writes.push((address, value));
+    }
+
+    // Called by the LCD when entering VBlank, writes are applied in the order they were queued
+    // and GameShark codes go last so they win over the pokes
+    pub(crate) fn apply_pending_writes(&mut self) {
+        let writes = std::mem::take(&mut self.pending_writes);
+        for (address, value) in writes {
+            MMU::write_byte(self, address, value);
+        }
+        CheatManager::apply_ram_cheats(self);
+    }
+
+    // The registers of CGB mode that are emulated answer for cartridges made for the CGB
+    pub(crate) fn cgb_mode(&self) -> bool {
+        self.cartridge.as_ref().is_some_and(Cartridge::supports_cgb)
+    }
+
     pub(crate) fn read_serial(&self) -> Option<u8> {
         self.serial
     }
 
-    pub(crate) fn frame(&self) -> GameBoyFrame {
-        LCD::screen_buffer(self)
+    pub(crate) fn frame(&self) -> Cow<'_, GameBoyFrame> {
+        LCD::screen(self)
+    }
+
+    pub(crate) fn stable_frame(&self) -> Cow<'_, GameBoyFrame> {
+        LCD::stable_screen(self)
     }
 
     pub(crate) fn tiledata(&self) -> GameBoyFrame {
@@ -62,7 +196,77 @@ impl GameBoy {
 
     pub(crate) fn background(&self) -> GameBoyFrame {
         LCD::background_buffer(self)
-    } 
+    }
+
+    pub(crate) fn window(&self) -> GameBoyFrame {
+        LCD::window_buffer(self)
+    }
+
+    pub(crate) fn ppu_timing(&self) -> PpuTiming {
+        LCD::timing(self)
+    }
+
+    // Readable report of the hardware state for bug reports, unlike the one line
+    // Display used to compare traces
+    pub fn dump_state_text(&self) -> String {
+        let io = |address| MMU::read_byte(self, address);
+        let on = |value: u8, bit: u8| value & (1 << bit) != 0;
+        let regs = &self.cpu.regs;
+        let mut text = String::new();
+
+        let flags = &regs.flags;
+        let flag = |set, name| if set { name } else { "-" };
+        let _ = writeln!(text, "CPU");
+        let _ = writeln!(text, "  AF={:04X} BC={:04X} DE={:04X} HL={:04X} SP={:04X} PC={:04X}",
+            regs.get_af(), regs.get_bc(), regs.get_de(), regs.get_hl(), self.cpu.sp, self.cpu.pc);
+        let _ = writeln!(text, "  Flags {}{}{}{} IME={} halted={} stopped={} locked={}",
+            flag(flags.zero, "Z"), flag(flags.subtract, "N"), flag(flags.half_carry, "H"), flag(flags.carry, "C"),
+            self.cpu.ime as u8, self.cpu.is_halted as u8, self.cpu.is_stopped as u8, self.cpu.is_locked as u8);
+        let code: Vec<String> = (0..4).map(|offset| format!("{:02X}", io(self.cpu.pc.wrapping_add(offset)))).collect();
+        let _ = writeln!(text, "  Code at PC {}", code.join(" "));
+
+        let (enabled, requested) = (io(0xFFFF), io(0xFF0F));
+        let list = |value: u8, names: &[(u8, &str)]| {
+            let set: Vec<&str> = names.iter().filter(|(bit, _)| on(value, *bit)).map(|(_, name)| *name).collect();
+            if set.is_empty() { "none".to_string() } else { set.join(" ") }
+        };
+        let names = [(0, "VBlank"), (1, "STAT"), (2, "Timer"), (3, "Serial"), (4, "Joypad")];
+        let _ = writeln!(text, "Interrupts");
+        let _ = writeln!(text, "  IE={:02X} {}", enabled, list(enabled, &names));
+        let _ = writeln!(text, "  IF={:02X} {}", requested, list(requested, &names));
+
+        let (lcdc, stat) = (io(0xFF40), io(0xFF41));
+        let _ = writeln!(text, "LCD");
+        let _ = writeln!(text, "  LCDC={:02X} LCD {}, window {} map {}, tiles {}, BG {} map {}, sprites {} {}",
+            lcdc, if on(lcdc, 7) { "on" } else { "off" },
+            if on(lcdc, 5) { "on" } else { "off" }, if on(lcdc, 6) { "9C00" } else { "9800" },
+            if on(lcdc, 4) { "8000" } else { "8800" },
+            if on(lcdc, 0) { "on" } else { "off" }, if on(lcdc, 3) { "9C00" } else { "9800" },
+            if on(lcdc, 1) { "on" } else { "off" }, if on(lcdc, 2) { "8x16" } else { "8x8" });
+        let _ = writeln!(text, "  STAT={:02X} mode {:?}, LY=LYC {}, interrupts on {}",
+            stat, LCD::mode(self), on(stat, 2) as u8, list(stat, &[(3, "HBlank"), (4, "VBlank"), (5, "OAM"), (6, "LYC")]));
+        let _ = writeln!(text, "  LY={:02X} LYC={:02X} SCX={:02X} SCY={:02X} WX={:02X} WY={:02X} BGP={:02X} OBP0={:02X} OBP1={:02X}",
+            io(0xFF44), io(0xFF45), io(0xFF43), io(0xFF42), io(0xFF4B), io(0xFF4A), io(0xFF47), io(0xFF48), io(0xFF49));
+
+        let tac = io(0xFF07);
+        let frequency = match tac & 0b11 { 0 => 4096, 1 => 262144, 2 => 65536, _ => 16384 };
+        let _ = writeln!(text, "Timer");
+        let _ = writeln!(text, "  DIV={:02X} TIMA={:02X} TMA={:02X} TAC={:02X} {} at {} Hz",
+            io(0xFF04), io(0xFF05), io(0xFF06), tac, if on(tac, 2) { "running" } else { "stopped" }, frequency);
+
+        let _ = writeln!(text, "Cartridge");
+        match &self.cartridge {
+            Some(cartridge) => { let _ = writeln!(text, "  {} {}", cartridge.title(), cartridge.describe_mapper()); },
+            None => { let _ = writeln!(text, "  none"); },
+        }
+
+        let _ = writeln!(text, "Stack");
+        for address in (self.cpu.sp..0xFFFF).step_by(2).take(8) {
+            let value = io(address) as u16 | (io(address.wrapping_add(1)) as u16) << 8;
+            let _ = writeln!(text, "  {:04X}: {:04X}", address, value);
+        }
+        text
+    }
  
 }
 
@@ -90,9 +294,9 @@ impl fmt::Display for GameBoy {
             self.cpu.sp, 
             self.cpu.pc,
             MMU::read_byte(self, self.cpu.pc), 
-            MMU::read_byte(self,self.cpu.pc+1), 
-            MMU::read_byte(self,self.cpu.pc+2), 
-            MMU::read_byte(self,self.cpu.pc+3)
+            MMU::read_byte(self,self.cpu.pc.wrapping_add(1)), 
+            MMU::read_byte(self,self.cpu.pc.wrapping_add(2)), 
+            MMU::read_byte(self,self.cpu.pc.wrapping_add(3))
             )
     }
 }
\ No newline at end of file
@@ -0,0 +1,54 @@
+use std::{fs, io::{Error, ErrorKind}, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::tilepack::TileReplacements;
+
+// Settings remembered for a single game, stored next to the ROM
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    // Milliseconds the video is delayed against the audio, negative values
+    // delay the audio instead. See avsync::AvSync.
+    pub audio_offset_ms: i32,
+    // Graphics mod, see tilepack::TileReplacements
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tiles: Option<TileMod>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileMod {
+    // Relative to the directory of the config
+    pub sheet: PathBuf,
+    // Tiles of the sheet drawn instead of the game's, 0 to 383
+    pub replace: Vec<usize>,
+}
+
+impl GameConfig {
+    // game.gb is configured by game.toml
+    pub fn path_for<P: AsRef<Path>>(rom: P) -> PathBuf {
+        rom.as_ref().with_extension("toml")
+    }
+
+    // A missing file gives the default settings
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        match fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(|error| Error::new(ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(GameConfig::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn tile_replacements<P: AsRef<Path>>(&self, config: P) -> Result<Option<TileReplacements>, Error> {
+        let Some(tiles) = &self.tiles else {
+            return Ok(None);
+        };
+        let sheet = config.as_ref().parent().unwrap_or(Path::new("")).join(&tiles.sheet);
+        TileReplacements::load(sheet, &tiles.replace).map(Some)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let text = toml::to_string(self).map_err(Error::other)?;
+        fs::write(path, text)
+    }
+}
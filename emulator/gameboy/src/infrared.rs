@@ -0,0 +1,60 @@
+use std::{cell::Cell, sync::mpsc::{channel, Receiver, Sender}};
+
+// Something facing the infrared port of RP (FF56). The Game Boy turns its LED
+// on and off and reads whether light comes in, the protocol is all timing so
+// the transceiver only carries the state of the light.
+pub trait IrTransceiver: Send {
+    fn set_led(&mut self, on: bool);
+    // Light seen by the sensor
+    fn receiving(&self) -> bool;
+}
+
+// The sensor sees the Game Boy's own LED, like a mirror in front of the port
+#[derive(Default)]
+pub struct IrLoopback {
+    led: bool,
+}
+
+impl IrTransceiver for IrLoopback {
+    fn set_led(&mut self, on: bool) {
+        self.led = on;
+    }
+
+    fn receiving(&self) -> bool {
+        self.led
+    }
+}
+
+// One side of two Game Boys facing each other, for Mystery Gift and other
+// exchanges between two emulators. Each end sends the changes of its LED and
+// sees the last state the other end sent. The instances are not kept in step,
+// they have to run at the same speed for the timing of the protocol to hold.
+pub struct IrLink {
+    sender: Sender<bool>,
+    receiver: Receiver<bool>,
+    remote: Cell<bool>,
+}
+
+impl IrLink {
+    pub fn pair() -> (IrLink, IrLink) {
+        let (first_sender, second_receiver) = channel();
+        let (second_sender, first_receiver) = channel();
+        let first = IrLink { sender: first_sender, receiver: first_receiver, remote: Cell::new(false) };
+        let second = IrLink { sender: second_sender, receiver: second_receiver, remote: Cell::new(false) };
+        (first, second)
+    }
+}
+
+impl IrTransceiver for IrLink {
+    // A gone peer is like one that moved away
+    fn set_led(&mut self, on: bool) {
+        let _ = self.sender.send(on);
+    }
+
+    fn receiving(&self) -> bool {
+        while let Ok(on) = self.receiver.try_recv() {
+            self.remote.set(on);
+        }
+        self.remote.get()
+    }
+}
@@ -0,0 +1,159 @@
+use std::io::{Error, ErrorKind};
+
+use crate::{movie::MovieEvent, Button, InputEvent, ResetKind};
+
+// Frames a tapped button is held, and then left released before the next input.
+// Games poll the joypad once per frame, some only every other frame.
+const TAP_FRAMES: u64 = 4;
+
+// Timed inputs written in code, for the attract mode of a kiosk or a gameplay
+// smoke test:
+//
+//     InputMacro::new().wait(120).tap(Button::Start).hold(Button::Right, 300)
+//
+// The inputs go at a cursor that wait and hold move forward, press leaves it in
+// place so buttons can be held together. Frames are counted from when the macro
+// starts playing with Emulation::play_macro. Unlike a movie there is no savestate
+// to start from, the macro plays over whatever the game is doing.
+//
+// Scripts and settings files write the same steps as text, see parse.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InputMacro {
+    events: Vec<MovieEvent>,
+    cursor: u64,
+    repeat: bool,
+}
+
+impl InputMacro {
+    pub fn new() -> Self {
+        InputMacro::default()
+    }
+
+    // Moves the cursor to a frame, before or after where it is
+    pub fn at(mut self, frame: u64) -> Self {
+        self.cursor = frame;
+        self
+    }
+
+    pub fn wait(mut self, frames: u64) -> Self {
+        self.cursor += frames;
+        self
+    }
+
+    // Held for some frames from the cursor, which stays where it is
+    pub fn press(mut self, button: Button, frames: u64) -> Self {
+        self.events.push(MovieEvent { frame: self.cursor, input: InputEvent::Pressed(button) });
+        self.events.push(MovieEvent { frame: self.cursor + frames.max(1), input: InputEvent::Released(button) });
+        self
+    }
+
+    // Held for some frames, the next input comes once it is released
+    pub fn hold(self, button: Button, frames: u64) -> Self {
+        self.press(button, frames).wait(frames.max(1))
+    }
+
+    pub fn tap(self, button: Button) -> Self {
+        self.hold(button, TAP_FRAMES).wait(TAP_FRAMES)
+    }
+
+    pub fn reset(mut self, kind: ResetKind) -> Self {
+        self.events.push(MovieEvent { frame: self.cursor, input: InputEvent::Reset(kind) });
+        self
+    }
+
+    // Plays again from the start after the last frame, until it is stopped
+    pub fn repeat(mut self) -> Self {
+        self.repeat = true;
+        self
+    }
+
+    // The builder methods as steps separated by commas or new lines:
+    //
+    //     wait 120, tap start, hold right 300, press a 10, at 0, repeat
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut input_macro = InputMacro::new();
+        for step in text.split([',', '\n']).map(str::trim).filter(|step| !step.is_empty()) {
+            let invalid = |reason: &str| Error::new(ErrorKind::InvalidInput, format!("{} in macro step \"{}\"", reason, step));
+            let words: Vec<&str> = step.split_whitespace().collect();
+            let button = |index: usize| words.get(index)
+                .ok_or_else(|| invalid("Missing button"))
+                .and_then(|name| Button::from_name(name).ok_or_else(|| invalid("Unknown button")));
+            let frames = |index: usize| words.get(index)
+                .ok_or_else(|| invalid("Missing frames"))
+                .and_then(|frames| frames.parse::<u64>().map_err(|_| invalid("Invalid frames")));
+            let expected = match words[0].to_ascii_lowercase().as_str() {
+                "wait" => { input_macro = input_macro.wait(frames(1)?); 2 },
+                "at" => { input_macro = input_macro.at(frames(1)?); 2 },
+                "tap" => { input_macro = input_macro.tap(button(1)?); 2 },
+                "hold" => { input_macro = input_macro.hold(button(1)?, frames(2)?); 3 },
+                "press" => { input_macro = input_macro.press(button(1)?, frames(2)?); 3 },
+                "reset" => { input_macro = input_macro.reset(ResetKind::Soft); 1 },
+                "repeat" => { input_macro = input_macro.repeat(); 1 },
+                _ => return Err(invalid("Unknown step")),
+            };
+            if words.len() != expected {
+                return Err(invalid("Wrong number of arguments"));
+            }
+        }
+        Ok(input_macro)
+    }
+
+    // Up to the cursor or the last input, whatever is later
+    pub fn frames(&self) -> u64 {
+        self.events.iter().map(|event| event.frame + 1).max().unwrap_or(0).max(self.cursor)
+    }
+
+    // In the order they were added
+    pub fn events(&self) -> &[MovieEvent] {
+        &self.events
+    }
+}
+
+pub(crate) struct MacroPlayer {
+    events: Vec<MovieEvent>,
+    frames: u64,
+    repeat: bool,
+    frame: u64,
+    next_event: usize,
+    // Pressed by the macro and not released yet
+    held: Vec<Button>,
+}
+
+impl MacroPlayer {
+    pub(crate) fn new(input_macro: InputMacro) -> Self {
+        let frames = input_macro.frames();
+        let mut events = input_macro.events;
+        // Stable, inputs of the same frame keep their order
+        events.sort_by_key(|event| event.frame);
+        MacroPlayer { events, frames, repeat: input_macro.repeat, frame: 0, next_event: 0, held: Vec::new() }
+    }
+
+    pub(crate) fn is_playing(&self) -> bool {
+        self.frame < self.frames
+    }
+
+    // Inputs to send before running the next frame
+    pub(crate) fn frame_inputs(&mut self) -> Vec<InputEvent> {
+        if self.repeat && self.frames > 0 && self.frame >= self.frames {
+            self.frame = 0;
+            self.next_event = 0;
+        }
+        let mut inputs = Vec::new();
+        while let Some(event) = self.events.get(self.next_event).filter(|event| event.frame <= self.frame) {
+            match event.input {
+                InputEvent::Pressed(button) => self.held.push(button),
+                InputEvent::Released(button) => self.held.retain(|held| *held != button),
+                _ => {},
+            }
+            inputs.push(event.input);
+            self.next_event += 1;
+        }
+        self.frame += 1;
+        inputs
+    }
+
+    // Releases what the macro still holds, so the player gets the buttons back
+    pub(crate) fn stop(self) -> Vec<InputEvent> {
+        self.held.into_iter().map(InputEvent::Released).collect()
+    }
+}
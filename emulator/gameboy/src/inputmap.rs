@@ -0,0 +1,180 @@
+use std::{collections::HashSet, fmt, io::{Error, ErrorKind}};
+
+use crate::{Button, InputEvent};
+
+// Sticks and triggers count as pressed past half of their range
+pub const AXIS_THRESHOLD: i16 = 0x4000;
+
+// A control of the host: a key, a gamepad button, or a gamepad axis pushed
+// one way. They are written "Z", "pad:a" and "axis:leftx+" in the settings,
+// with the names the frontend gives them (SDL names for the desktop one).
+// Names are compared ignoring case.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum HostInput {
+    Key(String),
+    GamepadButton(String),
+    GamepadAxis { axis: String, positive: bool },
+}
+
+// A button pressed and released over and over: held for the first frames of
+// every period, released for the rest. Games that read the joypad every other
+// frame may miss presses shorter than 2 frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Turbo {
+    pub period: u32,
+    pub pressed: u32,
+}
+
+// Turns the state of the host controls into Game Boy buttons once per frame.
+// A button is held while any of its bindings is. Turbo bindings press and
+// release their button while they are held, for games that want the button
+// mashed.
+#[derive(Default)]
+pub struct InputMap {
+    bindings: Vec<Binding>,
+    held: HashSet<HostInput>,
+    // Indexed like Button::ALL
+    pressed: [bool; 8],
+    frame: u64,
+}
+
+struct Binding {
+    input: HostInput,
+    button: Button,
+    turbo: Option<Turbo>,
+    // Frame the turbo binding went down
+    turbo_start: Option<u64>,
+}
+
+impl Turbo {
+    // Pressed half of the time
+    pub fn new(period: u32) -> Self {
+        let period = period.max(2);
+        Turbo { period, pressed: period / 2 }
+    }
+
+    // Pressed for some frames out of the period, at least one frame is
+    // pressed and one released
+    pub fn with_duty(period: u32, pressed: u32) -> Self {
+        let period = period.max(2);
+        Turbo { period, pressed: pressed.clamp(1, period - 1) }
+    }
+
+    // Frames are counted from the first press
+    pub fn is_pressed(&self, frame: u64) -> bool {
+        frame % (self.period as u64) < self.pressed as u64
+    }
+}
+
+impl HostInput {
+    pub fn key(name: &str) -> Self {
+        HostInput::Key(name.to_lowercase())
+    }
+
+    pub fn gamepad_button(name: &str) -> Self {
+        HostInput::GamepadButton(name.to_lowercase())
+    }
+
+    pub fn gamepad_axis(name: &str, positive: bool) -> Self {
+        HostInput::GamepadAxis { axis: name.to_lowercase(), positive }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let text = text.trim();
+        if let Some(button) = text.strip_prefix("pad:") {
+            return Ok(HostInput::gamepad_button(button));
+        }
+        if let Some(axis) = text.strip_prefix("axis:") {
+            return match (axis.strip_suffix('+'), axis.strip_suffix('-')) {
+                (Some(axis), _) => Ok(HostInput::gamepad_axis(axis, true)),
+                (_, Some(axis)) => Ok(HostInput::gamepad_axis(axis, false)),
+                _ => Err(Error::new(ErrorKind::InvalidInput, format!("The axis of {} needs a direction, + or -", text))),
+            };
+        }
+        if text.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "Empty input binding"));
+        }
+        Ok(HostInput::key(text))
+    }
+}
+
+impl fmt::Display for HostInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostInput::Key(name) => write!(f, "{}", name),
+            HostInput::GamepadButton(name) => write!(f, "pad:{}", name),
+            HostInput::GamepadAxis { axis, positive } => write!(f, "axis:{}{}", axis, if *positive { '+' } else { '-' }),
+        }
+    }
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        InputMap::default()
+    }
+
+    pub fn bind(&mut self, input: HostInput, button: Button) {
+        self.bindings.push(Binding { input, button, turbo: None, turbo_start: None });
+    }
+
+    pub fn bind_turbo(&mut self, input: HostInput, button: Button, turbo: Turbo) {
+        self.bindings.push(Binding { input, button, turbo: Some(turbo), turbo_start: None });
+    }
+
+    pub fn is_bound(&self, input: &HostInput) -> bool {
+        self.bindings.iter().any(|binding| binding.input == *input)
+    }
+
+    // Every control bound to something, for frontends checking the names exist
+    pub fn inputs(&self) -> impl Iterator<Item = &HostInput> {
+        self.bindings.iter().map(|binding| &binding.input)
+    }
+
+    // A key or gamepad button went down or up
+    pub fn set(&mut self, input: HostInput, pressed: bool) {
+        if pressed {
+            self.held.insert(input);
+        } else {
+            self.held.remove(&input);
+        }
+    }
+
+    // Both directions of an axis follow its position
+    pub fn set_axis(&mut self, axis: &str, value: i16) {
+        self.set(HostInput::gamepad_axis(axis, true), value > AXIS_THRESHOLD);
+        self.set(HostInput::gamepad_axis(axis, false), value < -AXIS_THRESHOLD);
+    }
+
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.pressed[button as usize]
+    }
+
+    // Called before running every frame, gives the buttons that changed
+    pub fn update(&mut self) -> Vec<InputEvent> {
+        let mut pressed = [false; 8];
+        for binding in &mut self.bindings {
+            let held = self.held.contains(&binding.input);
+            pressed[binding.button as usize] |= match binding.turbo {
+                // Turbo starts pressed, on the frame its binding goes down
+                Some(turbo) if held => turbo.is_pressed(self.frame - *binding.turbo_start.get_or_insert(self.frame)),
+                Some(_) => {
+                    binding.turbo_start = None;
+                    false
+                },
+                None => held,
+            };
+        }
+
+        let mut events = Vec::new();
+        for button in Button::ALL {
+            let index = button as usize;
+            let pressed = pressed[index];
+            if pressed != self.pressed[index] {
+                self.pressed[index] = pressed;
+                events.push(if pressed { InputEvent::Pressed(button) } else { InputEvent::Released(button) });
+            }
+        }
+        self.frame += 1;
+        events
+    }
+}
@@ -0,0 +1,340 @@
+// Timers count clock cycles down to the next step of the channel. A tick can
+// cover several steps, when mixing the APU splits ticks at every step instead.
+
+use crate::savestate::{StateReader, StateWriter};
+
+// Base periods of the noise channel in clock cycles, shifted left by NR43 bits 4-7
+// https://gbdev.io/pandocs/Audio_Registers.html#ff22--nr43-channel-4-frequency--randomness
+const NOISE_DIVISORS: [u32; 8] = [8, 16, 32, 48, 64, 80, 96, 112];
+const LFSR_SEED: u16 = 0x7FFF;
+
+// Waveforms selected by NRx1 bits 6-7, one bit per step from the MSB
+const DUTY_PATTERNS: [u8; 4] = [0b0000_0001, 0b1000_0001, 0b1000_0111, 0b0111_1110];
+
+// Right shifts applied to wave samples for the NR32 output levels: mute, 100%, 50% and 25%
+const WAVE_SHIFTS: [u8; 4] = [4, 0, 1, 2];
+const WAVE_SAMPLES: u8 = 32;
+
+const MAX_FREQUENCY: u16 = 2047;
+
+#[derive(Clone, Copy, Default)]
+struct FrequencyTimer {
+    remaining: u32,
+}
+
+impl FrequencyTimer {
+    fn reload(&mut self, period: u32) {
+        self.remaining = period;
+    }
+
+    // Returns how many times the period elapsed
+    fn advance(&mut self, period: u32, cycles: u32) -> u32 {
+        if self.remaining > cycles {
+            self.remaining -= cycles;
+            return 0;
+        }
+        let elapsed = cycles - self.remaining;
+        self.remaining = period - elapsed % period;
+        1 + elapsed / period
+    }
+
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.u32(self.remaining);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        self.remaining = reader.u32()?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Envelope {
+    pub(crate) volume: u8,
+    timer: u8,
+    // Automatic updates stop once the volume reaches 0 or 15
+    running: bool,
+}
+
+impl Envelope {
+    pub(super) fn save_state(&self, writer: &mut StateWriter) {
+        writer.u8(self.volume);
+        writer.u8(self.timer);
+        writer.bool(self.running);
+    }
+
+    pub(super) fn load_state(&mut self, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        self.volume = reader.u8()?;
+        self.timer = reader.u8()?;
+        self.running = reader.bool()?;
+        Ok(())
+    }
+
+    // Direction and period are read from NRx2 on every clock, like the hardware does
+    pub(super) fn clock(&mut self, register: u8) {
+        let period = register & 0x07;
+        if period == 0 || !self.running {
+            return;
+        }
+        self.timer = self.timer.saturating_sub(1);
+        if self.timer > 0 {
+            return;
+        }
+        self.timer = period;
+
+        let increasing = register & 0x08 != 0;
+        match (increasing, self.volume) {
+            (true, 15) | (false, 0) => self.running = false,
+            (true, volume) => self.volume = volume + 1,
+            (false, volume) => self.volume = volume - 1,
+        }
+    }
+
+    pub(super) fn trigger(&mut self, register: u8) {
+        self.volume = register >> 4;
+        self.timer = register & 0x07;
+        self.running = true;
+    }
+
+    // Writing NRx2 while the channel plays changes the volume in a way trackers use
+    // to set it without retriggering the note
+    // https://gbdev.gg8.se/wiki/articles/Gameboy_sound_hardware#Obscure_Behavior
+    pub(super) fn zombie_write(&mut self, old: u8, value: u8) {
+        let mut volume = self.volume;
+        if old & 0x07 == 0 && self.running {
+            volume += 1;
+        } else if old & 0x08 == 0 {
+            volume += 2;
+        }
+        if (old ^ value) & 0x08 != 0 {
+            volume = 16 - volume;
+        }
+        self.volume = volume & 0x0F;
+    }
+}
+
+pub(super) enum SweepUpdate {
+    None,
+    Frequency(u16),
+    // The new frequency went past 2047, the channel stops
+    Overflow,
+}
+
+// Frequency sweep of channel 1, NR10 has the period in bits 4-6,
+// the direction in bit 3 and the shift in bits 0-2
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Sweep {
+    enabled: bool,
+    shadow: u16,
+    timer: u8,
+}
+
+impl Sweep {
+    pub(super) fn save_state(&self, writer: &mut StateWriter) {
+        writer.bool(self.enabled);
+        writer.u16(self.shadow);
+        writer.u8(self.timer);
+    }
+
+    pub(super) fn load_state(&mut self, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        self.enabled = reader.bool()?;
+        self.shadow = reader.u16()?;
+        self.timer = reader.u8()?;
+        Ok(())
+    }
+
+    fn period(nr10: u8) -> u8 {
+        (nr10 >> 4) & 0x07
+    }
+
+    fn target(&self, nr10: u8) -> u16 {
+        let delta = self.shadow >> (nr10 & 0x07);
+        if nr10 & 0x08 != 0 { self.shadow - delta } else { self.shadow + delta }
+    }
+
+    // Returns false when the first calculation already overflows
+    pub(super) fn trigger(&mut self, nr10: u8, frequency: u16) -> bool {
+        self.shadow = frequency;
+        self.timer = if Sweep::period(nr10) == 0 { 8 } else { Sweep::period(nr10) };
+        self.enabled = Sweep::period(nr10) != 0 || nr10 & 0x07 != 0;
+        nr10 & 0x07 == 0 || self.target(nr10) <= MAX_FREQUENCY
+    }
+
+    pub(super) fn clock(&mut self, nr10: u8) -> SweepUpdate {
+        self.timer = self.timer.saturating_sub(1);
+        if self.timer > 0 {
+            return SweepUpdate::None;
+        }
+        self.timer = if Sweep::period(nr10) == 0 { 8 } else { Sweep::period(nr10) };
+        if !self.enabled || Sweep::period(nr10) == 0 {
+            return SweepUpdate::None;
+        }
+
+        let frequency = self.target(nr10);
+        if frequency > MAX_FREQUENCY {
+            return SweepUpdate::Overflow;
+        }
+        if nr10 & 0x07 == 0 {
+            return SweepUpdate::None;
+        }
+        self.shadow = frequency;
+        // The hardware checks the next value right away
+        if self.target(nr10) > MAX_FREQUENCY {
+            return SweepUpdate::Overflow;
+        }
+        SweepUpdate::Frequency(frequency)
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub(crate) struct PulseChannel {
+    timer: FrequencyTimer,
+    step: u8,
+}
+
+impl PulseChannel {
+    pub(super) fn save_state(&self, writer: &mut StateWriter) {
+        self.timer.save_state(writer);
+        writer.u8(self.step);
+    }
+
+    pub(super) fn load_state(&mut self, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        self.timer.load_state(reader)?;
+        self.step = reader.u8()?;
+        Ok(())
+    }
+
+    fn period(frequency: u16) -> u32 {
+        (2048 - frequency as u32) * 4
+    }
+
+    // The duty position is not reset by a trigger
+    pub(super) fn trigger(&mut self, frequency: u16) {
+        self.timer.reload(PulseChannel::period(frequency));
+    }
+
+    pub(super) fn advance(&mut self, frequency: u16, cycles: u32) {
+        let steps = self.timer.advance(PulseChannel::period(frequency), cycles);
+        self.step = ((self.step as u32 + steps) % 8) as u8;
+    }
+
+    pub(super) fn next_step(&self) -> u32 {
+        self.timer.remaining
+    }
+
+    pub(super) fn amplitude(&self, nrx1: u8, volume: u8) -> u8 {
+        let duty = DUTY_PATTERNS[(nrx1 >> 6) as usize];
+        ((duty >> (7 - self.step)) & 1) * volume
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub(crate) struct WaveChannel {
+    timer: FrequencyTimer,
+    // Index of the 4-bit sample being played, high nibbles first
+    pub(crate) position: u8,
+}
+
+impl WaveChannel {
+    pub(super) fn save_state(&self, writer: &mut StateWriter) {
+        self.timer.save_state(writer);
+        writer.u8(self.position);
+    }
+
+    pub(super) fn load_state(&mut self, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        self.timer.load_state(reader)?;
+        self.position = reader.u8()?;
+        Ok(())
+    }
+
+    fn period(frequency: u16) -> u32 {
+        (2048 - frequency as u32) * 2
+    }
+
+    pub(super) fn trigger(&mut self, frequency: u16) {
+        self.position = 0;
+        self.timer.reload(WaveChannel::period(frequency));
+    }
+
+    pub(super) fn advance(&mut self, frequency: u16, cycles: u32) {
+        let steps = self.timer.advance(WaveChannel::period(frequency), cycles);
+        self.position = ((self.position as u32 + steps) % WAVE_SAMPLES as u32) as u8;
+    }
+
+    pub(super) fn next_step(&self) -> u32 {
+        self.timer.remaining
+    }
+
+    pub(super) fn amplitude(&self, wave_ram: &[u8], nr32: u8) -> u8 {
+        let byte = wave_ram[(self.position / 2) as usize];
+        let sample = if self.position.is_multiple_of(2) { byte >> 4 } else { byte & 0x0F };
+        sample >> WAVE_SHIFTS[((nr32 >> 5) & 0x03) as usize]
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct NoiseChannel {
+    // The channel outputs its volume when bit 0 is clear
+    pub(crate) lfsr: u16,
+    timer: FrequencyTimer,
+}
+
+impl Default for NoiseChannel {
+    fn default() -> Self {
+        NoiseChannel { lfsr: LFSR_SEED, timer: FrequencyTimer::default() }
+    }
+}
+
+impl NoiseChannel {
+    pub(super) fn save_state(&self, writer: &mut StateWriter) {
+        self.timer.save_state(writer);
+        writer.u16(self.lfsr);
+    }
+
+    pub(super) fn load_state(&mut self, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        self.timer.load_state(reader)?;
+        self.lfsr = reader.u16()?;
+        Ok(())
+    }
+
+    // None for shifts 14 and 15, the LFSR doesn't get clocked at all
+    fn period(nr43: u8) -> Option<u32> {
+        let shift = nr43 >> 4;
+        if shift >= 14 {
+            return None;
+        }
+        Some(NOISE_DIVISORS[(nr43 & 0x07) as usize] << shift)
+    }
+
+    pub(super) fn trigger(&mut self, nr43: u8) {
+        self.lfsr = LFSR_SEED;
+        self.timer.reload(NoiseChannel::period(nr43).unwrap_or(0));
+    }
+
+    pub(super) fn advance(&mut self, nr43: u8, cycles: u32) {
+        let Some(period) = NoiseChannel::period(nr43) else { return };
+        for _ in 0..self.timer.advance(period, cycles) {
+            self.shift(nr43 & 0x08 != 0);
+        }
+    }
+
+    pub(super) fn next_step(&self, nr43: u8) -> u32 {
+        match NoiseChannel::period(nr43) {
+            Some(_) => self.timer.remaining,
+            None => u32::MAX,
+        }
+    }
+
+    // XOR of the two low bits is shifted in at bit 14, and also at bit 6 in 7-bit mode
+    fn shift(&mut self, short_mode: bool) {
+        let bit = (self.lfsr ^ (self.lfsr >> 1)) & 1;
+        self.lfsr = (self.lfsr >> 1) | (bit << 14);
+        if short_mode {
+            self.lfsr = (self.lfsr & !(1 << 6)) | (bit << 6);
+        }
+    }
+
+    pub(super) fn amplitude(&self, volume: u8) -> u8 {
+        (!self.lfsr & 1) as u8 * volume
+    }
+}
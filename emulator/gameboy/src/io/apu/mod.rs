@@ -0,0 +1,453 @@
+mod channels;
+
+use crate::{audio::{ChannelTaps, Resampler}, audiodump::AudioDump, gameboy::GameBoy, mmu::Address, savestate::{StateReader, StateWriter}};
+
+use channels::{Envelope, NoiseChannel, PulseChannel, Sweep, SweepUpdate, WaveChannel};
+
+pub(crate) const APU_BEGIN: Address = 0xFF10;
+pub(crate) const APU_END: Address = 0xFF3F;
+
+pub(crate) const NR11_ADDRESS: Address = 0xFF11;
+pub(crate) const NR12_ADDRESS: Address = 0xFF12;
+pub(crate) const NR14_ADDRESS: Address = 0xFF14;
+pub(crate) const NR21_ADDRESS: Address = 0xFF16;
+pub(crate) const NR22_ADDRESS: Address = 0xFF17;
+pub(crate) const NR24_ADDRESS: Address = 0xFF19;
+pub(crate) const NR30_ADDRESS: Address = 0xFF1A;
+pub(crate) const NR31_ADDRESS: Address = 0xFF1B;
+pub(crate) const NR34_ADDRESS: Address = 0xFF1E;
+pub(crate) const NR41_ADDRESS: Address = 0xFF20;
+pub(crate) const NR42_ADDRESS: Address = 0xFF21;
+pub(crate) const NR44_ADDRESS: Address = 0xFF23;
+pub(crate) const NR52_ADDRESS: Address = 0xFF26;
+
+const REGISTERS_SIZE: usize = 0x20;
+const WAVE_RAM_BEGIN: Address = 0xFF30;
+const WAVE_RAM_SIZE: usize = 0x10;
+
+const TRIGGER: u8 = 0b1000_0000;
+
+const LENGTH_ENABLE: u8 = 0b0100_0000;
+
+// Offsets of NRx1-NRx4 from NRx0, the registers of channel n start at NR10 + 5n
+const NRX1: usize = 1;
+const NRX2: usize = 2;
+const NRX3: usize = 3;
+const NRX4: usize = 4;
+const NR10_INDEX: usize = 0x00;
+const NR32_INDEX: usize = 0x0C;
+const NR43_INDEX: usize = 0x12;
+const NR50_INDEX: usize = 0x14;
+const NR51_INDEX: usize = 0x15;
+
+// The frame sequencer runs at 512 Hz. Even steps clock the length counters,
+// steps 2 and 6 the sweep and the last one the envelopes.
+const SEQUENCER_STEPS: u8 = 8;
+const ENVELOPE_STEP: u8 = 7;
+
+// Bits that always read as 1 for FF10-FF2F: unused bits, write-only
+// frequencies and length counters, and unmapped registers
+// https://gbdev.io/pandocs/Audio_Registers.html
+const READ_MASKS: [u8; REGISTERS_SIZE] = [
+    0x80, 0x3F, 0x00, 0xFF, 0xBF, // NR10-NR14
+    0xFF, 0x3F, 0x00, 0xFF, 0xBF, // NR20-NR24, NR20 doesn't exist
+    0x7F, 0xFF, 0x9F, 0xFF, 0xBF, // NR30-NR34
+    0xFF, 0xFF, 0x00, 0x00, 0xBF, // NR40-NR44, NR40 doesn't exist
+    0x00, 0x00, 0x70,             // NR50-NR52
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+];
+
+pub(crate) struct APU {
+    registers: [u8; REGISTERS_SIZE],
+    pub(crate) wave_ram: [u8; WAVE_RAM_SIZE],
+    powered: bool,
+    // Channel status reported in NR52
+    active: [bool; 4],
+    // Indexed by channel, the wave channel has no envelope
+    pub(crate) envelopes: [Envelope; 4],
+    lengths: [u16; 4],
+    sweep: Sweep,
+    pulses: [PulseChannel; 2],
+    pub(crate) wave: WaveChannel,
+    pub(crate) noise: NoiseChannel,
+    sequencer_step: u8,
+    // Emulate the NRx2 write quirk instead of only storing the register
+    pub(crate) zombie_mode: bool,
+    // Left out of the mix, indexed like AudioChannel::ALL
+    pub(crate) muted: [bool; 4],
+}
+
+impl APU {
+    pub(crate) fn new() -> Self {
+        APU {
+            registers: [0; REGISTERS_SIZE],
+            wave_ram: [0; WAVE_RAM_SIZE],
+            powered: false,
+            active: [false; 4],
+            envelopes: [Envelope::default(); 4],
+            lengths: [0; 4],
+            sweep: Sweep::default(),
+            pulses: [PulseChannel::default(); 2],
+            wave: WaveChannel::default(),
+            noise: NoiseChannel::default(),
+            sequencer_step: 0,
+            zombie_mode: false,
+            muted: [false; 4],
+        }
+    }
+
+    // zombie_mode and muted are settings, not hardware state
+    pub(crate) fn save_state(gb: &GameBoy, writer: &mut StateWriter) {
+        let apu = &gb.io.apu;
+        writer.bytes(&apu.registers);
+        writer.bytes(&apu.wave_ram);
+        writer.bool(apu.powered);
+        for active in apu.active {
+            writer.bool(active);
+        }
+        for envelope in &apu.envelopes {
+            envelope.save_state(writer);
+        }
+        for length in apu.lengths {
+            writer.u16(length);
+        }
+        apu.sweep.save_state(writer);
+        for pulse in &apu.pulses {
+            pulse.save_state(writer);
+        }
+        apu.wave.save_state(writer);
+        apu.noise.save_state(writer);
+        writer.u8(apu.sequencer_step);
+    }
+
+    pub(crate) fn load_state(gb: &mut GameBoy, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        let apu = &mut gb.io.apu;
+        apu.registers = reader.array()?;
+        apu.wave_ram = reader.array()?;
+        apu.powered = reader.bool()?;
+        for active in apu.active.iter_mut() {
+            *active = reader.bool()?;
+        }
+        for envelope in apu.envelopes.iter_mut() {
+            envelope.load_state(reader)?;
+        }
+        for length in apu.lengths.iter_mut() {
+            *length = reader.u16()?;
+        }
+        apu.sweep.load_state(reader)?;
+        for pulse in apu.pulses.iter_mut() {
+            pulse.load_state(reader)?;
+        }
+        apu.wave.load_state(reader)?;
+        apu.noise.load_state(reader)?;
+        apu.sequencer_step = reader.u8()?;
+        Ok(())
+    }
+
+    // Writes that bring a powered off APU where this one is. Playing channels
+    // are triggered again, which restarts their envelope and length.
+    pub(crate) fn restore_writes(&self) -> Vec<(Address, u8)> {
+        let mut writes: Vec<(Address, u8)> = (0..WAVE_RAM_SIZE).map(|index| (WAVE_RAM_BEGIN + index as Address, self.wave_ram[index])).collect();
+        if !self.powered {
+            return writes;
+        }
+        writes.push((NR52_ADDRESS, 0b1000_0000));
+        for index in 0..=NR51_INDEX {
+            let mut value = self.registers[index];
+            if index < NR50_INDEX && index % 5 == NRX4 {
+                value = (value & !TRIGGER) | if self.active[index / 5] { TRIGGER } else { 0 };
+            }
+            writes.push((APU_BEGIN + index as Address, value));
+        }
+        writes
+    }
+
+    // Without audio output only the channel states advance, otherwise the tick
+    // is split at every channel step so the resampler gets the exact waveform
+    pub(crate) fn tick(gb: &mut GameBoy, cycles: u32) {
+        if let Some(log) = &mut gb.vgm_log {
+            log.advance(cycles);
+        }
+        let apu = &mut gb.io.apu;
+        if gb.audio.is_none() && gb.channel_taps.is_none() && gb.audio_dump.is_none() {
+            apu.advance(cycles);
+            return;
+        }
+
+        let start = gb.audio.as_ref().map_or(0, Resampler::sample_count);
+        let mut remaining = cycles;
+        while remaining > 0 {
+            let step = remaining.min(apu.next_step())
+                .min(gb.channel_taps.as_ref().map_or(u32::MAX, ChannelTaps::next_step))
+                .min(gb.audio_dump.as_ref().map_or(u32::MAX, AudioDump::next_step));
+            if let Some(taps) = &mut gb.channel_taps {
+                taps.push(apu.levels(), step);
+            }
+            if let Some(dump) = &mut gb.audio_dump {
+                dump.push(apu.mix(), apu.levels(), step);
+            }
+            if let Some(resampler) = &mut gb.audio {
+                resampler.push(apu.mix(), step);
+            }
+            apu.advance(step);
+            remaining -= step;
+        }
+
+        if let (Some(observer), Some(resampler)) = (&mut gb.observer, &gb.audio) {
+            if resampler.sample_count() > start {
+                observer.on_audio(&resampler.pcm_since(start));
+            }
+        }
+    }
+
+    fn register(&self, channel: usize, offset: usize) -> u8 {
+        self.registers[channel * 5 + offset]
+    }
+
+    fn frequency(&self, channel: usize) -> u16 {
+        self.register(channel, NRX3) as u16 | ((self.register(channel, NRX4) as u16 & 0x07) << 8)
+    }
+
+    fn advance(&mut self, cycles: u32) {
+        if !self.powered {
+            return;
+        }
+        for channel in 0..2 {
+            if self.active[channel] {
+                let frequency = self.frequency(channel);
+                self.pulses[channel].advance(frequency, cycles);
+            }
+        }
+        if self.active[2] {
+            self.wave.advance(self.frequency(2), cycles);
+        }
+        if self.active[3] {
+            self.noise.advance(self.registers[NR43_INDEX], cycles);
+        }
+    }
+
+    // Cycles until the output of some channel can change
+    fn next_step(&self) -> u32 {
+        let steps = [
+            self.pulses[0].next_step(),
+            self.pulses[1].next_step(),
+            self.wave.next_step(),
+            self.noise.next_step(self.registers[NR43_INDEX]),
+        ];
+        (0..4).filter(|channel| self.powered && self.active[*channel])
+            .map(|channel| steps[channel].max(1))
+            .min()
+            .unwrap_or(u32::MAX)
+    }
+
+    // Digital output of a channel, from 0 to 15
+    fn amplitude(&self, channel: usize) -> u8 {
+        if !self.active[channel] {
+            return 0;
+        }
+        let volume = self.envelopes[channel].volume;
+        match channel {
+            0 | 1 => self.pulses[channel].amplitude(self.register(channel, NRX1), volume),
+            2 => self.wave.amplitude(&self.wave_ram, self.registers[NR32_INDEX]),
+            _ => self.noise.amplitude(volume),
+        }
+    }
+
+    // What each DAC outputs, between -1 and 1, 0 while it is off
+    fn levels(&self) -> [f32; 4] {
+        let mut levels = [0.0; 4];
+        for channel in (0..4).filter(|channel| self.powered && APU::dac_enabled(self, *channel)) {
+            levels[channel] = 1.0 - self.amplitude(channel) as f32 / 7.5;
+        }
+        levels
+    }
+
+    // Left and right outputs between -1 and 1. Each DAC maps 0-15 to 1..-1 and NR51
+    // routes the channels to the terminals, which are scaled by the NR50 volumes.
+    pub(crate) fn mix(&self) -> [f32; 2] {
+        if !self.powered {
+            return [0.0; 2];
+        }
+        let nr50 = self.registers[NR50_INDEX];
+        let nr51 = self.registers[NR51_INDEX];
+
+        let levels = self.levels();
+        let mut output = [0.0; 2];
+        for channel in (0..4).filter(|channel| !self.muted[*channel]) {
+            let analog = levels[channel];
+            if nr51 & (0x10 << channel) != 0 {
+                output[0] += analog;
+            }
+            if nr51 & (0x01 << channel) != 0 {
+                output[1] += analog;
+            }
+        }
+        let left_volume = ((nr50 >> 4) & 0x07) as f32 + 1.0;
+        let right_volume = (nr50 & 0x07) as f32 + 1.0;
+        [output[0] / 4.0 * left_volume / 8.0, output[1] / 4.0 * right_volume / 8.0]
+    }
+
+    // Clocked by the falling edge of bit 4 of DIV
+    pub(crate) fn clock_sequencer(gb: &mut GameBoy) {
+        let apu = &mut gb.io.apu;
+        if !apu.powered {
+            return;
+        }
+        apu.sequencer_step = (apu.sequencer_step + 1) % SEQUENCER_STEPS;
+
+        if apu.sequencer_step.is_multiple_of(2) {
+            APU::clock_lengths(apu);
+        }
+        if apu.sequencer_step == 2 || apu.sequencer_step == 6 {
+            APU::clock_sweep(apu);
+        }
+        if apu.sequencer_step == ENVELOPE_STEP {
+            for channel in [0, 1, 3] {
+                if apu.active[channel] {
+                    let register = apu.register(channel, NRX2);
+                    apu.envelopes[channel].clock(register);
+                }
+            }
+        }
+    }
+
+    fn clock_lengths(apu: &mut APU) {
+        for channel in 0..4 {
+            if apu.register(channel, NRX4) & LENGTH_ENABLE != 0 && apu.lengths[channel] > 0 {
+                apu.lengths[channel] -= 1;
+                if apu.lengths[channel] == 0 {
+                    apu.active[channel] = false;
+                }
+            }
+        }
+    }
+
+    fn clock_sweep(apu: &mut APU) {
+        if !apu.active[0] {
+            return;
+        }
+        match apu.sweep.clock(apu.registers[NR10_INDEX]) {
+            SweepUpdate::None => {},
+            SweepUpdate::Frequency(frequency) => {
+                apu.registers[NRX3] = frequency as u8;
+                apu.registers[NRX4] = (apu.registers[NRX4] & !0x07) | (frequency >> 8) as u8;
+            },
+            SweepUpdate::Overflow => apu.active[0] = false,
+        }
+    }
+
+    // Length counters count up from the written value to 64, or 256 for the wave channel
+    fn max_length(channel: usize) -> u16 {
+        if channel == 2 { 256 } else { 64 }
+    }
+
+    fn load_length(apu: &mut APU, channel: usize, value: u8) {
+        let mask = (APU::max_length(channel) - 1) as u8;
+        apu.lengths[channel] = APU::max_length(channel) - (value & mask) as u16;
+    }
+
+    pub(crate) fn read_byte(gb: &GameBoy, address: Address) -> u8 {
+        let apu = &gb.io.apu;
+        match address {
+            NR52_ADDRESS => {
+                let active = apu.active.iter().enumerate().fold(0, |bits, (channel, on)| bits | ((*on as u8) << channel));
+                READ_MASKS[(address - APU_BEGIN) as usize] | ((apu.powered as u8) << 7) | active
+            },
+            WAVE_RAM_BEGIN ..= APU_END => apu.wave_ram[(address - WAVE_RAM_BEGIN) as usize],
+            _ => {
+                let index = (address - APU_BEGIN) as usize;
+                apu.registers[index] | READ_MASKS[index]
+            }
+        }
+    }
+
+    pub(crate) fn write_byte(gb: &mut GameBoy, address: Address, value: u8) {
+        if let Some(log) = &mut gb.vgm_log {
+            log.write(address, value);
+        }
+        let apu = &mut gb.io.apu;
+        match address {
+            NR52_ADDRESS => {
+                let powered = value & 0b1000_0000 != 0;
+                // Turning the APU off clears every register, wave RAM is kept
+                if apu.powered && !powered {
+                    *apu = APU { wave_ram: apu.wave_ram, zombie_mode: apu.zombie_mode, muted: apu.muted, ..APU::new() };
+                }
+                apu.powered = powered;
+            },
+            // Wave RAM is usable while the APU is off
+            WAVE_RAM_BEGIN ..= APU_END => apu.wave_ram[(address - WAVE_RAM_BEGIN) as usize] = value,
+            _ if !apu.powered => {
+                // On DMG the length counters can still be written while off
+                let index = (address - APU_BEGIN) as usize;
+                match address {
+                    NR11_ADDRESS | NR21_ADDRESS => apu.registers[index] = (apu.registers[index] & 0xC0) | (value & 0x3F),
+                    NR31_ADDRESS | NR41_ADDRESS => apu.registers[index] = value,
+                    _ => return,
+                }
+                APU::load_length(apu, index / 5, value);
+            },
+            _ => {
+                let index = (address - APU_BEGIN) as usize;
+                let old = apu.registers[index];
+                apu.registers[index] = value;
+                APU::update_status(apu, address, old, value);
+            }
+        }
+    }
+
+    // A trigger turns the channel on if its DAC is, turning the DAC off stops the channel
+    fn update_status(apu: &mut APU, address: Address, old: u8, value: u8) {
+        match address {
+            NR14_ADDRESS | NR24_ADDRESS | NR34_ADDRESS | NR44_ADDRESS if value & TRIGGER != 0 => {
+                let channel = ((address - NR14_ADDRESS) / 5) as usize;
+                APU::trigger(apu, channel);
+            },
+            NR11_ADDRESS | NR21_ADDRESS | NR31_ADDRESS | NR41_ADDRESS => {
+                APU::load_length(apu, ((address - NR11_ADDRESS) / 5) as usize, value);
+            },
+            NR12_ADDRESS | NR22_ADDRESS | NR42_ADDRESS => {
+                let channel = ((address - NR12_ADDRESS) / 5) as usize;
+                if apu.zombie_mode && apu.active[channel] {
+                    apu.envelopes[channel].zombie_write(old, value);
+                }
+                if !APU::dac_enabled(apu, channel) {
+                    apu.active[channel] = false;
+                }
+            },
+            NR30_ADDRESS if !APU::dac_enabled(apu, 2) => apu.active[2] = false,
+            _ => {},
+        }
+    }
+
+    fn trigger(apu: &mut APU, channel: usize) {
+        apu.active[channel] = APU::dac_enabled(apu, channel);
+        if apu.lengths[channel] == 0 {
+            apu.lengths[channel] = APU::max_length(channel);
+        }
+        let envelope = apu.register(channel, NRX2);
+        let frequency = apu.frequency(channel);
+        match channel {
+            0 | 1 => {
+                apu.envelopes[channel].trigger(envelope);
+                apu.pulses[channel].trigger(frequency);
+            },
+            2 => apu.wave.trigger(frequency),
+            _ => {
+                apu.envelopes[channel].trigger(envelope);
+                apu.noise.trigger(apu.registers[NR43_INDEX]);
+            },
+        }
+        if channel == 0 && !apu.sweep.trigger(apu.registers[NR10_INDEX], frequency) {
+            apu.active[0] = false;
+        }
+    }
+
+    // The DAC is on when the upper 5 bits of NRx2 are set, or bit 7 of NR30 for the wave channel
+    fn dac_enabled(apu: &APU, channel: usize) -> bool {
+        match channel {
+            2 => apu.registers[(NR30_ADDRESS - APU_BEGIN) as usize] & 0b1000_0000 != 0,
+            _ => apu.register(channel, NRX2) & 0xF8 != 0,
+        }
+    }
+}
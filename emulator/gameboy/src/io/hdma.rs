@@ -0,0 +1,123 @@
+use crate::{gameboy::GameBoy, mmu::{Address, MMU}, ppu::PPU, savestate::{StateReader, StateWriter}};
+
+use super::{io::IO, timers::Timers};
+
+pub(crate) const HDMA_BEGIN: Address = 0xFF51;
+pub(crate) const HDMA_END: Address = 0xFF55;
+
+const BLOCK_SIZE: u16 = 0x10;
+// Machine cycles the CPU waits for each block at normal speed
+const BLOCK_MCYCLES: u16 = 8;
+
+// VRAM DMA of the CGB (HDMA1-HDMA5). A general purpose transfer copies every
+// block at once and the CPU waits for it, an HBlank transfer copies one block
+// at the start of each HBlank. Source and destination move forward as blocks
+// are copied, so a cancelled transfer can be resumed by writing HDMA5 again.
+pub(crate) struct Hdma {
+    source: Address,
+    // Offset inside VRAM
+    destination: Address,
+    // Blocks left minus one, as HDMA5 reads
+    remaining: u8,
+    hblank_active: bool,
+    // Machine cycles the CPU is held for the blocks copied since the last instruction
+    stall: u16,
+}
+
+impl Hdma {
+    pub(crate) fn new() -> Self {
+        Hdma { source: 0, destination: 0, remaining: 0x7F, hblank_active: false, stall: 0 }
+    }
+
+    // Only in CGB mode, like KEY1
+    pub(crate) fn read_byte(gb: &GameBoy, address: Address) -> u8 {
+        if !gb.cgb_mode() {
+            return IO::read_unmapped(gb, address);
+        }
+        match address {
+            // Bit 7 is set once the transfer is over or cancelled
+            HDMA_END => gb.io.hdma.remaining | if gb.io.hdma.hblank_active { 0x00 } else { 0x80 },
+            _ => 0xFF,
+        }
+    }
+
+    pub(crate) fn write_byte(gb: &mut GameBoy, address: Address, value: u8) {
+        if !gb.cgb_mode() {
+            return IO::write_unmapped(gb, address, value);
+        }
+        let hdma = &mut gb.io.hdma;
+        match address {
+            0xFF51 => hdma.source = (hdma.source & 0x00FF) | (value as Address) << 8,
+            // The low 4 bits are ignored, blocks are aligned
+            0xFF52 => hdma.source = (hdma.source & 0xFF00) | (value & 0xF0) as Address,
+            0xFF53 => hdma.destination = (hdma.destination & 0x00FF) | ((value & 0x1F) as Address) << 8,
+            0xFF54 => hdma.destination = (hdma.destination & 0xFF00) | (value & 0xF0) as Address,
+            _ => {
+                if hdma.hblank_active && value & 0x80 == 0 {
+                    hdma.hblank_active = false;
+                    return;
+                }
+                hdma.remaining = value & 0x7F;
+                tracing::debug!(target: "yagabor::mmu", source = hdma.source, destination = hdma.destination, blocks = hdma.remaining as u16 + 1, hblank = value & 0x80 != 0, "HDMA");
+                if value & 0x80 != 0 {
+                    hdma.hblank_active = true;
+                } else {
+                    while !Hdma::copy_block(gb) {}
+                }
+            },
+        }
+    }
+
+    // Called when the PPU enters HBlank
+    pub(crate) fn hblank(gb: &mut GameBoy) {
+        if gb.io.hdma.hblank_active && Hdma::copy_block(gb) {
+            gb.io.hdma.hblank_active = false;
+        }
+    }
+
+    // True when it was the last block
+    fn copy_block(gb: &mut GameBoy) -> bool {
+        for _ in 0..BLOCK_SIZE {
+            let value = MMU::read_byte(gb, gb.io.hdma.source);
+            PPU::write_vram(gb, 0x8000 | gb.io.hdma.destination, value);
+            gb.io.hdma.source = gb.io.hdma.source.wrapping_add(1);
+            gb.io.hdma.destination = (gb.io.hdma.destination + 1) & 0x1FFF;
+        }
+        // The copy takes the same time at double speed, twice the CPU cycles
+        gb.io.hdma.stall += if IO::double_speed(gb) { BLOCK_MCYCLES * 2 } else { BLOCK_MCYCLES };
+        let (remaining, done) = gb.io.hdma.remaining.overflowing_sub(1);
+        gb.io.hdma.remaining = remaining & 0x7F;
+        done
+    }
+
+    // The timers keep running while the CPU waits, returns the clock cycles of
+    // the wait at the pace of the PPU
+    pub(crate) fn stall(gb: &mut GameBoy) -> u16 {
+        let stall = std::mem::take(&mut gb.io.hdma.stall);
+        let mut left = stall;
+        while left > 0 {
+            let mcycles = left.min(u8::MAX as u16);
+            Timers::tick(gb, mcycles as u8);
+            left -= mcycles;
+        }
+        if IO::double_speed(gb) { stall * 2 } else { stall * 4 }
+    }
+
+    pub(crate) fn save_state(gb: &GameBoy, writer: &mut StateWriter) {
+        let hdma = &gb.io.hdma;
+        writer.u16(hdma.source);
+        writer.u16(hdma.destination);
+        writer.u8(hdma.remaining);
+        writer.bool(hdma.hblank_active);
+    }
+
+    pub(crate) fn load_state(gb: &mut GameBoy, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        let hdma = &mut gb.io.hdma;
+        hdma.source = reader.u16()?;
+        hdma.destination = reader.u16()?;
+        hdma.remaining = reader.u8()?;
+        hdma.hblank_active = reader.bool()?;
+        hdma.stall = 0;
+        Ok(())
+    }
+}
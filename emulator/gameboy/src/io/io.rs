@@ -1,6 +1,8 @@
-use crate::{mmu::{Address, IO_SIZE, IO_BEGIN, MMU}, gameboy::GameBoy};
+use std::cell::Cell;
 
-use super::{interrupts::{Interruption, Interrupts}, lcd::LCD, timers::Timers, joypad::Joypad};
+use crate::{mmu::{Address, IO_SIZE, IO_BEGIN, MMU}, debugger::{DeveloperMode, Diagnostics, DEBUG_OUTPUT_ADDRESS}, gameboy::GameBoy, savestate::{StateReader, StateWriter}};
+
+use super::{apu::{APU, APU_BEGIN, APU_END}, hdma::{Hdma, HDMA_BEGIN, HDMA_END}, interrupts::{Interruption, Interrupts}, lcd::LCD, oamscan::OamScan, timers::Timers, joypad::Joypad};
 
 pub(crate) const JOYPAD_INPUT_ADDRESS: Address = 0xFF00;
 pub(crate) const SERIAL_DATA_ADDRESS: Address = 0xFF01;
@@ -14,17 +16,55 @@ pub(crate) const TAC_ADDRESS: Address = 0xFF07;
 pub(crate) const LCD_BEGIN: Address = 0xFF40;
 pub(crate) const LCD_END: Address = 0xFF4B;
 
+pub(crate) const SPEED_SWITCH_ADDRESS: Address = 0xFF4D;
+
 pub(crate) const BOOT_SWITCH_ADDRESS: Address = 0xFF50;
 
+pub(crate) const INFRARED_ADDRESS: Address = 0xFF56;
+
 pub(crate) const INTERRUPT_FLAG_ADDRESS: Address = 0xFF0F;
 
 
+// What to do when the program uses a register that is not emulated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnknownIoPolicy {
+    Ignore,
+    // Warn about the first write to each register through tracing
+    LogOnce,
+    // Stop the emulation with EmulationError::UnmappedRead or UnmappedWrite
+    Break,
+}
+
+type IoRead = fn(&GameBoy, Address) -> u8;
+type IoWrite = fn(&mut GameBoy, Address, u8);
+
+#[derive(Clone, Copy)]
+struct IoHandler {
+    read: IoRead,
+    write: IoWrite,
+}
+
+const UNMAPPED: IoHandler = IoHandler { read: IO::read_unmapped, write: IO::write_unmapped };
+const STORED: IoHandler = IoHandler { read: IO::read_stored, write: IO::write_stored };
+
+// Handler of every register in FF00-FF7F
+static IO_HANDLERS: [IoHandler; IO_SIZE] = IO::handlers();
+
 pub(crate) struct IO {
     pub(crate) interrupts: Interrupts,
     pub(crate) lcd: LCD,
     pub(crate) timers: Timers,
     pub(crate) joypad: Joypad,
+    pub(crate) apu: APU,
+    pub(crate) hdma: Hdma,
+    pub(crate) oam_scan: OamScan,
     data: [u8; IO_SIZE],
+    pub(crate) unknown_policy: UnknownIoPolicy,
+    unknown_reported: [bool; IO_SIZE],
+    // Set by a write when the policy is Break, consumed by the CPU
+    pub(crate) unknown_write: Option<(Address, u8)>,
+    // Same for reads, which only borrow the Game Boy
+    pub(crate) unknown_read: Cell<Option<Address>>,
 }
 
 impl IO {
@@ -34,40 +74,212 @@ impl IO {
              lcd: LCD::new(),
              timers: Timers::new(),
              joypad: Joypad::new(),
-             data:[0; IO_SIZE] 
+             apu: APU::new(),
+             hdma: Hdma::new(),
+             oam_scan: OamScan::new(),
+             data:[0; IO_SIZE],
+             unknown_policy: UnknownIoPolicy::Ignore,
+             unknown_reported: [false; IO_SIZE],
+             unknown_write: None,
+             unknown_read: Cell::new(None),
         }
     }
 
     pub(crate) fn read_byte(gb: &GameBoy, address: Address) -> u8 {
-        match address {
-            JOYPAD_INPUT_ADDRESS => Joypad::read(gb),
-            LCD_BEGIN ..= LCD_END => LCD::read_byte(gb, address),
-            INTERRUPT_FLAG_ADDRESS => Interrupts::read_flag(gb),
-            // DIV value is 8 upper bits
-            DIV_ADDRESS => IO::get_div_register(gb),
-            _ => gb.io.data[(address - IO_BEGIN) as usize]
-        }
+        Diagnostics::io_read(gb, address);
+        (IO_HANDLERS[(address - IO_BEGIN) as usize].read)(gb, address)
     }
 
     pub(crate) fn write_byte(gb: &mut GameBoy, address: Address, value: u8) {
-        match address {
-            JOYPAD_INPUT_ADDRESS => Joypad::write(gb, value),
-            DIV_ADDRESS => {
-                // Writing DIV reset it
-                gb.io.data[(DIV_ADDRESS - IO_BEGIN) as usize] = 0;
-            },
-            LCD_BEGIN ..= LCD_END => LCD::write_byte(gb, address, value),
-            BOOT_SWITCH_ADDRESS => {
+        (IO_HANDLERS[(address - IO_BEGIN) as usize].write)(gb, address, value)
+    }
+
+    pub(crate) fn save_state(gb: &GameBoy, writer: &mut StateWriter) {
+        writer.bytes(&gb.io.data);
+        writer.u8(gb.io.interrupts.interrupt_enable);
+        writer.u8(gb.io.interrupts.interrupt_flag);
+        writer.u8(gb.io.timers.div_counter);
+        writer.u16(gb.io.timers.tima_counter);
+        Joypad::save_state(gb, writer);
+        LCD::save_state(gb, writer);
+        APU::save_state(gb, writer);
+        Hdma::save_state(gb, writer);
+    }
+
+    pub(crate) fn load_state(gb: &mut GameBoy, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        gb.io.data = reader.array()?;
+        gb.io.interrupts.interrupt_enable = reader.u8()?;
+        gb.io.interrupts.interrupt_flag = reader.u8()?;
+        gb.io.timers.div_counter = reader.u8()?;
+        gb.io.timers.tima_counter = reader.u16()?;
+        Joypad::load_state(gb, reader)?;
+        LCD::load_state(gb, reader)?;
+        APU::load_state(gb, reader)?;
+        Hdma::load_state(gb, reader)
+    }
+
+    const fn handlers() -> [IoHandler; IO_SIZE] {
+        let mut table = [UNMAPPED; IO_SIZE];
+        table[(JOYPAD_INPUT_ADDRESS - IO_BEGIN) as usize] = IoHandler {
+            read: |gb, _| Joypad::read(gb),
+            write: |gb, _, value| Joypad::write(gb, value),
+        };
+        table[(SERIAL_DATA_ADDRESS - IO_BEGIN) as usize] = STORED;
+        table[(SERIAL_CONTROL_ADDRESS - IO_BEGIN) as usize] = STORED;
+        table[(DIV_ADDRESS - IO_BEGIN) as usize] = IoHandler {
+            // DIV value is 8 upper bits
+            read: |gb, _| IO::get_div_register(gb),
+            // Writing DIV reset it
+            write: |gb, _, _| gb.io.data[(DIV_ADDRESS - IO_BEGIN) as usize] = 0,
+        };
+        table[(TIMA_ADDRESS - IO_BEGIN) as usize] = STORED;
+        table[(TMA_ADDRESS - IO_BEGIN) as usize] = STORED;
+        table[(TAC_ADDRESS - IO_BEGIN) as usize] = STORED;
+        table[(INTERRUPT_FLAG_ADDRESS - IO_BEGIN) as usize] = IoHandler {
+            read: |gb, _| 0b1110_0000 | Interrupts::read_flag(gb),
+            write: |gb, _, value| Interrupts::write_flag(gb, value),
+        };
+        table[(BOOT_SWITCH_ADDRESS - IO_BEGIN) as usize] = IoHandler {
+            read: |_, _| 0xFF,
+            write: |gb, address, value| {
                 gb.io.data[(address - IO_BEGIN) as usize] = value;
                 MMU::set_boot_mapping(gb, value);
             },
-            INTERRUPT_FLAG_ADDRESS => {
-                Interrupts::write_flag(gb, value);
+        };
+
+        table[(DEBUG_OUTPUT_ADDRESS - IO_BEGIN) as usize] = IoHandler {
+            read: IO::read_unmapped,
+            write: |gb, address, value| match gb.developer.enabled {
+                true => DeveloperMode::print(gb, value),
+                false => IO::write_unmapped(gb, address, value),
             },
-            _ => {
-                gb.io.data[(address - IO_BEGIN) as usize] = value;
-            }
+        };
+        table[(SPEED_SWITCH_ADDRESS - IO_BEGIN) as usize] = IoHandler { read: IO::read_speed_switch, write: IO::write_speed_switch };
+        table[(INFRARED_ADDRESS - IO_BEGIN) as usize] = IoHandler { read: IO::read_infrared, write: IO::write_infrared };
+
+        let mut address = HDMA_BEGIN;
+        while address <= HDMA_END {
+            table[(address - IO_BEGIN) as usize] = IoHandler { read: Hdma::read_byte, write: Hdma::write_byte };
+            address += 1;
+        }
+        let mut address = APU_BEGIN;
+        while address <= APU_END {
+            table[(address - IO_BEGIN) as usize] = IoHandler { read: APU::read_byte, write: APU::write_byte };
+            address += 1;
         }
+        let mut address = LCD_BEGIN;
+        while address <= LCD_END {
+            table[(address - IO_BEGIN) as usize] = IoHandler { read: LCD::read_byte, write: LCD::write_byte };
+            address += 1;
+        }
+        table
+    }
+
+    // Registers without side effects are kept in data, their unused bits read as 1
+    fn read_stored(gb: &GameBoy, address: Address) -> u8 {
+        let unused = match address {
+            SERIAL_CONTROL_ADDRESS => 0b0111_1110,
+            TAC_ADDRESS => 0b1111_1000,
+            _ => 0,
+        };
+        gb.io.data[(address - IO_BEGIN) as usize] | unused
+    }
+
+    fn write_stored(gb: &mut GameBoy, address: Address, value: u8) {
+        gb.io.data[(address - IO_BEGIN) as usize] = value;
+    }
+
+    // Nothing drives the bus for registers that don't exist on DMG
+    pub(super) fn read_unmapped(gb: &GameBoy, address: Address) -> u8 {
+        if gb.io.unknown_policy == UnknownIoPolicy::Break {
+            gb.io.unknown_read.set(Some(address));
+        }
+        0xFF
+    }
+
+    pub(super) fn write_unmapped(gb: &mut GameBoy, address: Address, value: u8) {
+        tracing::debug!(target: "yagabor::mmu", address, value, pc = gb.cpu.pc, "write to an unimplemented IO register");
+        match gb.io.unknown_policy {
+            UnknownIoPolicy::Ignore => {},
+            UnknownIoPolicy::LogOnce => {
+                let index = (address - IO_BEGIN) as usize;
+                if !gb.io.unknown_reported[index] {
+                    gb.io.unknown_reported[index] = true;
+                    tracing::warn!(target: "yagabor::mmu", "Write {:02X} to unimplemented IO register {:04X} at PC {:04X}", value, address, gb.cpu.pc);
+                }
+            },
+            UnknownIoPolicy::Break => gb.io.unknown_write = Some((address, value)),
+        }
+    }
+
+    // KEY1, bit 7 is the current speed and bit 0 arms the switch done by the next STOP.
+    // Both are kept in data, so savestates have them.
+    fn read_speed_switch(gb: &GameBoy, address: Address) -> u8 {
+        if !gb.cgb_mode() {
+            return IO::read_unmapped(gb, address);
+        }
+        gb.io.data[(address - IO_BEGIN) as usize] | 0x7E
+    }
+
+    fn write_speed_switch(gb: &mut GameBoy, address: Address, value: u8) {
+        if !gb.cgb_mode() {
+            return IO::write_unmapped(gb, address, value);
+        }
+        let index = (address - IO_BEGIN) as usize;
+        gb.io.data[index] = (gb.io.data[index] & 0x80) | (value & 0x01);
+    }
+
+    pub(crate) fn double_speed(gb: &GameBoy) -> bool {
+        gb.io.data[(SPEED_SWITCH_ADDRESS - IO_BEGIN) as usize] & 0x80 != 0
+    }
+
+    pub(crate) fn speed_switch_armed(gb: &GameBoy) -> bool {
+        gb.io.data[(SPEED_SWITCH_ADDRESS - IO_BEGIN) as usize] & 0x01 != 0
+    }
+
+    // Done by STOP. DIV is reset like by a write, the pause of the CPU while
+    // the clock settles is not emulated.
+    pub(crate) fn switch_speed(gb: &mut GameBoy) {
+        let index = (SPEED_SWITCH_ADDRESS - IO_BEGIN) as usize;
+        gb.io.data[index] = (gb.io.data[index] ^ 0x80) & 0x80;
+        IO::reset_div(gb);
+    }
+
+    // Also done by STOP
+    pub(crate) fn reset_div(gb: &mut GameBoy) {
+        gb.io.data[(DIV_ADDRESS - IO_BEGIN) as usize] = 0;
+        gb.io.timers.div_counter = 0;
+    }
+
+    // RP is a CGB register, it only answers when a transceiver is connected.
+    // Bit 0 is the LED, bits 6-7 enable the sensor and bit 1 is 0 while it sees light.
+    fn read_infrared(gb: &GameBoy, address: Address) -> u8 {
+        let Some(transceiver) = &gb.ir_transceiver else {
+            return IO::read_unmapped(gb, address);
+        };
+        let value = gb.io.data[(address - IO_BEGIN) as usize];
+        let dark = value & 0xC0 != 0xC0 || !transceiver.receiving();
+        (value & 0xC1) | 0x3C | if dark { 0x02 } else { 0x00 }
+    }
+
+    fn write_infrared(gb: &mut GameBoy, address: Address, value: u8) {
+        let Some(transceiver) = &mut gb.ir_transceiver else {
+            return IO::write_unmapped(gb, address, value);
+        };
+        let index = (address - IO_BEGIN) as usize;
+        let led = value & 0x01 != 0;
+        if led != (gb.io.data[index] & 0x01 != 0) {
+            transceiver.set_led(led);
+        }
+        gb.io.data[index] = value & 0xC1;
+    }
+
+    pub(crate) fn get_serial_data(gb: &GameBoy) -> u8 {
+        gb.io.data[(SERIAL_DATA_ADDRESS - IO_BEGIN) as usize]
+    }
+
+    pub(crate) fn get_serial_control(gb: &GameBoy) -> u8 {
+        gb.io.data[(SERIAL_CONTROL_ADDRESS - IO_BEGIN) as usize]
     }
 
     pub(crate) fn serial_control_clear(gb: &mut GameBoy) {
@@ -104,6 +316,11 @@ impl IO {
         gb.io.data[(TIMA_ADDRESS - IO_BEGIN) as usize] = tma;
     }
 
+    // Byte shifted in from the device on the other side of the link cable
+    pub(crate) fn receive_serial(gb: &mut GameBoy, value: u8) {
+        gb.io.data[(SERIAL_DATA_ADDRESS - IO_BEGIN) as usize] = value;
+    }
+
     pub(crate) fn ack_sent_serial(gb: &mut GameBoy){
         Interrupts::turnon(gb, Interruption::Serial);
         IO::serial_control_clear(gb);
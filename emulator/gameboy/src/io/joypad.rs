@@ -1,4 +1,4 @@
-use crate::{gameboy::GameBoy, Button};
+use crate::{gameboy::GameBoy, savestate::{StateReader, StateWriter}, Button};
 
 #[derive(Debug)]
 pub(crate) struct Joypad {
@@ -38,30 +38,51 @@ impl Joypad {
         }
     }
 
+    pub(crate) fn save_state(gb: &GameBoy, writer: &mut StateWriter) {
+        let state = &gb.io.joypad.state;
+        writer.u8(gb.io.joypad.register);
+        for pressed in [state.a, state.b, state.start, state.select, state.up, state.down, state.left, state.right] {
+            writer.bool(pressed);
+        }
+    }
+
+    pub(crate) fn load_state(gb: &mut GameBoy, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        gb.io.joypad.register = reader.u8()?;
+        let state = &mut gb.io.joypad.state;
+        for pressed in [&mut state.a, &mut state.b, &mut state.start, &mut state.select, &mut state.up, &mut state.down, &mut state.left, &mut state.right] {
+            *pressed = reader.bool()?;
+        }
+        Ok(())
+    }
+
     // Depending on selector we return a set of button states as u8
-    // http://imrannazar.com/GameBoy-Emulation-in-JavaScript:-Input
+    // https://gbdev.io/pandocs/Joypad_Input.html
     pub(crate) fn read(gb: &GameBoy) -> u8 {
         let jp = &gb.io.joypad;
         let selector = jp.register & 0x30;
-        let mut result = selector | 0b1100_0000;
-        // Buttons states are negated because 0 is interpreted as pressed and 1 as released
-        if selector & 0x20 != 0 {
-            let buttons: u8 =   ((!jp.state.down as u8) << 3) + 
-                                ((!jp.state.up as u8) << 2) + 
-                                ((!jp.state.left as u8) << 1) + 
-                                ((!jp.state.right as u8));
-            result |= buttons & 0x0F;      
-        }else if selector & 0x10 != 0 {
-            let buttons: u8 =   ((!jp.state.start as u8) << 3) + 
-                                ((!jp.state.select as u8) << 2) + 
-                                ((!jp.state.b as u8) << 1) + 
-                                ((!jp.state.a as u8));
-            result |= buttons & 0x0F;
-        }else if selector == 0 {
-            result |= 0x0F;
+        // Buttons states are negated because 0 is interpreted as pressed and 1 as released.
+        // A cleared bit 4 selects the directions and a cleared bit 5 the buttons, with
+        // both selected a line reads as pressed when either button on it is.
+        let mut lines = 0x0F;
+        if selector & 0x10 == 0 {
+            lines &= ((!jp.state.down as u8) << 3) |
+                     ((!jp.state.up as u8) << 2) |
+                     ((!jp.state.left as u8) << 1) |
+                     (!jp.state.right as u8);
         }
+        if selector & 0x20 == 0 {
+            lines &= ((!jp.state.start as u8) << 3) |
+                     ((!jp.state.select as u8) << 2) |
+                     ((!jp.state.b as u8) << 1) |
+                     (!jp.state.a as u8);
+        }
+        // The upper two bits don't exist and read as 1
+        0b1100_0000 | selector | lines
+    }
 
-        result
+    // A pressed button on a selected line ends STOP
+    pub(crate) fn wakes_from_stop(gb: &GameBoy) -> bool {
+        Joypad::read(gb) & 0x0F != 0x0F
     }
 
     pub(crate) fn write(gb: &mut GameBoy, value: u8) {
@@ -1,6 +1,10 @@
-use crate::{mmu::{Address, VRAM_BEGIN, MMU}, cpu::cpu::ClockCycles, gameboy::GameBoy, ppu::{PPU, BGMAP0_ADDRESS, BGMAP1_ADDRESS, TilePixelValue}, SCREEN_WIDTH, SCREEN_HEIGHT, TILEDATA_HEIGHT, TILEDATA_WIDTH, BACKGROUND_HEIGHT, BACKGROUND_WIDTH, ColoredPixel, GameBoyFrame};
+use crate::{mmu::{Address, VRAM_BEGIN}, cpu::cpu::ClockCycles, gameboy::GameBoy, ppu::{PPU, BGMAP0_ADDRESS, BGMAP1_ADDRESS, TilePixelValue}, SCREEN_WIDTH, SCREEN_HEIGHT, TILEDATA_HEIGHT, TILEDATA_WIDTH, BACKGROUND_HEIGHT, BACKGROUND_WIDTH, ColoredPixel, GameBoyFrame};
 
-use super::interrupts::{Interrupts, Interruption};
+use std::borrow::Cow;
+
+use crate::{debugger::MemoryWatches, display, recorder::Recorder, savestate::{StateReader, StateWriter}};
+
+use super::{hdma::Hdma, interrupts::{Interrupts, Interruption}, oamscan::OamScan};
 
 pub(crate) const TILE_SIZE: u32 = 8;
 
@@ -36,11 +40,39 @@ pub(crate) struct LCD {
     clock: u16,
     mode: LCDMode,
     scanline: u8,
+    lyc: u8,
+    // Interrupt selects of STAT, bits 3-6
+    stat: u8,
+    // OR of the selected STAT sources, the interrupt is requested when it goes up
+    stat_line: bool,
     scy: u8,
     scx: u8,
     bgpalette: Palette,
-    // Renders
+    // OBP0 and OBP1, color 0 is transparent for sprites
+    obpalettes: [Palette; 2],
+    wy: u8,
+    wx: u8,
+    // Registers used for each line of the frame being drawn and of the last complete one
+    line_registers: [ScanlineRegisters; SCREEN_HEIGHT as usize],
+    pub(crate) last_frame_registers: [ScanlineRegisters; SCREEN_HEIGHT as usize],
+    // The CPU can't reach VRAM while a line is drawn, nor OAM while it is scanned
+    pub(crate) block_cpu_access: bool,
+    // Garble OAM on 16-bit increments of pointers into it during mode 2
+    pub(crate) oam_bug: bool,
+    pub(crate) off_display: LcdOffDisplay,
+    // Screen when the game turned the LCD off
+    off_frame: Option<GameBoyFrame>,
+    // The first frame after the LCD is turned on is not shown by the panel
+    first_frame: bool,
+    // Lines the frame would be at while the LCD is off, frames keep their pace
+    off_lines: u8,
+    // Double buffered screen: the PPU draws into back and the buffers are
+    // swapped when the frame is complete, so the last frame can be lent out
+    // while the next one is drawn.
     screen: GameBoyFrame,
+    back: GameBoyFrame,
+    // The two frames before the screen, for captures that merge flickering sprites
+    previous: [GameBoyFrame; 2],
     tiledata: GameBoyFrame,
     background: GameBoyFrame
 }
@@ -76,11 +108,63 @@ impl std::convert::From<u8> for ColoredPixel {
     }
 }
 
-#[derive(Clone, Debug)]
-pub(crate) enum LCDMode {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LCDMode {
     SearchingOAM, Transfering, HBlank, VBlank 
 }
 
+impl LCDMode {
+    // Dots spent in the mode, for VBlank it is per scanline
+    fn duration(&self) -> u16 {
+        match self {
+            LCDMode::SearchingOAM => CLOCKS_SEARCHING_OAM,
+            LCDMode::Transfering => CLOCKS_TRANSFERING,
+            LCDMode::HBlank => CLOCKS_HBLANK,
+            LCDMode::VBlank => CLOCKS_VBLANK,
+        }
+    }
+
+    // Value of the two lower bits of STAT
+    pub(crate) fn stat_bits(&self) -> u8 {
+        match self {
+            LCDMode::HBlank => 0,
+            LCDMode::VBlank => 1,
+            LCDMode::SearchingOAM => 2,
+            LCDMode::Transfering => 3,
+        }
+    }
+}
+
+// What the frontend shows while the game keeps the LCD off, a real DMG shows white
+// but long loading periods then look like a crash
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LcdOffDisplay {
+    #[default]
+    White,
+    LastFrame,
+    // The last frame one shade lighter, like the panel fading out
+    Dimmed,
+}
+
+// Where the PPU is inside the frame, so HBlank and VBlank synchronized code can be checked
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PpuTiming {
+    pub mode: LCDMode,
+    pub scanline: u8,
+    pub remaining_dots: u16,
+}
+
+// Values the renderer saw when drawing a line, so raster effects can be checked
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScanlineRegisters {
+    pub scx: u8,
+    pub scy: u8,
+    pub wx: u8,
+    pub wy: u8,
+    pub lcdc: u8,
+    pub bgp: u8,
+}
+
 pub(crate) enum LCDControl {
     Power, 
     WindowTileMap, 
@@ -99,7 +183,7 @@ pub(crate) struct Palette {
 }
 
 impl Palette {
-    fn apply(&self, p: TilePixelValue) -> ColoredPixel {
+    pub(crate) fn apply(&self, p: TilePixelValue) -> ColoredPixel {
         match p {
             TilePixelValue::Zero => self.index0,
             TilePixelValue::One => self.index1,
@@ -131,25 +215,47 @@ impl LCD {
         LCD { 
             control:0, 
             clock: 0, 
-            mode: LCDMode::SearchingOAM , 
+            mode: LCDMode::HBlank, 
+            block_cpu_access: false,
+            oam_bug: false,
+            off_display: LcdOffDisplay::default(),
+            off_frame: None,
+            first_frame: false,
+            off_lines: 0,
             scanline: 0, 
+            lyc: 0,
+            stat: 0,
+            stat_line: false,
             scy: 0, 
             scx: 0, 
             bgpalette: Palette::from(0), 
+            obpalettes: [Palette::from(0); 2],
+            wy: 0,
+            wx: 0,
+            line_registers: [ScanlineRegisters::default(); SCREEN_HEIGHT as usize],
+            last_frame_registers: [ScanlineRegisters::default(); SCREEN_HEIGHT as usize],
             screen: GameBoyFrame::new(SCREEN_WIDTH, SCREEN_HEIGHT, vec![ColoredPixel::Black; (SCREEN_WIDTH*SCREEN_HEIGHT) as usize]),
+            back: GameBoyFrame::new(SCREEN_WIDTH, SCREEN_HEIGHT, vec![ColoredPixel::Black; (SCREEN_WIDTH*SCREEN_HEIGHT) as usize]),
+            previous: std::array::from_fn(|_| GameBoyFrame::new(SCREEN_WIDTH, SCREEN_HEIGHT, vec![ColoredPixel::Black; (SCREEN_WIDTH*SCREEN_HEIGHT) as usize])),
             // For debug
-            tiledata: GameBoyFrame::new(SCREEN_WIDTH, SCREEN_HEIGHT, vec![ColoredPixel::Black; (TILEDATA_WIDTH*TILEDATA_HEIGHT) as usize]),
-            background: GameBoyFrame::new(SCREEN_WIDTH, SCREEN_HEIGHT, vec![ColoredPixel::Black; (BACKGROUND_WIDTH*BACKGROUND_HEIGHT) as usize]),
+            tiledata: GameBoyFrame::new(TILEDATA_WIDTH, TILEDATA_HEIGHT, vec![ColoredPixel::Black; (TILEDATA_WIDTH*TILEDATA_HEIGHT) as usize]),
+            background: GameBoyFrame::new(BACKGROUND_WIDTH, BACKGROUND_HEIGHT, vec![ColoredPixel::Black; (BACKGROUND_WIDTH*BACKGROUND_HEIGHT) as usize]),
         }
     }
 
     // https://gbdev.io/pandocs/STAT.html#stat-modes
     pub(crate) fn tick(gb: &mut GameBoy, cycles: ClockCycles) {
+        // The PPU idles with LY at 0 while the LCD is off
+        if !LCD::read_control(gb, LCDControl::Power) {
+            LCD::tick_off(gb, cycles);
+            return;
+        }
         gb.io.lcd.clock = gb.io.lcd.clock.wrapping_add(cycles);
 
         match LCD::mode(gb) {
             LCDMode::SearchingOAM => {
                 if LCD::clock(gb) >= CLOCKS_SEARCHING_OAM {
+                    OamScan::scan_line(gb);
                     LCD::reset_clock(gb);
                     LCD::start_mode(gb, LCDMode::Transfering);
                 }
@@ -158,7 +264,9 @@ impl LCD {
                 if LCD::clock(gb) >= CLOCKS_TRANSFERING {
                     LCD::reset_clock(gb);
                     LCD::start_mode(gb, LCDMode::HBlank);
+                    LCD::capture_registers(gb);
                     LCD::render_scanline(gb);
+                    Hdma::hblank(gb);
                 }
             },
             LCDMode::HBlank => {
@@ -166,12 +274,12 @@ impl LCD {
                     LCD::reset_clock(gb);
                     LCD::next_scanline(gb);
 
-                    if LCD::read_scanline(gb) == 143 {
+                    if LCD::read_scanline(gb) == SCREEN_HEIGHT as u8 {
+                        gb.io.lcd.last_frame_registers = gb.io.lcd.line_registers;
                         Interrupts::turnon(gb, Interruption::VBlank);
                         LCD::start_mode(gb, LCDMode::VBlank);
-                        // Debug
-                        LCD::render_tiledata(gb);
-                        LCD::render_background(gb);
+                        OamScan::end_frame(gb);
+                        LCD::end_frame(gb);
                     }else{
                         LCD::start_mode(gb, LCDMode::SearchingOAM);
                     }
@@ -185,10 +293,144 @@ impl LCD {
                     if LCD::read_scanline(gb) > 153 {
                         LCD::start_mode(gb, LCDMode::SearchingOAM);
                         LCD::reset_scanline(gb);
+                        gb.io.lcd.first_frame = false;
                     }
                 }
             },
         }
+        LCD::update_stat_line(gb);
+    }
+
+    // Pending writes, observers and recordings still see a frame go by every
+    // 154 lines, without the VBlank interrupt
+    fn tick_off(gb: &mut GameBoy, cycles: ClockCycles) {
+        let lcd = &mut gb.io.lcd;
+        lcd.clock = lcd.clock.wrapping_add(cycles);
+        if lcd.clock < CLOCKS_VBLANK {
+            return;
+        }
+        lcd.clock -= CLOCKS_VBLANK;
+        lcd.off_lines += 1;
+        if lcd.off_lines == SCREEN_HEIGHT as u8 {
+            LCD::end_frame(gb);
+        } else if lcd.off_lines > 153 {
+            lcd.off_lines = 0;
+        }
+    }
+
+    fn end_frame(gb: &mut GameBoy) {
+        tracing::trace!(target: "yagabor::ppu", "end of frame");
+        gb.apply_pending_writes();
+        if LCD::read_control(gb, LCDControl::Power) {
+            // The oldest frame is drawn over next, the others move down the history
+            let lcd = &mut gb.io.lcd;
+            let [previous, before] = &mut lcd.previous;
+            std::mem::swap(&mut lcd.back, before);
+            std::mem::swap(before, previous);
+            std::mem::swap(previous, &mut lcd.screen);
+        }
+        if gb.observer.is_some() || gb.recorder.is_some() {
+            // What the panel shows, which is not the PPU output while it is off
+            let screen = LCD::screen(gb).into_owned();
+            let stable = gb.recorder.as_ref().is_some_and(Recorder::dedups_flicker).then(|| LCD::stable_screen(gb).into_owned());
+            if let Some(observer) = &mut gb.observer {
+                observer.on_vblank(&screen);
+            }
+            if let Some(recorder) = &mut gb.recorder {
+                recorder.capture(stable.as_ref().unwrap_or(&screen));
+            }
+        }
+        if let Some(log) = &mut gb.io_log {
+            log.next_frame();
+        }
+        MemoryWatches::notify(gb);
+        // Debug
+        LCD::render_tiledata(gb);
+        LCD::render_background(gb);
+    }
+
+    // https://gbdev.io/pandocs/STAT.html#ff41--stat-lcd-status
+    // Sources that stay selected keep the line up, so a second one doesn't fire
+    // another interrupt until the line goes down (STAT blocking). The line is
+    // held low while the LCD is off.
+    fn update_stat_line(gb: &mut GameBoy) {
+        let power = LCD::read_control(gb, LCDControl::Power);
+        let lcd = &gb.io.lcd;
+        let line = power && ((lcd.stat & 0x40 != 0 && lcd.scanline == lcd.lyc)
+            || (lcd.stat & 0x20 != 0 && lcd.mode == LCDMode::SearchingOAM)
+            || (lcd.stat & 0x10 != 0 && lcd.mode == LCDMode::VBlank)
+            || (lcd.stat & 0x08 != 0 && lcd.mode == LCDMode::HBlank));
+        if line && !lcd.stat_line {
+            Interrupts::turnon(gb, Interruption::LCDStat);
+        }
+        gb.io.lcd.stat_line = line;
+    }
+
+    fn read_stat(gb: &GameBoy) -> u8 {
+        let lcd = &gb.io.lcd;
+        let coincidence = if lcd.scanline == lcd.lyc { 0x04 } else { 0x00 };
+        // Bit 7 is unused and always reads as set
+        0x80 | lcd.stat | coincidence | lcd.mode.stat_bits()
+    }
+
+    pub(crate) fn save_state(gb: &GameBoy, writer: &mut StateWriter) {
+        let lcd = &gb.io.lcd;
+        writer.u8(lcd.control);
+        writer.u16(lcd.clock);
+        writer.u8(lcd.mode.stat_bits());
+        writer.u8(lcd.scanline);
+        writer.u8(lcd.lyc);
+        writer.u8(lcd.stat);
+        writer.bool(lcd.stat_line);
+        writer.bool(lcd.first_frame);
+        writer.u8(lcd.off_lines);
+        writer.u8(lcd.scy);
+        writer.u8(lcd.scx);
+        writer.u8(u8::from(lcd.bgpalette));
+        writer.u8(u8::from(lcd.obpalettes[0]));
+        writer.u8(u8::from(lcd.obpalettes[1]));
+        writer.u8(lcd.wy);
+        writer.u8(lcd.wx);
+        // The frame being drawn, the complete one is the same until the next swap
+        for pixel in &lcd.back.buffer {
+            writer.u8(u8::from(*pixel));
+        }
+    }
+
+    pub(crate) fn load_state(gb: &mut GameBoy, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        let lcd = &mut gb.io.lcd;
+        lcd.control = reader.u8()?;
+        lcd.clock = reader.u16()?;
+        lcd.mode = match reader.u8()? {
+            0 => LCDMode::HBlank,
+            1 => LCDMode::VBlank,
+            2 => LCDMode::SearchingOAM,
+            _ => LCDMode::Transfering,
+        };
+        lcd.scanline = reader.u8()?;
+        lcd.lyc = reader.u8()?;
+        lcd.stat = reader.u8()?;
+        lcd.stat_line = reader.bool()?;
+        lcd.first_frame = reader.bool()?;
+        lcd.off_lines = reader.u8()?;
+        lcd.scy = reader.u8()?;
+        lcd.scx = reader.u8()?;
+        lcd.bgpalette = Palette::from(reader.u8()?);
+        lcd.obpalettes = [Palette::from(reader.u8()?), Palette::from(reader.u8()?)];
+        lcd.wy = reader.u8()?;
+        lcd.wx = reader.u8()?;
+        for pixel in lcd.back.buffer.iter_mut() {
+            *pixel = ColoredPixel::from(reader.u8()?);
+        }
+        lcd.screen.buffer.copy_from_slice(&lcd.back.buffer);
+        Ok(())
+    }
+
+    fn capture_registers(gb: &mut GameBoy) {
+        let lcd = &mut gb.io.lcd;
+        if let Some(line) = lcd.line_registers.get_mut(lcd.scanline as usize) {
+            *line = ScanlineRegisters { scx: lcd.scx, scy: lcd.scy, wx: lcd.wx, wy: lcd.wy, lcdc: lcd.control, bgp: u8::from(lcd.bgpalette) };
+        }
     }
 
     pub(crate) fn render_scanline(gb: &mut GameBoy) {
@@ -199,6 +441,7 @@ impl LCD {
 
         let lcd = &mut gb.io.lcd;
         let ppu = &gb.ppu;
+        let replacements = gb.tile_replacements.as_ref();
 
         let mut scan_line: [TilePixelValue; SCREEN_WIDTH as usize] = [Default::default(); SCREEN_WIDTH as usize];
         
@@ -229,13 +472,14 @@ impl LCD {
             // Start at the beginning of the line and go pixel by pixel
             for line_x in 0..SCREEN_WIDTH as usize {
                 // Grab the tile index specified in the tile map
-                let tile_index = ppu.vram[tile_map_offset + tile_x_index as usize];
+                // The background wraps around after 32 tiles
+                let tile_index = ppu.vram[tile_map_offset + (tile_x_index % 32) as usize];
 
-                let tile_value = ppu.tile_set[tile_index as usize][row_y_offset as usize]
-                    [pixel_x_index as usize];
+                let tile_value = replacements.and_then(|tiles| tiles.pixel(tile_index as usize, pixel_x_index, row_y_offset))
+                    .unwrap_or(ppu.tile_set[tile_index as usize][row_y_offset as usize][pixel_x_index as usize]);
                 let color: ColoredPixel = lcd.bgpalette.apply(tile_value);
 
-                lcd.screen.buffer[canvas_buffer_offset] = color;
+                lcd.back.buffer[canvas_buffer_offset] = color;
                 canvas_buffer_offset += 1;
                 scan_line[line_x] = tile_value;
                 // Loop through the 8 pixels within the tile
@@ -244,16 +488,59 @@ impl LCD {
                 // Check if we've fully looped through the tile
                 if pixel_x_index == 0 {
                     // Now increase the tile x_offset by 1
-                    tile_x_index = tile_x_index + 1;
+                    tile_x_index = tile_x_index.wrapping_add(1);
                 }
 
             }
+        } else {
+            // The line is blank, the buffer still holds an older frame
+            let start = lcd.scanline as usize * SCREEN_WIDTH as usize;
+            lcd.back.buffer[start..start + SCREEN_WIDTH as usize].fill(ColoredPixel::White);
         }
-        
+
+        if let Some(hook) = &mut gb.scanline_hook {
+            let line = gb.io.lcd.scanline;
+            let start = line as usize * SCREEN_WIDTH as usize;
+            hook.on_scanline(line, &mut gb.io.lcd.back.buffer[start..start + SCREEN_WIDTH as usize]);
+        }
+    }
+
+    // The screen of a stopped Game Boy is shown like one with the LCD off
+    pub(crate) fn freeze_screen(gb: &mut GameBoy) {
+        if LCD::read_control(gb, LCDControl::Power) {
+            gb.io.lcd.off_frame = Some(gb.io.lcd.screen.clone());
+        }
+    }
+
+    fn shows_ppu_output(gb: &GameBoy) -> bool {
+        LCD::read_control(gb, LCDControl::Power) && !gb.cpu.is_stopped && !gb.io.lcd.first_frame
+    }
+
+    // The screen with the sprites flickering over the last frames merged
+    pub(crate) fn stable_screen(gb: &GameBoy) -> Cow<'_, GameBoyFrame> {
+        let lcd = &gb.io.lcd;
+        if !LCD::shows_ppu_output(gb) {
+            return LCD::screen(gb);
+        }
+        Cow::Owned(display::merge_flicker(&lcd.screen, &lcd.previous[0], &lcd.previous[1]))
     }
 
-    pub(crate) fn screen_buffer(gb: &GameBoy) -> GameBoyFrame {
-        gb.io.lcd.screen.clone()
+    // The last complete frame is lent, only the off displays that don't show
+    // it as it is are built
+    pub(crate) fn screen(gb: &GameBoy) -> Cow<'_, GameBoyFrame> {
+        let lcd = &gb.io.lcd;
+        if LCD::shows_ppu_output(gb) {
+            return Cow::Borrowed(&lcd.screen);
+        }
+        let last = lcd.off_frame.as_ref().unwrap_or(&lcd.screen);
+        match lcd.off_display {
+            LcdOffDisplay::White => Cow::Owned(GameBoyFrame::new(SCREEN_WIDTH, SCREEN_HEIGHT, vec![ColoredPixel::White; (SCREEN_WIDTH*SCREEN_HEIGHT) as usize])),
+            LcdOffDisplay::LastFrame => Cow::Borrowed(last),
+            LcdOffDisplay::Dimmed => {
+                let buffer = last.buffer.iter().map(|pixel| ColoredPixel::from(u8::from(*pixel).saturating_sub(1))).collect();
+                Cow::Owned(GameBoyFrame::new(last.width, last.height, buffer))
+            },
+        }
     }
 
     pub(crate) fn tiledata_buffer(gb: &GameBoy) -> GameBoyFrame {
@@ -272,22 +559,31 @@ impl LCD {
     }
 
     pub(crate) fn render_background(gb: &mut GameBoy) {
-        let tiles = PPU::tile_set(gb);
-        let mut tiles_bg = vec![[[TilePixelValue::Zero; 8]; 8]; BACKGROUND_COLS*BACKGROUND_ROWS];
-
         let bg_map_address = LCD::background_tile_map(gb);
+        gb.io.lcd.background = LCD::render_tile_map(gb, bg_map_address);
+    }
+
+    // The window is only needed by exports, so it is rendered on demand
+    pub(crate) fn window_buffer(gb: &GameBoy) -> GameBoyFrame {
+        LCD::render_tile_map(gb, LCD::window_tile_map(gb))
+    }
+
+    // Renders the full 32x32 tiles map starting at map_address
+    pub(crate) fn render_tile_map(gb: &GameBoy, map_address: Address) -> GameBoyFrame {
+        let tiles = PPU::tile_set(gb);
+        let mut tiles_map = vec![[[TilePixelValue::Zero; 8]; 8]; BACKGROUND_COLS*BACKGROUND_ROWS];
 
         for x in 0..BACKGROUND_COLS {
             for y in 0..BACKGROUND_ROWS {
                 let tile_idx: usize = y*BACKGROUND_COLS + x;
-                let tile = MMU::read_byte(gb, bg_map_address + tile_idx as Address) as usize;
-                tiles_bg[tile_idx] = tiles[tile];
+                let tile = PPU::read_vram(gb, map_address + tile_idx as Address) as usize;
+                tiles_map[tile_idx] = tiles[tile];
             }
         }
 
-        let bgbuffer = LCD::tiles_to_buffer(gb, &tiles_bg, BACKGROUND_WIDTH, BACKGROUND_HEIGHT);
+        let buffer = LCD::tiles_to_buffer(gb, &tiles_map, BACKGROUND_WIDTH, BACKGROUND_HEIGHT);
 
-        gb.io.lcd.background = GameBoyFrame::new(BACKGROUND_WIDTH, BACKGROUND_HEIGHT, bgbuffer);
+        GameBoyFrame::new(BACKGROUND_WIDTH, BACKGROUND_HEIGHT, buffer)
     }
 
     pub(crate) fn tiles_to_buffer(gb: &GameBoy, tiles: &Vec<[[TilePixelValue; 8]; 8]>, width: u32, height: u32) -> Vec<ColoredPixel> {
@@ -311,18 +607,52 @@ impl LCD {
     }
 
     pub(crate) fn mode(gb: &GameBoy) -> LCDMode {
-        gb.io.lcd.mode.clone()
+        gb.io.lcd.mode
+    }
+
+    // https://gbdev.io/pandocs/Accessing_VRAM_and_OAM.html
+    pub(crate) fn vram_blocked(gb: &GameBoy) -> bool {
+        LCD::cpu_access_checked(gb) && gb.io.lcd.mode == LCDMode::Transfering
+    }
+
+    pub(crate) fn oam_blocked(gb: &GameBoy) -> bool {
+        LCD::cpu_access_checked(gb) && matches!(gb.io.lcd.mode, LCDMode::SearchingOAM | LCDMode::Transfering)
+    }
+
+    // Everything is reachable while the LCD is off
+    fn cpu_access_checked(gb: &GameBoy) -> bool {
+        gb.io.lcd.block_cpu_access && LCD::read_control(gb, LCDControl::Power)
+    }
+
+    pub(crate) fn timing(gb: &GameBoy) -> PpuTiming {
+        let mode = LCD::mode(gb);
+        PpuTiming {
+            mode,
+            scanline: LCD::read_scanline(gb),
+            remaining_dots: mode.duration().saturating_sub(LCD::clock(gb)),
+        }
     }
 
     pub(crate) fn clock(gb: &GameBoy) -> u16 {
         gb.io.lcd.clock
     }
 
+    // Position inside the current line of 456 dots
+    pub(crate) fn dot(gb: &GameBoy) -> u16 {
+        let before = match gb.io.lcd.mode {
+            LCDMode::SearchingOAM | LCDMode::VBlank => 0,
+            LCDMode::Transfering => CLOCKS_SEARCHING_OAM,
+            LCDMode::HBlank => CLOCKS_SEARCHING_OAM + CLOCKS_TRANSFERING,
+        };
+        before + gb.io.lcd.clock
+    }
+
     pub(crate) fn reset_clock(gb: &mut GameBoy) {
         gb.io.lcd.clock = 0;
     }
 
     pub(crate) fn start_mode(gb: &mut GameBoy, mode: LCDMode) {
+        tracing::trace!(target: "yagabor::ppu", ?mode, ly = gb.io.lcd.scanline);
         gb.io.lcd.mode = mode;
     }
 
@@ -348,6 +678,14 @@ impl LCD {
         }
     }
 
+    pub(crate) fn window_tile_map(gb: &GameBoy) -> Address {
+        if LCD::read_control(gb, LCDControl::WindowTileMap) {
+            BGMAP1_ADDRESS
+        } else {
+            BGMAP0_ADDRESS
+        }
+    }
+
     pub(crate) fn read_control(gb: &GameBoy, parameter: LCDControl) -> bool {
         match parameter {
             LCDControl::Power               => (gb.io.lcd.control & 0b10000000) > 0, 
@@ -380,18 +718,60 @@ impl LCD {
             LCD_SCY_ADDRESS => { gb.io.lcd.scy },
             LCD_SCX_ADDRESS => { gb.io.lcd.scx },
             LCD_CONTROL_ADDRESS => { gb.io.lcd.control },
+            LCD_STATUS_ADDRESS => { LCD::read_stat(gb) },
+            LCD_LYC_ADDRESS => { gb.io.lcd.lyc },
             LCD_BGPALETTE_ADDRESS => { u8::from(gb.io.lcd.bgpalette) },
-            _ => { 0 }
+            LCD_OBP0_ADDRESS => { u8::from(gb.io.lcd.obpalettes[0]) },
+            LCD_OBP1_ADDRESS => { u8::from(gb.io.lcd.obpalettes[1]) },
+            LCD_WY_ADDRESS => { gb.io.lcd.wy },
+            LCD_WX_ADDRESS => { gb.io.lcd.wx },
+            _ => { 0xFF }
         }
     }
 
     pub(crate) fn write_byte(gb: &mut GameBoy, address: Address, value: u8) {
         match address {
-            LCD_LY_ADDRESS => { gb.io.lcd.scanline = value },
+            // LY is read-only, writing it would move the LCD past the last scanline
+            LCD_LY_ADDRESS => {},
+            // Mode and coincidence are read-only
+            LCD_STATUS_ADDRESS => {
+                gb.io.lcd.stat = value & 0x78;
+                LCD::update_stat_line(gb);
+            },
+            LCD_LYC_ADDRESS => {
+                gb.io.lcd.lyc = value;
+                LCD::update_stat_line(gb);
+            },
             LCD_SCY_ADDRESS => { gb.io.lcd.scy = value },
             LCD_SCX_ADDRESS => { gb.io.lcd.scx = value },
-            LCD_CONTROL_ADDRESS => { gb.io.lcd.control = value },
+            LCD_CONTROL_ADDRESS => {
+                let lcd = &mut gb.io.lcd;
+                let (was_on, on) = (lcd.control & 0x80 != 0, value & 0x80 != 0);
+                lcd.control = value;
+                if was_on != on {
+                    tracing::debug!(target: "yagabor::ppu", on, ly = lcd.scanline, pc = gb.cpu.pc, "LCD power");
+                }
+                if was_on && !on {
+                    lcd.off_frame = Some(lcd.screen.clone());
+                    // STAT reads mode 0 and LY 0 until it is turned on again
+                    lcd.scanline = 0;
+                    lcd.clock = 0;
+                    lcd.mode = LCDMode::HBlank;
+                    lcd.stat_line = false;
+                    lcd.off_lines = 0;
+                } else if !was_on && on {
+                    // Starts at the beginning of line 0 without the off display going away
+                    lcd.mode = LCDMode::SearchingOAM;
+                    lcd.clock = 0;
+                    lcd.first_frame = true;
+                    LCD::update_stat_line(gb);
+                }
+            },
             LCD_BGPALETTE_ADDRESS => { gb.io.lcd.bgpalette = Palette::from(value) },
+            LCD_OBP0_ADDRESS => { gb.io.lcd.obpalettes[0] = Palette::from(value) },
+            LCD_OBP1_ADDRESS => { gb.io.lcd.obpalettes[1] = Palette::from(value) },
+            LCD_WY_ADDRESS => { gb.io.lcd.wy = value },
+            LCD_WX_ADDRESS => { gb.io.lcd.wx = value },
             _ => {}
         }
     }
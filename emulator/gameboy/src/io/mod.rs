@@ -1,5 +1,10 @@
+pub(crate) mod apu;
+pub(crate) mod hdma;
 pub(crate) mod io;
 pub(crate) mod interrupts;
 pub mod lcd;
+pub(crate) mod oambug;
+pub(crate) mod oamscan;
 pub(crate) mod timers;
-pub(crate) mod joypad;
\ No newline at end of file
+pub(crate) mod joypad;
+mod tests;
@@ -0,0 +1,75 @@
+use crate::{gameboy::GameBoy, mmu::{Address, OAM_BEGIN}};
+
+use super::lcd::{LCDControl, LCDMode, LCD};
+
+const ROW_SIZE: usize = 8;
+const ROWS: usize = 20;
+// The PPU scans a row of OAM every machine cycle of mode 2
+const DOTS_PER_ROW: u16 = 4;
+
+// https://gbdev.io/pandocs/OAM_Corruption_Bug.html
+// On DMG, a 16-bit increment or decrement of a pointer into FE00-FEFF while the
+// PPU scans OAM puts the address on the bus and garbles the row being scanned.
+// The row is a mix of itself and the row before, which usually comes out as
+// sprites flickering in the wrong place. Only the cases of INC rr, DEC rr and the
+// HL+ and HL- loads are emulated, PUSH and POP are not.
+pub(crate) struct OamBug;
+
+impl OamBug {
+    // INC rr, DEC rr and LD (HL+/-),A, before the register changes
+    pub(crate) fn write(gb: &mut GameBoy, address: Address) {
+        let Some(row) = OamBug::scanned_row(gb, address) else {
+            return;
+        };
+        let (a, b, c) = (OamBug::word(gb, row, 0), OamBug::word(gb, row - 1, 0), OamBug::word(gb, row - 1, 2));
+        OamBug::set_word(gb, row, 0, ((a ^ c) & (b ^ c)) ^ c);
+        OamBug::copy_tail(gb, row);
+    }
+
+    // LD A,(HL+/-), the read and the increment happen in the same cycle
+    pub(crate) fn read_increment(gb: &mut GameBoy, address: Address) {
+        let Some(row) = OamBug::scanned_row(gb, address) else {
+            return;
+        };
+        if (4..ROWS - 1).contains(&row) {
+            let (a, b) = (OamBug::word(gb, row - 2, 0), OamBug::word(gb, row - 1, 0));
+            let (c, d) = (OamBug::word(gb, row, 0), OamBug::word(gb, row - 1, 2));
+            OamBug::set_word(gb, row - 1, 0, (b & (a | c | d)) | (a & c & d));
+            let preceding: [u8; ROW_SIZE] = gb.ppu.oam[(row - 1) * ROW_SIZE..row * ROW_SIZE].try_into().unwrap();
+            gb.ppu.oam[row * ROW_SIZE..(row + 1) * ROW_SIZE].copy_from_slice(&preceding);
+            gb.ppu.oam[(row - 2) * ROW_SIZE..(row - 1) * ROW_SIZE].copy_from_slice(&preceding);
+        }
+        // Followed by the corruption of a plain read
+        let (a, b, c) = (OamBug::word(gb, row, 0), OamBug::word(gb, row - 1, 0), OamBug::word(gb, row - 1, 2));
+        OamBug::set_word(gb, row, 0, b | (a & c));
+        OamBug::copy_tail(gb, row);
+    }
+
+    // The first row is never corrupted, there is no row before it
+    fn scanned_row(gb: &GameBoy, address: Address) -> Option<usize> {
+        if !gb.io.lcd.oam_bug || gb.cgb_mode() || !(OAM_BEGIN..=0xFEFF).contains(&address) {
+            return None;
+        }
+        if !LCD::read_control(gb, LCDControl::Power) || LCD::mode(gb) != LCDMode::SearchingOAM {
+            return None;
+        }
+        let row = (LCD::clock(gb) / DOTS_PER_ROW) as usize;
+        (1..ROWS).contains(&row).then_some(row)
+    }
+
+    fn word(gb: &GameBoy, row: usize, word: usize) -> u16 {
+        let offset = row * ROW_SIZE + word * 2;
+        u16::from_le_bytes([gb.ppu.oam[offset], gb.ppu.oam[offset + 1]])
+    }
+
+    fn set_word(gb: &mut GameBoy, row: usize, word: usize, value: u16) {
+        let offset = row * ROW_SIZE + word * 2;
+        gb.ppu.oam[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    // The last three words come from the row before
+    fn copy_tail(gb: &mut GameBoy, row: usize) {
+        let (start, end) = (row * ROW_SIZE, (row + 1) * ROW_SIZE);
+        gb.ppu.oam.copy_within(start - ROW_SIZE + 2..end - ROW_SIZE, start + 2);
+    }
+}
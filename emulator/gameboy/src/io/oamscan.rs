@@ -0,0 +1,60 @@
+use crate::gameboy::GameBoy;
+
+use super::lcd::{LCDControl, LCD};
+
+pub(crate) const OAM_ENTRIES: usize = 40;
+// https://gbdev.io/pandocs/OAM.html, the PPU keeps the first 10 sprites of a line
+const SPRITES_PER_LINE: u8 = 10;
+// OAM Y is the screen line plus 16, so sprites can go above the screen
+const Y_OFFSET: u16 = 16;
+
+// What the OAM scans of one frame did with a sprite, counted in lines
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct SpriteLines {
+    pub(crate) selected: u8,
+    // Lines that already had 10 sprites
+    pub(crate) dropped: u8,
+    // Lines it covers while LCDC turns sprites off
+    pub(crate) hidden: u8,
+}
+
+// The sprite selection of mode 2, for the OAM inspector. Sprites aren't drawn
+// yet, the selection is done the same way as the hardware anyway.
+pub(crate) struct OamScan {
+    lines: [SpriteLines; OAM_ENTRIES],
+    pub(crate) last_frame: [SpriteLines; OAM_ENTRIES],
+}
+
+impl OamScan {
+    pub(crate) fn new() -> Self {
+        OamScan { lines: [SpriteLines::default(); OAM_ENTRIES], last_frame: [SpriteLines::default(); OAM_ENTRIES] }
+    }
+
+    // At the end of mode 2 of every visible line
+    pub(crate) fn scan_line(gb: &mut GameBoy) {
+        let line = LCD::read_scanline(gb) as u16 + Y_OFFSET;
+        let height = if LCD::read_control(gb, LCDControl::SpriteSize) { 16 } else { 8 };
+        let enabled = LCD::read_control(gb, LCDControl::SpritesEnabled);
+        let mut selected = 0;
+        for (index, entry) in gb.ppu.oam.chunks(4).take(OAM_ENTRIES).enumerate() {
+            let y = entry[0] as u16;
+            if line < y || line >= y + height {
+                continue;
+            }
+            let lines = &mut gb.io.oam_scan.lines[index];
+            if !enabled {
+                lines.hidden += 1;
+            } else if selected < SPRITES_PER_LINE {
+                lines.selected += 1;
+                selected += 1;
+            } else {
+                lines.dropped += 1;
+            }
+        }
+    }
+
+    pub(crate) fn end_frame(gb: &mut GameBoy) {
+        let scan = &mut gb.io.oam_scan;
+        scan.last_frame = std::mem::replace(&mut scan.lines, [SpriteLines::default(); OAM_ENTRIES]);
+    }
+}
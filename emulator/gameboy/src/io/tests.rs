@@ -0,0 +1,706 @@
+#[cfg(test)]
+use std::borrow::Cow;
+
+#[cfg(test)]
+use crate::{accuracy::{AccuracyProfile, EmulationConfig}, cartridge::Cartridge, cpu::instructions::decode::{Instruction, WordRegister}, gameboy::GameBoy, infrared::{IrLink, IrLoopback}, io::{joypad::Joypad, lcd::{LcdOffDisplay, LCDMode, LCD}}, mmu::MMU, ppu::PPU, Button, ColoredPixel};
+
+#[test]
+fn apu_registers_read_back_with_masks() {
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+
+    // Everything reads as its mask while the APU is off
+    assert_eq!(MMU::read_byte(&gb, 0xFF10), 0x80);
+    assert_eq!(MMU::read_byte(&gb, 0xFF26), 0x70);
+
+    MMU::write_byte(&mut gb, 0xFF26, 0x80);
+    MMU::write_byte(&mut gb, 0xFF11, 0x00);
+    MMU::write_byte(&mut gb, 0xFF12, 0xF0);
+    // Length counters and frequencies are write-only
+    assert_eq!(MMU::read_byte(&gb, 0xFF11), 0x3F);
+    MMU::write_byte(&mut gb, 0xFF13, 0x12);
+    assert_eq!(MMU::read_byte(&gb, 0xFF13), 0xFF);
+
+    // Triggering channel 1 sets its flag in NR52
+    MMU::write_byte(&mut gb, 0xFF14, 0x80);
+    assert_eq!(MMU::read_byte(&gb, 0xFF26), 0xF1);
+
+    // Turning the APU off clears the registers
+    MMU::write_byte(&mut gb, 0xFF26, 0x00);
+    assert_eq!(MMU::read_byte(&gb, 0xFF12), 0x00);
+    assert_eq!(MMU::read_byte(&gb, 0xFF26), 0x70);
+}
+
+#[test]
+fn envelope_and_zombie_mode() {
+    use super::apu::APU;
+
+    let mut gb = GameBoy::new(None, AccuracyProfile::Fast.into());
+    MMU::write_byte(&mut gb, 0xFF26, 0x80);
+
+    // Volume 15, decreasing every sequencer period
+    MMU::write_byte(&mut gb, 0xFF12, 0xF1);
+    MMU::write_byte(&mut gb, 0xFF14, 0x80);
+    for _ in 0..8 {
+        APU::clock_sequencer(&mut gb);
+    }
+    assert_eq!(gb.io.apu.envelopes[0].volume, 14);
+
+    // Without zombie mode writing NRx2 leaves the volume alone
+    MMU::write_byte(&mut gb, 0xFF12, 0x50);
+    MMU::write_byte(&mut gb, 0xFF14, 0x80);
+    MMU::write_byte(&mut gb, 0xFF12, 0x50);
+    assert_eq!(gb.io.apu.envelopes[0].volume, 5);
+
+    gb.io.apu.zombie_mode = true;
+    // Period was 0: +1
+    MMU::write_byte(&mut gb, 0xFF12, 0x50);
+    assert_eq!(gb.io.apu.envelopes[0].volume, 6);
+    // +1, then the direction changed: 16 - volume
+    MMU::write_byte(&mut gb, 0xFF12, 0x58);
+    assert_eq!(gb.io.apu.envelopes[0].volume, 9);
+}
+
+#[test]
+fn noise_lfsr_periods() {
+    use super::apu::APU;
+
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+    MMU::write_byte(&mut gb, 0xFF26, 0x80);
+    MMU::write_byte(&mut gb, 0xFF21, 0xF0);
+
+    // Divisor code 0 is 8 cycles, so each tick of 8 cycles shifts once.
+    // In 7-bit mode only the low 7 bits follow the short sequence.
+    for (nr43, mask, period) in [(0x00, 0x7FFF, 32767), (0x08, 0x7F, 127)] {
+        MMU::write_byte(&mut gb, 0xFF22, nr43);
+        MMU::write_byte(&mut gb, 0xFF23, 0x80);
+        let seed = gb.io.apu.noise.lfsr;
+
+        let mut shifts = 0;
+        loop {
+            APU::tick(&mut gb, 8);
+            shifts += 1;
+            if gb.io.apu.noise.lfsr & mask == seed & mask {
+                break;
+            }
+        }
+        assert_eq!(shifts, period);
+    }
+
+    // Shifts 14 and 15 stop the LFSR
+    MMU::write_byte(&mut gb, 0xFF22, 0xE0);
+    MMU::write_byte(&mut gb, 0xFF23, 0x80);
+    APU::tick(&mut gb, 1000);
+    assert_eq!(gb.io.apu.noise.lfsr, 0x7FFF);
+}
+
+#[test]
+fn pulse_channel_resampled_at_every_quality() {
+    use super::apu::APU;
+    use crate::audio::{HighPassFilter, Resampler, ResamplerQuality};
+
+    for quality in [ResamplerQuality::Nearest, ResamplerQuality::Linear, ResamplerQuality::BandLimited] {
+        let mut gb = GameBoy::new(None, EmulationConfig::default());
+        gb.audio = Some(Resampler::new(48000, quality, HighPassFilter::Off));
+        MMU::write_byte(&mut gb, 0xFF26, 0x80);
+        MMU::write_byte(&mut gb, 0xFF24, 0x77);
+        MMU::write_byte(&mut gb, 0xFF25, 0x11);
+        // 50% duty at 131072 / (2048 - 1750) = 440 Hz
+        MMU::write_byte(&mut gb, 0xFF11, 0x80);
+        MMU::write_byte(&mut gb, 0xFF12, 0xF0);
+        MMU::write_byte(&mut gb, 0xFF13, (1750 & 0xFF) as u8);
+        MMU::write_byte(&mut gb, 0xFF14, 0x80 | (1750 >> 8) as u8);
+
+        // A tenth of a second
+        for _ in 0..crate::CPU_CLOCK_HZ / 10 / 16 {
+            APU::tick(&mut gb, 16);
+        }
+        let samples = gb.audio.as_mut().unwrap().take_samples();
+        assert!((samples.len() as i32 / 2 - 4800).abs() <= 1, "{:?}", quality);
+
+        // Skips the DAC turning on
+        let left: Vec<f32> = samples.iter().step_by(2).skip(100).copied().collect();
+        let mean = left.iter().sum::<f32>() / left.len() as f32;
+        let crossings = left.windows(2).filter(|pair| (pair[0] < mean) != (pair[1] < mean)).count();
+        assert!((84..=88).contains(&crossings), "{:?} {}", quality, crossings);
+        assert!(left.iter().all(|sample| sample.abs() <= 1.1), "{:?}", quality);
+    }
+}
+
+#[test]
+fn high_pass_filter_removes_the_dac_offset() {
+    use crate::audio::HighPassFilter;
+
+    let mut last_samples = Vec::new();
+    for filter in [HighPassFilter::Off, HighPassFilter::Dmg, HighPassFilter::Cgb] {
+        let mut emulation = crate::Emulation::new(None);
+        emulation.set_high_pass_filter(filter);
+        emulation.enable_audio(48000, crate::audio::ResamplerQuality::Nearest);
+        let gb = &mut emulation.gameboy;
+        MMU::write_byte(gb, 0xFF26, 0x80);
+        MMU::write_byte(gb, 0xFF24, 0x77);
+        MMU::write_byte(gb, 0xFF25, 0x11);
+        // The DAC of pulse 1 on at volume 0, a constant level
+        MMU::write_byte(gb, 0xFF12, 0x08);
+        MMU::write_byte(gb, 0xFF14, 0x80);
+        for _ in 0..crate::CPU_CLOCK_HZ / 500 / 16 {
+            super::apu::APU::tick(gb, 16);
+        }
+        last_samples.push(*emulation.take_audio().last().unwrap());
+    }
+    // In 2 ms the DMG capacitor takes away a third of the offset, the CGB one nearly all of it
+    assert_eq!(last_samples[0], 0.25);
+    assert!(last_samples[1] > 0.15 && last_samples[1] < 0.2, "{}", last_samples[1]);
+    assert!(last_samples[2].abs() < 0.001, "{}", last_samples[2]);
+}
+
+#[test]
+fn observer_gets_the_audio_samples() {
+    use std::sync::{Arc, Mutex};
+    use crate::observer::EmulatorObserver;
+
+    struct Samples(Arc<Mutex<Vec<i16>>>);
+    impl EmulatorObserver for Samples {
+        fn on_audio(&mut self, samples: &[i16]) {
+            self.0.lock().unwrap().extend_from_slice(samples);
+        }
+    }
+
+    let samples = Arc::new(Mutex::new(Vec::new()));
+    let mut emulation = crate::Emulation::new(None);
+    emulation.set_observer(Box::new(Samples(samples.clone())));
+    emulation.skip_frame().unwrap();
+    assert!(samples.lock().unwrap().is_empty());
+
+    emulation.enable_audio(48000, crate::audio::ResamplerQuality::Nearest);
+    let gb = &mut emulation.gameboy;
+    MMU::write_byte(gb, 0xFF26, 0x80);
+    MMU::write_byte(gb, 0xFF24, 0x77);
+    MMU::write_byte(gb, 0xFF25, 0x11);
+    // The DAC of pulse 1 on at volume 0, a constant level
+    MMU::write_byte(gb, 0xFF12, 0x08);
+    MMU::write_byte(gb, 0xFF14, 0x80);
+    for _ in 0..crate::CPU_CLOCK_HZ / 100 / 16 {
+        super::apu::APU::tick(gb, 16);
+    }
+    let expected: Vec<i16> = emulation.take_audio().iter().map(|sample| (sample * i16::MAX as f32) as i16).collect();
+    assert!((expected.len() as i32 / 2 - 480).abs() <= 1);
+    assert_eq!(*samples.lock().unwrap(), expected);
+}
+
+#[test]
+fn memory_map_regions() {
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+
+    // Echo RAM mirrors WRAM both ways
+    MMU::write_byte(&mut gb, 0xC123, 0x42);
+    assert_eq!(MMU::read_byte(&gb, 0xE123), 0x42);
+    MMU::write_byte(&mut gb, 0xFDFF, 0x24);
+    assert_eq!(MMU::read_byte(&gb, 0xDDFF), 0x24);
+
+    MMU::write_byte(&mut gb, 0xFE10, 0x99);
+    assert_eq!(MMU::read_byte(&gb, 0xFE10), 0x99);
+    MMU::write_byte(&mut gb, 0xFEA0, 0x99);
+    assert_eq!(MMU::read_byte(&gb, 0xFEA0), 0x00);
+
+    // Open bus on registers that don't exist, unused bits read as 1
+    MMU::write_byte(&mut gb, 0xFF03, 0x12);
+    assert_eq!(MMU::read_byte(&gb, 0xFF03), 0xFF);
+    MMU::write_byte(&mut gb, 0xFF0F, 0x01);
+    assert_eq!(MMU::read_byte(&gb, 0xFF0F), 0xE1);
+    MMU::write_byte(&mut gb, 0xFF07, 0x05);
+    assert_eq!(MMU::read_byte(&gb, 0xFF07), 0xFD);
+}
+
+#[test]
+fn wave_ram_edited_while_playing() {
+    use super::apu::APU;
+
+    let mut emulation = crate::Emulation::new(None);
+    let gb = &mut emulation.gameboy;
+    MMU::write_byte(gb, 0xFF26, 0x80);
+    MMU::write_byte(gb, 0xFF1A, 0x80);
+    MMU::write_byte(gb, 0xFF1C, 0x20);
+    // Period of (2048 - 2000) * 2 = 96 cycles per sample
+    MMU::write_byte(gb, 0xFF1D, (2000 & 0xFF) as u8);
+    MMU::write_byte(gb, 0xFF1E, 0x80 | (2000 >> 8) as u8);
+    APU::tick(gb, 96 * 5);
+
+    let mut debugger = emulation.debugger();
+    assert_eq!(debugger.wave_position(), 5);
+    debugger.set_wave_sample(5, 0xA);
+    debugger.set_wave_sample(6, 0x3);
+    assert_eq!(&debugger.wave_samples()[4..8], &[0x0, 0xA, 0x3, 0x0]);
+    assert_eq!(debugger.read_byte(0xFF32), 0x0A);
+    assert_eq!(debugger.read_byte(0xFF33), 0x30);
+}
+
+#[test]
+fn muted_channels_are_still_tapped() {
+    use super::apu::APU;
+    use crate::{audio::AudioChannel, CPU_CLOCK_HZ};
+
+    let mut emulation = crate::Emulation::new(None);
+    // A sample every 64 cycles
+    emulation.start_channel_taps(CPU_CLOCK_HZ as u32 / 64);
+    let gb = &mut emulation.gameboy;
+    MMU::write_byte(gb, 0xFF26, 0x80);
+    MMU::write_byte(gb, 0xFF25, 0xFF);
+    // Pulse 1 at full volume, 50% duty, a period of (2048 - 1984) * 4 = 256 cycles per step
+    MMU::write_byte(gb, 0xFF11, 0x80);
+    MMU::write_byte(gb, 0xFF12, 0xF0);
+    MMU::write_byte(gb, 0xFF13, (1984 & 0xFF) as u8);
+    MMU::write_byte(gb, 0xFF14, 0x80 | (1984 >> 8) as u8);
+    APU::tick(gb, 64 * 100);
+    assert_ne!(gb.io.apu.mix(), [0.0; 2]);
+
+    emulation.solo_channel(Some(AudioChannel::Wave));
+    assert!(emulation.is_channel_muted(AudioChannel::Pulse1));
+    assert_eq!(emulation.gameboy.io.apu.mix(), [0.0; 2]);
+    APU::tick(&mut emulation.gameboy, 64 * 100);
+
+    let taps = emulation.take_channel_taps();
+    assert_eq!(taps[AudioChannel::Pulse1 as usize].len(), 200);
+    assert!(taps[AudioChannel::Pulse1 as usize][100..].iter().any(|level| *level > 0.5));
+    assert!(taps[AudioChannel::Pulse1 as usize][100..].iter().any(|level| *level < -0.5));
+    assert!(taps[AudioChannel::Noise as usize].iter().all(|level| *level == 0.0));
+    emulation.solo_channel(None);
+    assert!(!emulation.is_channel_muted(AudioChannel::Pulse1));
+}
+
+#[test]
+fn vram_and_oam_blocked_by_ppu_mode() {
+    use super::lcd::{LCD, LCDMode};
+
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+    MMU::write_byte(&mut gb, 0x8000, 0x12);
+    MMU::write_byte(&mut gb, 0xFE00, 0x34);
+    MMU::write_byte(&mut gb, 0xFF40, 0x80);
+    gb.io.lcd.block_cpu_access = true;
+
+    LCD::start_mode(&mut gb, LCDMode::SearchingOAM);
+    assert_eq!(MMU::read_byte(&gb, 0x8000), 0x12);
+    assert_eq!(MMU::read_byte(&gb, 0xFE00), 0xFF);
+    assert_eq!(MMU::read_byte(&gb, 0xFEA0), 0xFF);
+
+    LCD::start_mode(&mut gb, LCDMode::Transfering);
+    assert_eq!(MMU::read_byte(&gb, 0x8000), 0xFF);
+    MMU::write_byte(&mut gb, 0x8000, 0x56);
+
+    LCD::start_mode(&mut gb, LCDMode::HBlank);
+    assert_eq!(MMU::read_byte(&gb, 0x8000), 0x12);
+    assert_eq!(MMU::read_byte(&gb, 0xFE00), 0x34);
+
+    // Nothing is blocked with the LCD off
+    MMU::write_byte(&mut gb, 0xFF40, 0x00);
+    LCD::start_mode(&mut gb, LCDMode::Transfering);
+    assert_eq!(MMU::read_byte(&gb, 0x8000), 0x12);
+}
+
+#[test]
+fn io_writes_logged_by_frame() {
+    let mut emulation = crate::Emulation::new(None);
+    emulation.start_io_log();
+    // Clearing VRAM keeps the boot ROM busy for the first frames
+    for _ in 0..10 {
+        emulation.step().unwrap();
+    }
+    let log = emulation.stop_io_log().unwrap();
+
+    // The boot ROM turns the APU on before anything else
+    let first = log.writes()[0];
+    assert_eq!((first.address, first.value), (0xFF26, 0x80));
+    assert!(log.writes().iter().any(|write| write.address == 0xFF40 && write.value == 0x91));
+
+    let mut csv = Vec::new();
+    log.write_csv(&mut csv, first.frame..=first.frame).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("frame,scanline,dot,pc,address,register,value"));
+    assert!(lines.next().unwrap().ends_with(",FF26,NR52,80"));
+    assert!(lines.all(|line| line.starts_with(&format!("{},", first.frame))));
+}
+
+#[test]
+fn registers_captured_per_scanline() {
+    use super::lcd::LCD;
+
+    let mut emulation = crate::Emulation::new(None);
+    let gb = &mut emulation.gameboy;
+    MMU::write_byte(gb, 0xFF40, 0x91);
+    MMU::write_byte(gb, 0xFF43, 5);
+    MMU::write_byte(gb, 0xFF4A, 40);
+
+    // A mid-frame scroll change only affects the following lines
+    while LCD::read_scanline(gb) < 144 {
+        if LCD::read_scanline(gb) == 10 {
+            MMU::write_byte(gb, 0xFF43, 7);
+        }
+        LCD::tick(gb, 4);
+    }
+
+    let lines = emulation.debugger().scanline_registers();
+    assert_eq!(lines.len(), 144);
+    assert_eq!((lines[9].scx, lines[9].wy, lines[9].lcdc), (5, 40, 0x91));
+    assert_eq!(lines[10].scx, 7);
+    assert_eq!(lines[143].scx, 7);
+}
+
+#[test]
+fn ppu_events_placed_on_the_frame_timeline() {
+    let mut emulation = crate::Emulation::new(None);
+    emulation.start_event_log(2);
+    let gb = &mut emulation.gameboy;
+    MMU::write_byte(gb, 0xFF40, 0x91);
+    for frame in 0..3 {
+        while LCD::read_scanline(gb) != 10 || LCD::mode(gb) != LCDMode::Transfering {
+            LCD::tick(gb, 4);
+        }
+        MMU::write_byte(gb, 0xFF43, frame);
+        // Only the PPU registers are kept
+        MMU::write_byte(gb, 0xFF26, 0x80);
+        while LCD::read_scanline(gb) != 0 {
+            LCD::tick(gb, 4);
+        }
+    }
+
+    let log = emulation.io_log().unwrap();
+    assert_eq!(log.frame(), 3);
+    // The first frame is gone, with the LCDC write
+    assert!(log.writes().iter().all(|write| write.frame >= 1 && write.address == 0xFF43));
+    let writes = log.frame_writes(2);
+    assert_eq!(writes.len(), 1);
+    assert_eq!((writes[0].scanline, writes[0].value), (10, 2));
+    assert!((80..252).contains(&writes[0].dot));
+
+    let timeline = log.timeline(2);
+    assert_eq!((timeline.width, timeline.height), (456, 154));
+    assert_eq!(timeline.buffer[writes[0].dot as usize + 10 * 456], ColoredPixel::Black);
+    assert_eq!(timeline.buffer[0], ColoredPixel::White);
+    assert_eq!(timeline.buffer[100 + 10 * 456], ColoredPixel::LightGray);
+    assert_eq!(timeline.buffer[150 * 456], ColoredPixel::DarkGray);
+}
+
+#[test]
+fn flickering_frames_merged_for_captures() {
+    let mut emulation = crate::Emulation::new(None);
+    let gb = &mut emulation.gameboy;
+    MMU::write_byte(gb, 0xFF40, 0x91);
+    let frame_with_palette = |gb: &mut GameBoy, bgp| {
+        MMU::write_byte(gb, 0xFF47, bgp);
+        while LCD::read_scanline(gb) != 144 {
+            LCD::tick(gb, 4);
+        }
+        while LCD::read_scanline(gb) != 0 {
+            LCD::tick(gb, 4);
+        }
+    };
+    // Shown every other frame, like sprites over the limit of a line
+    for bgp in [0x00, 0xFF, 0x00, 0xFF, 0x00] {
+        frame_with_palette(gb, bgp);
+    }
+    let is_all = |frame: &crate::GameBoyFrame, color| frame.buffer.iter().all(|pixel| *pixel == color);
+    assert!(is_all(&emulation.screen(), ColoredPixel::White));
+    assert!(is_all(&emulation.stable_screen(), ColoredPixel::Black));
+
+    // A change that stays is not a flicker
+    let gb = &mut emulation.gameboy;
+    frame_with_palette(gb, 0xFF);
+    frame_with_palette(gb, 0xFF);
+    frame_with_palette(gb, 0x00);
+    assert!(is_all(&emulation.stable_screen(), ColoredPixel::White));
+}
+
+#[test]
+fn lcd_off_display_options() {
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+    MMU::write_byte(&mut gb, 0xFF40, 0x91);
+    MMU::write_byte(&mut gb, 0xFF47, 0xFF);
+    for _ in 0..70224 / 4 {
+        gb.tick().unwrap();
+    }
+    assert!(gb.frame().buffer.iter().all(|pixel| *pixel == ColoredPixel::Black));
+
+    MMU::write_byte(&mut gb, 0xFF40, 0x11);
+    assert!(gb.frame().buffer.iter().all(|pixel| *pixel == ColoredPixel::White));
+    gb.io.lcd.off_display = LcdOffDisplay::LastFrame;
+    assert!(gb.frame().buffer.iter().all(|pixel| *pixel == ColoredPixel::Black));
+    gb.io.lcd.off_display = LcdOffDisplay::Dimmed;
+    assert!(gb.frame().buffer.iter().all(|pixel| *pixel == ColoredPixel::DarkGray));
+}
+
+#[test]
+fn screen_shows_the_last_complete_frame_while_the_next_is_drawn() {
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+    let run_to_line = |gb: &mut GameBoy, line: u8| {
+        while MMU::read_byte(gb, 0xFF44) != line {
+            gb.tick().unwrap();
+        }
+    };
+    // Once the boot ROM has turned the LCD on, a whole frame in black
+    run_to_line(&mut gb, 1);
+    run_to_line(&mut gb, 0);
+    MMU::write_byte(&mut gb, 0xFF47, 0xFF);
+    run_to_line(&mut gb, 1);
+    run_to_line(&mut gb, 0);
+    assert!(matches!(gb.frame(), Cow::Borrowed(_)));
+
+    MMU::write_byte(&mut gb, 0xFF47, 0x00);
+    run_to_line(&mut gb, 72);
+    assert!(gb.frame().buffer.iter().all(|pixel| *pixel == ColoredPixel::Black));
+    run_to_line(&mut gb, 145);
+    assert!(gb.frame().buffer.iter().all(|pixel| *pixel == ColoredPixel::White));
+}
+
+#[test]
+fn tiles_replaced_when_drawn() {
+    use crate::tilepack::TileReplacements;
+
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+    MMU::write_byte(&mut gb, 0xFF40, 0x91);
+    MMU::write_byte(&mut gb, 0xFF47, 0xE4);
+    // Every map entry is tile 0, which is white. The sheet turns its top half
+    // black and leaves the bottom half transparent.
+    let mut sheet = vec![0; 8 * 8 * 4];
+    for pixel in sheet[..8 * 4 * 4].chunks_mut(4) {
+        pixel[3] = 0xFF;
+    }
+    gb.tile_replacements = Some(TileReplacements::from_sheet(8, 8, &sheet, &[0]).unwrap());
+    for _ in 0..70224 / 4 {
+        gb.tick().unwrap();
+    }
+
+    let frame = gb.frame();
+    assert_eq!(frame.buffer[3 * 160], ColoredPixel::Black);
+    assert_eq!(frame.buffer[4 * 160], ColoredPixel::White);
+    assert_eq!(MMU::read_byte(&gb, 0x8000), 0x00);
+    assert!(TileReplacements::from_sheet(8, 8, &sheet, &[1]).is_err());
+}
+
+#[test]
+fn infrared_port_sees_the_light_of_the_other_side() {
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+    assert_eq!(MMU::read_byte(&gb, 0xFF56), 0xFF);
+
+    gb.ir_transceiver = Some(Box::new(IrLoopback::default()));
+    MMU::write_byte(&mut gb, 0xFF56, 0x01);
+    // The sensor is off until bits 6-7 enable it
+    assert_eq!(MMU::read_byte(&gb, 0xFF56), 0x3F);
+    MMU::write_byte(&mut gb, 0xFF56, 0xC1);
+    assert_eq!(MMU::read_byte(&gb, 0xFF56), 0xFD);
+
+    let (first, second) = IrLink::pair();
+    let mut sender = GameBoy::new(None, EmulationConfig::default());
+    let mut receiver = GameBoy::new(None, EmulationConfig::default());
+    sender.ir_transceiver = Some(Box::new(first));
+    receiver.ir_transceiver = Some(Box::new(second));
+    MMU::write_byte(&mut receiver, 0xFF56, 0xC0);
+    assert_eq!(MMU::read_byte(&receiver, 0xFF56) & 0x02, 0x02);
+    MMU::write_byte(&mut sender, 0xFF56, 0x01);
+    assert_eq!(MMU::read_byte(&receiver, 0xFF56) & 0x02, 0x00);
+    MMU::write_byte(&mut sender, 0xFF56, 0x00);
+    assert_eq!(MMU::read_byte(&receiver, 0xFF56) & 0x02, 0x02);
+}
+
+#[test]
+fn stop_switches_to_double_speed_when_armed() {
+    let mut rom = vec![0; 0x8000];
+    rom[0x0143] = 0x80;
+    let mut gb = GameBoy::new(Some(Cartridge::from_data(rom).unwrap()), EmulationConfig::default());
+    gb.cpu.pc = 0x0100;
+
+    // STOP without arming the switch keeps the speed and stops the clocks
+    let _ = Instruction::STOP.execute(&mut gb);
+    assert_eq!(MMU::read_byte(&gb, 0xFF4D), 0x7E);
+    assert!(gb.cpu.is_stopped);
+    gb.cpu.is_stopped = false;
+
+    MMU::write_byte(&mut gb, 0xFF04, 0x00);
+    MMU::write_byte(&mut gb, 0xFF4D, 0x01);
+    assert_eq!(MMU::read_byte(&gb, 0xFF4D), 0x7F);
+    let _ = Instruction::STOP.execute(&mut gb);
+    assert_eq!(MMU::read_byte(&gb, 0xFF4D), 0xFE);
+    assert_eq!(MMU::read_byte(&gb, 0xFF04), 0x00);
+
+    // The PPU sees half the cycles of a NOP
+    gb.cpu.is_halted = true;
+    assert_eq!(gb.tick().unwrap(), 2);
+
+    // DMG cartridges have no KEY1
+    let mut dmg = GameBoy::new(Some(Cartridge::from_data(vec![0; 0x8000]).unwrap()), EmulationConfig::default());
+    MMU::write_byte(&mut dmg, 0xFF4D, 0x01);
+    assert_eq!(MMU::read_byte(&dmg, 0xFF4D), 0xFF);
+}
+
+#[test]
+fn vram_dma_copies_blocks_at_once_or_each_hblank() {
+    let mut rom = vec![0; 0x8000];
+    rom[0x0143] = 0xC0;
+    for (offset, byte) in rom[0x4000..0x4060].iter_mut().enumerate() {
+        *byte = offset as u8 + 1;
+    }
+    let mut gb = GameBoy::new(Some(Cartridge::from_data(rom).unwrap()), EmulationConfig::default());
+    gb.cpu.is_halted = true;
+    MMU::write_byte(&mut gb, 0xFF40, 0x80);
+
+    // General purpose, 2 blocks from 4000 to 8800 while the CPU waits 16 cycles
+    for (address, value) in [(0xFF51, 0x40), (0xFF52, 0x00), (0xFF53, 0x08), (0xFF54, 0x00), (0xFF55, 0x01)] {
+        MMU::write_byte(&mut gb, address, value);
+    }
+    assert_eq!(PPU::read_vram(&gb, 0x881F), 0x20);
+    assert_eq!(MMU::read_byte(&gb, 0xFF55), 0xFF);
+    assert_eq!(gb.tick().unwrap(), 4 + 16 * 4);
+
+    // HBlank, the next 2 blocks go one per line
+    MMU::write_byte(&mut gb, 0xFF55, 0x81);
+    assert_eq!(MMU::read_byte(&gb, 0xFF55), 0x01);
+    while LCD::mode(&gb) != LCDMode::HBlank {
+        gb.tick().unwrap();
+    }
+    assert_eq!(PPU::read_vram(&gb, 0x882F), 0x30);
+    assert_eq!(PPU::read_vram(&gb, 0x8830), 0x00);
+    assert_eq!(MMU::read_byte(&gb, 0xFF55), 0x00);
+    while LCD::mode(&gb) == LCDMode::HBlank {
+        gb.tick().unwrap();
+    }
+    while LCD::mode(&gb) != LCDMode::HBlank {
+        gb.tick().unwrap();
+    }
+    assert_eq!(PPU::read_vram(&gb, 0x883F), 0x40);
+    assert_eq!(MMU::read_byte(&gb, 0xFF55), 0xFF);
+    // The CPU waits for the block on its next instruction
+    assert_eq!(gb.tick().unwrap(), 4 + 8 * 4);
+
+    // At double speed the CPU waits twice the cycles, the copy takes the same time
+    MMU::write_byte(&mut gb, 0xFF4D, 0x01);
+    let _ = Instruction::STOP.execute(&mut gb);
+    gb.cpu.is_stopped = false;
+    gb.cpu.is_halted = true;
+    MMU::write_byte(&mut gb, 0xFF55, 0x01);
+    assert_eq!(PPU::read_vram(&gb, 0x885F), 0x60);
+    assert_eq!(gb.tick().unwrap(), 2 + 16 * 4);
+}
+
+#[test]
+fn oam_bug_garbles_the_scanned_row() {
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+    for (index, byte) in gb.ppu.oam.iter_mut().enumerate() {
+        *byte = index as u8;
+    }
+    let original = gb.ppu.oam;
+    MMU::write_byte(&mut gb, 0xFF40, 0x91);
+    // Row 2 of OAM is scanned
+    LCD::tick(&mut gb, 8);
+    gb.cpu.regs.set_hl(0xFE20);
+
+    let _ = Instruction::INC16(WordRegister::HL).execute(&mut gb);
+    assert_eq!(gb.ppu.oam, original);
+
+    gb.io.lcd.oam_bug = true;
+    let _ = Instruction::INC16(WordRegister::HL).execute(&mut gb);
+    let (a, b, c) = (0x1110u16, 0x0908u16, 0x0D0Cu16);
+    assert_eq!(u16::from_le_bytes([gb.ppu.oam[16], gb.ppu.oam[17]]), ((a ^ c) & (b ^ c)) ^ c);
+    assert_eq!(gb.ppu.oam[18..24], original[10..16]);
+    assert_eq!(gb.ppu.oam[..16], original[..16]);
+    assert_eq!(gb.ppu.oam[24..], original[24..]);
+}
+
+#[test]
+fn stop_waits_for_a_selected_button() {
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+    gb.cpu.pc = 0xC000;
+    MMU::write_byte(&mut gb, 0xFF00, 0x20);
+    let _ = Instruction::STOP.execute(&mut gb);
+    assert!(gb.cpu.is_stopped);
+
+    let ppu_clock = LCD::clock(&gb);
+    gb.tick().unwrap();
+    assert_eq!(LCD::clock(&gb), ppu_clock);
+
+    // Start is not on the directions line
+    Joypad::button_pressed(&mut gb, Button::Start);
+    gb.tick().unwrap();
+    assert!(gb.cpu.is_stopped);
+    Joypad::button_pressed(&mut gb, Button::Left);
+    gb.tick().unwrap();
+    assert!(!gb.cpu.is_stopped);
+
+    // With both lines selected a button on either reads as pressed
+    MMU::write_byte(&mut gb, 0xFF00, 0x00);
+    assert_eq!(MMU::read_byte(&gb, 0xFF00), 0xC5);
+    MMU::write_byte(&mut gb, 0xFF00, 0x30);
+    assert_eq!(MMU::read_byte(&gb, 0xFF00), 0xFF);
+}
+
+#[test]
+fn lyc_coincidence_requests_a_stat_interrupt() {
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+    MMU::write_byte(&mut gb, 0xFF40, 0x91);
+    MMU::write_byte(&mut gb, 0xFF45, 0x02);
+    MMU::write_byte(&mut gb, 0xFF41, 0x40);
+    assert_eq!(MMU::read_byte(&gb, 0xFF45), 0x02);
+    assert_eq!(MMU::read_byte(&gb, 0xFF41) & 0xFC, 0xC0);
+
+    while LCD::read_scanline(&gb) != 2 {
+        LCD::tick(&mut gb, 4);
+    }
+    assert_eq!(MMU::read_byte(&gb, 0xFF41) & 0x44, 0x44);
+    assert_eq!(MMU::read_byte(&gb, 0xFF0F) & 0x02, 0x02);
+
+    // The line stays up while LY matches, a new source doesn't fire again
+    MMU::write_byte(&mut gb, 0xFF0F, 0x00);
+    MMU::write_byte(&mut gb, 0xFF41, 0x48);
+    while LCD::mode(&gb) != LCDMode::HBlank {
+        LCD::tick(&mut gb, 4);
+    }
+    assert_eq!(MMU::read_byte(&gb, 0xFF0F) & 0x02, 0x00);
+
+    // With the LCD off LY and LYC both read 0 but the line stays low until it is turned on
+    MMU::write_byte(&mut gb, 0xFF40, 0x11);
+    MMU::write_byte(&mut gb, 0xFF45, 0x00);
+    MMU::write_byte(&mut gb, 0xFF41, 0x40);
+    assert_eq!(MMU::read_byte(&gb, 0xFF0F) & 0x02, 0x00);
+    MMU::write_byte(&mut gb, 0xFF40, 0x91);
+    assert_eq!(MMU::read_byte(&gb, 0xFF0F) & 0x02, 0x02);
+}
+
+#[test]
+fn lcd_turned_off_idles_at_line_0_and_hides_its_first_frame() {
+    let mut gb = GameBoy::new(None, EmulationConfig::default());
+    MMU::write_byte(&mut gb, 0xFF40, 0x91);
+    MMU::write_byte(&mut gb, 0xFF47, 0xFF);
+    while LCD::read_scanline(&gb) != 50 {
+        LCD::tick(&mut gb, 4);
+    }
+
+    MMU::write_byte(&mut gb, 0xFF40, 0x11);
+    assert_eq!(MMU::read_byte(&gb, 0xFF44), 0);
+    assert_eq!(MMU::read_byte(&gb, 0xFF41) & 0x03, 0);
+    for _ in 0..1000 {
+        LCD::tick(&mut gb, 4);
+    }
+    assert_eq!(MMU::read_byte(&gb, 0xFF44), 0);
+
+    // Drawing starts over from line 0, the panel stays blank until the next frame
+    MMU::write_byte(&mut gb, 0xFF40, 0x91);
+    assert_eq!(LCD::mode(&gb), LCDMode::SearchingOAM);
+    while LCD::read_scanline(&gb) != 144 {
+        LCD::tick(&mut gb, 4);
+    }
+    assert!(gb.frame().buffer.iter().all(|pixel| *pixel == ColoredPixel::White));
+    while LCD::read_scanline(&gb) != 0 {
+        LCD::tick(&mut gb, 4);
+    }
+    assert!(gb.frame().buffer.iter().all(|pixel| *pixel == ColoredPixel::Black));
+}
+
+#[test]
+fn accuracy_profile_survives_resets() {
+    let mut gb = GameBoy::new(None, AccuracyProfile::Accurate.into());
+    assert!(gb.io.lcd.block_cpu_access && gb.io.lcd.oam_bug && gb.io.apu.zombie_mode);
+
+    gb.io.lcd.oam_bug = false;
+    gb.reset(crate::ResetKind::Hard);
+    let config = EmulationConfig::of(&gb);
+    assert_eq!(config, EmulationConfig { oam_bug: false, ..EmulationConfig::profile(AccuracyProfile::Accurate) });
+    assert_eq!(EmulationConfig::default(), EmulationConfig::profile(AccuracyProfile::Balanced));
+}
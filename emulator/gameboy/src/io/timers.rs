@@ -1,6 +1,6 @@
 use crate::gameboy::GameBoy;
 
-use super::{io::IO, interrupts::{Interruption, Interrupts}};
+use super::{apu::APU, io::IO, interrupts::{Interruption, Interrupts}};
 
 pub(crate) struct Timers {
     pub(super) div_counter: u8,
@@ -16,10 +16,14 @@ impl Timers {
     pub(crate) fn tick(gb: &mut GameBoy, cycles: u8) {
 
         let (new_div, div_overflow) = gb.io.timers.div_counter.overflowing_add(cycles);
+        gb.io.timers.div_counter = new_div;
 
         if div_overflow {
-            gb.io.timers.div_counter = new_div;
+            let div = IO::get_div_register(gb);
             IO::inc_div(gb);
+            if div & !IO::get_div_register(gb) & 0b0001_0000 != 0 {
+                APU::clock_sequencer(gb);
+            }
         }
 
         if Timers::timer_enabled(gb) {
@@ -0,0 +1,173 @@
+use std::{fs::File, io::{BufWriter, Error, Write}, ops::RangeInclusive, path::Path};
+
+use crate::{io::lcd::{CLOCKS_HBLANK, CLOCKS_SEARCHING_OAM, CLOCKS_TRANSFERING}, ColoredPixel, GameBoyFrame};
+
+// Registers that change what the PPU draws in the middle of a frame
+pub const PPU_REGISTERS: [u16; 8] = [0xFF40, 0xFF42, 0xFF43, 0xFF47, 0xFF48, 0xFF49, 0xFF4A, 0xFF4B];
+
+pub const TIMELINE_WIDTH: u32 = (CLOCKS_SEARCHING_OAM + CLOCKS_TRANSFERING + CLOCKS_HBLANK) as u32;
+pub const TIMELINE_HEIGHT: u32 = 154;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IoWrite {
+    // Counted from the start of the log, incremented at every VBlank
+    pub frame: u64,
+    pub scanline: u8,
+    // 0 to 455 inside the scanline, the OAM scan takes the first 80
+    pub dot: u16,
+    pub pc: u16,
+    pub address: u16,
+    pub value: u8,
+}
+
+// Every write to FF00-FF7F and IE while logging is enabled, to see how a game
+// drives the hardware during an effect
+#[derive(Default)]
+pub struct IoLog {
+    frame: u64,
+    writes: Vec<IoWrite>,
+    // Only these addresses are logged when set
+    registers: Option<Vec<u16>>,
+    // Older frames are dropped when set
+    keep_frames: Option<u64>,
+}
+
+impl IoLog {
+    pub fn new() -> Self {
+        IoLog::default()
+    }
+
+    // The PPU registers of the last frames, for the event viewer
+    pub fn ppu_events(frames: u64) -> Self {
+        IoLog { registers: Some(PPU_REGISTERS.to_vec()), keep_frames: Some(frames.max(1)), ..IoLog::default() }
+    }
+
+    pub fn writes(&self) -> &[IoWrite] {
+        &self.writes
+    }
+
+    // The frame being logged, the previous one is the last complete one
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    // Writes are logged in order, so they are sorted by scanline and dot inside a frame
+    pub fn frame_writes(&self, frame: u64) -> &[IoWrite] {
+        let start = self.writes.partition_point(|write| write.frame < frame);
+        let end = self.writes.partition_point(|write| write.frame <= frame);
+        &self.writes[start..end]
+    }
+
+    // One pixel per dot and one line per scanline: the pixel transfer in light
+    // gray, VBlank in dark gray and every write of the frame in black
+    pub fn timeline(&self, frame: u64) -> GameBoyFrame {
+        let mut buffer = vec![ColoredPixel::White; (TIMELINE_WIDTH * TIMELINE_HEIGHT) as usize];
+        let transfer = CLOCKS_SEARCHING_OAM as u32..(CLOCKS_SEARCHING_OAM + CLOCKS_TRANSFERING) as u32;
+        for y in 0..TIMELINE_HEIGHT {
+            for x in 0..TIMELINE_WIDTH {
+                buffer[(x + y * TIMELINE_WIDTH) as usize] = match y {
+                    144.. => ColoredPixel::DarkGray,
+                    _ if transfer.contains(&x) => ColoredPixel::LightGray,
+                    _ => ColoredPixel::White,
+                };
+            }
+        }
+        for write in self.frame_writes(frame) {
+            let (x, y) = ((write.dot as u32).min(TIMELINE_WIDTH - 1), (write.scanline as u32).min(TIMELINE_HEIGHT - 1));
+            buffer[(x + y * TIMELINE_WIDTH) as usize] = ColoredPixel::Black;
+        }
+        GameBoyFrame { width: TIMELINE_WIDTH, height: TIMELINE_HEIGHT, buffer }
+    }
+
+    pub(crate) fn record(&mut self, scanline: u8, dot: u16, pc: u16, address: u16, value: u8) {
+        if self.registers.as_ref().is_some_and(|registers| !registers.contains(&address)) {
+            return;
+        }
+        self.writes.push(IoWrite { frame: self.frame, scanline, dot, pc, address, value });
+    }
+
+    pub(crate) fn next_frame(&mut self) {
+        self.frame += 1;
+        if let Some(frames) = self.keep_frames {
+            let oldest = self.frame.saturating_sub(frames);
+            let dropped = self.writes.partition_point(|write| write.frame < oldest);
+            self.writes.drain(..dropped);
+        }
+    }
+
+    pub fn write_csv<W: Write>(&self, mut writer: W, frames: RangeInclusive<u64>) -> Result<(), Error> {
+        writeln!(writer, "frame,scanline,dot,pc,address,register,value")?;
+        for write in self.writes.iter().filter(|write| frames.contains(&write.frame)) {
+            writeln!(writer, "{},{},{},{:04X},{:04X},{},{:02X}",
+                write.frame, write.scanline, write.dot, write.pc, write.address, register_name(write.address), write.value)?;
+        }
+        writer.flush()
+    }
+
+    pub fn save_csv<P: AsRef<Path>>(&self, path: P, frames: RangeInclusive<u64>) -> Result<(), Error> {
+        self.write_csv(BufWriter::new(File::create(path)?), frames)
+    }
+}
+
+// Address of a register by its name, like "LCDC"
+pub(crate) fn register_address(name: &str) -> Option<u16> {
+    (0xFF00..=0xFFFF).find(|address| register_name(*address).eq_ignore_ascii_case(name) && !name.is_empty())
+}
+
+// https://gbdev.io/pandocs/Hardware_Reg_List.html
+pub(crate) fn register_name(address: u16) -> &'static str {
+    match address {
+        0xFF00 => "P1",
+        0xFF01 => "SB",
+        0xFF02 => "SC",
+        0xFF04 => "DIV",
+        0xFF05 => "TIMA",
+        0xFF06 => "TMA",
+        0xFF07 => "TAC",
+        0xFF0F => "IF",
+        0xFF10 => "NR10",
+        0xFF11 => "NR11",
+        0xFF12 => "NR12",
+        0xFF13 => "NR13",
+        0xFF14 => "NR14",
+        0xFF16 => "NR21",
+        0xFF17 => "NR22",
+        0xFF18 => "NR23",
+        0xFF19 => "NR24",
+        0xFF1A => "NR30",
+        0xFF1B => "NR31",
+        0xFF1C => "NR32",
+        0xFF1D => "NR33",
+        0xFF1E => "NR34",
+        0xFF20 => "NR41",
+        0xFF21 => "NR42",
+        0xFF22 => "NR43",
+        0xFF23 => "NR44",
+        0xFF24 => "NR50",
+        0xFF25 => "NR51",
+        0xFF26 => "NR52",
+        0xFF30 ..= 0xFF3F => "WAVE",
+        0xFF40 => "LCDC",
+        0xFF41 => "STAT",
+        0xFF42 => "SCY",
+        0xFF43 => "SCX",
+        0xFF44 => "LY",
+        0xFF45 => "LYC",
+        0xFF46 => "DMA",
+        0xFF47 => "BGP",
+        0xFF48 => "OBP0",
+        0xFF49 => "OBP1",
+        0xFF4A => "WY",
+        0xFF4B => "WX",
+        0xFF4D => "KEY1",
+        0xFF50 => "BOOT",
+        0xFF51 => "HDMA1",
+        0xFF52 => "HDMA2",
+        0xFF53 => "HDMA3",
+        0xFF54 => "HDMA4",
+        0xFF55 => "HDMA5",
+        0xFF56 => "RP",
+        0xFFFF => "IE",
+        _ => "",
+    }
+}
@@ -1,15 +1,86 @@
+pub mod accuracy;
+#[cfg(feature = "async")]
+pub mod asyncworker;
+pub mod audio;
+pub mod audiodump;
+pub mod avsync;
+pub mod capabilities;
 pub mod cartridge;
+pub mod cheats;
+pub mod clock;
+pub mod compatibility;
+pub mod config;
+pub mod coverage;
+pub mod debugger;
+pub mod determinism;
+pub mod display;
+pub mod error;
+pub mod fuzz;
+pub mod gameconfig;
+pub mod infrared;
+pub mod inputmacro;
+pub mod inputmap;
+pub mod iolog;
+pub mod movie;
+pub mod multiplayer;
+pub mod music;
+pub mod netplay;
+pub mod observer;
+pub mod palette;
+pub mod profiler;
+pub mod recorder;
+pub mod savestate;
+pub mod screenshot;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod serial;
+pub mod testrom;
+pub mod tilepack;
+pub mod worker;
 pub(crate) mod io;
 pub(crate) mod gameboy;
 mod ppu;
 mod rom;
 mod cpu;
 mod mmu;
+mod checksum;
+#[cfg(feature = "rom-database")]
+mod romdb;
 
-use std::io::Error;
+use std::borrow::Cow;
+use std::mem;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
 
+use accuracy::EmulationConfig;
+use audio::{AudioChannel, ChannelTaps, HighPassFilter, Resampler, ResamplerQuality};
 use cartridge::Cartridge;
+use cheats::{CheatCode, CheatId, CheatManager};
+use coverage::OpcodeCoverage;
+use debugger::{Debugger, DeveloperMode, TileMap};
+use determinism::StateHashes;
 use gameboy::GameBoy;
+use infrared::IrTransceiver;
+use inputmacro::{InputMacro, MacroPlayer};
+use inputmap::Turbo;
+use iolog::IoLog;
+use music::VgmLog;
+use mmu::MMU;
+use profiler::Profiler;
+use movie::{Movie, MovieSession};
+use observer::EmulatorObserver;
+use display::ScanlineHook;
+use palette::ColorPalette;
+use screenshot::{SheetOptions, View};
+use serial::SerialDevice;
+use recorder::{Recorder, RecordingOptions};
+use audiodump::{AudioDump, AudioDumpOptions};
+use savestate::{SaveState, SlotInfo, StateSlots};
+use tilepack::TileReplacements;
+pub use cpu::cpu::IllegalOpcodePolicy;
+pub use error::{CartridgeError, EmulationError};
+pub use io::io::UnknownIoPolicy;
+pub use io::lcd::{LCDMode, LcdOffDisplay, PpuTiming};
 use io::{interrupts::{Interruption, Interrupts}, joypad::Joypad};
 use wasm_bindgen::prelude::*;
 
@@ -33,10 +104,53 @@ pub struct EmulationStep {
 }
 
 #[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum Button {
     Up, Down, Left, Right, Start, Select, A, B
 }
 
+impl Button {
+    // In declaration order, the bit order of netplay::button_mask
+    pub const ALL: [Button; 8] = [Button::Up, Button::Down, Button::Left, Button::Right, Button::Start, Button::Select, Button::A, Button::B];
+
+    // "A", "start", ignoring case
+    pub fn from_name(name: &str) -> Option<Button> {
+        let button = match name.to_ascii_lowercase().as_str() {
+            "up" => Button::Up,
+            "down" => Button::Down,
+            "left" => Button::Left,
+            "right" => Button::Right,
+            "start" => Button::Start,
+            "select" => Button::Select,
+            "a" => Button::A,
+            "b" => Button::B,
+            _ => return None,
+        };
+        Some(button)
+    }
+}
+
+// Both restart the boot ROM with the cartridge RAM and clock kept. A soft reset
+// keeps the contents of work RAM, VRAM and OAM like a short power loss would,
+// a hard reset starts from cleared memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResetKind {
+    Soft, Hard
+}
+
+// Everything that comes from outside the emulated hardware, see Emulation::send_input.
+// Pokes and cheat changes go through it too, so movies replay them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputEvent {
+    Pressed(Button),
+    Released(Button),
+    Reset(ResetKind),
+    Poke { address: u16, value: u8 },
+    AddCheat(CheatCode),
+    RemoveCheat(CheatId),
+    EnableCheat(CheatId, bool),
+}
+
 #[wasm_bindgen]
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -58,9 +172,33 @@ pub struct GameBoyFrame {
 pub struct Emulation {
   pub(crate) gameboy: GameBoy,
   pub running: bool,
-  pub total_cycles: u64
+  paused: bool,
+  // Sent while paused, applied when the next frame starts
+  latched_inputs: Vec<InputEvent>,
+  pub total_cycles: u64,
+  // Frames run by skip_frame and step, restored with the savestates
+  pub total_frames: u64,
+  movie: Option<MovieSession>,
+  input_macro: Option<MacroPlayer>,
+  // Buttons mashed by the core, with the frames run since their turbo was set
+  turbo: Vec<(Button, Turbo, u64)>,
+  state_hashes: Option<StateHashes>,
+  slot_directory: Option<PathBuf>,
 }
 
+// Frontends run the core in its own thread and batch runners move it between
+// workers, so everything it owns must stay Send. Hooks given by the embedder
+// (observer, serial devices, mappers) are required to be Send by their traits.
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    assert_send::<Emulation>();
+    assert_send::<GameBoy>();
+    assert_send::<SaveState>();
+    assert_send::<audio::AudioProducer>();
+    assert_send::<audio::AudioConsumer>();
+    assert_send::<worker::EmulatorHandle>();
+};
+
 #[wasm_bindgen]
 pub struct EmulationWasm {
   pub(crate) gameboy: GameBoy,
@@ -70,19 +208,118 @@ pub struct EmulationWasm {
 
 impl Emulation {
   pub fn new(cartridge: Option<Cartridge>) -> Self {
-      let gameboy = GameBoy::new(cartridge);
+      Emulation::with_config(cartridge, EmulationConfig::default())
+  }
+
+  // EmulationConfig::profile gives the presets of accuracy::AccuracyProfile
+  pub fn with_config(cartridge: Option<Cartridge>, config: EmulationConfig) -> Self {
+      let gameboy = GameBoy::new(cartridge, config);
       Emulation { 
           gameboy,
           running: false,
-          total_cycles: 0
+          paused: false,
+          latched_inputs: Vec::new(),
+          total_cycles: 0,
+          total_frames: 0,
+          movie: None,
+          input_macro: None,
+          turbo: Vec::new(),
+          state_hashes: None,
+          slot_directory: StateSlots::default_directory(),
       }
   } 
 
+  // Without a cartridge only the boot ROM runs: the logo scrolls as a black
+  // block and the boot ROM locks up when the logo check fails, like real hardware
+  pub fn has_cartridge(&self) -> bool {
+      self.gameboy.cartridge.is_some()
+  }
+
   pub fn start(&mut self) {
     self.running = true;
   }
 
-  pub fn step(&mut self) -> Result<EmulationStep,Error> {
+  pub fn step(&mut self) -> Result<EmulationStep, EmulationError> {
+      self.skip_frame()?;
+      Ok(self.current_step())
+  }
+
+  // While paused the frontend stops calling step and moves on a frame at a
+  // time with frame_advance, for TAS work and glitches that last a frame.
+  // Inputs sent while paused are held back and reach the joypad together when
+  // the next frame starts, so a game reads the same buttons whenever they were
+  // pressed during the pause, and a movie records them on that frame.
+  pub fn pause(&mut self) {
+      self.paused = true;
+  }
+
+  // The inputs sent during the pause are applied before the next frame
+  pub fn resume(&mut self) {
+      self.paused = false;
+  }
+
+  pub fn is_paused(&self) -> bool {
+      self.paused
+  }
+
+  // Runs exactly one frame with the buttons held now, pausing first when the
+  // emulation wasn't
+  pub fn frame_advance(&mut self) -> Result<EmulationStep, EmulationError> {
+      self.pause();
+      self.step()
+  }
+
+  pub(crate) fn current_step(&self) -> EmulationStep {
+      let framebuffer = self.gameboy.frame().into_owned();
+      let tiledata = self.gameboy.tiledata();
+      let background = self.gameboy.background();
+
+      EmulationStep { framebuffer, tiledata, background }
+  }
+
+  // The last complete frame, without copying it while the LCD is on. With
+  // skip_frame, frontends present every frame without the copies of step.
+  pub fn screen(&self) -> Cow<'_, GameBoyFrame> {
+      self.gameboy.frame()
+  }
+
+  // The screen for captures, with the sprites a game flickers on alternate
+  // frames shown together instead of half of them
+  pub fn stable_screen(&self) -> Cow<'_, GameBoyFrame> {
+      self.gameboy.stable_frame()
+  }
+
+  // Runs a frame like step without copying the screens, for frames the
+  // frontend doesn't present
+  pub fn skip_frame(&mut self) -> Result<(), EmulationError> {
+      // Every event of the core happens inside the span of its frame
+      let _frame = tracing::debug_span!(target: "yagabor", "frame", number = self.total_frames).entered();
+      for input in mem::take(&mut self.latched_inputs) {
+          self.deliver_input(input);
+      }
+      if let Some(movie) = &mut self.movie {
+          for input in movie.frame_inputs() {
+              self.apply_input(input);
+          }
+      }
+      if let Some(player) = &mut self.input_macro {
+          for input in player.frame_inputs() {
+              self.deliver_input(input);
+          }
+      }
+      let mut turbo_inputs = Vec::new();
+      for (button, turbo, frame) in &mut self.turbo {
+          let phase = *frame % turbo.period as u64;
+          *frame += 1;
+          if phase == 0 {
+              turbo_inputs.push(InputEvent::Pressed(*button));
+          } else if phase == turbo.pressed as u64 {
+              turbo_inputs.push(InputEvent::Released(*button));
+          }
+      }
+      for input in turbo_inputs {
+          self.deliver_input(input);
+      }
 
       let mut frame_cycles = 0;           
       
@@ -101,28 +338,566 @@ impl Emulation {
               }
           }
       }
+      self.total_frames += 1;
+      if let Some(movie) = &mut self.movie {
+          movie.frame_done();
+      }
+      if let Some(hashes) = &mut self.state_hashes {
+          hashes.record(&self.gameboy, self.total_cycles);
+      }
+      Ok(())
+  }
 
-      let framebuffer = self.gameboy.frame();
-      let tiledata = self.gameboy.tiledata();
-      let background = self.gameboy.background();
+  // Headless runs for benchmarks and tools, movies and macros still play
+  pub fn run_frames(&mut self, frames: u64) -> Result<(), EmulationError> {
+      for _ in 0..frames {
+          self.skip_frame()?;
+      }
+      Ok(())
+  }
+
+  pub fn save_state(&self) -> SaveState {
+      SaveState::capture(&self.gameboy, self.total_cycles, self.total_frames)
+  }
+
+  // Fails when the state was made with another ROM
+  pub fn load_state(&mut self, state: &SaveState) -> Result<(), Error> {
+      state.restore(&mut self.gameboy)?;
+      self.total_cycles = state.total_cycles();
+      self.total_frames = state.metadata().frames;
+      Ok(())
+  }
+
+  // Slots are kept per ROM under this directory, StateSlots::default_directory unless changed
+  pub fn set_slot_directory<P: Into<PathBuf>>(&mut self, directory: P) {
+      self.slot_directory = Some(directory.into());
+  }
+
+  pub fn state_slots(&self) -> Result<StateSlots, Error> {
+      let cartridge = self.gameboy.cartridge.as_ref().ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Savestate slots need a cartridge"))?;
+      let directory = self.slot_directory.as_ref().ok_or_else(|| Error::new(ErrorKind::NotFound, "No directory for the savestate slots"))?;
+      Ok(StateSlots::new(directory, cartridge.crc32()))
+  }
+
+  pub fn save_slot(&self, slot: u8) -> Result<(), Error> {
+      self.state_slots()?.save(slot, &self.save_state())
+  }
+
+  pub fn load_slot(&mut self, slot: u8) -> Result<(), Error> {
+      let state = self.state_slots()?.load(slot)?;
+      self.load_state(&state)
+  }
 
-      Ok(EmulationStep { framebuffer, tiledata, background })  
+  pub fn list_slots(&self) -> Result<Vec<SlotInfo>, Error> {
+      self.state_slots()?.list()
   }
 
   pub fn button_pressed(&mut self, b: Button) {
-      Joypad::button_pressed(&mut self.gameboy, b);
-      Interrupts::turnon(&mut self.gameboy, Interruption::Joypad);
+      self.send_input(InputEvent::Pressed(b));
   } 
 
   pub fn button_released(&mut self, b: Button) {
-      Joypad::button_released(&mut self.gameboy, b);
+      self.send_input(InputEvent::Released(b));
+  }
+
+  pub fn reset(&mut self, kind: ResetKind) {
+      self.send_input(InputEvent::Reset(kind));
+  }
+
+  // Inputs take effect immediately, or when the next frame starts while paused,
+  // and are recorded with the frame about to run. They are ignored while a
+  // movie is playing.
+  pub fn send_input(&mut self, input: InputEvent) {
+      if self.paused {
+          self.latched_inputs.push(input);
+      } else {
+          self.deliver_input(input);
+      }
+  }
+
+  fn deliver_input(&mut self, input: InputEvent) {
+      match &mut self.movie {
+          Some(movie) if movie.is_playing() => return,
+          Some(movie) => movie.record(input),
+          None => {},
+      }
+      self.apply_input(input);
+  }
+
+  pub(crate) fn apply_input(&mut self, input: InputEvent) {
+      match input {
+          InputEvent::Pressed(button) => {
+              Joypad::button_pressed(&mut self.gameboy, button);
+              Interrupts::turnon(&mut self.gameboy, Interruption::Joypad);
+          },
+          InputEvent::Released(button) => Joypad::button_released(&mut self.gameboy, button),
+          InputEvent::Reset(kind) => self.gameboy.reset(kind),
+          InputEvent::Poke { address, value } => self.gameboy.queue_write(address, value),
+          InputEvent::AddCheat(code) => {
+              self.gameboy.cheats.add(code);
+          },
+          InputEvent::RemoveCheat(id) => {
+              self.gameboy.cheats.remove(id);
+          },
+          InputEvent::EnableCheat(id, enabled) => self.gameboy.cheats.set_enabled(id, enabled),
+      }
+  }
+
+  // Hard resets the emulation and records the inputs from there, replacing
+  // the movie being recorded or played
+  pub fn start_movie_recording(&mut self) {
+      self.gameboy.reset(ResetKind::Hard);
+      self.movie = Some(MovieSession::Recording(Movie::new(self.save_state())));
+  }
+
+  // Fails when the movie was recorded with another ROM
+  pub fn play_movie(&mut self, movie: Movie) -> Result<(), Error> {
+      self.load_state(movie.start())?;
+      self.movie = Some(MovieSession::Playing { movie, frame: 0, next_event: 0 });
+      Ok(())
+  }
+
+  // False once the movie has been played to the end, live input works again then
+  pub fn is_playing_movie(&self) -> bool {
+      self.movie.as_ref().is_some_and(MovieSession::is_playing)
+  }
+
+  pub fn is_recording_movie(&self) -> bool {
+      matches!(self.movie, Some(MovieSession::Recording(_)))
+  }
+
+  // The recorded movie, or the one that was playing
+  pub fn stop_movie(&mut self) -> Option<Movie> {
+      self.movie.take().map(MovieSession::into_movie)
+  }
+
+  // The inputs of the macro are sent like live ones before each frame, from the next
+  // one on. It replaces the macro playing, whose buttons stay held.
+  pub fn play_macro(&mut self, input_macro: InputMacro) {
+      self.input_macro = Some(MacroPlayer::new(input_macro));
+  }
+
+  // False once the macro has been played to the end, repeating ones play until stopped
+  pub fn is_playing_macro(&self) -> bool {
+      self.input_macro.as_ref().is_some_and(MacroPlayer::is_playing)
+  }
+
+  // Buttons the macro was holding are released
+  pub fn stop_macro(&mut self) {
+      if let Some(player) = self.input_macro.take() {
+          for input in player.stop() {
+              self.send_input(input);
+          }
+      }
+  }
+
+  // The core presses and releases the button from the next frame on, like the
+  // turbo switch of a third party controller, until stop_turbo. Unlike the
+  // turbo bindings of inputmap::InputMap it needs nothing held on the host.
+  pub fn set_turbo(&mut self, button: Button, turbo: Turbo) {
+      self.turbo.retain(|(turbo_button, _, _)| *turbo_button != button);
+      self.turbo.push((button, turbo, 0));
+  }
+
+  pub fn turbo(&self, button: Button) -> Option<Turbo> {
+      self.turbo.iter().find(|(turbo_button, _, _)| *turbo_button == button).map(|(_, turbo, _)| *turbo)
+  }
+
+  // The button is left released
+  pub fn stop_turbo(&mut self, button: Button) {
+      if self.turbo(button).is_some() {
+          self.turbo.retain(|(turbo_button, _, _)| *turbo_button != button);
+          self.send_input(InputEvent::Released(button));
+      }
+  }
+
+  // Writes the cartridge RAM to its save file, for cartridges with a battery
+  pub fn save_battery<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+      match &self.gameboy.cartridge {
+          Some(cartridge) => cartridge.save_battery(path),
+          None => Ok(()),
+      }
+  }
+
+  pub fn screenshot<P: AsRef<Path>>(&self, path: P, palette: &ColorPalette) -> Result<(), Error> {
+      self.export_view(View::Screen, path, palette)
+  }
+
+  pub fn export_view<P: AsRef<Path>>(&self, view: View, path: P, palette: &ColorPalette) -> Result<(), Error> {
+      let frame = match view {
+          View::Screen => self.gameboy.frame(),
+          View::StableScreen => self.gameboy.stable_frame(),
+          View::Background => Cow::Owned(self.gameboy.background()),
+          View::Window => Cow::Owned(self.gameboy.window()),
+          View::TileData => Cow::Owned(self.gameboy.tiledata()),
+      };
+      screenshot::save_png(&frame, palette, path)
+  }
+
+  // The tile data and both tile maps as sprite sheets in a directory: tiles.png,
+  // map_9800.png and map_9C00.png, and the same in the binary formats of
+  // RGBDS: tiles.2bpp, map_9800.tilemap and map_9C00.tilemap. Returns the
+  // files written.
+  pub fn export_sheets<P: AsRef<Path>>(&mut self, directory: P, palette: &ColorPalette, options: SheetOptions) -> Result<Vec<PathBuf>, Error> {
+      std::fs::create_dir_all(&directory)?;
+      let debugger = self.debugger();
+      let sheets = [
+          ("tiles.png", debugger.tile_data()),
+          ("map_9800.png", debugger.tile_map(TileMap::Low)),
+          ("map_9C00.png", debugger.tile_map(TileMap::High)),
+      ];
+      let binaries = [
+          ("tiles.2bpp", debugger.tile_data_2bpp()),
+          ("map_9800.tilemap", debugger.tile_map_indices(TileMap::Low)),
+          ("map_9C00.tilemap", debugger.tile_map_indices(TileMap::High)),
+      ];
+      let mut paths = Vec::new();
+      for (name, frame) in sheets {
+          let path = directory.as_ref().join(name);
+          screenshot::save_sheet(&frame, palette, options, &path)?;
+          paths.push(path);
+      }
+      for (name, bytes) in binaries {
+          let path = directory.as_ref().join(name);
+          std::fs::write(&path, bytes)?;
+          paths.push(path);
+      }
+      Ok(paths)
+  }
+
+  // Records every frame into an animated GIF until stop_recording is called
+  pub fn start_recording<P: AsRef<Path>>(&mut self, path: P, options: RecordingOptions) -> Result<(), Error> {
+      let recorder = Recorder::new(path, SCREEN_WIDTH, SCREEN_HEIGHT, options)?;
+      if let Some(previous) = self.gameboy.recorder.replace(recorder) {
+          previous.finish()?;
+      }
+      Ok(())
+  }
+
+  pub fn stop_recording(&mut self) -> Result<(), Error> {
+      match self.gameboy.recorder.take() {
+          Some(recorder) => recorder.finish(),
+          None => Ok(())
+      }
+  }
+
+  pub fn is_recording(&self) -> bool {
+      self.gameboy.recorder.is_some()
+  }
+
+  // Logs the IO register writes from now on, replacing the current log
+  pub fn start_io_log(&mut self) {
+      self.gameboy.io_log = Some(IoLog::new());
+  }
+
+  pub fn stop_io_log(&mut self) -> Option<IoLog> {
+      self.gameboy.io_log.take()
+  }
+
+  // Logs the writes to the PPU registers of the last frames with their dot,
+  // replacing the current IO log. io_log().timeline() draws them.
+  pub fn start_event_log(&mut self, frames: u64) {
+      self.gameboy.io_log = Some(IoLog::ppu_events(frames));
+  }
+
+  // Logs the sound register writes from now on as a VGM file, starting from
+  // the current state of the APU. Replaces the current log.
+  pub fn start_vgm_log(&mut self) {
+      self.gameboy.vgm_log = Some(VgmLog::new(&self.gameboy.io.apu.restore_writes()));
+  }
+
+  pub fn stop_vgm_log(&mut self) -> Option<VgmLog> {
+      self.gameboy.vgm_log.take()
+  }
+
+  pub fn is_logging_vgm(&self) -> bool {
+      self.gameboy.vgm_log.is_some()
+  }
+
+  pub fn io_log(&self) -> Option<&IoLog> {
+      self.gameboy.io_log.as_ref()
+  }
+
+  // Counts the cycles of every instruction from now on, replacing the current profile
+  pub fn start_profiler(&mut self) {
+      self.gameboy.profiler = Some(Profiler::new());
+  }
+
+  pub fn stop_profiler(&mut self) -> Option<Profiler> {
+      self.gameboy.profiler.take()
+  }
+
+  pub fn profiler(&self) -> Option<&Profiler> {
+      self.gameboy.profiler.as_ref()
+  }
+
+  // Hashes the state after every frame from now on, to check that two runs
+  // are the same, see determinism::StateHashes
+  pub fn start_state_hashes(&mut self) {
+      self.state_hashes = Some(StateHashes::new());
+  }
+
+  pub fn stop_state_hashes(&mut self) -> Option<StateHashes> {
+      self.state_hashes.take()
+  }
+
+  pub fn state_hashes(&self) -> Option<&StateHashes> {
+      self.state_hashes.as_ref()
+  }
+
+  // Counts the opcodes executed from now on, replacing the current counts
+  pub fn start_opcode_coverage(&mut self) {
+      self.gameboy.opcode_coverage = Some(OpcodeCoverage::new());
+  }
+
+  pub fn stop_opcode_coverage(&mut self) -> Option<OpcodeCoverage> {
+      self.gameboy.opcode_coverage.take()
+  }
+
+  pub fn set_unknown_io_policy(&mut self, policy: UnknownIoPolicy) {
+      self.gameboy.io.unknown_policy = policy;
+  }
+
+  // Debugging aids for homebrew that only emulators have: bytes written to
+  // FF7F are kept for take_debug_output, and LD B,B or the illegal opcode ED
+  // stop the emulation with EmulationError::Breakpoint before running
+  pub fn set_developer_mode(&mut self, enabled: bool) {
+      self.gameboy.developer.enabled = enabled;
+  }
+
+  pub fn is_developer_mode(&self) -> bool {
+      self.gameboy.developer.enabled
+  }
+
+  // What the game printed since the last call
+  pub fn take_debug_output(&mut self) -> String {
+      DeveloperMode::take_output(&mut self.gameboy)
+  }
+
+  // Starts producing interleaved stereo samples at the host rate, collect them with take_audio
+  pub fn enable_audio(&mut self, sample_rate: u32, quality: ResamplerQuality) {
+      self.gameboy.audio = Some(Resampler::new(sample_rate, quality, self.gameboy.high_pass_filter));
+  }
+
+  // HighPassFilter::Dmg by default, Off leaves the DC offset in the samples
+  pub fn set_high_pass_filter(&mut self, filter: HighPassFilter) {
+      self.gameboy.high_pass_filter = filter;
+      if let Some(audio) = &mut self.gameboy.audio {
+          audio.set_high_pass_filter(filter);
+      }
+  }
+
+  pub fn disable_audio(&mut self) {
+      self.gameboy.audio = None;
+  }
+
+  // Samples produced since the last call, empty when audio is disabled
+  pub fn take_audio(&mut self) -> Vec<f32> {
+      self.gameboy.audio.as_mut().map(Resampler::take_samples).unwrap_or_default()
+  }
+
+  // Feed the fill level of the host buffer (0 to 1) once per frame to keep it
+  // from running dry or overflowing, see audio::AudioProducer::fill_level
+  pub fn adjust_audio_rate(&mut self, buffer_fill: f32) {
+      if let Some(audio) = &mut self.gameboy.audio {
+          audio.adjust_rate(buffer_fill);
+      }
+  }
+
+  // A muted channel keeps playing, it is only left out of the samples
+  pub fn set_channel_muted(&mut self, channel: AudioChannel, muted: bool) {
+      self.gameboy.io.apu.muted[channel as usize] = muted;
+  }
+
+  pub fn is_channel_muted(&self, channel: AudioChannel) -> bool {
+      self.gameboy.io.apu.muted[channel as usize]
+  }
+
+  // Mutes every other channel, None unmutes them all
+  pub fn solo_channel(&mut self, channel: Option<AudioChannel>) {
+      for other in AudioChannel::ALL {
+          self.set_channel_muted(other, channel.is_some_and(|channel| channel != other));
+      }
+  }
+
+  // Starts sampling the output of every channel on its own at the given rate,
+  // for oscilloscope views. Works with audio disabled.
+  pub fn start_channel_taps(&mut self, sample_rate: u32) {
+      self.gameboy.channel_taps = Some(ChannelTaps::new(sample_rate));
+  }
+
+  // Samples since the last call, indexed like AudioChannel::ALL, empty when not started
+  pub fn take_channel_taps(&mut self) -> [Vec<f32>; 4] {
+      self.gameboy.channel_taps.as_mut().map(ChannelTaps::take_samples).unwrap_or_default()
+  }
+
+  pub fn stop_channel_taps(&mut self) {
+      self.gameboy.channel_taps = None;
+  }
+
+  // Writes the sound to a WAV file until stop_audio_dump is called, with the
+  // high-pass filter of the audio output. Replaces the current dump.
+  pub fn start_audio_dump<P: AsRef<Path>>(&mut self, path: P, options: AudioDumpOptions) -> Result<(), Error> {
+      let dump = AudioDump::new(path, options, self.gameboy.high_pass_filter)?;
+      if let Some(previous) = self.gameboy.audio_dump.replace(dump) {
+          previous.finish()?;
+      }
+      Ok(())
+  }
+
+  // Reports the errors that happened while writing
+  pub fn stop_audio_dump(&mut self) -> Result<(), Error> {
+      match self.gameboy.audio_dump.take() {
+          Some(dump) => dump.finish(),
+          None => Ok(())
+      }
+  }
+
+  pub fn is_dumping_audio(&self) -> bool {
+      self.gameboy.audio_dump.is_some()
+  }
+
+  pub fn set_illegal_opcode_policy(&mut self, policy: IllegalOpcodePolicy) {
+      self.gameboy.cpu.illegal_opcode_policy = policy;
+  }
+
+  // Emulates the volume change when NRx2 is written while a channel plays,
+  // some tracker music relies on it
+  pub fn set_zombie_mode(&mut self, enabled: bool) {
+      self.gameboy.io.apu.zombie_mode = enabled;
+  }
+
+  // Accuracy option: VRAM reads return 0xFF while a line is drawn (mode 3) and OAM
+  // while it is scanned too (modes 2 and 3), writes are ignored. Off by default
+  // because it hides errors in homebrew that real hardware would show as garbage.
+  pub fn set_vram_oam_blocking(&mut self, enabled: bool) {
+      self.gameboy.io.lcd.block_cpu_access = enabled;
+  }
+
+  // Accuracy option: the DMG OAM corruption bug, for the test ROMs that check it.
+  // Off by default, most games avoid it and the others only flicker.
+  pub fn set_oam_bug(&mut self, enabled: bool) {
+      self.gameboy.io.lcd.oam_bug = enabled;
+  }
+
+  // The accuracy options in use, including the ones changed one by one
+  pub fn emulation_config(&self) -> EmulationConfig {
+      EmulationConfig::of(&self.gameboy)
+  }
+
+  pub fn set_emulation_config(&mut self, config: EmulationConfig) {
+      config.apply(&mut self.gameboy);
+  }
+
+  // Replaces the DMG boot ROM built in, also after resets. It only runs
+  // again after a reset once the emulation has started.
+  pub fn set_boot_rom(&mut self, data: &[u8]) -> Result<(), Error> {
+      if data.len() != 0x100 {
+          return Err(Error::new(ErrorKind::InvalidData, format!("A boot ROM is 256 bytes, not {}", data.len())));
+      }
+      MMU::set_boot_rom(&mut self.gameboy, data.to_vec());
+      Ok(())
+  }
+
+  // Registers, LCD, timer, mapper and the top of the stack as text, for bug reports
+  pub fn dump_state_text(&self) -> String {
+      self.gameboy.dump_state_text()
+  }
+
+  pub fn set_lcd_off_display(&mut self, display: LcdOffDisplay) {
+      self.gameboy.io.lcd.off_display = display;
+  }
+
+  // Graphics mod drawn over the game's tiles, None goes back to the originals
+  pub fn set_tile_replacements(&mut self, replacements: Option<TileReplacements>) {
+      self.gameboy.tile_replacements = replacements;
+  }
+
+  // Sees and may change every line before it goes into the frame, kept across resets
+  pub fn set_scanline_hook(&mut self, hook: Box<dyn ScanlineHook>) {
+      self.gameboy.scanline_hook = Some(hook);
+  }
+
+  pub fn take_scanline_hook(&mut self) -> Option<Box<dyn ScanlineHook>> {
+      self.gameboy.scanline_hook.take()
+  }
+
+  pub fn set_observer(&mut self, observer: Box<dyn EmulatorObserver>) {
+      self.gameboy.observer = Some(observer);
+  }
+
+  pub fn take_observer(&mut self) -> Option<Box<dyn EmulatorObserver>> {
+      self.gameboy.observer.take()
+  }
+
+  // The write is not performed immediately but at the start of the next VBlank
+  pub fn poke(&mut self, address: u16, value: u8) {
+      self.send_input(InputEvent::Poke { address, value });
+  }
+
+  // Game Genie codes apply on the next ROM read, GameShark codes at every VBlank.
+  // Cheat changes aren't held back by a pause, but are ignored while a movie plays
+  // like live input.
+  pub fn add_cheat(&mut self, code: &str) -> Result<CheatId, Error> {
+      let code = CheatCode::parse(code)?;
+      if self.is_playing_movie() {
+          return Err(Error::new(ErrorKind::InvalidInput, "Cheats can't change while a movie plays"));
+      }
+      let id = self.gameboy.cheats.next_id();
+      self.deliver_input(InputEvent::AddCheat(code));
+      Ok(id)
+  }
+
+  pub fn remove_cheat(&mut self, id: CheatId) {
+      self.deliver_input(InputEvent::RemoveCheat(id));
+  }
+
+  pub fn set_cheat_enabled(&mut self, id: CheatId, enabled: bool) {
+      self.deliver_input(InputEvent::EnableCheat(id, enabled));
+  }
+
+  pub fn cheats(&self) -> &CheatManager {
+      &self.gameboy.cheats
+  }
+
+  // Runs a single instruction, so the PPU timing can be followed between frames
+  pub fn step_instruction(&mut self) -> Result<u64, EmulationError> {
+      let cycles = u64::from(self.gameboy.tick()?);
+      self.total_cycles += cycles;
+      Ok(cycles)
+  }
+
+  // Plugs a device in the link port, replacing the previous one
+  pub fn connect_serial(&mut self, device: Box<dyn SerialDevice>) {
+      self.gameboy.serial_device = Some(device);
+  }
+
+  pub fn disconnect_serial(&mut self) -> Option<Box<dyn SerialDevice>> {
+      self.gameboy.serial_device.take()
+  }
+
+  // Puts something in front of the infrared port, RP at FF56 reads as unmapped without it
+  pub fn connect_infrared(&mut self, transceiver: Box<dyn IrTransceiver>) {
+      self.gameboy.ir_transceiver = Some(transceiver);
+  }
+
+  pub fn disconnect_infrared(&mut self) -> Option<Box<dyn IrTransceiver>> {
+      self.gameboy.ir_transceiver.take()
+  }
+
+  pub fn debugger(&mut self) -> Debugger<'_> {
+      Debugger::new(&mut self.gameboy)
+  }
+
+  pub fn ppu_timing(&self) -> PpuTiming {
+      self.gameboy.ppu_timing()
   }
 }
 
 #[wasm_bindgen]
 impl EmulationWasm {
   pub fn new() -> Self {
-    let gameboy = GameBoy::new(None);
+    let gameboy = GameBoy::new(None, EmulationConfig::default());
     let screenbuffer: Vec<ColoredPixel> = Vec::new();
     EmulationWasm { 
         gameboy,
@@ -136,25 +911,14 @@ impl EmulationWasm {
 	} 
 
   pub fn step(&mut self) -> Result<JsValue,JsValue> {
-
-    let mut frame_cycles = 0;           
-    
-    while frame_cycles < CPU_CYCLES_PER_FRAME {
-        let gb_step_res = self.gameboy.tick();
-
-        match gb_step_res {
-            Ok(cycles) => {
-                let executed_cycles = u64::from(cycles);
-                frame_cycles += executed_cycles as usize;
-                self.total_cycles += executed_cycles;
-            },
-            Err(_) => {
-                return Err(JsValue::from_str("error"))
-            }
-        }
+    match self.gameboy.run_frames(1) {
+        Ok(cycles) => self.total_cycles += cycles,
+        Err(_) => return Err(JsValue::from_str("error")),
     }
 
-    self.screenbuffer = self.gameboy.frame().buffer.clone();
+    // The allocation is kept, JS holds a pointer into it
+    self.screenbuffer.clear();
+    self.screenbuffer.extend_from_slice(&self.gameboy.frame().buffer);
 
     Ok(JsValue::from_str(&self.total_cycles.to_string()))  
   }
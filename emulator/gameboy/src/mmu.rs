@@ -1,6 +1,6 @@
-use crate::{ppu::*, rom::*, cartridge::Cartridge};
+use crate::{ppu::*, rom::*, cartridge::Cartridge, savestate::{StateReader, StateWriter}};
 
-use super::{io::{io::IO, interrupts::Interrupts}, gameboy::GameBoy};
+use super::{io::{io::IO, interrupts::Interrupts, lcd::LCD}, gameboy::GameBoy, debugger::{Diagnostics, Watchpoints}};
 
 pub(crate) type Address = u16;
 
@@ -44,6 +44,23 @@ const HRAM_SIZE: usize = (HRAM_END - HRAM_BEGIN + 1) as usize;
 
 pub(crate) const INTERRUPT_ENABLE_ADDRESS: Address = 0xFFFF;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Region {
+    BootRom,
+    Rom0,
+    RomN,
+    Vram,
+    ExternalRam,
+    Wram,
+    // Mirror of C000-DDFF
+    Echo,
+    Oam,
+    Unusable,
+    Io,
+    Hram,
+    InterruptEnable,
+}
+
 pub(crate) struct MMU {
     is_boot_rom_mapped: bool,
     bootrom: ROM,
@@ -54,7 +71,10 @@ pub(crate) struct MMU {
 
 impl MMU {
     pub fn new() -> Self {
-        let bootrom = ROM::dmg();
+        MMU::with_boot_rom(ROM::dmg())
+    }
+
+    fn with_boot_rom(bootrom: ROM) -> Self {
         MMU { 
             is_boot_rom_mapped: true, 
             bootrom,
@@ -64,62 +84,99 @@ impl MMU {
         }
     }
 
-    pub(super) fn read_byte(gb: &GameBoy, address: Address) -> u8 {
+    pub(crate) fn region(gb: &GameBoy, address: Address) -> Region {
         match address {
-            GAMEROM_0_BEGIN ..= GAMEROM_0_END => {
-                match address {
-                    BOOT_BEGIN ..= BOOT_END => {
-                        if gb.mmu.is_boot_rom_mapped {
-                            gb.mmu.bootrom.read_byte(address)
-                        }else{
-                            Cartridge::read_byte(&gb, address)
-                        }
-                    },
-                    _ => Cartridge::read_byte(&gb, address)
-                }
-            },
-            GAMEROM_N_BEGIN ..= GAMEROM_N_END => Cartridge::read_byte(&gb, address),
-            VRAM_BEGIN ..= VRAM_END => PPU::read_byte(gb, address),
-            EXTRAM_BEGIN ..= EXTRAM_END => MMU::read_eram(gb, address),
-            WRAM_BEGIN ..= WRAM_END => MMU::read_wram(gb, address),
+            BOOT_BEGIN ..= BOOT_END if gb.mmu.is_boot_rom_mapped => Region::BootRom,
+            GAMEROM_0_BEGIN ..= GAMEROM_0_END => Region::Rom0,
+            GAMEROM_N_BEGIN ..= GAMEROM_N_END => Region::RomN,
+            VRAM_BEGIN ..= VRAM_END => Region::Vram,
+            EXTRAM_BEGIN ..= EXTRAM_END => Region::ExternalRam,
+            WRAM_BEGIN ..= WRAM_END => Region::Wram,
+            ERAM_BEGIN ..= ERAM_END => Region::Echo,
+            OAM_BEGIN ..= OAM_END => Region::Oam,
+            NOTUSABLE_BEGIN ..= NOTUSABLE_END => Region::Unusable,
+            IO_BEGIN ..= IO_END => Region::Io,
+            HRAM_BEGIN ..= HRAM_END => Region::Hram,
+            INTERRUPT_ENABLE_ADDRESS => Region::InterruptEnable,
+        }
+    }
+
+    pub(super) fn read_byte(gb: &GameBoy, address: Address) -> u8 {
+        match MMU::region(gb, address) {
+            Region::BootRom => gb.mmu.bootrom.read_byte(address),
+            Region::Rom0 | Region::RomN => MMU::read_rom(gb, address),
+            Region::Vram if LCD::vram_blocked(gb) => 0xFF,
+            Region::Vram => PPU::read_vram(gb, address),
+            Region::ExternalRam => MMU::read_eram(gb, address),
+            Region::Wram => MMU::read_wram(gb, address),
             // ERAM is mapped to WRAM, so we change its base
-            ERAM_BEGIN ..= ERAM_END => MMU::read_wram(gb, address-ERAM_BEGIN+WRAM_BEGIN),
-            OAM_BEGIN ..= OAM_END => PPU::read_byte(gb, address),
+            Region::Echo => MMU::read_wram(gb, address-ERAM_BEGIN+WRAM_BEGIN),
+            Region::Oam | Region::Unusable if LCD::oam_blocked(gb) => 0xFF,
+            Region::Oam => PPU::read_oam(gb, address),
+            // DMG reads 0 while the PPU doesn't block OAM
             // https://gbdev.io/pandocs/Memory_Map.html#fea0-feff-range
-            NOTUSABLE_BEGIN ..= NOTUSABLE_END => 0xFF,
-            IO_BEGIN ..= IO_END => IO::read_byte(gb, address),
-            HRAM_BEGIN ..= HRAM_END => MMU::read_hram(gb, address),
-            INTERRUPT_ENABLE_ADDRESS => Interrupts::read_enable(gb),
+            Region::Unusable => 0x00,
+            Region::Io => IO::read_byte(gb, address),
+            Region::Hram => MMU::read_hram(gb, address),
+            Region::InterruptEnable => Interrupts::read_enable(gb),
         }
     }
 
     pub(super) fn write_byte(gb: &mut GameBoy, address: Address, value: u8) {
-        match address {
-            GAMEROM_0_BEGIN ..= GAMEROM_0_END => {
-                //panic!("Writing in ROM {:x} is not possible", address);
-            },
-            GAMEROM_N_BEGIN ..= GAMEROM_N_END => {
-                //panic!("Writing in ROM {:x} is not possible", address);
-            },
-            VRAM_BEGIN ..= VRAM_END => PPU::write_byte(gb, address, value),
-            EXTRAM_BEGIN ..= EXTRAM_END => MMU::write_eram(gb, address, value),
-            WRAM_BEGIN ..= WRAM_END => MMU::write_wram(gb, address, value),
+        let region = MMU::region(gb, address);
+        if matches!(region, Region::Io | Region::InterruptEnable) {
+            tracing::trace!(target: "yagabor::mmu", address, value, pc = gb.cpu.pc, "IO write");
+            let (scanline, dot) = (LCD::read_scanline(gb), LCD::dot(gb));
+            if let Some(log) = &mut gb.io_log {
+                log.record(scanline, dot, gb.cpu.pc, address, value);
+            }
+        }
+        if Diagnostics::enabled(gb) {
+            Diagnostics::write(gb, region, address, value);
+        }
+        let watched = !gb.watchpoints.watchpoints.is_empty() && Watchpoints::watches(gb, address);
+        let old = if watched { MMU::read_byte(gb, address) } else { 0 };
+
+        match region {
+            // Writes to the boot ROM area still reach the cartridge controller
+            Region::BootRom | Region::Rom0 | Region::RomN => Cartridge::write_byte(gb, address, value),
+            Region::Vram if LCD::vram_blocked(gb) => {},
+            Region::Vram => PPU::write_vram(gb, address, value),
+            Region::ExternalRam => MMU::write_eram(gb, address, value),
+            Region::Wram => MMU::write_wram(gb, address, value),
             // ERAM is mapped to WRAM, so we change its base
-            ERAM_BEGIN ..= ERAM_END => MMU::write_wram(gb, address-ERAM_BEGIN+WRAM_BEGIN, value),
-            OAM_BEGIN ..= OAM_END => PPU::write_byte(gb, address, value),
-            NOTUSABLE_BEGIN ..= NOTUSABLE_END => {},
-            IO_BEGIN ..= IO_END => IO::write_byte(gb, address, value),
-            HRAM_BEGIN ..= HRAM_END => MMU::write_hram(gb, address, value),
-            INTERRUPT_ENABLE_ADDRESS => Interrupts::write_enable(gb, value)
+            Region::Echo => MMU::write_wram(gb, address-ERAM_BEGIN+WRAM_BEGIN, value),
+            Region::Oam if LCD::oam_blocked(gb) => {},
+            Region::Oam => PPU::write_oam(gb, address, value),
+            Region::Unusable => {},
+            Region::Io => IO::write_byte(gb, address, value),
+            Region::Hram => MMU::write_hram(gb, address, value),
+            Region::InterruptEnable => Interrupts::write_enable(gb, value)
         };
+
+        if watched {
+            Watchpoints::check(gb, address, old);
+        }
     }
 
     fn read_wram(gb: &GameBoy, address: Address) -> u8 {
         gb.mmu.wram[address as usize - WRAM_BEGIN as usize]
     }
 
+    // Game Genie codes patch the bytes as they are read from the cartridge
+    fn read_rom(gb: &GameBoy, address: Address) -> u8 {
+        gb.cheats.patch_rom(address, Cartridge::read_byte(gb, address))
+    }
+
     fn read_eram(gb: &GameBoy, address: Address) -> u8 {
-        gb.mmu.eram[address as usize - EXTRAM_BEGIN as usize]
+        if Cartridge::handles_ram(gb) {
+            Cartridge::read_ram(gb, address)
+        }else if gb.cartridge.is_none() {
+            // Nothing drives the bus without a cartridge
+            0xFF
+        }else{
+            gb.mmu.eram[address as usize - EXTRAM_BEGIN as usize]
+        }
     }
 
     fn read_hram(gb: &GameBoy, address: Address) -> u8 {
@@ -131,22 +188,59 @@ impl MMU {
     }
 
     fn write_eram(gb: &mut GameBoy, address: Address, value: u8) {
-        gb.mmu.eram[address as usize - EXTRAM_BEGIN as usize] = value;
+        if Cartridge::handles_ram(gb) {
+            Cartridge::write_ram(gb, address, value);
+        }else if gb.cartridge.is_none() {
+            return;
+        }else{
+            gb.mmu.eram[address as usize - EXTRAM_BEGIN as usize] = value;
+        }
+        if let Some(observer) = &mut gb.observer {
+            observer.on_save_ram_write(address, value);
+        }
     }
 
     fn write_hram(gb: &mut GameBoy, address: Address, value: u8) {
         gb.mmu.hram[address as usize - HRAM_BEGIN as usize] = value;
     }
 
+    pub(crate) fn save_state(gb: &GameBoy, writer: &mut StateWriter) {
+        writer.bool(gb.mmu.is_boot_rom_mapped);
+        writer.bytes(&gb.mmu.eram);
+        writer.bytes(&gb.mmu.wram);
+        writer.bytes(&gb.mmu.hram);
+    }
+
+    pub(crate) fn load_state(gb: &mut GameBoy, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        gb.mmu.is_boot_rom_mapped = reader.bool()?;
+        gb.mmu.eram = reader.array()?;
+        gb.mmu.wram = reader.array()?;
+        gb.mmu.hram = reader.array()?;
+        Ok(())
+    }
+
+    pub(crate) fn set_boot_rom(gb: &mut GameBoy, data: Vec<u8>) {
+        gb.mmu.bootrom = ROM::new(data);
+    }
+
+    // Hard resets clear the memory, the boot ROM stays
+    pub(crate) fn clear(gb: &mut GameBoy) {
+        let bootrom = std::mem::replace(&mut gb.mmu.bootrom, ROM::new(Vec::new()));
+        gb.mmu = MMU::with_boot_rom(bootrom);
+    }
+
     pub(crate) fn set_boot_mapping(gb: &mut GameBoy, value: u8) {
+        if gb.mmu.is_boot_rom_mapped && value != 0 {
+            tracing::debug!(target: "yagabor::mmu", pc = gb.cpu.pc, "boot ROM unmapped");
+        }
         gb.mmu.is_boot_rom_mapped = value == 0;
     }
 
     pub(super) fn read_next_byte(gb: &GameBoy, address: Address) -> u8 {
-        MMU::read_byte(&gb, address+1)
+        MMU::read_byte(&gb, address.wrapping_add(1))
     }
     
     pub(super) fn read_next_word(gb: &GameBoy, address: Address) -> u16 {
-        ((MMU::read_byte(&gb, address+2) as u16) << 8) | (MMU::read_byte(&gb, address+1) as u16)
+        ((MMU::read_byte(&gb, address.wrapping_add(2)) as u16) << 8) | (MMU::read_byte(&gb, address.wrapping_add(1)) as u16)
     }
 }
\ No newline at end of file
@@ -0,0 +1,193 @@
+use std::{fs, io::{Error, ErrorKind}, path::Path};
+
+use crate::{cheats::{CheatCode, CheatId}, savestate::{SaveState, StateReader, StateWriter}, Button, InputEvent, ResetKind};
+
+const MAGIC: &[u8; 4] = b"YGBM";
+const VERSION: u8 = 2;
+
+// An input applied right before the given frame, counted from the start of the movie
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MovieEvent {
+    pub frame: u64,
+    pub input: InputEvent,
+}
+
+// Everything sent through Emulation::send_input while recording, resets, pokes and
+// cheat changes included. The movie starts with a savestate taken after a hard reset,
+// so the cartridge RAM, clock and the cheats on are replayed as they were.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Movie {
+    start: SaveState,
+    frames: u64,
+    events: Vec<MovieEvent>,
+}
+
+pub(crate) enum MovieSession {
+    Recording(Movie),
+    Playing { movie: Movie, frame: u64, next_event: usize },
+}
+
+impl Movie {
+    pub(crate) fn new(start: SaveState) -> Self {
+        Movie { start, frames: 0, events: Vec::new() }
+    }
+
+    pub fn start(&self) -> &SaveState {
+        &self.start
+    }
+
+    // Length of the movie
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn events(&self) -> &[MovieEvent] {
+        &self.events
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = StateWriter::default();
+        writer.bytes(MAGIC);
+        writer.u8(VERSION);
+        writer.vec(self.start.as_bytes());
+        writer.u64(self.frames);
+        writer.u32(self.events.len() as u32);
+        for event in &self.events {
+            writer.u64(event.frame);
+            match event.input {
+                InputEvent::Pressed(button) => {
+                    writer.u8(0);
+                    writer.u8(button as u8);
+                },
+                InputEvent::Released(button) => {
+                    writer.u8(1);
+                    writer.u8(button as u8);
+                },
+                InputEvent::Reset(reset) => {
+                    writer.u8(2);
+                    writer.u8(reset as u8);
+                },
+                InputEvent::Poke { address, value } => {
+                    writer.u8(3);
+                    writer.u16(address);
+                    writer.u8(value);
+                },
+                InputEvent::AddCheat(code) => {
+                    writer.u8(4);
+                    code.save(&mut writer);
+                },
+                InputEvent::RemoveCheat(id) => {
+                    writer.u8(5);
+                    writer.u32(id as u32);
+                },
+                InputEvent::EnableCheat(id, enabled) => {
+                    writer.u8(6);
+                    writer.u32(id as u32);
+                    writer.bool(enabled);
+                },
+            }
+        }
+        writer.into_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let invalid = || Error::new(ErrorKind::InvalidData, "Invalid movie event");
+        let mut reader = StateReader::new(bytes);
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "Not a movie"));
+        }
+        if reader.u8()? != VERSION {
+            return Err(Error::new(ErrorKind::InvalidData, "Movie made by another version"));
+        }
+        let start = SaveState::from_bytes(reader.vec()?.to_vec())?;
+        let frames = reader.u64()?;
+
+        let count = reader.u32()?;
+        let mut events = Vec::new();
+        for _ in 0..count {
+            let frame = reader.u64()?;
+            let input = match reader.u8()? {
+                0 => InputEvent::Pressed(button_from_u8(reader.u8()?).ok_or_else(invalid)?),
+                1 => InputEvent::Released(button_from_u8(reader.u8()?).ok_or_else(invalid)?),
+                2 => match reader.u8()? {
+                    0 => InputEvent::Reset(ResetKind::Soft),
+                    1 => InputEvent::Reset(ResetKind::Hard),
+                    _ => return Err(invalid()),
+                },
+                3 => InputEvent::Poke { address: reader.u16()?, value: reader.u8()? },
+                4 => InputEvent::AddCheat(CheatCode::load(&mut reader)?),
+                5 => InputEvent::RemoveCheat(reader.u32()? as CheatId),
+                6 => InputEvent::EnableCheat(reader.u32()? as CheatId, reader.bool()?),
+                _ => return Err(invalid()),
+            };
+            events.push(MovieEvent { frame, input });
+        }
+        Ok(Movie { start, frames, events })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Movie::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write(path, self.to_bytes())
+    }
+}
+
+impl MovieSession {
+    // Recorded inputs are tagged with the frame about to run
+    pub(crate) fn record(&mut self, input: InputEvent) {
+        if let MovieSession::Recording(movie) = self {
+            movie.events.push(MovieEvent { frame: movie.frames, input });
+        }
+    }
+
+    // Live input is ignored until the movie has been played to the end
+    pub(crate) fn is_playing(&self) -> bool {
+        match self {
+            MovieSession::Recording(_) => false,
+            MovieSession::Playing { movie, frame, .. } => *frame < movie.frames,
+        }
+    }
+
+    // Inputs to apply before running the next frame
+    pub(crate) fn frame_inputs(&mut self) -> Vec<InputEvent> {
+        let MovieSession::Playing { movie, frame, next_event } = self else {
+            return Vec::new();
+        };
+        let mut inputs = Vec::new();
+        while let Some(event) = movie.events.get(*next_event).filter(|event| event.frame <= *frame) {
+            inputs.push(event.input);
+            *next_event += 1;
+        }
+        inputs
+    }
+
+    pub(crate) fn frame_done(&mut self) {
+        match self {
+            MovieSession::Recording(movie) => movie.frames += 1,
+            MovieSession::Playing { frame, .. } => *frame += 1,
+        }
+    }
+
+    pub(crate) fn into_movie(self) -> Movie {
+        match self {
+            MovieSession::Recording(movie) | MovieSession::Playing { movie, .. } => movie,
+        }
+    }
+}
+
+fn button_from_u8(value: u8) -> Option<Button> {
+    let button = match value {
+        0 => Button::Up,
+        1 => Button::Down,
+        2 => Button::Left,
+        3 => Button::Right,
+        4 => Button::Start,
+        5 => Button::Select,
+        6 => Button::A,
+        7 => Button::B,
+        _ => return None,
+    };
+    Some(button)
+}
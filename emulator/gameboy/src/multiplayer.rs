@@ -0,0 +1,75 @@
+use std::{io::{Error, ErrorKind}, sync::{Arc, Mutex}};
+
+use crate::{cartridge::Cartridge, error::EmulationError, serial::{FourPlayerAdapter, ADAPTER_PLAYERS}, Emulation, EmulationStep, CPU_CYCLES_PER_FRAME};
+
+// The instances take turns running this many cycles, a scanline, so none of
+// them gets far ahead of the others between two bytes of the adapter
+const SLICE_CYCLES: usize = 456;
+// Cycles between two bytes clocked by the adapter, about 4 KB/s. The RATE
+// chosen by player 1 is not emulated.
+const BYTE_CYCLES: usize = 1024;
+
+// Two to four Game Boys in one process, linked through a DMG-07 adapter for
+// the four player modes of F-1 Race, Faceball 2000 and the like. Frames run in
+// lockstep, the adapter clocks one byte to every player at a time.
+pub struct MultiplayerSession {
+    adapter: Arc<Mutex<FourPlayerAdapter>>,
+    players: Vec<Emulation>,
+    // Cycles each player ran past the end of the last slice, instructions
+    // don't stop on it
+    overshoot: Vec<usize>,
+    // Cycles since the adapter clocked the last byte
+    byte_cycles: usize,
+}
+
+impl MultiplayerSession {
+    // Every player needs its own copy of the cartridge, for its save RAM
+    pub fn new(cartridges: Vec<Cartridge>) -> Result<Self, Error> {
+        if !(2..=ADAPTER_PLAYERS).contains(&cartridges.len()) {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("The adapter links 2 to {} players, not {}", ADAPTER_PLAYERS, cartridges.len())));
+        }
+        let adapter = FourPlayerAdapter::new();
+        let players: Vec<Emulation> = cartridges.into_iter().enumerate().map(|(index, cartridge)| {
+            let mut emu = Emulation::new(Some(cartridge));
+            emu.connect_serial(Box::new(FourPlayerAdapter::port(&adapter, index + 1)));
+            emu
+        }).collect();
+        let overshoot = vec![0; players.len()];
+        Ok(MultiplayerSession { adapter, players, overshoot, byte_cycles: 0 })
+    }
+
+    pub fn players(&self) -> usize {
+        self.players.len()
+    }
+
+    // Player 1 to 4, for its inputs and debugging
+    pub fn player(&mut self, player: usize) -> &mut Emulation {
+        &mut self.players[player - 1]
+    }
+
+    pub fn adapter(&self) -> Arc<Mutex<FourPlayerAdapter>> {
+        Arc::clone(&self.adapter)
+    }
+
+    // Runs a frame on every Game Boy, the steps are in player order
+    pub fn step(&mut self) -> Result<Vec<EmulationStep>, EmulationError> {
+        let mut frame_cycles = 0;
+        while frame_cycles < CPU_CYCLES_PER_FRAME {
+            for (emu, overshoot) in self.players.iter_mut().zip(self.overshoot.iter_mut()) {
+                let mut cycles = *overshoot;
+                while cycles < SLICE_CYCLES {
+                    cycles += emu.step_instruction()? as usize;
+                }
+                *overshoot = cycles - SLICE_CYCLES;
+            }
+            frame_cycles += SLICE_CYCLES;
+
+            self.byte_cycles += SLICE_CYCLES;
+            if self.byte_cycles >= BYTE_CYCLES {
+                self.adapter.lock().unwrap().clock();
+                self.byte_cycles -= BYTE_CYCLES;
+            }
+        }
+        Ok(self.players.iter().map(Emulation::current_step).collect())
+    }
+}
@@ -0,0 +1,182 @@
+use std::{fs, io::{Error, ErrorKind}, path::Path};
+
+use crate::{cartridge::Cartridge, gameboy::GameBoy, mmu::MMU, Emulation, ResetKind};
+
+const MAGIC: &[u8; 3] = b"GBS";
+const HEADER_SIZE: usize = 0x70;
+const TEXT_SIZE: usize = 32;
+// Code can't be loaded over the vectors and the header of the player
+const MIN_LOAD_ADDRESS: u16 = 0x0400;
+const BANK_SIZE: usize = 0x4000;
+
+// Where the player stub goes in the cartridge built around the music
+const RST_VECTORS: [u16; 8] = [0x00, 0x08, 0x10, 0x18, 0x20, 0x28, 0x30, 0x38];
+const VBLANK_VECTOR: usize = 0x40;
+const TIMER_VECTOR: usize = 0x50;
+const CARTRIDGE_TYPE: usize = 0x147;
+const ROM_SIZE: usize = 0x148;
+const RAM_SIZE: usize = 0x149;
+const MAIN: u16 = 0x0150;
+const INTERRUPT: u16 = 0x0160;
+
+// MBC1 with 8 KiB of RAM, which some rips use as work memory
+const MBC1_RAM: u8 = 0x02;
+const RAM_8K: u8 = 0x02;
+
+const TIMER_INTERRUPT: u8 = 0x04;
+const VBLANK_INTERRUPT: u8 = 0x01;
+
+// Game Boy Sound System rips: the music code and data of a game with the
+// addresses of its init and play routines. They play on a cartridge made
+// around them, whose stub calls init with the song number and then play at
+// every VBlank, or at every timer interrupt when the timer is set.
+// https://ocremix.org/info/GBS_Format_Specification
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GbsFile {
+    pub title: String,
+    pub author: String,
+    pub copyright: String,
+    pub song_count: u8,
+    // Counted from 0, the header counts from 1
+    pub first_song: u8,
+    pub load_address: u16,
+    pub init_address: u16,
+    pub play_address: u16,
+    pub stack_pointer: u16,
+    pub timer_modulo: u8,
+    pub timer_control: u8,
+    data: Vec<u8>,
+}
+
+impl GbsFile {
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let invalid = |reason: &str| Error::new(ErrorKind::InvalidData, reason.to_string());
+        if bytes.len() < HEADER_SIZE || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(invalid("Not a GBS file"));
+        }
+        let word = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+        let text = |offset: usize| {
+            let text = &bytes[offset..offset + TEXT_SIZE];
+            let end = text.iter().position(|byte| *byte == 0).unwrap_or(TEXT_SIZE);
+            String::from_utf8_lossy(&text[..end]).trim().to_string()
+        };
+        let gbs = GbsFile {
+            title: text(0x10),
+            author: text(0x30),
+            copyright: text(0x50),
+            song_count: bytes[0x04],
+            first_song: bytes[0x05].saturating_sub(1),
+            load_address: word(0x06),
+            init_address: word(0x08),
+            play_address: word(0x0A),
+            stack_pointer: word(0x0C),
+            timer_modulo: bytes[0x0E],
+            timer_control: bytes[0x0F],
+            data: bytes[HEADER_SIZE..].to_vec(),
+        };
+        if gbs.load_address < MIN_LOAD_ADDRESS || gbs.load_address >= 0x8000 {
+            return Err(invalid("The load address of the GBS file is outside of the ROM"));
+        }
+        if gbs.song_count == 0 {
+            return Err(invalid("The GBS file has no songs"));
+        }
+        Ok(gbs)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        GbsFile::parse(&fs::read(path)?)
+    }
+
+    fn uses_timer(&self) -> bool {
+        self.timer_control & 0x04 != 0
+    }
+
+    // The music at its load address, banked like a MBC1 game, below the stub
+    pub fn cartridge(&self) -> Result<Cartridge, Error> {
+        let size = (self.load_address as usize + self.data.len()).next_power_of_two().max(2 * BANK_SIZE);
+        let mut rom = vec![0; size];
+        let load = self.load_address as usize;
+        rom[load..load + self.data.len()].copy_from_slice(&self.data);
+
+        // Restarts go to the same offset from the load address
+        for vector in RST_VECTORS {
+            write_jump(&mut rom, vector as usize, self.load_address + vector);
+        }
+        write_jump(&mut rom, VBLANK_VECTOR, INTERRUPT);
+        write_jump(&mut rom, TIMER_VECTOR, INTERRUPT);
+        rom[CARTRIDGE_TYPE] = MBC1_RAM;
+        rom[ROM_SIZE] = (size / (2 * BANK_SIZE)).trailing_zeros() as u8;
+        rom[RAM_SIZE] = RAM_8K;
+
+        // The song is in A, the interrupts are set up before
+        let [init_low, init_high] = self.init_address.to_le_bytes();
+        let main = [
+            0xCD, init_low, init_high, // CALL init
+            0xAF,                      // XOR A
+            0xE0, 0x0F,                // LDH (IF),A
+            0xFB,                      // EI
+            0x76,                      // HALT
+            0x18, 0xFD,                // JR HALT
+        ];
+        rom[MAIN as usize..MAIN as usize + main.len()].copy_from_slice(&main);
+        let [play_low, play_high] = self.play_address.to_le_bytes();
+        let interrupt = [
+            0xF5, 0xC5, 0xD5, 0xE5,    // PUSH AF, BC, DE, HL
+            0xCD, play_low, play_high, // CALL play
+            0xE1, 0xD1, 0xC1, 0xF1,    // POP HL, DE, BC, AF
+            0xD9,                      // RETI
+        ];
+        rom[INTERRUPT as usize..INTERRUPT as usize + interrupt.len()].copy_from_slice(&interrupt);
+
+        Ok(Cartridge::from_data(rom)?)
+    }
+
+    // An emulation playing the first song
+    pub fn emulation(&self) -> Result<Emulation, Error> {
+        let mut emu = Emulation::new(Some(self.cartridge()?));
+        self.start_song(&mut emu, self.first_song)?;
+        Ok(emu)
+    }
+
+    // Starts a song from the beginning on the emulation made by emulation(),
+    // from a hard reset. Songs are counted from 0.
+    pub fn start_song(&self, emu: &mut Emulation, song: u8) -> Result<(), Error> {
+        if song >= self.song_count {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("There are {} songs, counted from 0", self.song_count)));
+        }
+        let gb = &mut emu.gameboy;
+        gb.reset(ResetKind::Hard);
+        MMU::set_boot_mapping(gb, 1);
+        // RAM enabled, the bank after the first one mapped
+        MMU::write_byte(gb, 0x0000, 0x0A);
+        MMU::write_byte(gb, 0x2000, 0x01);
+        // Sound on at full volume on both sides, like after the boot ROM
+        MMU::write_byte(gb, 0xFF26, 0x80);
+        MMU::write_byte(gb, 0xFF25, 0xFF);
+        MMU::write_byte(gb, 0xFF24, 0x77);
+        self.set_up_interrupts(gb);
+
+        gb.cpu.regs.a = song;
+        gb.cpu.sp = self.stack_pointer;
+        gb.cpu.pc = MAIN;
+        gb.cpu.ime = false;
+        Ok(())
+    }
+
+    fn set_up_interrupts(&self, gb: &mut GameBoy) {
+        if self.uses_timer() {
+            MMU::write_byte(gb, 0xFF06, self.timer_modulo);
+            MMU::write_byte(gb, 0xFF07, self.timer_control & 0x07);
+            MMU::write_byte(gb, 0xFFFF, TIMER_INTERRUPT);
+        } else {
+            // VBlank needs the LCD on
+            MMU::write_byte(gb, 0xFF40, 0x80);
+            MMU::write_byte(gb, 0xFFFF, VBLANK_INTERRUPT);
+        }
+    }
+}
+
+fn write_jump(rom: &mut [u8], at: usize, target: u16) {
+    let [low, high] = target.to_le_bytes();
+    rom[at..at + 3].copy_from_slice(&[0xC3, low, high]);
+}
@@ -0,0 +1,5 @@
+mod gbs;
+mod vgm;
+
+pub use gbs::GbsFile;
+pub use vgm::VgmLog;
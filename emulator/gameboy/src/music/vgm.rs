@@ -0,0 +1,91 @@
+use std::{fs, io::Error, path::Path};
+
+use crate::{mmu::Address, CPU_CLOCK_HZ};
+
+// https://vgmrips.net/wiki/VGM_Specification
+const MAGIC: &[u8; 4] = b"Vgm ";
+// The first version with the Game Boy DMG
+const VERSION: u32 = 0x161;
+const HEADER_SIZE: usize = 0x100;
+const EOF_OFFSET: usize = 0x04;
+const VERSION_OFFSET: usize = 0x08;
+const TOTAL_SAMPLES_OFFSET: usize = 0x18;
+const DATA_OFFSET: usize = 0x34;
+const DMG_CLOCK_OFFSET: usize = 0x80;
+
+// Waits are counted in samples at 44.1 kHz whatever the chip
+const SAMPLE_RATE: u64 = 44100;
+const GB_WRITE: u8 = 0xB3;
+const WAIT: u8 = 0x61;
+const END: u8 = 0x66;
+
+const APU_BEGIN: Address = 0xFF10;
+
+// The writes to the sound registers and wave RAM with the time between them,
+// which VGM players replay on their own APU. The log starts with the
+// registers as they were, so it plays from any point of the game.
+pub struct VgmLog {
+    commands: Vec<u8>,
+    cycles: u64,
+    // Waited in the commands so far
+    samples: u64,
+}
+
+impl VgmLog {
+    // The writes bringing a powered off APU to its current state
+    pub(crate) fn new(initial_writes: &[(Address, u8)]) -> Self {
+        let mut log = VgmLog { commands: Vec::new(), cycles: 0, samples: 0 };
+        for (address, value) in initial_writes {
+            log.write(*address, *value);
+        }
+        log
+    }
+
+    pub(crate) fn advance(&mut self, cycles: u32) {
+        self.cycles += cycles as u64;
+    }
+
+    pub(crate) fn write(&mut self, address: Address, value: u8) {
+        self.wait();
+        self.commands.extend_from_slice(&[GB_WRITE, (address - APU_BEGIN) as u8, value]);
+    }
+
+    // Catches up with the emulated time, in samples
+    fn wait(&mut self) {
+        let target = self.cycles * SAMPLE_RATE / CPU_CLOCK_HZ as u64;
+        while self.samples < target {
+            let samples = (target - self.samples).min(u16::MAX as u64);
+            self.commands.push(WAIT);
+            self.commands.extend_from_slice(&(samples as u16).to_le_bytes());
+            self.samples += samples;
+        }
+    }
+
+    // Length of the log in samples at 44.1 kHz
+    pub fn samples(&self) -> u64 {
+        self.cycles * SAMPLE_RATE / CPU_CLOCK_HZ as u64
+    }
+
+    // The log ends with the time elapsed since the last write
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut log = VgmLog { commands: self.commands.clone(), ..*self };
+        log.wait();
+
+        let mut bytes = vec![0; HEADER_SIZE];
+        bytes.extend_from_slice(&log.commands);
+        bytes.push(END);
+        let mut set = |offset: usize, value: u32| bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        set(VERSION_OFFSET, VERSION);
+        set(TOTAL_SAMPLES_OFFSET, log.samples as u32);
+        set(DATA_OFFSET, (HEADER_SIZE - DATA_OFFSET) as u32);
+        set(DMG_CLOCK_OFFSET, CPU_CLOCK_HZ as u32);
+        let length = bytes.len();
+        bytes[EOF_OFFSET..EOF_OFFSET + 4].copy_from_slice(&((length - EOF_OFFSET) as u32).to_le_bytes());
+        bytes[..MAGIC.len()].copy_from_slice(MAGIC);
+        bytes
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write(path, self.to_bytes())
+    }
+}
@@ -0,0 +1,291 @@
+use std::{collections::VecDeque, io::{Error, ErrorKind}, net::{ToSocketAddrs, UdpSocket}, sync::mpsc::{self, Receiver, Sender}};
+
+use crate::{checksum::crc32, savestate::SaveState, Button, Emulation, EmulationStep, InputEvent};
+
+// Frames that can be run ahead of the peer's inputs, the session waits for
+// the peer when it falls further behind
+pub const MAX_ROLLBACK: u32 = 8;
+// Frames between two comparisons of the emulation state with the peer
+const CHECKSUM_INTERVAL: u32 = 60;
+// Inputs not acknowledged yet are resent with every frame, so lost packets
+// don't matter, up to this many at once
+const MAX_INPUTS_PER_PACKET: usize = 255;
+
+const INPUTS_PACKET: u8 = 0;
+const CHECKSUM_PACKET: u8 = 1;
+
+pub fn button_mask(buttons: &[Button]) -> u8 {
+    buttons.iter().fold(0, |mask, button| mask | 1 << *button as u8)
+}
+
+// Carries the packets between the two peers, they may be lost or reordered
+pub trait NetplayTransport: Send {
+    fn send(&mut self, packet: &[u8]) -> Result<(), Error>;
+    // None when nothing has arrived, it must not block
+    fn receive(&mut self) -> Result<Option<Vec<u8>>, Error>;
+}
+
+pub struct UdpTransport {
+    socket: UdpSocket,
+}
+
+impl UdpTransport {
+    pub fn connect<A: ToSocketAddrs, B: ToSocketAddrs>(local: A, peer: B) -> Result<Self, Error> {
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(peer)?;
+        socket.set_nonblocking(true)?;
+        Ok(UdpTransport { socket })
+    }
+}
+
+impl NetplayTransport for UdpTransport {
+    fn send(&mut self, packet: &[u8]) -> Result<(), Error> {
+        match self.socket.send(packet) {
+            // The peer isn't listening yet
+            Err(error) if error.kind() == ErrorKind::ConnectionRefused => Ok(()),
+            result => result.map(|_| ()),
+        }
+    }
+
+    fn receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        let mut buffer = [0; 1024];
+        match self.socket.recv(&mut buffer) {
+            Ok(size) => Ok(Some(buffer[..size].to_vec())),
+            Err(error) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::ConnectionRefused) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+// Both ends of a connection inside the process, for two instances running side by side
+pub struct ChannelTransport {
+    sender: Sender<Vec<u8>>,
+    receiver: Receiver<Vec<u8>>,
+}
+
+impl ChannelTransport {
+    pub fn pair() -> (ChannelTransport, ChannelTransport) {
+        let (first_sender, first_receiver) = mpsc::channel();
+        let (second_sender, second_receiver) = mpsc::channel();
+        (ChannelTransport { sender: first_sender, receiver: second_receiver }, ChannelTransport { sender: second_sender, receiver: first_receiver })
+    }
+}
+
+impl NetplayTransport for ChannelTransport {
+    fn send(&mut self, packet: &[u8]) -> Result<(), Error> {
+        // A closed peer is the same as a lost packet
+        let _ = self.sender.send(packet.to_vec());
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.receiver.try_recv().ok())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NetplayStats {
+    // Next frame to run
+    pub frame: u32,
+    // Frames with the inputs of the peer
+    pub confirmed_frames: u32,
+    pub rollbacks: u64,
+    // Last frame whose state matched the peer's
+    pub verified_frame: Option<u32>,
+}
+
+struct SavedFrame {
+    frame: u32,
+    state: SaveState,
+    // Buttons applied before the frame
+    held: u8,
+}
+
+// Two players on one cartridge, the buttons of both are held together. Local
+// inputs are delayed by a few frames and sent to the peer; frames run with a
+// guess of the peer's inputs and are run again from a savestate when the guess
+// was wrong. The states are compared with checksums now and then to catch desyncs.
+// Both peers must start from the same state, like a hard reset of the same ROM.
+pub struct NetplaySession {
+    transport: Box<dyn NetplayTransport>,
+    delay: u32,
+    frame: u32,
+    // Inputs by frame, the local ones are known delay frames ahead
+    local: Vec<u8>,
+    remote: Vec<u8>,
+    // The remote inputs the frames were run with
+    used: Vec<u8>,
+    // Local frames the peer has
+    peer_ack: u32,
+    held: u8,
+    states: VecDeque<SavedFrame>,
+    next_checksum: u32,
+    checksums: VecDeque<(u32, u32)>,
+    peer_checksums: VecDeque<(u32, u32)>,
+    stats: NetplayStats,
+}
+
+impl NetplaySession {
+    pub fn new(transport: Box<dyn NetplayTransport>, delay: u32) -> Self {
+        NetplaySession {
+            transport,
+            delay,
+            frame: 0,
+            local: vec![0; delay as usize],
+            remote: Vec::new(),
+            used: Vec::new(),
+            peer_ack: 0,
+            held: 0,
+            states: VecDeque::new(),
+            next_checksum: 0,
+            checksums: VecDeque::new(),
+            peer_checksums: VecDeque::new(),
+            stats: NetplayStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> NetplayStats {
+        NetplayStats { frame: self.frame, confirmed_frames: self.remote.len() as u32, ..self.stats }
+    }
+
+    // Runs the next frame with the buttons held locally, None while waiting for the
+    // peer. Fails when the peers have diverged.
+    pub fn advance_frame(&mut self, emu: &mut Emulation, buttons: u8) -> Result<Option<EmulationStep>, Error> {
+        if self.local.len() as u32 <= self.frame + self.delay {
+            self.local.push(buttons);
+        }
+        self.send_inputs()?;
+        if let Some(frame) = self.receive()? {
+            self.run_again(emu, frame)?;
+        }
+        self.check_sync()?;
+
+        if self.frame >= self.remote.len() as u32 + MAX_ROLLBACK {
+            return Ok(None);
+        }
+        self.run_frame(emu, true)
+    }
+
+    fn send_inputs(&mut self) -> Result<(), Error> {
+        let start = self.peer_ack as usize;
+        let inputs = &self.local[start.min(self.local.len())..];
+        let inputs = &inputs[..inputs.len().min(MAX_INPUTS_PER_PACKET)];
+        let mut packet = vec![INPUTS_PACKET];
+        packet.extend_from_slice(&(self.remote.len() as u32).to_le_bytes());
+        packet.extend_from_slice(&(start as u32).to_le_bytes());
+        packet.push(inputs.len() as u8);
+        packet.extend_from_slice(inputs);
+        self.transport.send(&packet)
+    }
+
+    // The first frame that ran with a wrong guess of the peer's inputs
+    fn receive(&mut self) -> Result<Option<u32>, Error> {
+        let mut mispredicted = None;
+        while let Some(packet) = self.transport.receive()? {
+            let invalid = || Error::new(ErrorKind::InvalidData, "Invalid netplay packet");
+            let word = |offset: usize| packet.get(offset..offset + 4).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap())).ok_or_else(invalid);
+            match packet.first() {
+                Some(&INPUTS_PACKET) => {
+                    self.peer_ack = self.peer_ack.max(word(1)?);
+                    let start = word(5)? as usize;
+                    let count = *packet.get(9).ok_or_else(invalid)? as usize;
+                    let inputs = packet.get(10..10 + count).ok_or_else(invalid)?;
+                    for (frame, &input) in (start..).zip(inputs) {
+                        if frame != self.remote.len() {
+                            continue;
+                        }
+                        self.remote.push(input);
+                        let wrong_guess = self.used.get(frame).is_some_and(|&used| used != input);
+                        if wrong_guess && mispredicted.is_none() {
+                            mispredicted = Some(frame as u32);
+                        }
+                    }
+                },
+                Some(&CHECKSUM_PACKET) => self.peer_checksums.push_back((word(1)?, word(5)?)),
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(mispredicted)
+    }
+
+    // Back to the state before the frame, then up to where the emulation was
+    fn run_again(&mut self, emu: &mut Emulation, from: u32) -> Result<(), Error> {
+        let index = self.states.iter().position(|saved| saved.frame == from)
+            .ok_or_else(|| Error::other(format!("No state to roll back to frame {}", from)))?;
+        let saved = &self.states[index];
+        emu.load_state(&saved.state)?;
+        self.held = saved.held;
+        self.states.truncate(index);
+
+        let end = self.frame;
+        self.frame = from;
+        while self.frame < end {
+            self.run_frame(emu, false)?;
+        }
+        self.stats.rollbacks += 1;
+        Ok(())
+    }
+
+    fn run_frame(&mut self, emu: &mut Emulation, render: bool) -> Result<Option<EmulationStep>, Error> {
+        let frame = self.frame as usize;
+        // The peer is guessed to keep holding what it held last
+        let remote = self.remote.get(frame).or(self.remote.last()).copied().unwrap_or(0);
+        self.used.truncate(frame);
+        self.used.push(remote);
+        self.states.push_back(SavedFrame { frame: self.frame, state: emu.save_state(), held: self.held });
+
+        let buttons = self.local[frame] | remote;
+        for (bit, button) in Button::ALL.iter().enumerate() {
+            let (before, now) = (self.held & 1 << bit != 0, buttons & 1 << bit != 0);
+            if now && !before {
+                emu.apply_input(InputEvent::Pressed(*button));
+            } else if before && !now {
+                emu.apply_input(InputEvent::Released(*button));
+            }
+        }
+        self.held = buttons;
+
+        let step = if render { Some(emu.step()?) } else { emu.skip_frame().map(|_| None)? };
+        self.frame += 1;
+        Ok(step)
+    }
+
+    // States are final once every frame before them has the peer's inputs
+    fn check_sync(&mut self) -> Result<(), Error> {
+        while self.next_checksum <= self.remote.len() as u32 && self.next_checksum < self.frame {
+            let frame = self.next_checksum;
+            if let Some(saved) = self.states.iter().find(|saved| saved.frame == frame) {
+                let checksum = crc32(saved.state.as_bytes());
+                let mut packet = vec![CHECKSUM_PACKET];
+                packet.extend_from_slice(&frame.to_le_bytes());
+                packet.extend_from_slice(&checksum.to_le_bytes());
+                self.transport.send(&packet)?;
+                self.checksums.push_back((frame, checksum));
+            }
+            self.next_checksum += CHECKSUM_INTERVAL;
+        }
+
+        while let Some(&(frame, checksum)) = self.peer_checksums.front() {
+            let Some(&(_, local)) = self.checksums.iter().find(|(local_frame, _)| *local_frame == frame) else {
+                // Not there yet, or the state was gone before it could be checked
+                if frame < self.next_checksum {
+                    self.peer_checksums.pop_front();
+                    continue;
+                }
+                break;
+            };
+            if local != checksum {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Netplay desync at frame {}", frame)));
+            }
+            self.stats.verified_frame = Some(frame);
+            self.peer_checksums.pop_front();
+            self.checksums.retain(|(local_frame, _)| *local_frame > frame);
+        }
+
+        let keep_from = (self.remote.len() as u32).min(self.next_checksum);
+        while self.states.front().is_some_and(|saved| saved.frame < keep_from) {
+            self.states.pop_front();
+        }
+        Ok(())
+    }
+}
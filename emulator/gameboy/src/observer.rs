@@ -0,0 +1,30 @@
+use crate::{debugger::{Diagnostic, RegionId, StackWarning}, GameBoyFrame};
+
+// Embedders implement this trait to be notified of emulation events instead of polling.
+// Every method has an empty default so only the interesting ones need to be written.
+// Observers move with the emulation across threads, share state with Arc and Mutex.
+pub trait EmulatorObserver: Send {
+    // A complete frame is available, called when the LCD enters VBlank
+    fn on_vblank(&mut self, _frame: &GameBoyFrame) {}
+
+    // Samples the audio output produced, interleaved stereo at the rate given to
+    // Emulation::enable_audio. Only called while the audio is enabled, take_audio
+    // still returns the same samples.
+    fn on_audio(&mut self, _samples: &[i16]) {}
+
+    // A byte was sent through the serial port
+    fn on_serial(&mut self, _data: u8) {}
+
+    // The program wrote to cartridge RAM (0xA000-0xBFFF)
+    fn on_save_ram_write(&mut self, _address: u16, _value: u8) {}
+
+    // A region watched with Debugger::watch_region changed during the last frame
+    fn on_memory_changed(&mut self, _region: RegionId, _start: u16, _bytes: &[u8]) {}
+
+    // A RET didn't go back to where its call came from, see Debugger::stack_warnings
+    fn on_stack_warning(&mut self, _warning: &StackWarning) {}
+
+    // The program did something suspicious that the hardware lets go, like
+    // writing VRAM while the PPU reads it. Only checked with an observer set.
+    fn on_diagnostic(&mut self, _diagnostic: &Diagnostic) {}
+}
@@ -0,0 +1,72 @@
+use crate::{ColoredPixel, GameBoyFrame};
+
+// RGB colors used to display each of the four shades of the DMG
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorPalette {
+    // Indexed by ColoredPixel: white, light gray, dark gray, black
+    pub colors: [[u8; 3]; 4],
+}
+
+impl ColorPalette {
+    pub const GRAYSCALE: ColorPalette = ColorPalette {
+        colors: [[0xFF, 0xFF, 0xFF], [0xC0, 0xC0, 0xC0], [0x60, 0x60, 0x60], [0x00, 0x00, 0x00]]
+    };
+
+    // The green tint of the original DMG screen
+    pub const CLASSIC_GREEN: ColorPalette = ColorPalette {
+        colors: [[0x9B, 0xBC, 0x0F], [0x8B, 0xAC, 0x0F], [0x30, 0x62, 0x30], [0x0F, 0x38, 0x0F]]
+    };
+
+    // The gray-olive shades of the Game Boy Pocket screen
+    pub const POCKET_GRAY: ColorPalette = ColorPalette {
+        colors: [[0xE0, 0xDB, 0xCD], [0xA8, 0x9F, 0x94], [0x70, 0x6B, 0x66], [0x2B, 0x2B, 0x26]]
+    };
+
+    pub const PRESETS: [(&'static str, ColorPalette); 3] = [
+        ("grayscale", ColorPalette::GRAYSCALE),
+        ("dmg-green", ColorPalette::CLASSIC_GREEN),
+        ("pocket-gray", ColorPalette::POCKET_GRAY),
+    ];
+
+    pub fn preset(name: &str) -> Option<ColorPalette> {
+        ColorPalette::PRESETS.iter().find(|(preset, _)| *preset == name).map(|(_, palette)| *palette)
+    }
+
+    // Parses four RRGGBB colors separated by commas, from white to black
+    pub fn parse(text: &str) -> Option<ColorPalette> {
+        let colors: Vec<[u8; 3]> = text.split(',')
+            .map(|color| {
+                let hex = color.trim().trim_start_matches('#');
+                let value = u32::from_str_radix(hex, 16).ok()?;
+                (hex.len() == 6).then_some([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+            })
+            .collect::<Option<_>>()?;
+        Some(ColorPalette { colors: colors.try_into().ok()? })
+    }
+
+    pub fn custom(colors: [[u8; 3]; 4]) -> Self {
+        ColorPalette { colors }
+    }
+
+    pub fn rgb(&self, pixel: ColoredPixel) -> [u8; 3] {
+        self.colors[pixel as usize]
+    }
+}
+
+impl Default for ColorPalette {
+    fn default() -> Self {
+        ColorPalette::GRAYSCALE
+    }
+}
+
+impl GameBoyFrame {
+    // 4 bytes per pixel, alpha is always opaque
+    pub fn to_rgba(&self, palette: &ColorPalette) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(self.buffer.len() * 4);
+        for pixel in &self.buffer {
+            let [r, g, b] = palette.rgb(*pixel);
+            rgba.extend_from_slice(&[r, g, b, 0xFF]);
+        }
+        rgba
+    }
+}
@@ -2,7 +2,7 @@ use std::fmt;
 
 use pretty_hex::*;
 
-use super::{mmu::*, gameboy::GameBoy};
+use super::{mmu::*, gameboy::GameBoy, savestate::{StateReader, StateWriter}};
 
 pub(crate) const BGMAP0_ADDRESS: Address = 0x9800;
 pub(crate) const BGMAP1_ADDRESS: Address = 0x9C00;
@@ -43,24 +43,23 @@ impl PPU {
     }
     
 
-    pub(super) fn read_byte(gb: &GameBoy, address: Address) -> u8 {
-        match address {
-            OAM_BEGIN ..= OAM_END => PPU::read_oam(gb, address),
-            VRAM_BEGIN ..= VRAM_END => PPU::read_vram(gb, address),
-            _ => panic!("Invalid read PPU")
-        }
+    pub(crate) fn save_state(gb: &GameBoy, writer: &mut StateWriter) {
+        writer.bytes(&gb.ppu.vram);
+        writer.bytes(&gb.ppu.oam);
     }
 
-    pub(super) fn write_byte(gb: &mut GameBoy, address: Address, value: u8) {
-        match address {
-            OAM_BEGIN ..= OAM_END => PPU::write_oam(gb, address, value),
-            VRAM_BEGIN ..= VRAM_END => PPU::write_vram(gb, address, value),
-            _ => panic!("Invalid write PPU")
+    // The tile set is decoded again from VRAM
+    pub(crate) fn load_state(gb: &mut GameBoy, reader: &mut StateReader) -> Result<(), std::io::Error> {
+        let vram: [u8; VRAM_SIZE] = reader.array()?;
+        for (offset, value) in vram.into_iter().enumerate() {
+            PPU::write_vram(gb, VRAM_BEGIN + offset as Address, value);
         }
+        gb.ppu.oam = reader.array()?;
+        Ok(())
     }
 
     pub(super) fn read_oam(gb: &GameBoy, address: Address) -> u8 {
-        gb.ppu.vram[(address - OAM_BEGIN) as usize]
+        gb.ppu.oam[(address - OAM_BEGIN) as usize]
     }  
 
     pub(super) fn write_oam(gb: &mut GameBoy, address: Address, value: u8) {
@@ -0,0 +1,136 @@
+use std::{collections::HashMap, fs::File, io::{BufWriter, Error, Write}, path::Path};
+
+use crate::{cpu::cpu::{ClockCycles, Flow}, debugger::{self, SymbolTable}, gameboy::GameBoy, mmu::Address};
+
+// Deeper call chains are cut, games that never return from calls would grow it forever
+const MAX_DEPTH: usize = 256;
+
+// Bank and address
+type BankAddress = (u16, u16);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionCycles {
+    pub name: String,
+    pub cycles: u64,
+}
+
+// Calls made while profiling form a tree, the cycles of every instruction go to the
+// chain of calls that led to it
+struct Node {
+    entry: BankAddress,
+    parent: usize,
+    children: HashMap<BankAddress, usize>,
+    cycles: u64,
+}
+
+struct Frame {
+    node: usize,
+    // Where the return address is, the frame is left once the stack pointer goes above it
+    sp: u16,
+}
+
+// Cycles per instruction address and per call stack while profiling is enabled.
+// Calls are followed through CALL, RST and interrupts, returns through the stack pointer
+// so frames dropped with POP or LD SP are left too.
+#[derive(Default)]
+pub struct Profiler {
+    nodes: Vec<Node>,
+    frames: Vec<Frame>,
+    addresses: HashMap<BankAddress, u64>,
+    total_cycles: u64,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler::default()
+    }
+
+    pub fn total_cycles(&self) -> u64 {
+        self.total_cycles
+    }
+
+    // After the CPU ran the instruction at address, sp is the stack pointer it started with
+    pub(crate) fn instruction(gb: &mut GameBoy, address: Address, sp: u16, interrupted: bool, flow: Flow, cycles: ClockCycles) {
+        let bank = debugger::bank_at(gb, address);
+        let Some(profiler) = &mut gb.profiler else { return };
+
+        if profiler.nodes.is_empty() {
+            // The first function is whatever was running when profiling started
+            profiler.nodes.push(Node { entry: (bank, address), parent: 0, children: HashMap::new(), cycles: 0 });
+        }
+        if interrupted {
+            profiler.enter((bank, address), sp);
+        }
+
+        let cycles = cycles as u64;
+        *profiler.addresses.entry((bank, address)).or_default() += cycles;
+        let node = profiler.frames.last().map_or(0, |frame| frame.node);
+        profiler.nodes[node].cycles += cycles;
+        profiler.total_cycles += cycles;
+
+        let sp_after = gb.cpu.sp;
+        while profiler.frames.last().is_some_and(|frame| frame.sp < sp_after) {
+            profiler.frames.pop();
+        }
+        if flow == Flow::Call && sp_after == sp.wrapping_sub(2) {
+            let target = gb.cpu.pc;
+            let target_bank = debugger::bank_at(gb, target);
+            if let Some(profiler) = &mut gb.profiler {
+                profiler.enter((target_bank, target), sp_after);
+            }
+        }
+    }
+
+    fn enter(&mut self, entry: BankAddress, sp: u16) {
+        if self.frames.len() >= MAX_DEPTH {
+            return;
+        }
+        let parent = self.frames.last().map_or(0, |frame| frame.node);
+        let node = match self.nodes[parent].children.get(&entry) {
+            Some(&node) => node,
+            None => {
+                self.nodes.push(Node { entry, parent, children: HashMap::new(), cycles: 0 });
+                let node = self.nodes.len() - 1;
+                self.nodes[parent].children.insert(entry, node);
+                node
+            },
+        };
+        self.frames.push(Frame { node, sp });
+    }
+
+    // Cycles spent in the range of every label, most expensive first
+    pub fn functions(&self, symbols: &SymbolTable) -> Vec<FunctionCycles> {
+        let mut cycles: HashMap<String, u64> = HashMap::new();
+        for (&(bank, address), &spent) in &self.addresses {
+            let name = symbols.containing(bank, address).map_or("(unknown)".to_string(), |symbol| symbol.name.clone());
+            *cycles.entry(name).or_default() += spent;
+        }
+        let mut functions: Vec<FunctionCycles> = cycles.into_iter().map(|(name, cycles)| FunctionCycles { name, cycles }).collect();
+        functions.sort_by(|a, b| b.cycles.cmp(&a.cycles).then_with(|| a.name.cmp(&b.name)));
+        functions
+    }
+
+    // One "Main;UpdateActors;DrawSprite 1234" line per call stack, the collapsed
+    // format flamegraph.pl and inferno read
+    pub fn write_collapsed<W: Write>(&self, mut writer: W, symbols: &SymbolTable) -> Result<(), Error> {
+        for (index, node) in self.nodes.iter().enumerate().filter(|(_, node)| node.cycles > 0) {
+            let mut names = vec![frame_name(node.entry, symbols)];
+            let mut parent = index;
+            while parent != 0 {
+                parent = self.nodes[parent].parent;
+                names.push(frame_name(self.nodes[parent].entry, symbols));
+            }
+            names.reverse();
+            writeln!(writer, "{} {}", names.join(";"), node.cycles)?;
+        }
+        writer.flush()
+    }
+
+    pub fn save_collapsed<P: AsRef<Path>>(&self, path: P, symbols: &SymbolTable) -> Result<(), Error> {
+        self.write_collapsed(BufWriter::new(File::create(path)?), symbols)
+    }
+}
+
+fn frame_name((bank, address): BankAddress, symbols: &SymbolTable) -> String {
+    symbols.containing(bank, address).map_or_else(|| format!("{:02X}:{:04X}", bank, address), |symbol| symbol.name.clone())
+}
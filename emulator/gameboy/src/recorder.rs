@@ -0,0 +1,99 @@
+use std::{fs::File, io::{BufWriter, Error}, path::Path};
+
+use crate::{palette::ColorPalette, GameBoyFrame, FPS};
+
+// Browsers and most viewers play shorter delays at about 10 hundredths of
+// second, frames are dropped until this much time has passed instead
+const MIN_DELAY: f32 = 2.0;
+
+pub struct RecordingOptions {
+    pub palette: ColorPalette,
+    // 1 keeps the original size, 2 halves it, ...
+    pub downscale: u32,
+    // Number of frames dropped after each recorded one. Some more are dropped
+    // to keep the GIF at 50 fps at most.
+    pub frame_skip: u32,
+    // Sprites flickering on alternate frames are merged, see display::merge_flicker
+    pub dedup_flicker: bool,
+}
+
+impl Default for RecordingOptions {
+    fn default() -> Self {
+        RecordingOptions { palette: ColorPalette::default(), downscale: 1, frame_skip: 0, dedup_flicker: false }
+    }
+}
+
+// Encodes the frames produced at every VBlank into an animated GIF
+pub(crate) struct Recorder {
+    encoder: gif::Encoder<BufWriter<File>>,
+    options: RecordingOptions,
+    // Since the last recorded frame
+    frames_seen: u64,
+    // GIF delays are in hundredths of second, the time since the last recorded
+    // frame with the remainder of its delay
+    pending_delay: f32,
+    error: Option<Error>,
+}
+
+impl Recorder {
+    pub(crate) fn new<P: AsRef<Path>>(path: P, width: u32, height: u32, options: RecordingOptions) -> Result<Self, Error> {
+        let downscale = options.downscale.max(1);
+        let palette: Vec<u8> = options.palette.colors.iter().flatten().copied().collect();
+
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = gif::Encoder::new(file, (width / downscale) as u16, (height / downscale) as u16, &palette)
+            .map_err(Error::other)?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(Error::other)?;
+
+        Ok(Recorder { encoder, options, frames_seen: 0, pending_delay: 0.0, error: None })
+    }
+
+    pub(crate) fn dedups_flicker(&self) -> bool {
+        self.options.dedup_flicker
+    }
+
+    pub(crate) fn capture(&mut self, frame: &GameBoyFrame) {
+        let frame_time = 100.0 / FPS;
+        self.frames_seen += 1;
+        self.pending_delay += frame_time;
+        if self.frames_seen <= self.options.frame_skip as u64 || self.pending_delay < MIN_DELAY || self.error.is_some() {
+            return;
+        }
+        self.frames_seen = 0;
+
+        let downscale = self.options.downscale.max(1) as usize;
+        let width = frame.width as usize / downscale;
+        let height = frame.height as usize / downscale;
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(frame.buffer[x * downscale + y * downscale * frame.width as usize] as u8);
+            }
+        }
+
+        // Every recorded frame stands for itself and the ones dropped before it.
+        // The delay leaves less than a frame for the next one, so without frame
+        // skip the frames all get 2 and the time still adds up.
+        let delay = (self.pending_delay - frame_time).ceil().max(MIN_DELAY);
+        self.pending_delay -= delay;
+
+        let mut gif_frame = gif::Frame::from_indexed_pixels(width as u16, height as u16, pixels, None);
+        gif_frame.delay = delay as u16;
+
+        if let Err(error) = self.encoder.write_frame(&gif_frame) {
+            self.error = Some(Error::other(error));
+        }
+    }
+
+    // Errors while capturing are reported when the recording is finished
+    pub(crate) fn finish(self) -> Result<(), Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => {
+                self.encoder.into_inner()?;
+                Ok(())
+            }
+        }
+    }
+}
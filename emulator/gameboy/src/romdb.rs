@@ -0,0 +1,107 @@
+use std::sync::OnceLock;
+
+// Embedded copy of a No-Intro DAT in clrmamepro format. Replace it with the full
+// "Nintendo - Game Boy" DAT from https://datomatic.no-intro.org to recognize commercial games.
+const EMBEDDED_DAT: &str = include_str!("../data/gb.dat");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DatabaseEntry {
+    pub(crate) name: String,
+    pub(crate) crc32: u32,
+    pub(crate) sha1: Option<[u8; 20]>,
+}
+
+impl DatabaseEntry {
+    // No-Intro names look like "Title (Region) (Other flags)"
+    pub(crate) fn title(&self) -> &str {
+        self.name.split(" (").next().unwrap_or(&self.name)
+    }
+
+    pub(crate) fn region(&self) -> Option<&str> {
+        let start = self.name.find(" (")? + 2;
+        let end = start + self.name[start..].find(')')?;
+        Some(&self.name[start..end])
+    }
+}
+
+fn database() -> &'static [DatabaseEntry] {
+    static DATABASE: OnceLock<Vec<DatabaseEntry>> = OnceLock::new();
+    DATABASE.get_or_init(|| parse_dat(EMBEDDED_DAT))
+}
+
+// Matches by SHA-1 when the DAT has it, CRC32 collisions between dumps do happen
+pub(crate) fn lookup(crc32: u32, sha1: &[u8; 20]) -> Option<&'static DatabaseEntry> {
+    database().iter().find(|entry| entry.crc32 == crc32 && entry.sha1.is_none_or(|hash| &hash == sha1))
+}
+
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+
+    while let Some(first) = rest.chars().next() {
+        let (token, remaining) = match first {
+            '"' => {
+                let end = rest[1..].find('"').map(|end| end + 1).unwrap_or(rest.len());
+                (&rest[1..end], rest.get(end + 1..).unwrap_or(""))
+            },
+            '(' | ')' => (&rest[..1], &rest[1..]),
+            _ => {
+                let end = rest.find(|c: char| c.is_whitespace() || c == '(' || c == ')').unwrap_or(rest.len());
+                (&rest[..end], &rest[end..])
+            },
+        };
+        tokens.push(token);
+        rest = remaining.trim_start();
+    }
+    tokens
+}
+
+fn parse_hex<const N: usize>(text: &str) -> Option<[u8; N]> {
+    if text.len() != N * 2 {
+        return None;
+    }
+    let mut bytes = [0u8; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(text.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+// Only the game name and the rom checksums are used, everything else is skipped
+pub(crate) fn parse_dat(text: &str) -> Vec<DatabaseEntry> {
+    let tokens = tokenize(text);
+    let mut entries = Vec::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if tokens[i] != "game" || tokens.get(i + 1) != Some(&"(") {
+            i += 1;
+            continue;
+        }
+
+        let mut depth = 0;
+        let mut name = None;
+        let mut crc32 = None;
+        let mut sha1 = None;
+        i += 1;
+        while i < tokens.len() {
+            match tokens[i] {
+                "(" => depth += 1,
+                ")" => {
+                    depth -= 1;
+                    if depth == 0 { break }
+                },
+                "name" if depth == 1 => name = tokens.get(i + 1).map(|name| name.to_string()),
+                "crc" if depth == 2 => crc32 = tokens.get(i + 1).and_then(|crc| u32::from_str_radix(crc, 16).ok()),
+                "sha1" if depth == 2 => sha1 = tokens.get(i + 1).and_then(|hash| parse_hex::<20>(hash)),
+                _ => {},
+            }
+            i += 1;
+        }
+
+        if let (Some(name), Some(crc32)) = (name, crc32) {
+            entries.push(DatabaseEntry { name, crc32, sha1 });
+        }
+    }
+    entries
+}
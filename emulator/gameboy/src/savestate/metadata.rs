@@ -0,0 +1,74 @@
+use std::{io::Error, time::Duration};
+
+use crate::{gameboy::GameBoy, ColoredPixel, GameBoyFrame, CPU_CLOCK_HZ};
+
+use super::{StateReader, StateWriter};
+
+// Every side of the screen is divided by this in the thumbnail
+const THUMBNAIL_DOWNSCALE: u32 = 2;
+
+// What a load menu shows about a state, stored before the hardware so it can
+// be read without the rest
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateMetadata {
+    // From the cartridge header, empty without a cartridge
+    pub title: String,
+    pub total_cycles: u64,
+    // Frames run since the emulation started
+    pub frames: u64,
+    // Version of the core that saved the state
+    pub emulator_version: String,
+    // The last complete frame at half size
+    pub thumbnail: GameBoyFrame,
+}
+
+impl StateMetadata {
+    pub(crate) fn capture(gb: &GameBoy, total_cycles: u64, frames: u64) -> Self {
+        StateMetadata {
+            title: gb.cartridge.as_ref().map(|cartridge| cartridge.title()).unwrap_or_default(),
+            total_cycles,
+            frames,
+            emulator_version: env!("CARGO_PKG_VERSION").to_string(),
+            thumbnail: thumbnail(&gb.frame()),
+        }
+    }
+
+    // Emulated time, which doesn't count pauses and runs faster while fast-forwarding
+    pub fn play_time(&self) -> Duration {
+        Duration::from_secs_f64(self.total_cycles as f64 / CPU_CLOCK_HZ as f64)
+    }
+
+    pub(crate) fn save(&self, writer: &mut StateWriter) {
+        writer.vec(self.title.as_bytes());
+        writer.u64(self.total_cycles);
+        writer.u64(self.frames);
+        writer.vec(self.emulator_version.as_bytes());
+        writer.u16(self.thumbnail.width as u16);
+        writer.u16(self.thumbnail.height as u16);
+        for pixel in &self.thumbnail.buffer {
+            writer.u8(u8::from(*pixel));
+        }
+    }
+
+    pub(crate) fn load(reader: &mut StateReader) -> Result<Self, Error> {
+        let title = String::from_utf8_lossy(reader.vec()?).to_string();
+        let total_cycles = reader.u64()?;
+        let frames = reader.u64()?;
+        let emulator_version = String::from_utf8_lossy(reader.vec()?).to_string();
+        let width = reader.u16()? as u32;
+        let height = reader.u16()? as u32;
+        let buffer = reader.bytes((width * height) as usize)?.iter().map(|pixel| ColoredPixel::from(*pixel)).collect();
+        Ok(StateMetadata { title, total_cycles, frames, emulator_version, thumbnail: GameBoyFrame { width, height, buffer } })
+    }
+}
+
+// Nearest neighbour, the top left pixel of every block
+fn thumbnail(frame: &GameBoyFrame) -> GameBoyFrame {
+    let width = frame.width / THUMBNAIL_DOWNSCALE;
+    let height = frame.height / THUMBNAIL_DOWNSCALE;
+    let buffer = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| frame.buffer[((x + y * frame.width) * THUMBNAIL_DOWNSCALE) as usize])
+        .collect();
+    GameBoyFrame { width, height, buffer }
+}
@@ -0,0 +1,238 @@
+mod metadata;
+mod slots;
+
+use std::{fs::{self, File}, io::{Error, ErrorKind, Read}, path::Path};
+
+use crate::{cartridge::Cartridge, cpu::cpu::CPU, gameboy::GameBoy, io::io::IO, mmu::MMU, ppu::PPU};
+
+pub use metadata::StateMetadata;
+pub use slots::{SlotInfo, StateSlots, SLOT_COUNT};
+
+const MAGIC: &[u8; 4] = b"YGBS";
+// Increased when the layout changes, older states are rejected
+const VERSION: u8 = 8;
+// Magic, version and the length of the metadata
+const HEADER_SIZE: usize = MAGIC.len() + 1 + 4;
+
+// Complete state of the emulated hardware, the ROM is not included. The cheats
+// and the pokes waiting for VBlank are saved with it.
+// The host side (observer, memory watches, recorder, serial device, audio output) is not
+// part of it and is kept when a state is loaded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SaveState {
+    bytes: Vec<u8>,
+    metadata: StateMetadata,
+}
+
+impl SaveState {
+    pub(crate) fn capture(gb: &GameBoy, total_cycles: u64, frames: u64) -> Self {
+        let metadata = StateMetadata::capture(gb, total_cycles, frames);
+        let mut header = StateWriter::default();
+        metadata.save(&mut header);
+
+        let mut writer = StateWriter::default();
+        writer.bytes(MAGIC);
+        writer.u8(VERSION);
+        writer.vec(&header.into_bytes());
+        writer.u32(gb.cartridge.as_ref().map_or(0, Cartridge::crc32));
+
+        CPU::save_state(gb, &mut writer);
+        MMU::save_state(gb, &mut writer);
+        PPU::save_state(gb, &mut writer);
+        IO::save_state(gb, &mut writer);
+        Cartridge::save_state(gb, &mut writer);
+        writer.option_u8(gb.serial);
+        gb.cheats.save_state(&mut writer);
+        writer.u32(gb.pending_writes.len() as u32);
+        for (address, value) in &gb.pending_writes {
+            writer.u16(*address);
+            writer.u8(*value);
+        }
+
+        SaveState { bytes: writer.into_bytes(), metadata }
+    }
+
+    // Fails without touching the Game Boy when the state is for another ROM
+    pub(crate) fn restore(&self, gb: &mut GameBoy) -> Result<(), Error> {
+        let mut reader = StateReader::new(&self.bytes[MAGIC.len() + 1..]);
+        reader.vec()?;
+        if reader.u32()? != gb.cartridge.as_ref().map_or(0, Cartridge::crc32) {
+            return Err(Error::new(ErrorKind::InvalidInput, "Savestate made with another ROM"));
+        }
+
+        CPU::load_state(gb, &mut reader)?;
+        MMU::load_state(gb, &mut reader)?;
+        PPU::load_state(gb, &mut reader)?;
+        IO::load_state(gb, &mut reader)?;
+        Cartridge::load_state(gb, &mut reader)?;
+        gb.serial = reader.option_u8()?;
+        gb.cheats.load_state(&mut reader)?;
+        let mut pending_writes = Vec::new();
+        for _ in 0..reader.u32()? {
+            pending_writes.push((reader.u16()?, reader.u8()?));
+        }
+        gb.pending_writes = pending_writes;
+        gb.call_stack.clear();
+        Ok(())
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        let metadata = SaveState::read_metadata(&bytes)?;
+        Ok(SaveState { bytes, metadata })
+    }
+
+    // Works on the beginning of a state, up to the end of the metadata
+    fn read_metadata(bytes: &[u8]) -> Result<StateMetadata, Error> {
+        let mut reader = StateReader::new(bytes);
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "Not a savestate"));
+        }
+        if reader.u8()? != VERSION {
+            return Err(Error::new(ErrorKind::InvalidData, "Savestate made by another version"));
+        }
+        StateMetadata::load(&mut StateReader::new(reader.vec()?))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn metadata(&self) -> &StateMetadata {
+        &self.metadata
+    }
+
+    // Clock cycles run since the emulation started
+    pub fn total_cycles(&self) -> u64 {
+        self.metadata.total_cycles
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        SaveState::from_bytes(fs::read(path)?)
+    }
+
+    // Reads the metadata only, for menus listing many states
+    pub fn peek_metadata<P: AsRef<Path>>(path: P) -> Result<StateMetadata, Error> {
+        let mut file = File::open(path)?;
+        let mut bytes = vec![0; HEADER_SIZE];
+        file.read_exact(&mut bytes)?;
+        let len = u32::from_le_bytes(bytes[HEADER_SIZE - 4..].try_into().unwrap());
+        file.take(len as u64).read_to_end(&mut bytes)?;
+        SaveState::read_metadata(&bytes)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write(path, &self.bytes)
+    }
+}
+
+// Values are stored little endian one after the other, in the order
+// the subsystems write them
+#[derive(Default)]
+pub(crate) struct StateWriter {
+    bytes: Vec<u8>,
+}
+
+impl StateWriter {
+    pub(crate) fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub(crate) fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub(crate) fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn bytes(&mut self, value: &[u8]) {
+        self.bytes.extend_from_slice(value);
+    }
+
+    // Prefixed with its length, for buffers whose size depends on the cartridge
+    pub(crate) fn vec(&mut self, value: &[u8]) {
+        self.u32(value.len() as u32);
+        self.bytes(value);
+    }
+
+    pub(crate) fn option_u8(&mut self, value: Option<u8>) {
+        self.bool(value.is_some());
+        self.u8(value.unwrap_or(0));
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+pub(crate) struct StateReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        StateReader { bytes }
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.bytes.len() < len {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "Truncated savestate"));
+        }
+        let (value, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(value)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub(crate) fn bool(&mut self) -> Result<bool, Error> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    pub(crate) fn vec(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.u32()? as usize;
+        self.bytes(len)
+    }
+
+    // Fails when the length doesn't match the buffer of the current cartridge
+    pub(crate) fn vec_into(&mut self, target: &mut [u8]) -> Result<(), Error> {
+        let value = self.vec()?;
+        if value.len() != target.len() {
+            return Err(Error::new(ErrorKind::InvalidData, "Savestate memory size doesn't match"));
+        }
+        target.copy_from_slice(value);
+        Ok(())
+    }
+
+    pub(crate) fn option_u8(&mut self) -> Result<Option<u8>, Error> {
+        let present = self.bool()?;
+        let value = self.u8()?;
+        Ok(present.then_some(value))
+    }
+}
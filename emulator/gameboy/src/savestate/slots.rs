@@ -0,0 +1,77 @@
+use std::{env, fs, io::{Error, ErrorKind}, path::{Path, PathBuf}, time::SystemTime};
+
+use super::{SaveState, StateMetadata};
+
+pub const SLOT_COUNT: u8 = 10;
+
+// Numbered savestates of one game, for quick save and load. They live in a
+// directory named after the CRC32 of the ROM, so renaming or moving the ROM
+// keeps them and two ROMs with the same name don't share them.
+pub struct StateSlots {
+    directory: PathBuf,
+}
+
+// A filled slot, as shown by a load menu
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlotInfo {
+    pub slot: u8,
+    pub saved_at: SystemTime,
+    pub metadata: StateMetadata,
+}
+
+impl StateSlots {
+    // $XDG_DATA_HOME/yagabor/states, or under ~/.local/share
+    pub fn default_directory() -> Option<PathBuf> {
+        let base = env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()).map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))?;
+        Some(base.join("yagabor").join("states"))
+    }
+
+    pub fn new<P: AsRef<Path>>(base: P, rom_crc32: u32) -> Self {
+        StateSlots { directory: base.as_ref().join(format!("{:08x}", rom_crc32)) }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn state_path(&self, slot: u8) -> PathBuf {
+        self.directory.join(format!("{}.state", slot))
+    }
+
+    pub fn save(&self, slot: u8, state: &SaveState) -> Result<(), Error> {
+        check_slot(slot)?;
+        fs::create_dir_all(&self.directory)?;
+        state.save(self.state_path(slot))
+    }
+
+    pub fn load(&self, slot: u8) -> Result<SaveState, Error> {
+        check_slot(slot)?;
+        SaveState::load(self.state_path(slot)).map_err(|error| match error.kind() {
+            ErrorKind::NotFound => Error::new(ErrorKind::NotFound, format!("Slot {} is empty", slot)),
+            _ => error,
+        })
+    }
+
+    // Only the filled slots, in order. Only the metadata of the states is read.
+    pub fn list(&self) -> Result<Vec<SlotInfo>, Error> {
+        let mut slots = Vec::new();
+        for slot in 0..SLOT_COUNT {
+            let saved_at = match fs::metadata(self.state_path(slot)) {
+                Ok(metadata) => metadata.modified()?,
+                Err(error) if error.kind() == ErrorKind::NotFound => continue,
+                Err(error) => return Err(error),
+            };
+            let metadata = SaveState::peek_metadata(self.state_path(slot))?;
+            slots.push(SlotInfo { slot, saved_at, metadata });
+        }
+        Ok(slots)
+    }
+}
+
+fn check_slot(slot: u8) -> Result<(), Error> {
+    if slot >= SLOT_COUNT {
+        return Err(Error::new(ErrorKind::InvalidInput, format!("There are only {} slots, numbered from 0", SLOT_COUNT)));
+    }
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use std::{fs::File, io::{BufReader, BufWriter, Error, ErrorKind}, path::Path};
+
+use crate::{display::{DisplayPipeline, PostFilter, RgbaImage, Scaler}, palette::ColorPalette, GameBoyFrame};
+
+const TILE_SIZE: u32 = 8;
+const GRID_COLOR: [u8; 4] = [0xFF, 0x00, 0xFF, 0xFF];
+
+// What to capture, besides the screen the debug views can be exported
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum View {
+    Screen,
+    // The screen with the sprites flickering on alternate frames merged
+    StableScreen,
+    // The full 256x256 background map
+    Background,
+    // The full 256x256 window map
+    Window,
+    TileData,
+}
+
+// How the tile data and the maps are exported for ripping graphics
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SheetOptions {
+    // Every Game Boy pixel becomes scale x scale pixels
+    pub scale: u32,
+    // A one pixel line between the tiles
+    pub grid: bool,
+}
+
+impl Default for SheetOptions {
+    fn default() -> Self {
+        SheetOptions { scale: 1, grid: false }
+    }
+}
+
+pub fn save_png<P: AsRef<Path>>(frame: &GameBoyFrame, palette: &ColorPalette, path: P) -> Result<(), Error> {
+    save_rgba_png(&RgbaImage { width: frame.width, height: frame.height, pixels: frame.to_rgba(palette) }, path)
+}
+
+pub fn save_rgba_png<P: AsRef<Path>>(image: &RgbaImage, path: P) -> Result<(), Error> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(Error::other)?;
+    writer.write_image_data(&image.pixels).map_err(Error::other)
+}
+
+// A debug view made of tiles, scaled without smoothing so the pixels stay sharp
+pub fn save_sheet<P: AsRef<Path>>(frame: &GameBoyFrame, palette: &ColorPalette, options: SheetOptions, path: P) -> Result<(), Error> {
+    save_rgba_png(&render_sheet(frame, palette, options), path)
+}
+
+pub fn render_sheet(frame: &GameBoyFrame, palette: &ColorPalette, options: SheetOptions) -> RgbaImage {
+    let pipeline = DisplayPipeline { palette: *palette, scaler: Scaler::Nearest(options.scale), filter: PostFilter::None };
+    let image = pipeline.render(frame);
+    if !options.grid {
+        return image;
+    }
+
+    // The tiles are moved apart to make room for the lines, none of their pixels is covered
+    let cell = TILE_SIZE * options.scale.max(1);
+    let (columns, rows) = (image.width.div_ceil(cell), image.height.div_ceil(cell));
+    let (width, height) = (columns * (cell + 1) + 1, rows * (cell + 1) + 1);
+    let mut pixels: Vec<u8> = GRID_COLOR.repeat((width * height) as usize);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let (to_x, to_y) = (x + x / cell + 1, y + y / cell + 1);
+            let from = ((x + y * image.width) * 4) as usize;
+            let to = ((to_x + to_y * width) * 4) as usize;
+            pixels[to..to + 4].copy_from_slice(&image.pixels[from..from + 4]);
+        }
+    }
+    RgbaImage { width, height, pixels }
+}
+
+// RGBA pixels of any 8-bit PNG, with its width and height
+pub(crate) fn load_png(path: &Path) -> Result<(u32, u32, Vec<u8>), Error> {
+    let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().map_err(Error::other)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(Error::other)?;
+    buffer.truncate(info.buffer_size());
+
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer.chunks(3).flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xFF]).collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|gray| [*gray, *gray, *gray, 0xFF]).collect(),
+        png::ColorType::GrayscaleAlpha => buffer.chunks(2).flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]]).collect(),
+        png::ColorType::Indexed => return Err(Error::new(ErrorKind::InvalidData, "Unexpected indexed PNG")),
+    };
+    Ok((info.width, info.height, rgba))
+}
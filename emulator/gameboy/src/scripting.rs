@@ -0,0 +1,272 @@
+use std::{cell::RefCell, fs, io::{Error, ErrorKind}, mem, path::Path, rc::Rc};
+
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+
+use crate::{accuracy::EmulationConfig, debugger::{Debugger, Location}, display::RgbaImage, gameboy::GameBoy, inputmacro::InputMacro, inputmap::Turbo, io::lcd::PpuTiming, Button, Emulation, InputEvent, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+const ON_FRAME: &str = "on_frame";
+const ON_BREAKPOINT: &str = "on_breakpoint";
+
+// Shapes drawn by the script on top of the frame, in Game Boy pixels
+// with 0xRRGGBBAA colors. Cleared before every on_frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Overlay {
+    pixels: Vec<u32>,
+}
+
+// What the functions given to the script work on. The Game Boy is only
+// here while a callback runs.
+struct Shared {
+    gameboy: Option<GameBoy>,
+    inputs: Vec<InputEvent>,
+    // Applied to the emulation after the inputs
+    commands: Vec<Command>,
+    overlay: Overlay,
+    frame: u64,
+}
+
+enum Command {
+    PlayMacro(InputMacro),
+    StopMacro,
+    SetTurbo(Button, Turbo),
+    StopTurbo(Button),
+}
+
+// A Rhai script driving the emulation, like the Lua scripts of FCEUX or BizHawk.
+// The top level runs once when started, then the frontend calls on_frame after
+// every frame and on_breakpoint when a breakpoint is hit. Both get the object
+// map `this`, which keeps the state of the script between calls:
+//
+//   fn on_frame() { if read8(0xC0A0) == 0 { press("A") } else { release("A") } }
+//   fn on_breakpoint(address) { this.hits += 1; print(`hit ${address}`) }
+//
+// read8, read16 and write8 access memory as the CPU would, reg and set_reg take
+// register names like "a" or "hl", press and release take button names, pixel,
+// rect and clear draw the overlay, add_breakpoint stops at an address and frame
+// counts the frames the script has seen. ppu_mode gives the mode number of STAT,
+// scanline the value of LY and ppu_dots_left the dots until the mode ends, for
+// code timed against HBlank and VBlank. play_macro takes the steps of
+// InputMacro::parse and stop_macro stops it, turbo("B", period, pressed) mashes
+// a button until stop_turbo("B").
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    this: Dynamic,
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl Script {
+    pub fn compile(source: &str) -> Result<Self, Error> {
+        let shared = Rc::new(RefCell::new(Shared { gameboy: None, inputs: Vec::new(), commands: Vec::new(), overlay: Overlay::new(), frame: 0 }));
+        let mut engine = Engine::new();
+        register_functions(&mut engine, &shared);
+        let ast = engine.compile(source).map_err(|error| Error::new(ErrorKind::InvalidData, error.to_string()))?;
+        Ok(Script { engine, ast, scope: Scope::new(), this: Map::new().into(), shared })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Script::compile(&fs::read_to_string(path)?)
+    }
+
+    // Runs the top level of the script
+    pub fn start(&mut self, emu: &mut Emulation) -> Result<(), Error> {
+        self.with_gameboy(emu, |script| script.engine.run_ast_with_scope(&mut script.scope, &script.ast))
+    }
+
+    pub fn on_frame(&mut self, emu: &mut Emulation) -> Result<(), Error> {
+        {
+            let mut shared = self.shared.borrow_mut();
+            shared.frame += 1;
+            shared.overlay.clear();
+        }
+        self.call(emu, ON_FRAME, ()).map(|_| ())
+    }
+
+    // False when the script has no on_breakpoint, so the frontend stops as usual
+    pub fn on_breakpoint(&mut self, emu: &mut Emulation, address: u16) -> Result<bool, Error> {
+        self.call(emu, ON_BREAKPOINT, (address as i64,))
+    }
+
+    pub fn overlay(&self) -> Overlay {
+        self.shared.borrow().overlay.clone()
+    }
+
+    fn call(&mut self, emu: &mut Emulation, name: &str, args: impl rhai::FuncArgs) -> Result<bool, Error> {
+        let arity = args_len(name);
+        if !self.ast.iter_functions().any(|function| function.name == name && function.params.len() == arity) {
+            return Ok(false);
+        }
+        self.with_gameboy(emu, |script| {
+            let options = CallFnOptions::new().eval_ast(false).rewind_scope(false).bind_this_ptr(&mut script.this);
+            script.engine.call_fn_with_options::<Dynamic>(options, &mut script.scope, &script.ast, name, args).map(|_| ())
+        })?;
+        Ok(true)
+    }
+
+    // The Game Boy is lent to the script for the call, the inputs it sent are
+    // applied afterwards through the emulation so movies record them
+    fn with_gameboy(&mut self, emu: &mut Emulation, run: impl FnOnce(&mut Self) -> Result<(), Box<EvalAltResult>>) -> Result<(), Error> {
+        let gameboy = mem::replace(&mut emu.gameboy, GameBoy::new(None, EmulationConfig::default()));
+        self.shared.borrow_mut().gameboy = Some(gameboy);
+        let result = run(self);
+        let mut shared = self.shared.borrow_mut();
+        if let Some(gameboy) = shared.gameboy.take() {
+            emu.gameboy = gameboy;
+        }
+        for input in mem::take(&mut shared.inputs) {
+            emu.send_input(input);
+        }
+        for command in mem::take(&mut shared.commands) {
+            match command {
+                Command::PlayMacro(input_macro) => emu.play_macro(input_macro),
+                Command::StopMacro => emu.stop_macro(),
+                Command::SetTurbo(button, turbo) => emu.set_turbo(button, turbo),
+                Command::StopTurbo(button) => emu.stop_turbo(button),
+            }
+        }
+        result.map_err(|error| Error::other(error.to_string()))
+    }
+}
+
+fn args_len(name: &str) -> usize {
+    if name == ON_BREAKPOINT { 1 } else { 0 }
+}
+
+fn register_functions(engine: &mut Engine, shared: &Rc<RefCell<Shared>>) {
+    let state = shared.clone();
+    engine.register_fn("read8", move |address: i64| with_debugger(&state, |debugger| debugger.read_byte(address as u16) as i64));
+    let state = shared.clone();
+    engine.register_fn("read16", move |address: i64| with_debugger(&state, |debugger| {
+        let address = address as u16;
+        debugger.read_byte(address) as i64 | (debugger.read_byte(address.wrapping_add(1)) as i64) << 8
+    }));
+    let state = shared.clone();
+    engine.register_fn("write8", move |address: i64, value: i64| with_debugger(&state, |debugger| {
+        debugger.write_byte(address as u16, value as u8)
+    }));
+    let state = shared.clone();
+    engine.register_fn("reg", move |name: &str| -> Result<i64, Box<EvalAltResult>> {
+        with_debugger(&state, |debugger| debugger.register(name).map(|value| value as i64))
+            .ok_or_else(|| format!("Unknown register {}", name).into())
+    });
+    let state = shared.clone();
+    engine.register_fn("set_reg", move |name: &str, value: i64| -> Result<(), Box<EvalAltResult>> {
+        if with_debugger(&state, |debugger| debugger.set_register(name, value as u16)) {
+            Ok(())
+        } else {
+            Err(format!("Unknown register {}", name).into())
+        }
+    });
+    let state = shared.clone();
+    engine.register_fn("add_breakpoint", move |address: i64| with_debugger(&state, |debugger| {
+        debugger.add_breakpoint(Location { bank: None, address: address as u16 })
+    }));
+
+    let state = shared.clone();
+    engine.register_fn("press", move |name: &str| -> Result<(), Box<EvalAltResult>> {
+        let button = Button::from_name(name).ok_or_else(|| format!("Unknown button {}", name))?;
+        state.borrow_mut().inputs.push(InputEvent::Pressed(button));
+        Ok(())
+    });
+    let state = shared.clone();
+    engine.register_fn("release", move |name: &str| -> Result<(), Box<EvalAltResult>> {
+        let button = Button::from_name(name).ok_or_else(|| format!("Unknown button {}", name))?;
+        state.borrow_mut().inputs.push(InputEvent::Released(button));
+        Ok(())
+    });
+    let state = shared.clone();
+    engine.register_fn("play_macro", move |steps: &str| -> Result<(), Box<EvalAltResult>> {
+        let input_macro = InputMacro::parse(steps).map_err(|error| error.to_string())?;
+        state.borrow_mut().commands.push(Command::PlayMacro(input_macro));
+        Ok(())
+    });
+    let state = shared.clone();
+    engine.register_fn("stop_macro", move || state.borrow_mut().commands.push(Command::StopMacro));
+    let state = shared.clone();
+    engine.register_fn("turbo", move |name: &str, period: i64, pressed: i64| -> Result<(), Box<EvalAltResult>> {
+        let button = Button::from_name(name).ok_or_else(|| format!("Unknown button {}", name))?;
+        let turbo = Turbo::with_duty(period.clamp(0, u32::MAX as i64) as u32, pressed.clamp(0, u32::MAX as i64) as u32);
+        state.borrow_mut().commands.push(Command::SetTurbo(button, turbo));
+        Ok(())
+    });
+    let state = shared.clone();
+    engine.register_fn("stop_turbo", move |name: &str| -> Result<(), Box<EvalAltResult>> {
+        let button = Button::from_name(name).ok_or_else(|| format!("Unknown button {}", name))?;
+        state.borrow_mut().commands.push(Command::StopTurbo(button));
+        Ok(())
+    });
+    let state = shared.clone();
+    engine.register_fn("frame", move || state.borrow().frame as i64);
+    let state = shared.clone();
+    engine.register_fn("ppu_mode", move || with_timing(&state, |timing| timing.mode.stat_bits() as i64));
+    let state = shared.clone();
+    engine.register_fn("ppu_dots_left", move || with_timing(&state, |timing| timing.remaining_dots as i64));
+    let state = shared.clone();
+    engine.register_fn("scanline", move || with_timing(&state, |timing| timing.scanline as i64));
+
+    let state = shared.clone();
+    engine.register_fn("pixel", move |x: i64, y: i64, color: i64| state.borrow_mut().overlay.set(x, y, color as u32));
+    let state = shared.clone();
+    engine.register_fn("rect", move |x: i64, y: i64, width: i64, height: i64, color: i64| {
+        let overlay = &mut state.borrow_mut().overlay;
+        for row in y..y + height {
+            for column in x..x + width {
+                overlay.set(column, row, color as u32);
+            }
+        }
+    });
+    let state = shared.clone();
+    engine.register_fn("clear", move || state.borrow_mut().overlay.clear());
+}
+
+// Outside of the callbacks there is no Game Boy and everything reads as zero
+fn with_debugger<T: Default>(shared: &Rc<RefCell<Shared>>, run: impl FnOnce(&mut Debugger) -> T) -> T {
+    match &mut shared.borrow_mut().gameboy {
+        Some(gameboy) => run(&mut Debugger::new(gameboy)),
+        None => T::default(),
+    }
+}
+
+fn with_timing(shared: &Rc<RefCell<Shared>>, read: impl FnOnce(PpuTiming) -> i64) -> i64 {
+    shared.borrow().gameboy.as_ref().map_or(0, |gameboy| read(gameboy.ppu_timing()))
+}
+
+impl Overlay {
+    fn new() -> Self {
+        Overlay { pixels: vec![0; (SCREEN_WIDTH * SCREEN_HEIGHT) as usize] }
+    }
+
+    fn set(&mut self, x: i64, y: i64, color: u32) {
+        if (0..SCREEN_WIDTH as i64).contains(&x) && (0..SCREEN_HEIGHT as i64).contains(&y) {
+            self.pixels[(x + y * SCREEN_WIDTH as i64) as usize] = color;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.pixels.fill(0);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pixels.iter().all(|&color| color & 0xFF == 0)
+    }
+
+    // Blends the overlay onto a rendered frame, scaled to its size
+    pub fn draw(&self, image: &mut RgbaImage) {
+        let factor = (image.width / SCREEN_WIDTH).max(1);
+        for y in 0..image.height.min(SCREEN_HEIGHT * factor) {
+            for x in 0..image.width.min(SCREEN_WIDTH * factor) {
+                let color = self.pixels[((x / factor) + (y / factor) * SCREEN_WIDTH) as usize];
+                let alpha = color & 0xFF;
+                if alpha == 0 {
+                    continue;
+                }
+                let offset = ((x + y * image.width) * 4) as usize;
+                for (channel, shift) in image.pixels[offset..offset + 3].iter_mut().zip([24, 16, 8]) {
+                    let source = (color >> shift) & 0xFF;
+                    *channel = ((source * alpha + *channel as u32 * (255 - alpha)) / 255) as u8;
+                }
+            }
+        }
+    }
+}
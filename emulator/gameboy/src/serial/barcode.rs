@@ -0,0 +1,71 @@
+use std::{collections::VecDeque, io::{Error, ErrorKind}, sync::{Arc, Mutex}};
+
+use super::SerialDevice;
+
+// Namco's Barcode Boy answers the Game Boy's 10 07 10 07 with FF FF 10 07
+const HANDSHAKE: [u8; 4] = [0x10, 0x07, 0x10, 0x07];
+const HANDSHAKE_REPLY: [u8; 4] = [0xFF, 0xFF, 0x10, 0x07];
+const BARCODE_START: u8 = 0x02;
+const BARCODE_END: u8 = 0x03;
+const IDLE: u8 = 0xFF;
+const EAN13_DIGITS: usize = 13;
+
+#[derive(Default)]
+struct BarcodeState {
+    // Handshake bytes matched so far
+    handshake: usize,
+    pending: VecDeque<u8>,
+}
+
+// Barcode reader used by Battle Space, Monster Maker and the Famista games.
+// Clones share the same reader, so one can be kept to scan cards while the
+// other is plugged in the emulation.
+#[derive(Clone, Default)]
+pub struct BarcodeBoy {
+    state: Arc<Mutex<BarcodeState>>,
+}
+
+impl BarcodeBoy {
+    pub fn new() -> Self {
+        BarcodeBoy::default()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.state.lock().unwrap().handshake == HANDSHAKE.len()
+    }
+
+    // Queues an EAN-13 barcode, sent as ASCII digits between 0x02 and 0x03
+    pub fn scan(&self, barcode: &str) -> Result<(), Error> {
+        let digits: Vec<u32> = barcode.chars().filter_map(|c| c.to_digit(10)).collect();
+        if digits.len() != EAN13_DIGITS || barcode.chars().count() != EAN13_DIGITS {
+            return Err(Error::new(ErrorKind::InvalidInput, "Barcodes must have 13 digits"));
+        }
+
+        // Odd positions weigh 1 and even ones 3, the last digit makes the sum a multiple of 10
+        let sum: u32 = digits[..12].iter().enumerate().map(|(i, d)| if i % 2 == 0 { *d } else { d * 3 }).sum();
+        if (10 - sum % 10) % 10 != digits[12] {
+            return Err(Error::new(ErrorKind::InvalidInput, "Invalid barcode check digit"));
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.pending.push_back(BARCODE_START);
+        state.pending.extend(barcode.bytes());
+        state.pending.push_back(BARCODE_END);
+        Ok(())
+    }
+}
+
+impl SerialDevice for BarcodeBoy {
+    fn exchange(&mut self, sent: u8) -> u8 {
+        let mut state = self.state.lock().unwrap();
+
+        if state.handshake < HANDSHAKE.len() {
+            let step = state.handshake;
+            state.handshake = if sent == HANDSHAKE[step] { step + 1 } else { 0 };
+            return if sent == HANDSHAKE[step] { HANDSHAKE_REPLY[step] } else { IDLE };
+        }
+
+        // Barcodes are only sent once the game knows the reader is there
+        state.pending.pop_front().unwrap_or(IDLE)
+    }
+}
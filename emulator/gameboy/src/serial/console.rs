@@ -0,0 +1,88 @@
+use std::{io::Write, sync::{Arc, Mutex}};
+
+use super::SerialDevice;
+
+// Nothing answers on the other side of the cable
+const IDLE: u8 = 0xFF;
+
+#[derive(Default)]
+struct ConsoleState {
+    text: String,
+    // Mirrored to stdout a line at a time, so the output of the emulator
+    // isn't cut in the middle of a line
+    echo: bool,
+    line: String,
+}
+
+// Collects what the game writes to the link port as text, for the test ROMs
+// that print their results there and homebrew printing debug messages. Bytes
+// are taken as Latin-1 like the test ROM runner does. Clones share the same
+// text, so one can be kept to read it while the other is plugged in.
+#[derive(Clone, Default)]
+pub struct SerialConsole {
+    state: Arc<Mutex<ConsoleState>>,
+}
+
+impl SerialConsole {
+    pub fn new() -> Self {
+        SerialConsole::default()
+    }
+
+    // Also prints every line on stdout as it's completed
+    pub fn with_echo() -> Self {
+        let console = SerialConsole::new();
+        console.set_echo(true);
+        console
+    }
+
+    pub fn set_echo(&self, echo: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.echo = echo;
+        if !echo {
+            state.line.clear();
+        }
+    }
+
+    // Everything received since the last take
+    pub fn text(&self) -> String {
+        self.state.lock().unwrap().text.clone()
+    }
+
+    pub fn take(&self) -> String {
+        std::mem::take(&mut self.state.lock().unwrap().text)
+    }
+
+    pub fn contains(&self, pattern: &str) -> bool {
+        self.state.lock().unwrap().text.contains(pattern)
+    }
+
+    // Prints the unfinished line when echoing, for a game that doesn't end
+    // its last line
+    pub fn flush_echo(&self) {
+        let mut state = self.state.lock().unwrap();
+        if !state.line.is_empty() {
+            let line = std::mem::take(&mut state.line);
+            // Like exchange, a closed stdout must not bring the emulator down
+            let _ = writeln!(std::io::stdout().lock(), "{}", line.trim_end_matches('\r'));
+        }
+    }
+}
+
+impl SerialDevice for SerialConsole {
+    fn exchange(&mut self, sent: u8) -> u8 {
+        let mut state = self.state.lock().unwrap();
+        let c = sent as char;
+        state.text.push(c);
+        if state.echo {
+            if c == '\n' {
+                let line = std::mem::take(&mut state.line);
+                let mut stdout = std::io::stdout().lock();
+                // A closed stdout only loses the mirror, the text is still kept
+                let _ = writeln!(stdout, "{}", line.trim_end_matches('\r'));
+            } else {
+                state.line.push(c);
+            }
+        }
+        IDLE
+    }
+}
@@ -0,0 +1,211 @@
+use std::sync::{Arc, Mutex};
+
+use super::SerialDevice;
+
+pub const ADAPTER_PLAYERS: usize = 4;
+
+const PING_HEADER: u8 = 0xFE;
+const PING_ACK: u8 = 0x88;
+const START_REQUEST: u8 = 0xAA;
+const START_REPLY: u8 = 0xCC;
+const RESTART_REQUEST: u8 = 0xFF;
+// Bytes in a ping cycle: header and three status bytes from the adapter,
+// ACK1, ACK2, RATE and SIZE from the Game Boy
+const PING_CYCLE: usize = 4;
+const START_CYCLE: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Phase {
+    Ping,
+    // Player 1 asked to start, 0xCC is sent to every player before the transmission
+    Starting,
+    Transmission,
+}
+
+#[derive(Clone, Default)]
+struct Port {
+    connected: bool,
+    // Position inside the current ping or transmission cycle
+    position: usize,
+    acks: usize,
+    restart_requests: usize,
+    // Transmission cycles this port went through
+    cycles: usize,
+    // The adapter clocked the next byte for this port
+    clocked: bool,
+}
+
+// DMG-07 four player adapter, https://gbdev.io/pandocs/Four_Player_Adapter.html
+//
+// Each Game Boy talks to the adapter through an AdapterPort. During the ping phase
+// the adapter reports which players are connected and player 1 chooses the packet
+// size; after player 1 sends 0xAA every cycle collects one packet from each player
+// and sends back the packets of all four from the previous cycle.
+pub struct FourPlayerAdapter {
+    phase: Phase,
+    ports: [Port; ADAPTER_PLAYERS],
+    packet_size: usize,
+    rate: u8,
+    // Packets from the previous cycle, sent back to the players
+    outgoing: Vec<u8>,
+    incoming: Vec<u8>,
+    cycles: usize,
+    // Once clock is called the ports wait for it, otherwise bytes go through
+    // as soon as the Game Boy sends them
+    paced: bool,
+}
+
+impl FourPlayerAdapter {
+    pub fn new() -> Arc<Mutex<FourPlayerAdapter>> {
+        Arc::new(Mutex::new(FourPlayerAdapter {
+            phase: Phase::Ping,
+            ports: Default::default(),
+            packet_size: 1,
+            rate: 0,
+            outgoing: vec![0; ADAPTER_PLAYERS],
+            incoming: vec![0; ADAPTER_PLAYERS],
+            cycles: 0,
+            paced: false,
+        }))
+    }
+
+    // Port for player 1 to 4
+    pub fn port(adapter: &Arc<Mutex<FourPlayerAdapter>>, player: usize) -> AdapterPort {
+        assert!((1..=ADAPTER_PLAYERS).contains(&player), "The adapter has players 1 to 4");
+        AdapterPort { adapter: Arc::clone(adapter), player: player - 1 }
+    }
+
+    pub fn is_transmitting(&self) -> bool {
+        self.phase == Phase::Transmission
+    }
+
+    pub fn connected_players(&self) -> usize {
+        self.ports.iter().filter(|port| port.connected).count()
+    }
+
+    // Sends the next byte to every port, whose transfer completes when the
+    // Game Boy has one ready
+    pub fn clock(&mut self) {
+        self.paced = true;
+        self.ports.iter_mut().for_each(|port| port.clocked = true);
+    }
+
+    // Bits 0-2 are the player number, bits 4-7 tell which players are connected
+    fn status(&self, player: usize) -> u8 {
+        let connected = self.ports.iter().enumerate()
+            .filter(|(_, port)| port.connected)
+            .fold(0u8, |bits, (index, _)| bits | (1 << (4 + index)));
+        connected | (player as u8 + 1)
+    }
+
+    fn exchange(&mut self, player: usize, sent: u8) -> u8 {
+        match self.phase {
+            Phase::Ping => self.ping(player, sent),
+            Phase::Starting => self.starting(player),
+            Phase::Transmission => self.transmission(player, sent),
+        }
+    }
+
+    fn ping(&mut self, player: usize, sent: u8) -> u8 {
+        if player == 0 && sent == START_REQUEST && self.ports[0].connected {
+            self.phase = Phase::Starting;
+            self.ports.iter_mut().for_each(|port| port.position = 0);
+            return START_REPLY;
+        }
+
+        let position = self.ports[player].position;
+        let received = if position == 0 { PING_HEADER } else { self.status(player) };
+
+        match position {
+            0 | 1 => {
+                if sent == PING_ACK {
+                    self.ports[player].acks += 1;
+                }
+            },
+            // Only player 1 decides the speed and size of the packets
+            2 => if player == 0 { self.rate = sent },
+            _ => {
+                if player == 0 && sent > 0 {
+                    self.packet_size = sent as usize;
+                }
+                let port = &mut self.ports[player];
+                port.connected = port.acks == 2;
+                port.acks = 0;
+            },
+        }
+
+        self.ports[player].position = (position + 1) % PING_CYCLE;
+        received
+    }
+
+    fn starting(&mut self, player: usize) -> u8 {
+        let port = &mut self.ports[player];
+        port.position += 1;
+
+        if self.ports.iter().filter(|port| port.connected).all(|port| port.position >= START_CYCLE) {
+            let cycle = self.packet_size * ADAPTER_PLAYERS;
+            self.outgoing = vec![0; cycle];
+            self.incoming = vec![0; cycle];
+            self.ports.iter_mut().for_each(|port| { port.position = 0; port.cycles = 0; });
+            self.cycles = 0;
+            self.phase = Phase::Transmission;
+        }
+        START_REPLY
+    }
+
+    fn transmission(&mut self, player: usize, sent: u8) -> u8 {
+        let cycle = self.packet_size * ADAPTER_PLAYERS;
+        let position = self.ports[player].position;
+
+        // Every player sends its packet in the first bytes of the cycle
+        if position < self.packet_size {
+            self.incoming[player * self.packet_size + position] = sent;
+        }
+        let received = self.outgoing[position];
+
+        let port = &mut self.ports[player];
+        port.restart_requests = if sent == RESTART_REQUEST { port.restart_requests + 1 } else { 0 };
+        port.position = (position + 1) % cycle;
+        if port.position == 0 {
+            port.cycles += 1;
+        }
+
+        // Player 1 ends the session by sending 0xFF for a whole cycle
+        if player == 0 && self.ports[0].restart_requests >= cycle {
+            self.restart();
+            return received;
+        }
+
+        // The cycle is over once every connected player went through it
+        if self.ports.iter().filter(|port| port.connected).all(|port| port.cycles > self.cycles) {
+            self.outgoing = std::mem::replace(&mut self.incoming, vec![0; cycle]);
+            self.cycles += 1;
+        }
+        received
+    }
+
+    fn restart(&mut self) {
+        self.phase = Phase::Ping;
+        for port in self.ports.iter_mut() {
+            *port = Port::default();
+        }
+    }
+}
+
+pub struct AdapterPort {
+    adapter: Arc<Mutex<FourPlayerAdapter>>,
+    player: usize,
+}
+
+impl SerialDevice for AdapterPort {
+    fn exchange(&mut self, sent: u8) -> u8 {
+        let mut adapter = self.adapter.lock().unwrap();
+        adapter.ports[self.player].clocked = false;
+        adapter.exchange(self.player, sent)
+    }
+
+    fn ready(&mut self) -> bool {
+        let adapter = self.adapter.lock().unwrap();
+        !adapter.paced || adapter.ports[self.player].clocked
+    }
+}
@@ -0,0 +1,19 @@
+mod barcode;
+mod console;
+mod dmg07;
+mod tests;
+
+pub use barcode::BarcodeBoy;
+pub use console::SerialConsole;
+pub use dmg07::{FourPlayerAdapter, AdapterPort, ADAPTER_PLAYERS};
+
+// Something plugged into the link port. Transfers are full duplex: every byte
+// sent by the Game Boy is exchanged for one coming from the device.
+pub trait SerialDevice: Send {
+    fn exchange(&mut self, sent: u8) -> u8;
+    // Asked before a transfer on the external clock. A device that drives
+    // the clock holds the transfer back until it sends the next byte.
+    fn ready(&mut self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+use super::{BarcodeBoy, FourPlayerAdapter, SerialConsole, SerialDevice};
+
+#[test]
+fn four_player_adapter_exchanges_packets() {
+    let adapter = FourPlayerAdapter::new();
+    let mut player1 = FourPlayerAdapter::port(&adapter, 1);
+    let mut player2 = FourPlayerAdapter::port(&adapter, 2);
+
+    // Ping: ACK1, ACK2, RATE, SIZE. Player 1 asks for 2 byte packets
+    for byte in [0x88, 0x88, 0x00, 0x02] {
+        player1.exchange(byte);
+        player2.exchange(byte);
+    }
+    assert_eq!(adapter.lock().unwrap().connected_players(), 2);
+    assert_eq!(player2.exchange(0x88), 0xFE);
+    // Player 2 with players 1 and 2 connected
+    assert_eq!(player2.exchange(0x88), 0b0011_0010);
+    player2.exchange(0x00);
+    player2.exchange(0x02);
+
+    // Player 1 starts the session
+    assert_eq!(player1.exchange(0xAA), 0xCC);
+    for _ in 0..4 {
+        player1.exchange(0x00);
+        player2.exchange(0x00);
+    }
+    assert!(adapter.lock().unwrap().is_transmitting());
+
+    // Cycles are 4 packets of 2 bytes, the data comes back one cycle later
+    let first: Vec<u8> = [0x11, 0x12, 0, 0, 0, 0, 0, 0].iter().map(|b| player1.exchange(*b)).collect();
+    [0x21, 0x22, 0, 0, 0, 0, 0, 0].iter().for_each(|b| { player2.exchange(*b); });
+    assert_eq!(first, vec![0; 8]);
+
+    let second: Vec<u8> = (0..8).map(|_| player2.exchange(0)).collect();
+    assert_eq!(second, vec![0x11, 0x12, 0x21, 0x22, 0, 0, 0, 0]);
+}
+
+#[test]
+fn clocked_adapter_holds_transfers_back() {
+    let adapter = FourPlayerAdapter::new();
+    let mut player1 = FourPlayerAdapter::port(&adapter, 1);
+    assert!(player1.ready());
+
+    adapter.lock().unwrap().clock();
+    assert!(player1.ready());
+    player1.exchange(0x88);
+    assert!(!player1.ready());
+    adapter.lock().unwrap().clock();
+    assert!(player1.ready());
+}
+
+#[test]
+fn serial_console_collects_text() {
+    let console = SerialConsole::new();
+    let mut plugged: Box<dyn SerialDevice> = Box::new(console.clone());
+    for byte in b"Passed\n" {
+        assert_eq!(plugged.exchange(*byte), 0xFF);
+    }
+    assert!(console.contains("Passed"));
+    assert_eq!(console.take(), "Passed\n");
+    assert_eq!(console.text(), "");
+}
+
+#[test]
+fn barcode_boy_sends_scans_after_the_handshake() {
+    let reader = BarcodeBoy::new();
+    let mut plugged = reader.clone();
+    assert!(reader.scan("4006381333932").is_err());
+    assert!(reader.scan("400638133393").is_err());
+    reader.scan("4006381333931").unwrap();
+
+    // A wrong byte starts the handshake over, nothing is sent before it is done
+    assert_eq!(plugged.exchange(0x10), 0xFF);
+    assert_eq!(plugged.exchange(0x00), 0xFF);
+    let replies: Vec<u8> = [0x10, 0x07, 0x10, 0x07].iter().map(|byte| plugged.exchange(*byte)).collect();
+    assert_eq!(replies, [0xFF, 0xFF, 0x10, 0x07]);
+    assert!(reader.is_connected());
+
+    let sent: Vec<u8> = (0..16).map(|_| plugged.exchange(0x00)).collect();
+    assert_eq!(sent[0], 0x02);
+    assert_eq!(&sent[1..14], b"4006381333931");
+    assert_eq!(&sent[14..], [0x03, 0xFF]);
+}
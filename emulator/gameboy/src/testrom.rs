@@ -0,0 +1,328 @@
+use std::{fmt, fs, io::{Error, ErrorKind}, path::{Path, PathBuf}, sync::{atomic::{AtomicUsize, Ordering}, Mutex}, thread, time::{Duration, Instant}};
+
+use serde::Deserialize;
+
+use crate::{accuracy::{AccuracyProfile, EmulationConfig}, capabilities, cartridge::Cartridge, checksum::crc32, coverage::OpcodeCoverage, gameboy::GameBoy, mmu::MMU, palette::ColorPalette, screenshot::{load_png, save_png}, Emulation, GameBoyFrame, CPU_CYCLES_PER_FRAME};
+
+// Set to regenerate the golden frames instead of comparing against them
+const UPDATE_GOLDEN_VARIABLE: &str = "YAGABOR_UPDATE_GOLDEN";
+
+// Mooneye test ROMs run LD B,B once they are done, with the Fibonacci numbers
+// in B, C, D, E, H and L when they pass or 0x42 everywhere when they fail
+const MOONEYE_BREAKPOINT: u8 = 0x40;
+const MOONEYE_PASS: [u8; 6] = [3, 5, 8, 13, 21, 34];
+const MOONEYE_FAIL: [u8; 6] = [0x42; 6];
+
+// Newer blargg ROMs write their status to 0xA000, followed by this signature and the text output
+const BLARGG_STATUS_ADDRESS: u16 = 0xA000;
+const BLARGG_SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61];
+const BLARGG_RUNNING: u8 = 0x80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestRomResult {
+    Passed,
+    Failed,
+    // No verdict before the frame limit
+    Timeout,
+}
+
+#[derive(Debug, Clone)]
+pub struct TestRomReport {
+    pub result: TestRomResult,
+    // Everything the ROM printed through the serial port
+    pub serial: String,
+    pub frames: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GoldenResult {
+    Match,
+    // Regeneration was requested, the golden file was written
+    Created,
+    Mismatch {
+        differing_pixels: usize,
+        // Coordinates of the first pixel that differs
+        first: (u32, u32),
+        // The frame that was produced, saved in the temporary directory
+        actual: PathBuf,
+    },
+}
+
+// A test ROM of the regression suite, with no serial or hash the ROM's own
+// pass or fail report is expected
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestEntry {
+    // Relative to the assets directory
+    pub rom: PathBuf,
+    pub frames: u64,
+    // Text the ROM has to print through the serial port
+    pub serial: Option<String>,
+    // CRC32 of the last frame in hex, see frame_crc32
+    pub hash: Option<String>,
+    // Skipped until the emulator supports all of them
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub tests: Vec<ManifestEntry>,
+    // Percentage of the legal opcodes the suite has to execute, only
+    // checked when no ROM was skipped
+    pub min_opcode_coverage: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestStatus {
+    Passed,
+    Failed(String),
+    Skipped(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ManifestOutcome {
+    pub rom: PathBuf,
+    pub status: ManifestStatus,
+    pub duration: Duration,
+    // Opcodes executed by the ROM, none when it was skipped
+    pub coverage: Option<OpcodeCoverage>,
+}
+
+// Runs the cartridge for a number of frames and returns the last one
+pub fn run_frames(cartridge: Cartridge, frames: u64) -> Result<GameBoyFrame, Error> {
+    let mut emu = Emulation::new(Some(cartridge));
+    emu.start();
+    frames_of(&mut emu, frames)
+}
+
+fn frames_of(emu: &mut Emulation, frames: u64) -> Result<GameBoyFrame, Error> {
+    for _ in 0..frames {
+        emu.skip_frame()?;
+    }
+    Ok(emu.screen().into_owned())
+}
+
+// Compares a frame with a golden PNG saved with the grayscale palette
+pub fn check_golden_frame<P: AsRef<Path>>(frame: &GameBoyFrame, golden: P) -> Result<GoldenResult, Error> {
+    let golden = golden.as_ref();
+    let palette = ColorPalette::GRAYSCALE;
+
+    if std::env::var_os(UPDATE_GOLDEN_VARIABLE).is_some() {
+        save_png(frame, &palette, golden)?;
+        return Ok(GoldenResult::Created);
+    }
+    // A deleted or renamed golden file would otherwise pass unnoticed
+    if !golden.exists() {
+        let message = format!("Golden frame {} is missing, set {} to create it", golden.display(), UPDATE_GOLDEN_VARIABLE);
+        return Err(Error::new(ErrorKind::NotFound, message));
+    }
+
+    let (width, height, expected) = load_png(golden)?;
+    let actual_pixels = frame.to_rgba(&palette);
+    if (width, height) != (frame.width, frame.height) {
+        return Err(Error::new(ErrorKind::InvalidData, format!("Golden frame is {}x{}, expected {}x{}", width, height, frame.width, frame.height)));
+    }
+
+    let differing: Vec<usize> = (0..actual_pixels.len() / 4)
+        .filter(|pixel| actual_pixels[pixel * 4..pixel * 4 + 4] != expected[pixel * 4..pixel * 4 + 4])
+        .collect();
+
+    match differing.first() {
+        None => Ok(GoldenResult::Match),
+        Some(first) => {
+            let name = golden.with_extension("actual.png");
+            let actual = std::env::temp_dir().join(name.file_name().unwrap_or_default());
+            save_png(frame, &palette, &actual)?;
+            Ok(GoldenResult::Mismatch {
+                differing_pixels: differing.len(),
+                first: (*first as u32 % width, *first as u32 / width),
+                actual,
+            })
+        }
+    }
+}
+
+// Runs a blargg or mooneye test ROM without any UI. Blargg ROMs report through
+// the serial port or cartridge RAM and mooneye ROMs through the register signature.
+pub fn run_test_rom(cartridge: Cartridge, max_frames: u64) -> Result<TestRomReport, Error> {
+    test_rom_report(&mut GameBoy::new(Some(cartridge), AccuracyProfile::Accurate.into()), max_frames)
+}
+
+fn test_rom_report(gb: &mut GameBoy, max_frames: u64) -> Result<TestRomReport, Error> {
+    // Some ROMs check the PPU locks VRAM and OAM
+    EmulationConfig::profile(AccuracyProfile::Accurate).apply(gb);
+    let mut serial = String::new();
+    let mut cycles: u64 = 0;
+    let max_cycles = max_frames * CPU_CYCLES_PER_FRAME as u64;
+
+    while cycles < max_cycles {
+        if MMU::read_byte(gb, gb.cpu.pc) == MOONEYE_BREAKPOINT {
+            if let Some(result) = mooneye_result(gb) {
+                return Ok(TestRomReport { result, serial, frames: cycles / CPU_CYCLES_PER_FRAME as u64 });
+            }
+        }
+
+        let executed = gb.tick()? as u64;
+        cycles += executed;
+
+        // Memory results are checked once per frame
+        if cycles % (CPU_CYCLES_PER_FRAME as u64) < executed {
+            if let Some((result, text)) = blargg_memory_result(gb) {
+                serial.push_str(&text);
+                return Ok(TestRomReport { result, serial, frames: cycles / CPU_CYCLES_PER_FRAME as u64 });
+            }
+        }
+
+        if let Some(data) = gb.read_serial() {
+            serial.push(data as char);
+            let result = if serial.contains("Passed") {
+                Some(TestRomResult::Passed)
+            } else if serial.contains("Failed") {
+                Some(TestRomResult::Failed)
+            } else {
+                None
+            };
+            if let Some(result) = result {
+                return Ok(TestRomReport { result, serial, frames: cycles / CPU_CYCLES_PER_FRAME as u64 });
+            }
+        }
+    }
+
+    Ok(TestRomReport { result: TestRomResult::Timeout, serial, frames: max_frames })
+}
+
+fn mooneye_result(gb: &GameBoy) -> Option<TestRomResult> {
+    let regs = &gb.cpu.regs;
+    let signature = [regs.b, regs.c, regs.d, regs.e, regs.h, regs.l];
+
+    if signature == MOONEYE_PASS {
+        Some(TestRomResult::Passed)
+    } else if signature == MOONEYE_FAIL {
+        Some(TestRomResult::Failed)
+    } else {
+        None
+    }
+}
+
+fn blargg_memory_result(gb: &GameBoy) -> Option<(TestRomResult, String)> {
+    let signature = [1, 2, 3].map(|offset| MMU::read_byte(gb, BLARGG_STATUS_ADDRESS + offset));
+    let status = MMU::read_byte(gb, BLARGG_STATUS_ADDRESS);
+    if signature != BLARGG_SIGNATURE || status == BLARGG_RUNNING {
+        return None;
+    }
+
+    let text = (BLARGG_STATUS_ADDRESS + 4..0xC000)
+        .map(|address| MMU::read_byte(gb, address))
+        .take_while(|byte| *byte != 0)
+        .map(|byte| byte as char)
+        .collect();
+    let result = if status == 0 { TestRomResult::Passed } else { TestRomResult::Failed };
+    Some((result, text))
+}
+
+// Stable fingerprint of a frame for manifest entries, independent of the palette
+pub fn frame_crc32(frame: &GameBoyFrame) -> u32 {
+    let pixels: Vec<u8> = frame.buffer.iter().map(|pixel| u8::from(*pixel)).collect();
+    crc32(&pixels)
+}
+
+pub fn load_manifest<P: AsRef<Path>>(path: P) -> Result<Manifest, Error> {
+    toml::from_str(&fs::read_to_string(path)?).map_err(|error| Error::new(ErrorKind::InvalidData, error))
+}
+
+// Runs every entry on all the cores, outcomes are in the order of the manifest
+pub fn run_manifest(entries: &[ManifestEntry], assets: &Path) -> Vec<ManifestOutcome> {
+    let next = AtomicUsize::new(0);
+    let outcomes = Mutex::new(vec![None; entries.len()]);
+    let workers = thread::available_parallelism().map_or(1, |count| count.get()).min(entries.len());
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(entry) = entries.get(index) else { break };
+                let start = Instant::now();
+                let (status, coverage) = run_manifest_entry(entry, assets);
+                let outcome = ManifestOutcome { rom: entry.rom.clone(), status, duration: start.elapsed(), coverage };
+                outcomes.lock().unwrap()[index] = Some(outcome);
+            });
+        }
+    });
+    outcomes.into_inner().unwrap().into_iter().flatten().collect()
+}
+
+fn run_manifest_entry(entry: &ManifestEntry, assets: &Path) -> (ManifestStatus, Option<OpcodeCoverage>) {
+    if let Some(feature) = entry.features.iter().find(|feature| !supports_feature(feature)) {
+        return (ManifestStatus::Skipped(format!("needs {}", feature)), None);
+    }
+    let path = assets.join(&entry.rom);
+    if !path.exists() {
+        return (ManifestStatus::Skipped("the ROM is missing".to_string()), None);
+    }
+    let cartridge = match Cartridge::new(path) {
+        Ok(cartridge) => cartridge,
+        Err(error) => return (ManifestStatus::Failed(error.to_string()), None),
+    };
+
+    let mut emu = Emulation::new(Some(cartridge));
+    emu.start();
+    emu.start_opcode_coverage();
+    let result = match (&entry.serial, &entry.hash) {
+        (_, Some(hash)) => frames_of(&mut emu, entry.frames).map(|frame| {
+            let actual = format!("{:08x}", frame_crc32(&frame));
+            if actual.eq_ignore_ascii_case(hash) { ManifestStatus::Passed } else { ManifestStatus::Failed(format!("last frame hash is {}", actual)) }
+        }),
+        (Some(expected), None) => test_rom_report(&mut emu.gameboy, entry.frames).map(|report| {
+            if report.serial.contains(expected.as_str()) { ManifestStatus::Passed } else { ManifestStatus::Failed(format!("serial output was {:?}", report.serial)) }
+        }),
+        (None, None) => test_rom_report(&mut emu.gameboy, entry.frames).map(|report| match report.result {
+            TestRomResult::Passed => ManifestStatus::Passed,
+            result => ManifestStatus::Failed(format!("{:?} after {} frames {}", result, report.frames, report.serial.trim())),
+        }),
+    };
+    (result.unwrap_or_else(|error| ManifestStatus::Failed(error.to_string())), emu.stop_opcode_coverage())
+}
+
+// Opcodes executed by all the ROMs that ran
+pub fn manifest_coverage(outcomes: &[ManifestOutcome]) -> OpcodeCoverage {
+    let mut coverage = OpcodeCoverage::new();
+    for outcome in outcomes.iter().filter_map(|outcome| outcome.coverage.as_ref()) {
+        coverage.merge(outcome);
+    }
+    coverage
+}
+
+// What manifest entries can require
+fn supports_feature(feature: &str) -> bool {
+    match feature {
+        "rtc" | "serial" | "apu" => true,
+        feature => {
+            let capabilities = capabilities::capabilities();
+            capabilities.has_feature(feature) || capabilities.supports_mapper(feature) || capabilities.supports_model(feature)
+        },
+    }
+}
+
+impl fmt::Display for ManifestOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let seconds = self.duration.as_secs_f32();
+        match &self.status {
+            ManifestStatus::Passed => write!(f, "PASS {} ({:.1}s)", self.rom.display(), seconds),
+            ManifestStatus::Failed(reason) => write!(f, "FAIL {} ({:.1}s): {}", self.rom.display(), seconds, reason),
+            ManifestStatus::Skipped(reason) => write!(f, "SKIP {}: {}", self.rom.display(), reason),
+        }
+    }
+}
+
+// One line per ROM and the totals
+pub fn manifest_summary(outcomes: &[ManifestOutcome]) -> String {
+    let count = |wanted: fn(&ManifestStatus) -> bool| outcomes.iter().filter(|outcome| wanted(&outcome.status)).count();
+    let mut lines: Vec<String> = outcomes.iter().map(|outcome| outcome.to_string()).collect();
+    lines.push(format!("{} passed, {} failed, {} skipped",
+        count(|status| *status == ManifestStatus::Passed),
+        count(|status| matches!(status, ManifestStatus::Failed(_))),
+        count(|status| matches!(status, ManifestStatus::Skipped(_)))));
+    lines.join("\n")
+}
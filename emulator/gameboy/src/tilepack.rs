@@ -0,0 +1,78 @@
+use std::{collections::HashMap, io::{Error, ErrorKind}, path::Path};
+
+use crate::{io::lcd::{TILEDATA_COLS, TILEDATA_ROWS, TILE_SIZE}, palette::ColorPalette, ppu::TilePixelValue, screenshot::load_png};
+
+// Pixels of a replacement tile, None where the sheet is transparent and the
+// game's own pixel shows through
+type ReplacementTile = [[Option<TilePixelValue>; 8]; 8];
+
+// Graphics mod: tiles drawn in place of some of the game's when the screen is
+// rendered, VRAM keeps what the game wrote. The sheet has the layout of the
+// tiles.png export at 1x, 16 tiles per row, so a modder can export it, redraw
+// some tiles and list them. Pixels are matched to the nearest shade of the
+// grayscale palette and still go through BGP, so fades keep working.
+#[derive(Clone, Default)]
+pub struct TileReplacements {
+    tiles: HashMap<usize, ReplacementTile>,
+}
+
+impl TileReplacements {
+    pub fn load<P: AsRef<Path>>(sheet: P, tiles: &[usize]) -> Result<Self, Error> {
+        let (width, height, rgba) = load_png(sheet.as_ref())?;
+        TileReplacements::from_sheet(width, height, &rgba, tiles)
+    }
+
+    // Tile N of the sheet replaces tile N of the tile data
+    pub fn from_sheet(width: u32, height: u32, rgba: &[u8], tiles: &[usize]) -> Result<Self, Error> {
+        let columns = (width / TILE_SIZE) as usize;
+        let rows = (height / TILE_SIZE) as usize;
+        let mut replacements = TileReplacements::default();
+        for &tile in tiles {
+            if tile >= TILEDATA_COLS * TILEDATA_ROWS {
+                return Err(Error::new(ErrorKind::InvalidInput, format!("There is no tile {}, the last one is {}", tile, TILEDATA_COLS * TILEDATA_ROWS - 1)));
+            }
+            // Sheets drawn with the layout of the export
+            let (column, row) = (tile % TILEDATA_COLS, tile / TILEDATA_COLS);
+            if column >= columns || row >= rows {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Tile {} is outside the {}x{} sheet", tile, width, height)));
+            }
+
+            let mut pixels: ReplacementTile = Default::default();
+            for (y, line) in pixels.iter_mut().enumerate() {
+                for (x, pixel) in line.iter_mut().enumerate() {
+                    let offset = ((row * 8 + y) * width as usize + column * 8 + x) * 4;
+                    *pixel = shade(&rgba[offset..offset + 4]);
+                }
+            }
+            replacements.tiles.insert(tile, pixels);
+        }
+        Ok(replacements)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    pub(crate) fn pixel(&self, tile: usize, x: u8, y: u8) -> Option<TilePixelValue> {
+        self.tiles.get(&tile).and_then(|pixels| pixels[y as usize][x as usize])
+    }
+}
+
+fn shade(rgba: &[u8]) -> Option<TilePixelValue> {
+    if rgba[3] < 0x80 {
+        return None;
+    }
+    let luma = (rgba[0] as i32 * 299 + rgba[1] as i32 * 587 + rgba[2] as i32 * 114) / 1000;
+    let colors = ColorPalette::GRAYSCALE.colors;
+    let nearest = (0..colors.len()).min_by_key(|&index| (colors[index][0] as i32 - luma).abs()).unwrap_or(0);
+    Some(match nearest {
+        0 => TilePixelValue::Zero,
+        1 => TilePixelValue::One,
+        2 => TilePixelValue::Two,
+        _ => TilePixelValue::Three,
+    })
+}
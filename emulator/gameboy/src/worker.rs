@@ -0,0 +1,175 @@
+use std::{io::{Error, ErrorKind}, mem, sync::{mpsc::{channel, Receiver, Sender, TryRecvError}, Arc, Mutex}, thread::{self, JoinHandle}};
+
+use crate::{audio::AudioProducer, clock::{Clock, Speed}, savestate::SaveState, Emulation, EmulationError, EmulationStep, InputEvent};
+
+// What the frontend asks the emulation thread
+pub enum Command {
+    Input(InputEvent),
+    Pause,
+    Resume,
+    // Runs one frame while paused
+    FrameAdvance,
+    SetSpeed(Speed),
+    // The state is sent back through the given channel
+    SaveState(Sender<SaveState>),
+    // So is whether it could be loaded, it fails for another ROM
+    LoadState(SaveState, Sender<Result<(), Error>>),
+}
+
+// The last value published and whether the reader has seen it
+type Shared<T> = Arc<Mutex<(Option<T>, bool)>>;
+
+// Triple buffering: the writer fills its own buffer and swaps it with the
+// shared one, the reader swaps the shared one with its own when it is newer.
+// The lock is only held for the swap, neither side waits for the other to
+// finish with a frame, and the reader always gets the most recent one.
+struct TripleBufferWriter<T> {
+    back: Option<T>,
+    shared: Shared<T>,
+}
+
+struct TripleBufferReader<T> {
+    front: Option<T>,
+    shared: Shared<T>,
+}
+
+fn triple_buffer<T>() -> (TripleBufferWriter<T>, TripleBufferReader<T>) {
+    let shared = Arc::new(Mutex::new((None, false)));
+    (TripleBufferWriter { back: None, shared: shared.clone() }, TripleBufferReader { front: None, shared })
+}
+
+impl<T> TripleBufferWriter<T> {
+    fn publish(&mut self, value: T) {
+        self.back = Some(value);
+        let mut shared = self.shared.lock().unwrap();
+        mem::swap(&mut shared.0, &mut self.back);
+        shared.1 = true;
+    }
+}
+
+impl<T> TripleBufferReader<T> {
+    // None when nothing was published since the last call
+    fn read(&mut self) -> Option<&T> {
+        {
+            let mut shared = self.shared.lock().unwrap();
+            if !shared.1 {
+                return None;
+            }
+            mem::swap(&mut shared.0, &mut self.front);
+            shared.1 = false;
+        }
+        self.front.as_ref()
+    }
+}
+
+// Runs an Emulation on its own thread, paced to real time, so a frontend only
+// sends commands and presents what comes out. Frames go through a triple
+// buffer and audio through the ring buffer given to spawn. Dropping the handle
+// ends the thread, stop also gives the emulation back.
+pub struct EmulatorHandle {
+    commands: Sender<Command>,
+    frames: TripleBufferReader<EmulationStep>,
+    errors: Receiver<EmulationError>,
+    thread: JoinHandle<Emulation>,
+}
+
+impl EmulatorHandle {
+    // Audio has to be enabled on the emulation for samples to reach the producer
+    pub fn spawn(emulation: Emulation, audio: Option<AudioProducer>) -> Self {
+        let (commands, receiver) = channel();
+        let (writer, frames) = triple_buffer();
+        let (error_sender, errors) = channel();
+        let thread = thread::spawn(move || run(emulation, receiver, writer, audio, error_sender));
+        EmulatorHandle { commands, frames, errors, thread }
+    }
+
+    // Ignored once the thread is gone
+    pub fn send(&self, command: Command) {
+        let _ = self.commands.send(command);
+    }
+
+    // Waits for the emulation thread to finish its frame, None if it is gone
+    pub fn save_state(&self) -> Option<SaveState> {
+        let (sender, receiver) = channel();
+        self.send(Command::SaveState(sender));
+        receiver.recv().ok()
+    }
+
+    // Waits for the emulation thread to load the state
+    pub fn load_state(&self, state: SaveState) -> Result<(), Error> {
+        let (sender, receiver) = channel();
+        self.send(Command::LoadState(state, sender));
+        receiver.recv().unwrap_or_else(|_| Err(Error::new(ErrorKind::BrokenPipe, "The emulation thread is gone")))
+    }
+
+    // The last frame when a new one was published since the last call
+    pub fn latest_frame(&mut self) -> Option<&EmulationStep> {
+        self.frames.read()
+    }
+
+    // The emulation pauses itself when a frame fails, Resume goes on
+    pub fn take_error(&self) -> Option<EmulationError> {
+        self.errors.try_recv().ok()
+    }
+
+    pub fn stop(self) -> Emulation {
+        drop(self.commands);
+        self.thread.join().expect("emulation thread panicked")
+    }
+}
+
+fn run(mut emu: Emulation, commands: Receiver<Command>, mut frames: TripleBufferWriter<EmulationStep>,
+    mut audio: Option<AudioProducer>, errors: Sender<EmulationError>) -> Emulation {
+    let mut clock = Clock::new();
+    loop {
+        // Nothing to do until the next command while paused
+        let command = if emu.is_paused() {
+            match commands.recv() {
+                Ok(command) => Some(command),
+                Err(_) => return emu,
+            }
+        } else {
+            match commands.try_recv() {
+                Ok(command) => Some(command),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => return emu,
+            }
+        };
+        if let Some(command) = command {
+            match command {
+                Command::Input(input) => emu.send_input(input),
+                Command::Pause => emu.pause(),
+                // The clock starts over by itself after the lag of the pause
+                Command::Resume => emu.resume(),
+                Command::FrameAdvance => match emu.frame_advance() {
+                    Ok(step) => frames.publish(step),
+                    Err(error) => {
+                        let _ = errors.send(error);
+                    },
+                },
+                Command::SetSpeed(speed) => clock.set_speed(speed),
+                Command::SaveState(reply) => {
+                    let _ = reply.send(emu.save_state());
+                },
+                Command::LoadState(state, reply) => {
+                    let _ = reply.send(emu.load_state(&state));
+                },
+            }
+            continue;
+        }
+
+        let cycles = emu.total_cycles;
+        match emu.step() {
+            Ok(step) => frames.publish(step),
+            Err(error) => {
+                emu.pause();
+                let _ = errors.send(error);
+            },
+        }
+        if let Some(audio) = &mut audio {
+            audio.push(&emu.take_audio());
+            emu.adjust_audio_rate(audio.fill_level());
+        }
+        thread::sleep(clock.frame_done(emu.total_cycles - cycles));
+    }
+}
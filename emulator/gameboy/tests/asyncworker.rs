@@ -0,0 +1,33 @@
+// Awaited frames come out on the async channels, with the sound when audio is enabled
+#![cfg(feature = "async")]
+use futures_executor::block_on;
+use futures_util::StreamExt;
+use gameboy::{asyncworker::AsyncEmulator, audio::ResamplerQuality, Button, InputEvent};
+
+mod common;
+
+#[test]
+fn async_emulator_runs_awaited_frames() {
+    let mut emu = common::bench_emulation();
+    emu.enable_audio(48000, ResamplerQuality::Linear);
+    let (emulator, mut outputs) = AsyncEmulator::spawn(emu);
+
+    block_on(async {
+        emulator.send_input(InputEvent::Pressed(Button::Start));
+        emulator.run_frames(3).await.unwrap();
+        let state = emulator.save_state().await;
+        for _ in 0..3 {
+            let frame = outputs.frames.next().await.unwrap();
+            assert_eq!((frame.framebuffer.width, frame.framebuffer.height), (160, 144));
+            assert!(!outputs.audio.next().await.unwrap().is_empty());
+        }
+
+        // The emulation given back is the one with the state loaded last
+        emulator.run_frame().await.unwrap();
+        emulator.load_state(state.clone()).await.unwrap();
+        let emu = emulator.stop().await;
+        assert_eq!(emu.save_state().as_bytes(), state.as_bytes());
+        assert!(outputs.frames.next().await.is_some());
+        assert!(outputs.frames.next().await.is_none());
+    });
+}
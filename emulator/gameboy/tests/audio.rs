@@ -0,0 +1,28 @@
+// The audio ring buffer keeps whole stereo frames, wraps around and repeats the last one when it runs dry
+use gameboy::audio::ring_buffer;
+
+#[test]
+fn ring_buffer_wraps_around_and_reports_its_fill_level() {
+    let (mut producer, mut consumer) = ring_buffer(8);
+    assert!(producer.is_empty());
+    assert_eq!(producer.push(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]), 6);
+    assert_eq!(producer.fill_level(), 0.75);
+    // Only whole frames that fit are kept
+    assert_eq!(producer.push(&[7.0, 8.0, 9.0, 10.0]), 2);
+    assert_eq!(producer.fill_level(), 1.0);
+
+    let mut output = [0.0; 4];
+    assert_eq!(consumer.pop(&mut output), 4);
+    assert_eq!(output, [1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(producer.push(&[9.0, 10.0, 11.0]), 2);
+    assert_eq!((producer.len(), producer.fill_level()), (6, 0.75));
+
+    // The slots written past the end come back from the start
+    let mut output = [0.0; 10];
+    assert_eq!(consumer.pop(&mut output), 6);
+    assert_eq!(output, [5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 9.0, 10.0, 9.0, 10.0]);
+    assert_eq!(producer.fill_level(), 0.0);
+    let mut output = [0.0; 4];
+    assert_eq!(consumer.pop(&mut output), 0);
+    assert_eq!(output, [9.0, 10.0, 9.0, 10.0]);
+}
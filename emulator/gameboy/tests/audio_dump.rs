@@ -0,0 +1,63 @@
+// WAV dumps of the mix and the channel stems have headers that match the samples written
+use std::path::Path;
+
+use gameboy::{audio::AudioChannel, audiodump::{stem_path, AudioDumpOptions}, CPU_CLOCK_HZ, FPS};
+
+mod common;
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+// Checks the header of a 16 bit PCM file and returns its sample rate and the
+// number of sample frames in it
+fn read_wav(path: &Path, channels: u16) -> (u32, usize) {
+    let bytes = std::fs::read(path).unwrap();
+    assert_eq!(&bytes[0..4], b"RIFF");
+    assert_eq!(u32_at(&bytes, 4) as usize, bytes.len() - 8);
+    assert_eq!(&bytes[8..16], b"WAVEfmt ");
+    assert_eq!(u32_at(&bytes, 16), 16);
+    assert_eq!(u16_at(&bytes, 20), 1);
+    assert_eq!(u16_at(&bytes, 22), channels);
+    let sample_rate = u32_at(&bytes, 24);
+    let block_align = channels * 2;
+    assert_eq!(u32_at(&bytes, 28), sample_rate * block_align as u32);
+    assert_eq!(u16_at(&bytes, 32), block_align);
+    assert_eq!(u16_at(&bytes, 34), 16);
+    assert_eq!(&bytes[36..40], b"data");
+    let data_size = u32_at(&bytes, 40) as usize;
+    assert_eq!(data_size, bytes.len() - 44);
+    assert_eq!(data_size % block_align as usize, 0);
+    std::fs::remove_file(path).unwrap();
+    (sample_rate, data_size / block_align as usize)
+}
+
+#[test]
+fn audio_dump_writes_the_mix_and_the_stems() {
+    let path = std::env::temp_dir().join(format!("yagabor-dump-{}.wav", std::process::id()));
+    let frames = 30;
+    let mut emulation = common::bench_emulation();
+    emulation.start_audio_dump(&path, AudioDumpOptions { sample_rate: 48000, stems: true, ..Default::default() }).unwrap();
+    assert!(emulation.is_dumping_audio());
+    emulation.run_frames(frames).unwrap();
+    emulation.stop_audio_dump().unwrap();
+    assert!(!emulation.is_dumping_audio());
+
+    // The length of the run, give or take the samples still in the resampler
+    let expected = |rate: u32| frames as f32 / FPS * rate as f32;
+    let (rate, samples) = read_wav(&path, 2);
+    assert_eq!(rate, 48000);
+    assert!((samples as f32 - expected(rate)).abs() < 100.0, "{} samples", samples);
+
+    // The stems take a whole number of cycles per sample
+    let stem_rate = (CPU_CLOCK_HZ / (CPU_CLOCK_HZ / 48000)) as u32;
+    for channel in AudioChannel::ALL {
+        let (rate, samples) = read_wav(&stem_path(&path, channel), 1);
+        assert_eq!(rate, stem_rate);
+        assert!((samples as f32 - expected(rate)).abs() < 100.0, "{:?}: {} samples", channel, samples);
+    }
+}
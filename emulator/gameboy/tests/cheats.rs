@@ -0,0 +1,63 @@
+// Cheat codes decoded from the Game Genie and GameShark formats
+use gameboy::{cheats::CheatCode, Emulation};
+
+#[test]
+fn game_genie_codes_decode_address_value_and_compare() {
+    // The top nibble of the address is stored inverted in the last digit of the second group
+    assert_eq!(CheatCode::parse("00A-17B").unwrap(), CheatCode::GameGenie { address: 0x4A17, value: 0x00, compare: None });
+    assert_eq!(CheatCode::parse("3E9-5EF-4C1").unwrap(), CheatCode::GameGenie { address: 0x095E, value: 0x3E, compare: Some(0xEA) });
+    assert_eq!(CheatCode::parse(" 3e9-5ef-4c1 ").unwrap(), CheatCode::parse("3E9-5EF-4C1").unwrap());
+    // Only the cartridge ROM can be patched
+    assert!(CheatCode::parse("00A-177").is_err());
+    assert!(CheatCode::parse("00A-17B-C4").is_err());
+    assert!(CheatCode::parse("00G-17B").is_err());
+}
+
+#[test]
+fn gameshark_codes_have_a_little_endian_address() {
+    assert_eq!(CheatCode::parse("01FF16D0").unwrap(), CheatCode::GameShark { bank: 0x01, address: 0xD016, value: 0xFF });
+    assert_eq!(CheatCode::parse("91630BC2").unwrap(), CheatCode::GameShark { bank: 0x91, address: 0xC20B, value: 0x63 });
+    assert!(CheatCode::parse("01FF16D").is_err());
+    assert!(CheatCode::parse("01FF16D0A").is_err());
+}
+
+#[test]
+fn freeze_codes_skip_the_rom_area() {
+    assert_eq!(CheatCode::parse("C0A0=FF").unwrap(), CheatCode::Freeze { address: 0xC0A0, value: 0xFF });
+    assert_eq!(CheatCode::parse("?0143=80").unwrap(), CheatCode::Condition { address: 0x0143, value: 0x80 });
+    assert!(CheatCode::parse("2000=03").is_err());
+    assert!(CheatCode::parse("C0A0=1FF").is_err());
+}
+
+#[test]
+fn frozen_addresses_follow_the_order_and_conditions_of_the_codes() {
+    let mut emulation = Emulation::new(None);
+    emulation.add_cheat("C000=01").unwrap();
+    // A condition sees the writes of the codes before it
+    emulation.add_cheat("?C000=01").unwrap();
+    emulation.add_cheat("C001=02").unwrap();
+    // Chained conditions must all hold
+    emulation.add_cheat("?C000=01").unwrap();
+    emulation.add_cheat("?C002=05").unwrap();
+    emulation.add_cheat("C003=04").unwrap();
+    // A disabled code between a condition and its target is skipped
+    emulation.add_cheat("?C000=01").unwrap();
+    let disabled = emulation.add_cheat("C004=06").unwrap();
+    emulation.set_cheat_enabled(disabled, false);
+    emulation.add_cheat("C005=07").unwrap();
+    // The last code for an address wins
+    emulation.add_cheat("C006=08").unwrap();
+    emulation.add_cheat("C006=09").unwrap();
+    emulation.skip_frame().unwrap();
+
+    let debugger = emulation.debugger();
+    let frozen: Vec<u8> = (0xC000..=0xC006).map(|address| debugger.read_byte(address)).collect();
+    assert_eq!(frozen, [0x01, 0x02, 0x00, 0x00, 0x00, 0x07, 0x09]);
+    let active: Vec<bool> = emulation.cheats().cheats().iter().map(|cheat| cheat.active).collect();
+    assert_eq!(active, [true, true, true, true, true, false, true, true, true, true, true]);
+
+    emulation.poke(0xC002, 0x05);
+    emulation.skip_frame().unwrap();
+    emulation.skip_frame().unwrap();
+    assert_eq!(emulation.debugger().read_byte(0xC003), 0x04);
+}
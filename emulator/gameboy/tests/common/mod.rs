@@ -0,0 +1,10 @@
+// Shared by the integration tests
+use std::path::PathBuf;
+
+use gameboy::{cartridge::Cartridge, Emulation};
+
+// The benchmark ROM, built from assets/bench/bench.asm
+pub fn bench_emulation() -> Emulation {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    Emulation::new(Some(Cartridge::new(root.join("../assets/bench/bench.gb")).unwrap()))
+}
@@ -0,0 +1,49 @@
+// The settings file fills what it leaves out with defaults and survives a save
+use std::{fs, path::PathBuf};
+
+use gameboy::{accuracy::AccuracyProfile, audio::ResamplerQuality, config::EmulatorConfig, Button};
+
+mod common;
+
+#[test]
+fn config_file_round_trip_and_apply() {
+    let config: EmulatorConfig = toml::from_str("
+        accuracy = \"Accurate\"
+        save_directory = \"saves\"
+
+        [keys]
+        a = \"Z\"
+
+        [audio]
+        quality = \"BandLimited\"
+    ").unwrap();
+    assert_eq!(config.accuracy, AccuracyProfile::Accurate);
+    assert_eq!(config.keys.button("z"), Some(Button::A));
+    assert_eq!(config.keys.button("S"), Some(Button::B));
+    assert_eq!(config.keys.button("A"), None);
+    assert_eq!((config.audio.enabled, config.audio.quality), (true, ResamplerQuality::BandLimited));
+    assert_eq!(config.palette, "grayscale");
+    assert_eq!(config.battery_path_for("roms/game.gb"), PathBuf::from("saves/game.sav"));
+
+    let directory = std::env::temp_dir().join(format!("yagabor-config-{}", std::process::id()));
+    let path = directory.join("nested/config.toml");
+    assert_eq!(EmulatorConfig::load(&path).unwrap(), EmulatorConfig::default());
+    config.save(&path).unwrap();
+    assert_eq!(EmulatorConfig::load(&path).unwrap(), config);
+
+    // A boot ROM that jumps to its own start never reaches the cartridge
+    let boot_rom = directory.join("boot.bin");
+    let mut data = vec![0; 0x100];
+    data[..3].copy_from_slice(&[0xC3, 0x00, 0x00]);
+    fs::write(&boot_rom, &data).unwrap();
+    let config = EmulatorConfig { boot_rom: Some(boot_rom.clone()), ..config };
+    let mut emu = common::bench_emulation();
+    config.apply(&mut emu).unwrap();
+    emu.start();
+    emu.run_frames(10).unwrap();
+    assert_eq!(emu.debugger().register("pc"), Some(0x0000));
+
+    fs::write(&boot_rom, [0; 16]).unwrap();
+    assert!(config.apply(&mut emu).is_err());
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,47 @@
+// Frames simulated again from a savestate hash the same as the first time
+use gameboy::{determinism::StateHashes, Button, Emulation};
+
+mod common;
+
+fn bench_emulation() -> Emulation {
+    let mut emu = common::bench_emulation();
+    emu.start();
+    emu
+}
+
+fn hashed_run(emu: &mut Emulation, frames: u64) -> StateHashes {
+    emu.start_state_hashes();
+    for frame in 0..frames {
+        if frame % 20 == 0 {
+            emu.button_pressed(Button::A);
+        } else if frame % 20 == 5 {
+            emu.button_released(Button::A);
+        }
+        emu.skip_frame().unwrap();
+    }
+    emu.stop_state_hashes().unwrap()
+}
+
+#[test]
+fn savestate_replays_hash_like_the_original_run() {
+    let mut emu = bench_emulation();
+    emu.run_frames(400).unwrap();
+    let state = emu.save_state();
+
+    let original = hashed_run(&mut emu, 120);
+    assert_eq!(original.len(), 120);
+    emu.load_state(&state).unwrap();
+    let replay = hashed_run(&mut emu, 120);
+    assert_eq!(original.first_divergence(&replay), None);
+
+    let saved = StateHashes::from_bytes(&original.to_bytes()).unwrap();
+    assert_eq!(saved, original);
+
+    // A single byte of WRAM is enough to tell the runs apart
+    emu.load_state(&state).unwrap();
+    let mut debugger = emu.debugger();
+    let value = debugger.read_byte(0xD000);
+    debugger.write_byte(0xD000, value ^ 0xFF);
+    let divergence = original.first_divergence(&hashed_run(&mut emu, 120)).unwrap();
+    assert_eq!(divergence.cycles, original.iter().next().unwrap().0);
+}
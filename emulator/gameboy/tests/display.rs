@@ -0,0 +1,30 @@
+// Scalers round the diagonals of the frame and the filters darken the lines between Game Boy pixels
+use gameboy::{display::{DisplayPipeline, PostFilter, Scaler}, palette::ColorPalette, ColoredPixel, GameBoyFrame};
+
+// Rows of B for black and anything else for white
+fn frame(rows: &[&str]) -> GameBoyFrame {
+    let buffer = rows.concat().chars().map(|c| if c == 'B' { ColoredPixel::Black } else { ColoredPixel::White }).collect();
+    GameBoyFrame { width: rows[0].len() as u32, height: rows.len() as u32, buffer }
+}
+
+#[test]
+fn scale2x_and_scale3x_round_the_diagonals() {
+    let corner = frame(&["B.", ".."]);
+    assert_eq!(Scaler::Nearest(0).scale(&corner), corner);
+    assert_eq!(Scaler::Nearest(2).scale(&corner), frame(&["BB..", "BB..", "....", "...."]));
+    assert_eq!(Scaler::Scale2x.scale(&corner), frame(&["BB..", "B...", "....", "...."]));
+    assert_eq!(Scaler::Scale3x.scale(&corner), frame(&["BBB...", "BB....", "B.....", "......", "......", "......"]));
+}
+
+#[test]
+fn filters_darken_the_last_row_and_column_of_each_pixel() {
+    let white = frame(&["."]);
+    let brightness = |filter, scaler| {
+        let image = DisplayPipeline { palette: ColorPalette::GRAYSCALE, scaler, filter }.render(&white);
+        image.pixels.chunks(4).map(|pixel| pixel[0]).collect::<Vec<u8>>()
+    };
+    assert_eq!(brightness(PostFilter::Scanlines, Scaler::Scale2x), [0xFF, 0xFF, 153, 153]);
+    assert_eq!(brightness(PostFilter::LcdGrid, Scaler::Nearest(2)), [0xFF, 153, 153, 153]);
+    // Nothing to darken without scaling
+    assert_eq!(brightness(PostFilter::LcdGrid, Scaler::None), [0xFF]);
+}
@@ -0,0 +1,35 @@
+// Inputs sent while paused reach the joypad when the next frame starts, one frame at a time
+use gameboy::{movie::MovieEvent, Button, InputEvent};
+
+mod common;
+
+#[test]
+fn frame_advance_latches_the_inputs_of_the_pause() {
+    let mut emulation = common::bench_emulation();
+    emulation.start_movie_recording();
+    emulation.run_frames(5).unwrap();
+
+    emulation.pause();
+    let before = emulation.save_state();
+    emulation.send_input(InputEvent::Pressed(Button::A));
+    emulation.send_input(InputEvent::Pressed(Button::Start));
+    emulation.send_input(InputEvent::Released(Button::Start));
+    assert_eq!(emulation.save_state().as_bytes(), before.as_bytes());
+
+    emulation.frame_advance().unwrap();
+    emulation.frame_advance().unwrap();
+    assert!(emulation.is_paused());
+    assert_eq!(emulation.total_frames, 7);
+    emulation.send_input(InputEvent::Released(Button::A));
+    emulation.resume();
+    emulation.run_frames(1).unwrap();
+
+    let movie = emulation.stop_movie().unwrap();
+    let event = |frame, input| MovieEvent { frame, input };
+    assert_eq!(movie.events(), &[
+        event(5, InputEvent::Pressed(Button::A)),
+        event(5, InputEvent::Pressed(Button::Start)),
+        event(5, InputEvent::Released(Button::Start)),
+        event(7, InputEvent::Released(Button::A)),
+    ]);
+}
@@ -0,0 +1,23 @@
+// Frames rendered by the emulator compared against PNGs in tests/golden.
+// Run with YAGABOR_UPDATE_GOLDEN=1 to regenerate them after an intended change.
+use std::path::PathBuf;
+
+use gameboy::{cartridge::Cartridge, testrom::{check_golden_frame, run_frames, GoldenResult}};
+
+fn assert_golden_frame(rom: &str, frames: u64, golden: &str) {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let cartridge = Cartridge::new(root.join("../assets").join(rom)).unwrap();
+    let frame = run_frames(cartridge, frames).unwrap();
+
+    match check_golden_frame(&frame, root.join("tests/golden").join(golden)).unwrap() {
+        GoldenResult::Match | GoldenResult::Created => {},
+        GoldenResult::Mismatch { differing_pixels, first, actual } => {
+            panic!("{} pixels differ from {}, first at {:?}, see {}", differing_pixels, golden, first, actual.display())
+        },
+    }
+}
+
+#[test]
+fn benchmark_rom_after_600_frames() {
+    assert_golden_frame("bench/bench.gb", 600, "bench_600.png");
+}
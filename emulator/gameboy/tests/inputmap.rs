@@ -0,0 +1,44 @@
+// Several controls can hold a button, axes have a threshold and turbo buttons toggle
+use gameboy::{config::EmulatorConfig, inputmap::HostInput, Button, InputEvent};
+
+#[test]
+fn bindings_from_the_settings_drive_the_buttons() {
+    let config: EmulatorConfig = toml::from_str("
+        turbo_period = 4
+
+        [keys]
+        a = [\"Z\", \"pad:a\"]
+        left = [\"Left\", \"axis:leftx-\"]
+
+        [[turbo]]
+        input = \"pad:x\"
+        button = \"B\"
+    ").unwrap();
+    assert_eq!(config.keys.button("PAD:A"), Some(Button::A));
+    assert_eq!(config.keys.inputs(Button::Start), ["Return"]);
+    let mut map = config.input_map().unwrap();
+
+    map.set(HostInput::key("z"), true);
+    map.set(HostInput::parse("pad:a").unwrap(), true);
+    assert_eq!(map.update(), [InputEvent::Pressed(Button::A)]);
+    // Still held by the gamepad
+    map.set(HostInput::key("Z"), false);
+    assert!(map.update().is_empty());
+    map.set(HostInput::gamepad_button("a"), false);
+    assert_eq!(map.update(), [InputEvent::Released(Button::A)]);
+
+    map.set_axis("leftx", -20000);
+    assert_eq!(map.update(), [InputEvent::Pressed(Button::Left)]);
+    map.set_axis("leftx", -1000);
+    assert_eq!(map.update(), [InputEvent::Released(Button::Left)]);
+
+    map.set(HostInput::gamepad_button("x"), true);
+    let pressed: Vec<bool> = (0..6).map(|_| {
+        map.update();
+        map.is_pressed(Button::B)
+    }).collect();
+    assert_eq!(pressed, [true, true, false, false, true, true]);
+
+    assert_eq!(HostInput::parse("axis:lefty+").unwrap().to_string(), "axis:lefty+");
+    assert!(HostInput::parse("axis:lefty").is_err());
+}
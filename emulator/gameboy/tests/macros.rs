@@ -0,0 +1,41 @@
+// Macros send their inputs like live ones, on the frames they were written for
+use gameboy::{inputmacro::InputMacro, movie::MovieEvent, Button, InputEvent};
+
+mod common;
+
+#[test]
+fn macro_inputs_are_sent_on_their_frames() {
+    let mut emulation = common::bench_emulation();
+    // Recorded in a movie to see when they arrive
+    emulation.start_movie_recording();
+    let demo = InputMacro::new()
+        .at(120).tap(Button::Start)
+        .press(Button::B, 10)
+        .hold(Button::Right, 300);
+    // The release of Right is sent before frame 428
+    assert_eq!(demo.frames(), 429);
+    emulation.play_macro(demo.repeat());
+    emulation.run_frames(429 + 121).unwrap();
+    assert!(emulation.is_playing_macro());
+    // B and Right are held by the second round when the macro is stopped
+    emulation.run_frames(10).unwrap();
+    emulation.stop_macro();
+    assert!(!emulation.is_playing_macro());
+
+    let movie = emulation.stop_movie().unwrap();
+    let events: Vec<MovieEvent> = movie.events().to_vec();
+    let event = |frame, input| MovieEvent { frame, input };
+    assert_eq!(&events[..5], &[
+        event(120, InputEvent::Pressed(Button::Start)),
+        event(124, InputEvent::Released(Button::Start)),
+        event(128, InputEvent::Pressed(Button::B)),
+        event(128, InputEvent::Pressed(Button::Right)),
+        event(138, InputEvent::Released(Button::B)),
+    ]);
+    assert_eq!(events[5], event(428, InputEvent::Released(Button::Right)));
+    assert_eq!(events[6], event(549, InputEvent::Pressed(Button::Start)));
+    assert_eq!(&events[events.len() - 2..], &[
+        event(560, InputEvent::Released(Button::B)),
+        event(560, InputEvent::Released(Button::Right)),
+    ]);
+}
@@ -0,0 +1,75 @@
+// Movies replay inputs and resets on the exact frame they were recorded
+use gameboy::{movie::Movie, Button, ResetKind};
+
+mod common;
+
+#[test]
+fn resets_replay_on_the_recorded_frame() {
+    let mut emulation = common::bench_emulation();
+    emulation.run_frames(10).unwrap();
+    emulation.start_movie_recording();
+    emulation.run_frames(100).unwrap();
+    emulation.button_pressed(Button::A);
+    emulation.run_frames(5).unwrap();
+    emulation.button_released(Button::A);
+    emulation.run_frames(20).unwrap();
+    emulation.reset(ResetKind::Soft);
+    emulation.run_frames(30).unwrap();
+    emulation.reset(ResetKind::Hard);
+    emulation.run_frames(10).unwrap();
+    let expected = emulation.save_state();
+
+    let movie = emulation.stop_movie().unwrap();
+    assert_eq!(movie.frames(), 165);
+    assert_eq!(movie.events().len(), 4);
+    assert_eq!(movie.events()[2].frame, 125);
+    let movie = Movie::from_bytes(&movie.to_bytes()).unwrap();
+
+    let mut replay = common::bench_emulation();
+    replay.play_movie(movie).unwrap();
+    replay.run_frames(120).unwrap();
+    // Live input is ignored during the playback
+    replay.reset(ResetKind::Hard);
+    replay.run_frames(45).unwrap();
+    assert!(!replay.is_playing_movie());
+    assert_eq!(replay.save_state(), expected);
+}
+
+#[test]
+fn cheats_and_pokes_replay_and_stay_in_savestates() {
+    let mut emulation = common::bench_emulation();
+    let frozen = emulation.add_cheat("D000=5A").unwrap();
+    emulation.start_movie_recording();
+    emulation.run_frames(10).unwrap();
+    emulation.poke(0xD001, 0x42);
+    let condition = emulation.add_cheat("?D001=42").unwrap();
+    emulation.add_cheat("D002=99").unwrap();
+    emulation.run_frames(10).unwrap();
+    emulation.remove_cheat(condition);
+    emulation.set_cheat_enabled(frozen, false);
+    emulation.run_frames(10).unwrap();
+    // Waits for the next VBlank in the savestate
+    emulation.poke(0xD003, 0x17);
+    let state = emulation.save_state();
+
+    let movie = emulation.stop_movie().unwrap();
+    assert_eq!(movie.events().len(), 6);
+    let movie = Movie::from_bytes(&movie.to_bytes()).unwrap();
+
+    let mut replay = common::bench_emulation();
+    replay.play_movie(movie).unwrap();
+    assert_eq!(replay.cheats().cheats().len(), 1);
+    assert!(replay.add_cheat("D004=01").is_err());
+    replay.run_frames(30).unwrap();
+    assert_eq!(replay.cheats().cheats(), emulation.cheats().cheats());
+    // The last poke was sent after the last frame of the movie
+    replay.poke(0xD003, 0x17);
+    assert_eq!(replay.save_state(), state);
+
+    let mut restored = common::bench_emulation();
+    restored.load_state(&state).unwrap();
+    assert_eq!(restored.cheats().cheats().len(), 2);
+    restored.skip_frame().unwrap();
+    let debugger = restored.debugger();
+    assert_eq!((debugger.read_byte(0xD002), debugger.read_byte(0xD003)), (0x99, 0x17));
+}
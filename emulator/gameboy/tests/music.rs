@@ -0,0 +1,51 @@
+// A GBS rip plays on its stub and the sound register writes come out as a VGM log
+use gameboy::music::GbsFile;
+
+// Init stores the song at C001, play counts its calls at C000 and writes the count to NR13
+fn gbs_file() -> Vec<u8> {
+    let mut bytes = vec![0; 0x70];
+    bytes[..4].copy_from_slice(b"GBS\x01");
+    bytes[0x04] = 3;
+    bytes[0x05] = 1;
+    bytes[0x06..0x0E].copy_from_slice(&[0x00, 0x04, 0x00, 0x04, 0x04, 0x04, 0xFE, 0xFF]);
+    bytes[0x10..0x15].copy_from_slice(b"Tests");
+    bytes.extend_from_slice(&[
+        0xEA, 0x01, 0xC0, // LD (C001),A
+        0xC9,             // RET
+        0x21, 0x00, 0xC0, // LD HL,C000
+        0x34,             // INC (HL)
+        0x7E,             // LD A,(HL)
+        0xE0, 0x13,       // LDH (NR13),A
+        0xC9,             // RET
+    ]);
+    bytes
+}
+
+#[test]
+fn gbs_songs_play_and_log_to_vgm() {
+    let gbs = GbsFile::parse(&gbs_file()).unwrap();
+    assert_eq!((gbs.title.as_str(), gbs.song_count, gbs.first_song), ("Tests", 3, 0));
+    assert!(GbsFile::parse(b"GBX").is_err());
+
+    let mut emu = gbs.emulation().unwrap();
+    gbs.start_song(&mut emu, 2).unwrap();
+    assert!(gbs.start_song(&mut emu, 3).is_err());
+    emu.start_vgm_log();
+    emu.run_frames(10).unwrap();
+    let debugger = emu.debugger();
+    assert_eq!(debugger.read_byte(0xC001), 2);
+    // Play runs at every VBlank
+    let calls = debugger.read_byte(0xC000);
+    assert!((9..=10).contains(&calls), "{}", calls);
+
+    let vgm = emu.stop_vgm_log().unwrap().to_bytes();
+    assert_eq!(&vgm[..4], b"Vgm ");
+    assert_eq!(u32::from_le_bytes(vgm[4..8].try_into().unwrap()) as usize, vgm.len() - 4);
+    // About 735 samples per frame
+    let samples = u32::from_le_bytes(vgm[0x18..0x1C].try_into().unwrap());
+    assert!((7300..7400).contains(&samples), "{}", samples);
+    // The writes of play to NR13, counting up
+    let nr13: Vec<u8> = vgm[0x100..].windows(3).filter(|command| command[..2] == [0xB3, 0x03]).map(|command| command[2]).collect();
+    assert_eq!(nr13.last(), Some(&calls));
+    assert_eq!(vgm.last(), Some(&0x66));
+}
@@ -0,0 +1,61 @@
+// Two sessions on the same ROM stay in sync through late inputs and rollbacks
+use std::{collections::VecDeque, io::Error, sync::{Arc, Mutex}};
+
+use gameboy::{netplay::{button_mask, NetplaySession, NetplayTransport}, Button};
+
+mod common;
+
+type Queue = Arc<Mutex<VecDeque<(u32, Vec<u8>)>>>;
+
+// Packets arrive a few calls after they were sent, and some never do
+struct LaggyTransport {
+    outgoing: Queue,
+    incoming: Queue,
+    lag: u32,
+    clock: u32,
+    sent: u32,
+}
+
+impl NetplayTransport for LaggyTransport {
+    fn send(&mut self, packet: &[u8]) -> Result<(), Error> {
+        self.sent += 1;
+        if !self.sent.is_multiple_of(7) {
+            self.outgoing.lock().unwrap().push_back((self.clock + self.lag, packet.to_vec()));
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        self.clock += 1;
+        let mut incoming = self.incoming.lock().unwrap();
+        match incoming.front() {
+            Some((arrival, _)) if *arrival <= self.clock => Ok(incoming.pop_front().map(|(_, packet)| packet)),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[test]
+fn peers_stay_in_sync_with_rollbacks() {
+    let (first, second) = (Queue::default(), Queue::default());
+    let transport = |outgoing: &Queue, incoming: &Queue| Box::new(LaggyTransport { outgoing: outgoing.clone(), incoming: incoming.clone(), lag: 12, clock: 0, sent: 0 });
+    let mut peers = [
+        (common::bench_emulation(), NetplaySession::new(transport(&first, &second), 2)),
+        (common::bench_emulation(), NetplaySession::new(transport(&second, &first), 2)),
+    ];
+
+    while peers.iter().any(|(_, session)| session.stats().frame < 300) {
+        for (player, (emulation, session)) in peers.iter_mut().enumerate() {
+            let frame = session.stats().frame;
+            // Both players change their buttons often, so guesses are wrong
+            let buttons = if (frame / 10 + player as u32).is_multiple_of(2) { button_mask(&[Button::A]) } else { button_mask(&[Button::Right]) };
+            session.advance_frame(emulation, buttons).unwrap();
+        }
+    }
+
+    for (_, session) in &peers {
+        let stats = session.stats();
+        assert!(stats.rollbacks > 0);
+        assert!(stats.verified_frame.is_some_and(|frame| frame >= 180), "{:?}", stats);
+    }
+}
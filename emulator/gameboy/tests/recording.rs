@@ -0,0 +1,42 @@
+// GIF recordings keep the timing of the emulation with delays that viewers don't slow down
+use gameboy::{recorder::RecordingOptions, FPS};
+
+mod common;
+
+// The delay of every frame, in hundredths of second
+fn recorded_delays(frame_skip: u32, frames: u64) -> Vec<u16> {
+    let path = std::env::temp_dir().join(format!("yagabor-recording-{}-{}.gif", std::process::id(), frame_skip));
+    let mut emulation = common::bench_emulation();
+    emulation.start_recording(&path, RecordingOptions { frame_skip, ..Default::default() }).unwrap();
+    emulation.run_frames(frames).unwrap();
+    emulation.stop_recording().unwrap();
+
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    let mut decoder = options.read_info(std::fs::File::open(&path).unwrap()).unwrap();
+    let mut delays = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        delays.push(frame.delay);
+    }
+    std::fs::remove_file(&path).unwrap();
+    delays
+}
+
+#[test]
+fn gif_delays_are_never_below_2_and_add_up_to_the_run() {
+    let frame_time = 100.0 / FPS;
+    // Without frame skip about 50 frames a second are kept
+    let delays = recorded_delays(0, 300);
+    assert!(delays.iter().all(|delay| *delay == 2), "{:?}", delays);
+    // The LCD is off for the first frames of the bench ROM, those aren't recorded
+    let total = delays.len() as f32 * 2.0;
+    assert!(total <= 300.0 * frame_time && total > 280.0 * frame_time, "{}", total);
+
+    for frame_skip in [1, 3] {
+        let delays = recorded_delays(frame_skip, 300);
+        assert!(delays.iter().all(|delay| *delay >= 2), "{:?}", delays);
+        let skipped_total: u32 = delays.iter().map(|delay| *delay as u32).sum();
+        // The frames dropped at the end aren't in any delay
+        assert!((skipped_total as f32 - total).abs() < (frame_skip + 2) as f32 * frame_time, "{} for {}", skipped_total, total);
+    }
+}
@@ -0,0 +1,29 @@
+// The scanline hook sees every visible line in order and what it draws ends up in the frame
+use std::sync::{Arc, Mutex};
+
+use gameboy::ColoredPixel;
+
+mod common;
+
+#[test]
+fn scanline_hook_changes_the_frame() {
+    let mut emulation = common::bench_emulation();
+    emulation.run_frames(5).unwrap();
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let seen = lines.clone();
+    emulation.set_scanline_hook(Box::new(move |line: u8, pixels: &mut [ColoredPixel]| {
+        assert_eq!(pixels.len(), 160);
+        seen.lock().unwrap().push(line);
+        pixels.fill(ColoredPixel::Black);
+    }));
+    // Frames are counted in cycles, the first one may have started before the hook
+    emulation.run_frames(1).unwrap();
+    let step = emulation.step().unwrap();
+
+    assert!(step.framebuffer.buffer.iter().all(|pixel| *pixel == ColoredPixel::Black));
+    let lines = lines.lock().unwrap();
+    assert!(lines.windows(2).all(|pair| pair[1] == (pair[0] + 1) % 144));
+    assert!(lines.len() >= 144);
+    assert!(emulation.take_scanline_hook().is_some());
+}
@@ -0,0 +1,67 @@
+// Scripts see the running emulation from their callbacks
+#![cfg(feature = "scripting")]
+use gameboy::{display::RgbaImage, scripting::Script, Emulation, EmulationError};
+
+mod common;
+
+const SCRIPT: &str = r#"
+    add_breakpoint(0x0100);
+
+    fn on_frame() {
+        write8(0xC100, frame());
+        pixel(1, 0, 0xFF0000FF);
+        if frame() == 3 { press("Start") }
+    }
+
+    fn on_breakpoint(address) {
+        this.entry = address;
+        write8(0xC101, if reg("pc") == address { 1 } else { 0 });
+    }
+"#;
+
+#[test]
+fn callbacks_access_memory_and_draw() {
+    let mut emulation = common::bench_emulation();
+    emulation.start();
+    let mut script = Script::compile(SCRIPT).unwrap();
+    script.start(&mut emulation).unwrap();
+
+    // The boot ROM takes a few seconds to reach the cartridge
+    let mut breakpoints = 0;
+    for frame in 1..=400u32 {
+        loop {
+            match emulation.skip_frame() {
+                Ok(()) => break,
+                Err(EmulationError::Breakpoint { address }) => {
+                    assert!(script.on_breakpoint(&mut emulation, address).unwrap());
+                    assert_eq!(emulation.debugger().read_byte(0xC101), 1);
+                    breakpoints += 1;
+                },
+                Err(error) => panic!("{}", error),
+            }
+        }
+        script.on_frame(&mut emulation).unwrap();
+        assert_eq!(emulation.debugger().read_byte(0xC100), frame as u8);
+    }
+    assert_eq!(breakpoints, 1);
+
+    let mut image = RgbaImage { width: 160, height: 144, pixels: vec![0; 160 * 144 * 4] };
+    script.overlay().draw(&mut image);
+    assert_eq!(&image.pixels[..8], &[0, 0, 0, 0, 0xFF, 0, 0, 0]);
+}
+
+#[test]
+fn scripts_see_the_ppu_timing() {
+    let mut emulation = Emulation::new(None);
+    let mut script = Script::compile("fn on_frame() { write8(0xC000, ppu_mode()); write8(0xC001, scanline()); write8(0xC002, ppu_dots_left() / 2) }").unwrap();
+    script.start(&mut emulation).unwrap();
+
+    for _ in 0..3 {
+        emulation.skip_frame().unwrap();
+        script.on_frame(&mut emulation).unwrap();
+        let timing = emulation.ppu_timing();
+        let debugger = emulation.debugger();
+        assert_eq!(debugger.read_byte(0xC000), debugger.read_byte(0xFF41) & 0x03);
+        assert_eq!((debugger.read_byte(0xC001), debugger.read_byte(0xC002)), (timing.scanline, (timing.remaining_dots / 2) as u8));
+    }
+}
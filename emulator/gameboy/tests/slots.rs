@@ -0,0 +1,36 @@
+// Savestate slots are kept per ROM and listed with the metadata of their states
+use gameboy::savestate::{SaveState, SLOT_COUNT};
+
+mod common;
+
+#[test]
+fn slots_save_load_and_list() {
+    let directory = std::env::temp_dir().join(format!("yagabor-slots-{}", std::process::id()));
+    let mut emu = common::bench_emulation();
+    emu.set_slot_directory(&directory);
+    emu.start();
+    assert!(emu.list_slots().unwrap().is_empty());
+
+    emu.run_frames(300).unwrap();
+    emu.save_slot(3).unwrap();
+    let saved = emu.save_state();
+    let metadata = saved.metadata();
+    assert_eq!((metadata.title.as_str(), metadata.frames), ("YAGABOR BENCH", 300));
+    assert_eq!((metadata.thumbnail.width, metadata.thumbnail.height), (80, 72));
+    assert_eq!(metadata.thumbnail.buffer[0], emu.screen().buffer[0]);
+    assert!(emu.state_slots().unwrap().directory().starts_with(&directory));
+
+    emu.run_frames(60).unwrap();
+    emu.load_slot(3).unwrap();
+    assert_eq!(emu.save_state(), saved);
+    assert_eq!(emu.total_frames, 300);
+
+    let slots = emu.list_slots().unwrap();
+    assert_eq!(slots.len(), 1);
+    assert_eq!((slots[0].slot, &slots[0].metadata), (3, saved.metadata()));
+    assert_eq!(&SaveState::peek_metadata(emu.state_slots().unwrap().state_path(3)).unwrap(), saved.metadata());
+
+    assert!(emu.load_slot(4).is_err());
+    assert!(emu.save_slot(SLOT_COUNT).is_err());
+    std::fs::remove_dir_all(directory).unwrap();
+}
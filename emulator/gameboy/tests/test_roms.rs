@@ -0,0 +1,29 @@
+// Accuracy test ROMs listed in test_roms.toml, run headlessly in parallel
+use std::{fs, path::PathBuf};
+
+use gameboy::testrom::{load_manifest, manifest_coverage, manifest_summary, run_manifest, ManifestStatus};
+
+// Set to a path to save the table of the opcodes the suite executed
+const COVERAGE_REPORT_VARIABLE: &str = "YAGABOR_OPCODE_COVERAGE";
+
+#[test]
+fn test_rom_manifest() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let manifest = load_manifest(root.join("tests/test_roms.toml")).unwrap();
+    let outcomes = run_manifest(&manifest.tests, &root.join("../assets"));
+
+    let summary = manifest_summary(&outcomes);
+    eprintln!("{}", summary);
+    assert!(outcomes.iter().all(|outcome| !matches!(outcome.status, ManifestStatus::Failed(_))), "{}", summary);
+
+    let coverage = manifest_coverage(&outcomes);
+    eprintln!("{:.1}% of the opcodes executed", coverage.percent());
+    if let Ok(path) = std::env::var(COVERAGE_REPORT_VARIABLE) {
+        fs::write(path, coverage.to_string()).unwrap();
+    }
+    // Missing ROMs would lower the coverage
+    let complete = outcomes.iter().all(|outcome| !matches!(outcome.status, ManifestStatus::Skipped(_)));
+    if let (Some(minimum), true) = (manifest.min_opcode_coverage, complete) {
+        assert!(coverage.percent() >= minimum, "{}", coverage);
+    }
+}
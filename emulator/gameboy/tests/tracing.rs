@@ -0,0 +1,49 @@
+// The core reports through tracing, with a target per subsystem and a span per frame
+use std::sync::{Arc, Mutex};
+
+use tracing::{span, subscriber, Event, Level, Metadata, Subscriber};
+
+mod common;
+
+// Keeps the targets of the debug events and the names of the spans
+#[derive(Clone, Default)]
+struct Targets(Arc<Mutex<Vec<String>>>);
+
+impl Subscriber for Targets {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= Level::DEBUG
+    }
+
+    fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        self.0.lock().unwrap().push(format!("span {}", span.metadata().name()));
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        self.0.lock().unwrap().push(event.metadata().target().to_string());
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+#[test]
+fn subsystems_have_their_own_targets() {
+    let mut emulation = common::bench_emulation();
+    emulation.start();
+    let targets = Targets::default();
+    subscriber::with_default(targets.clone(), || emulation.run_frames(400).unwrap());
+
+    let targets = targets.0.lock().unwrap();
+    assert_eq!(targets.iter().filter(|target| *target == "span frame").count(), 400);
+    // The boot ROM turns the LCD on and hands over to the cartridge
+    assert!(targets.iter().any(|target| target == "yagabor::ppu"));
+    assert!(targets.iter().any(|target| target == "yagabor::mmu"));
+    // Instructions are only traced at the trace level
+    assert!(!targets.iter().any(|target| target == "yagabor::cpu"));
+}
@@ -0,0 +1,34 @@
+// Turbo mashes a button with its duty cycle and macros written as text play like built ones
+use gameboy::{inputmacro::InputMacro, inputmap::Turbo, movie::MovieEvent, Button, InputEvent};
+
+mod common;
+
+#[test]
+fn turbo_duty_cycle_and_text_macros() {
+    let mut emulation = common::bench_emulation();
+    emulation.start_movie_recording();
+
+    // Pressed 1 frame out of 3
+    emulation.set_turbo(Button::A, Turbo::with_duty(3, 1));
+    emulation.run_frames(7).unwrap();
+    emulation.stop_turbo(Button::A);
+    assert_eq!(emulation.turbo(Button::A), None);
+
+    let movie = emulation.stop_movie().unwrap();
+    let event = |frame, input| MovieEvent { frame, input };
+    assert_eq!(movie.events(), &[
+        event(0, InputEvent::Pressed(Button::A)),
+        event(1, InputEvent::Released(Button::A)),
+        event(3, InputEvent::Pressed(Button::A)),
+        event(4, InputEvent::Released(Button::A)),
+        event(6, InputEvent::Pressed(Button::A)),
+        event(7, InputEvent::Released(Button::A)),
+    ]);
+    assert_eq!(Turbo::with_duty(4, 9), Turbo { period: 4, pressed: 3 });
+
+    let steps = "wait 120, tap start\n press b 10, hold right 300, repeat";
+    let built = InputMacro::new().wait(120).tap(Button::Start).press(Button::B, 10).hold(Button::Right, 300).repeat();
+    assert_eq!(InputMacro::parse(steps).unwrap(), built);
+    assert!(InputMacro::parse("hold right").is_err());
+    assert!(InputMacro::parse("tap x").is_err());
+}
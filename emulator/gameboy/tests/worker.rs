@@ -0,0 +1,40 @@
+// The emulation thread publishes frames, answers commands and gives the emulation back
+use std::{io::ErrorKind, thread, time::{Duration, Instant}};
+
+use gameboy::{clock::Speed, worker::{Command, EmulatorHandle}, Button, Emulation, InputEvent};
+
+mod common;
+
+fn bench_emulation() -> Emulation {
+    let mut emu = common::bench_emulation();
+    emu.start();
+    emu
+}
+
+#[test]
+fn worker_runs_the_emulation_until_stopped() {
+    let mut handle = EmulatorHandle::spawn(bench_emulation(), None);
+    handle.send(Command::SetSpeed(Speed::Uncapped));
+    handle.send(Command::Input(InputEvent::Pressed(Button::Start)));
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while handle.latest_frame().is_none() {
+        assert!(Instant::now() < deadline, "no frame published");
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert!(handle.take_error().is_none());
+
+    let state = handle.save_state().expect("emulation thread gone");
+    handle.send(Command::Pause);
+    handle.load_state(state.clone()).unwrap();
+    let paused = handle.save_state().unwrap();
+    // Nothing runs between commands while paused
+    assert_eq!(paused.as_bytes(), state.as_bytes());
+
+    // A state of another ROM is refused and the caller is told
+    let other_rom = Emulation::new(None).save_state();
+    assert_eq!(handle.load_state(other_rom).unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    let emu = handle.stop();
+    assert_eq!(emu.save_state().as_bytes(), state.as_bytes());
+}
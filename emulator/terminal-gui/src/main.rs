@@ -0,0 +1,411 @@
+mod screen;
+
+use std::{collections::HashMap, io::{Error, ErrorKind}, time::{Duration, Instant}};
+
+use clap::Parser;
+use crossterm::{
+    cursor::{Hide, Show},
+    event::{self, Event, KeyCode, KeyEventKind, KeyboardEnhancementFlags, PushKeyboardEnhancementFlags, PopKeyboardEnhancementFlags},
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use gameboy::{Emulation, capabilities, cartridge::{self, Cartridge}, debugger::{self, Annotations, Expression, SymbolTable, Watchpoint}, determinism::StateHashes, multiplayer::MultiplayerSession, palette::ColorPalette, savestate::SaveState, screenshot::SheetOptions, scripting::Script, Button, EmulationError};
+
+use crate::screen::{ColorMode, TerminalScreen};
+
+const FRAME_TIME: u128 = 1000/60;
+// Most terminals don't report key releases, so a press is held for a few frames
+const KEY_HOLD_FRAMES: u64 = 6;
+
+#[derive(Parser)]
+struct Cli {
+    cartridge: Option<std::path::PathBuf>,
+    /// IPS or BPS patch applied to the cartridge when loading it
+    #[arg(long)]
+    patch: Option<std::path::PathBuf>,
+    /// Unlicensed bank switching for ROMs where it isn't detected, like wisdom-tree
+    #[arg(long, value_name = "SCHEME")]
+    mapper: Option<String>,
+    /// Run this many frames without a terminal UI, print the last one and exit
+    #[arg(long)]
+    frames: Option<u64>,
+    /// With --frames, also save the state after the last frame
+    #[arg(long)]
+    save_state: Option<std::path::PathBuf>,
+    /// Find where the --invariant expression breaks between a good and a bad savestate
+    #[arg(long, num_args = 2, value_names = ["GOOD", "BAD"], requires = "invariant")]
+    bisect: Option<Vec<std::path::PathBuf>>,
+    #[arg(long)]
+    invariant: Option<String>,
+    /// RGBDS .sym file, for breakpoints and disassembly by label
+    #[arg(long)]
+    symbols: Option<std::path::PathBuf>,
+    /// Names a RAM address, "player_x = C0A3". Saved next to the ROM in a .labels file
+    /// and loaded with it from then on, they work like the labels of --symbols.
+    #[arg(long = "label", value_name = "NAME=ADDRESS", requires = "cartridge")]
+    labels: Vec<String>,
+    /// With --frames, print memory after the last frame with the labels in it, like $C000:100 or player_x:10
+    #[arg(long, value_name = "START:LENGTH")]
+    dump: Option<String>,
+    /// With --frames, save the tile data and both tile maps as PNG sheets in this directory
+    #[arg(long, value_name = "DIRECTORY")]
+    export_sheets: Option<std::path::PathBuf>,
+    /// Scale of the sheets, 4 makes every Game Boy pixel 4x4
+    #[arg(long, default_value_t = 1)]
+    sheet_scale: u32,
+    /// Lines between the tiles of the sheets
+    #[arg(long)]
+    sheet_grid: bool,
+    /// With --frames, stop at a label, BB:AAAA or $AAAA and show the code there
+    #[arg(long = "break", value_name = "LOCATION")]
+    breakpoints: Vec<String>,
+    /// With --frames, stop after a write to a register, label or address, optionally
+    /// when a condition on the old and new values holds: "LCDC if old & 0x80 && !(new & 0x80)"
+    #[arg(long = "watch", value_name = "WATCHPOINT")]
+    watchpoints: Vec<String>,
+    /// With --frames, run a Rhai script after every frame and on breakpoints
+    #[arg(long)]
+    script: Option<std::path::PathBuf>,
+    /// With --frames, profile the run and save the call stacks for flamegraph tools
+    #[arg(long)]
+    profile: Option<std::path::PathBuf>,
+    /// With --frames, run 2 to 4 copies of the cartridge linked through a four
+    /// player adapter and print the screen of every player
+    #[arg(long, requires_all = ["frames", "cartridge"])]
+    players: Option<usize>,
+    /// With --frames, save a hash of the state after every frame
+    #[arg(long, value_name = "FILE")]
+    record_hashes: Option<std::path::PathBuf>,
+    /// With --frames, compare the state after every frame with the hashes saved
+    /// by --record-hashes and report the first frame that differs
+    #[arg(long, value_name = "FILE")]
+    check_hashes: Option<std::path::PathBuf>,
+    /// Print the version of the core and what it can emulate, then exit
+    #[arg(long)]
+    capabilities: bool,
+    /// Use the 256 color palette even if the terminal supports truecolor
+    #[arg(long)]
+    ansi256: bool,
+}
+
+fn button_from_key(key: KeyCode) -> Option<Button> {
+    match key {
+        KeyCode::Char('a')  => Some(Button::A),
+        KeyCode::Char('s')  => Some(Button::B),
+        KeyCode::Enter      => Some(Button::Start),
+        KeyCode::Char(' ')  => Some(Button::Select),
+        KeyCode::Up         => Some(Button::Up),
+        KeyCode::Down       => Some(Button::Down),
+        KeyCode::Left       => Some(Button::Left),
+        KeyCode::Right      => Some(Button::Right),
+        _                   => None,
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let args = Cli::parse();
+
+    if args.capabilities {
+        println!("{}", capabilities::capabilities());
+        return Ok(());
+    }
+
+    let scheme = match &args.mapper {
+        Some(name) => Some(cartridge::unlicensed_scheme(name).ok_or_else(|| {
+            let names: Vec<&str> = cartridge::UNLICENSED_SCHEMES.iter().map(|scheme| scheme.name).collect();
+            Error::new(ErrorKind::InvalidInput, format!("Unknown mapper {}, expected one of {}", name, names.join(", ")))
+        })?),
+        None => None,
+    };
+    let load_cartridge = || {
+        let mut cartridge = match (&args.cartridge, &args.patch) {
+            (Some(c), Some(patch)) => Some(Cartridge::with_patch(c.clone(), patch.clone())?),
+            (Some(c), None) => Some(Cartridge::new(c.clone())?),
+            (None, _) => None,
+        };
+        if let (Some(cartridge), Some(scheme)) = (&mut cartridge, scheme) {
+            cartridge.set_unlicensed_mapper(scheme.create());
+        }
+        Ok::<_, Error>(cartridge)
+    };
+    let cartridge = load_cartridge()?;
+
+    if let Some(cartridge) = &cartridge {
+        for issue in cartridge.compatibility_issues() {
+            eprintln!("Warning: this game {}", issue);
+        }
+    }
+
+    let mode = if args.ansi256 { ColorMode::Ansi256 } else { ColorMode::detect() };
+    let screen = TerminalScreen::new(mode);
+
+    if let (Some(players), Some(frames)) = (args.players, args.frames) {
+        let cartridges = (0..players).map(|_| load_cartridge().map(Option::unwrap)).collect::<Result<_, _>>()?;
+        return run_multiplayer(MultiplayerSession::new(cartridges)?, &screen, frames);
+    }
+
+    let mut emu = Emulation::new(cartridge);
+    emu.start();
+
+    let mut symbols = match &args.symbols {
+        Some(path) => SymbolTable::load(path)?,
+        None => SymbolTable::default(),
+    };
+    if let Some(rom) = &args.cartridge {
+        let path = Annotations::path_for(rom);
+        let mut annotations = Annotations::load(&path)?;
+        if !args.labels.is_empty() {
+            for label in &args.labels {
+                annotations.add(label)?;
+            }
+            annotations.save(&path)?;
+        }
+        annotations.add_to(&mut symbols);
+    }
+    for breakpoint in &args.breakpoints {
+        let location = symbols.resolve(breakpoint)?;
+        emu.debugger().add_breakpoint(location);
+    }
+    for watchpoint in &args.watchpoints {
+        let watchpoint = Watchpoint::parse(watchpoint, &symbols)?;
+        emu.debugger().add_watchpoint(watchpoint);
+    }
+
+    if let (Some(states), Some(invariant)) = (&args.bisect, &args.invariant) {
+        return run_bisect(&mut emu, &states[0], &states[1], invariant);
+    }
+
+    if let Some(frames) = args.frames {
+        if args.profile.is_some() {
+            emu.start_profiler();
+        }
+        if args.record_hashes.is_some() || args.check_hashes.is_some() {
+            emu.start_state_hashes();
+        }
+        let mut script = args.script.as_ref().map(Script::load).transpose()?;
+        if let Some(script) = &mut script {
+            script.start(&mut emu)?;
+        }
+        run_headless(&mut emu, &screen, frames, &symbols, script.as_mut())?;
+        if let Some(directory) = &args.export_sheets {
+            let options = SheetOptions { scale: args.sheet_scale, grid: args.sheet_grid };
+            for path in emu.export_sheets(directory, &ColorPalette::default(), options)? {
+                println!("Saved {}", path.display());
+            }
+        }
+        if let Some(dump) = &args.dump {
+            let (start, len) = dump.split_once(':').ok_or_else(|| Error::new(ErrorKind::InvalidInput, "--dump takes START:LENGTH"))?;
+            let start = symbols.resolve(start)?.address;
+            let len = usize::from_str_radix(len, 16).map_err(|_| Error::new(ErrorKind::InvalidInput, "The length of --dump is in hex"))?;
+            print!("{}", emu.debugger().memory_view(start, len, Some(&symbols)));
+        }
+        if let (Some(path), Some(profiler)) = (&args.profile, emu.stop_profiler()) {
+            profiler.save_collapsed(path, &symbols)?;
+            for function in profiler.functions(&symbols).iter().take(10) {
+                println!("{:>6.2}% {}", function.cycles as f64 * 100.0 / profiler.total_cycles().max(1) as f64, function.name);
+            }
+        }
+        if let Some(hashes) = emu.stop_state_hashes() {
+            if let Some(path) = &args.record_hashes {
+                hashes.save(path)?;
+            }
+            if let Some(path) = &args.check_hashes {
+                let expected = StateHashes::load(path)?;
+                match expected.first_divergence(&hashes) {
+                    Some(divergence) => return Err(Error::other(format!("The state differs after the frame ending on cycle {}: {} instead of {}",
+                        divergence.cycles, hash_text(divergence.actual), hash_text(divergence.expected)))),
+                    None => println!("{} frames match {}", hashes.len().min(expected.len()), path.display()),
+                }
+            }
+        }
+        if let Some(path) = &args.save_state {
+            emu.save_state().save(path)?;
+        }
+        return Ok(());
+    }
+
+    let mut stdout = std::io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+
+    let enhanced_keyboard = terminal::supports_keyboard_enhancement().unwrap_or(false);
+    if enhanced_keyboard {
+        execute!(stdout, PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES))?;
+    }
+
+    let result = run_interactive(&mut emu, &screen, enhanced_keyboard);
+
+    if enhanced_keyboard {
+        execute!(stdout, PopKeyboardEnhancementFlags)?;
+    }
+    execute!(stdout, Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+
+    let result_message = result?;
+    println!("Emulation terminated, total executed cycles: {}. Reason: {}", emu.total_cycles, result_message);
+
+    Ok(())
+}
+
+fn run_headless(emu: &mut Emulation, screen: &TerminalScreen, frames: u64, symbols: &SymbolTable, mut script: Option<&mut Script>) -> Result<(), Error> {
+    let mut ran = false;
+
+    for _ in 0..frames {
+        match emu.skip_frame() {
+            Ok(()) => {
+                ran = true;
+                if let Some(script) = &mut script {
+                    script.on_frame(emu)?;
+                }
+            },
+            Err(EmulationError::Breakpoint { address }) => {
+                // Breakpoints handled by the script don't stop the run
+                if let Some(script) = &mut script {
+                    if script.on_breakpoint(emu, address)? {
+                        continue;
+                    }
+                }
+                print_breakpoint(emu, address, symbols);
+                return Ok(());
+            },
+            Err(EmulationError::Watchpoint { pc, address, old, new }) => {
+                let scanline = emu.ppu_timing().scanline;
+                let debugger = emu.debugger();
+                let instruction = debugger.disassemble(pc, Some(symbols));
+                println!("Watchpoint on {}, {:02X} -> {:02X} on scanline {}", debugger.symbolize(address, Some(symbols)), old, new, scanline);
+                println!("  {:<24} {}", debugger.symbolize(pc, Some(symbols)), instruction.text);
+                return Ok(());
+            },
+            Err(error) => {
+                eprintln!("{}\n{}", error, emu.dump_state_text());
+                print_backtrace(emu, symbols);
+                return Err(error.into());
+            },
+        }
+    }
+
+    if ran {
+        let mut stdout = std::io::stdout();
+        screen.print(&mut stdout, &emu.screen())?;
+    }
+
+    Ok(())
+}
+
+fn hash_text(hash: Option<u32>) -> String {
+    hash.map_or("no frame".to_string(), |hash| format!("{:08x}", hash))
+}
+
+fn run_multiplayer(mut session: MultiplayerSession, screen: &TerminalScreen, frames: u64) -> Result<(), Error> {
+    let mut steps = Vec::new();
+    for _ in 0..frames {
+        steps = session.step()?;
+    }
+
+    let mut stdout = std::io::stdout();
+    for (player, step) in steps.iter().enumerate() {
+        println!("Player {}", player + 1);
+        screen.print(&mut stdout, &step.framebuffer)?;
+    }
+    let adapter = session.adapter();
+    let adapter = adapter.lock().unwrap();
+    println!("{} players connected to the adapter{}", adapter.connected_players(), if adapter.is_transmitting() { ", in game" } else { "" });
+    Ok(())
+}
+
+// The instructions from the breakpoint on
+fn print_breakpoint(emu: &mut Emulation, address: u16, symbols: &SymbolTable) {
+    let debugger = emu.debugger();
+    println!("Breakpoint at {} ({:02X}:{:04X})", debugger.symbolize(address, Some(symbols)), debugger.bank_at(address), address);
+    let mut address = address;
+    for _ in 0..8 {
+        let instruction = debugger.disassemble(address, Some(symbols));
+        if let Some(name) = symbols.name_at(debugger.bank_at(address), address) {
+            println!("{}:", name);
+        }
+        let bytes: Vec<String> = instruction.bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        println!("  {:04X}  {:<8}  {}", address, bytes.join(" "), instruction.text);
+        address = address.wrapping_add(instruction.bytes.len() as u16);
+    }
+    print_backtrace(emu, symbols);
+}
+
+fn print_backtrace(emu: &mut Emulation, symbols: &SymbolTable) {
+    let debugger = emu.debugger();
+    for frame in debugger.backtrace() {
+        let kind = if frame.interrupt { "interrupt" } else { "called" };
+        eprintln!("  in {} {} from {}", debugger.symbolize(frame.target, Some(symbols)), kind, debugger.symbolize(frame.caller, Some(symbols)));
+    }
+    for warning in debugger.stack_warnings() {
+        eprintln!("Warning: RET at {} went to {} instead of {}", debugger.symbolize(warning.address, Some(symbols)),
+            debugger.symbolize(warning.returned_to, Some(symbols)), debugger.symbolize(warning.expected.return_address, Some(symbols)));
+    }
+}
+
+fn run_bisect(emu: &mut Emulation, good: &std::path::Path, bad: &std::path::Path, invariant: &str) -> Result<(), Error> {
+    let invariant = Expression::parse(invariant)?;
+    let bisection = debugger::bisect(emu, &SaveState::load(good)?, &SaveState::load(bad)?, &invariant)?;
+    println!("The invariant breaks in frame {} after {} instructions, executing the instruction at {:04X}",
+        bisection.frame, bisection.instruction, bisection.pc);
+
+    let path = good.with_extension("break.state");
+    bisection.state.save(&path)?;
+    println!("State before that instruction saved to {}", path.display());
+    Ok(())
+}
+
+fn run_interactive(emu: &mut Emulation, screen: &TerminalScreen, enhanced_keyboard: bool) -> Result<String, Error> {
+    let mut stdout = std::io::stdout().lock();
+    // Pressed keys and the frame in which they will be released
+    let mut held: HashMap<KeyCode, u64> = HashMap::new();
+    let mut frame: u64 = 0;
+
+    loop {
+        let now = Instant::now();
+
+        while event::poll(Duration::from_millis(0))? {
+            if let Event::Key(key) = event::read()? {
+                if key.code == KeyCode::Esc || key.code == KeyCode::Char('q') {
+                    return Ok(String::from("User terminated emulation."));
+                }
+
+                match key.kind {
+                    KeyEventKind::Release => {
+                        if held.remove(&key.code).is_some() {
+                            if let Some(button) = button_from_key(key.code) {
+                                emu.button_released(button);
+                            }
+                        }
+                    },
+                    _ => {
+                        if let Some(button) = button_from_key(key.code) {
+                            if !held.contains_key(&key.code) {
+                                emu.button_pressed(button);
+                            }
+                            // With release events the key is held until it is reported
+                            let release_at = if enhanced_keyboard { u64::MAX } else { frame + KEY_HOLD_FRAMES };
+                            held.insert(key.code, release_at);
+                        }
+                    },
+                }
+            }
+        }
+
+        let expired: Vec<KeyCode> = held.iter().filter(|(_, release_at)| **release_at <= frame).map(|(key, _)| *key).collect();
+        for key in expired {
+            held.remove(&key);
+            if let Some(button) = button_from_key(key) {
+                emu.button_released(button);
+            }
+        }
+
+        emu.skip_frame()?;
+        screen.render(&mut stdout, &emu.screen())?;
+        frame += 1;
+
+        let elapsed_processing = now.elapsed().as_millis();
+        if elapsed_processing < FRAME_TIME {
+            spin_sleep::sleep(Duration::from_millis((FRAME_TIME - elapsed_processing) as u64));
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::io::{Result, Write};
+
+use crossterm::{cursor::MoveTo, queue, style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor}};
+use gameboy::{ColoredPixel, GameBoyFrame};
+
+// Upper half block: foreground paints the top pixel and background the bottom one
+const HALF_BLOCK: char = '\u{2580}';
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    TrueColor,
+    Ansi256,
+}
+
+impl ColorMode {
+    // Terminals advertising 24-bit support usually set COLORTERM
+    pub fn detect() -> Self {
+        match std::env::var("COLORTERM") {
+            Ok(value) if value == "truecolor" || value == "24bit" => ColorMode::TrueColor,
+            _ => ColorMode::Ansi256,
+        }
+    }
+}
+
+pub struct TerminalScreen {
+    mode: ColorMode,
+}
+
+fn color_from_pixel(pixel: ColoredPixel, mode: ColorMode) -> Color {
+    match mode {
+        ColorMode::TrueColor => match pixel {
+            ColoredPixel::White => Color::Rgb { r: 255, g: 255, b: 255 },
+            ColoredPixel::LightGray => Color::Rgb { r: 192, g: 192, b: 192 },
+            ColoredPixel::DarkGray => Color::Rgb { r: 96, g: 96, b: 96 },
+            ColoredPixel::Black => Color::Rgb { r: 0, g: 0, b: 0 },
+        },
+        // Indexes from the grayscale ramp of the xterm 256 color palette
+        ColorMode::Ansi256 => match pixel {
+            ColoredPixel::White => Color::AnsiValue(231),
+            ColoredPixel::LightGray => Color::AnsiValue(250),
+            ColoredPixel::DarkGray => Color::AnsiValue(240),
+            ColoredPixel::Black => Color::AnsiValue(16),
+        },
+    }
+}
+
+impl TerminalScreen {
+    pub fn new(mode: ColorMode) -> TerminalScreen {
+        TerminalScreen { mode }
+    }
+
+    // Draws over the whole terminal, used by the interactive mode
+    pub(crate) fn render<W: Write>(&self, out: &mut W, frame: &GameBoyFrame) -> Result<()> {
+        self.draw(out, frame, true)
+    }
+
+    // Prints the frame as plain lines, so it can be piped or logged
+    pub(crate) fn print<W: Write>(&self, out: &mut W, frame: &GameBoyFrame) -> Result<()> {
+        self.draw(out, frame, false)
+    }
+
+    // Every terminal row holds two frame rows
+    fn draw<W: Write>(&self, out: &mut W, frame: &GameBoyFrame, positioned: bool) -> Result<()> {
+        let width = frame.width as usize;
+        let height = frame.height as usize;
+
+        for row in 0..height.div_ceil(2) {
+            if positioned {
+                queue!(out, MoveTo(0, row as u16))?;
+            }
+
+            let mut current: Option<(Color, Color)> = None;
+            for x in 0..width {
+                let top = frame.buffer[x + 2 * row * width];
+                let bottom = if 2 * row + 1 < height { frame.buffer[x + (2 * row + 1) * width] } else { top };
+
+                let colors = (color_from_pixel(top, self.mode), color_from_pixel(bottom, self.mode));
+                // Only emit escape sequences when the colors change
+                if current != Some(colors) {
+                    queue!(out, SetForegroundColor(colors.0), SetBackgroundColor(colors.1))?;
+                    current = Some(colors);
+                }
+                queue!(out, Print(HALF_BLOCK))?;
+            }
+            queue!(out, ResetColor)?;
+            if !positioned {
+                queue!(out, Print('\n'))?;
+            }
+        }
+
+        out.flush()
+    }
+}